@@ -0,0 +1,17 @@
+-- Login attempts, used for brute-force throttling and temporary lockout.
+-- Keyed by the normalized email even when no account exists (no enumeration through lockout).
+CREATE TABLE login_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    email TEXT NOT NULL,
+    ip TEXT,
+    outcome TEXT NOT NULL CHECK (outcome IN ('failure', 'success', 'unlock')),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_login_attempts_email_created_at ON login_attempts(email, created_at);
+CREATE INDEX idx_login_attempts_ip_created_at ON login_attempts(ip, created_at);
+
+-- Unlock links sent by email when an account gets locked
+ALTER TABLE user_tokens DROP CONSTRAINT user_tokens_purpose_check;
+ALTER TABLE user_tokens ADD CONSTRAINT user_tokens_purpose_check
+    CHECK (purpose IN ('password_reset', 'email_verification', 'mfa_pending', 'account_unlock'));
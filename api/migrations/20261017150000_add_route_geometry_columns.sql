@@ -0,0 +1,16 @@
+-- Values derived from path_data by the api (never trusted from the client).
+-- distance_meters already exists: it is now computed server-side.
+-- Rows created before this migration keep NULL until their path is updated.
+ALTER TABLE routes
+    ADD COLUMN min_lat DOUBLE PRECISION,
+    ADD COLUMN min_lon DOUBLE PRECISION,
+    ADD COLUMN max_lat DOUBLE PRECISION,
+    ADD COLUMN max_lon DOUBLE PRECISION,
+    ADD COLUMN start_lat DOUBLE PRECISION,
+    ADD COLUMN start_lon DOUBLE PRECISION,
+    ADD COLUMN end_lat DOUBLE PRECISION,
+    ADD COLUMN end_lon DOUBLE PRECISION,
+    ADD COLUMN elevation_gain_meters REAL,
+    ADD COLUMN elevation_loss_meters REAL;
+
+CREATE INDEX idx_routes_distance ON routes(distance_meters);
@@ -0,0 +1,21 @@
+-- Spatial lookups on routes (GET /routes/nearby and /routes/within).
+-- The B-tree indexes on the derived columns always exist and serve the pure SQL fallback.
+CREATE INDEX idx_routes_start_point ON routes(start_lat, start_lon);
+CREATE INDEX idx_routes_bbox ON routes(min_lat, max_lat, min_lon, max_lon);
+
+-- With PostGIS installed on the server, add GiST indexes used by the api instead.
+-- Creating the extension needs enough privileges: without them the fallback is kept.
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_available_extensions WHERE name = 'postgis') THEN
+        CREATE EXTENSION IF NOT EXISTS postgis;
+        EXECUTE 'CREATE INDEX idx_routes_start_geog ON routes USING GIST '
+             || '((ST_SetSRID(ST_MakePoint(start_lon, start_lat), 4326)::geography))';
+        EXECUTE 'CREATE INDEX idx_routes_bbox_geom ON routes USING GIST '
+             || '(ST_MakeEnvelope(min_lon, min_lat, max_lon, max_lat, 4326))';
+    END IF;
+EXCEPTION
+    WHEN insufficient_privilege THEN
+        RAISE NOTICE 'postgis unavailable for this role, using the B-tree fallback';
+END
+$$;
@@ -0,0 +1,38 @@
+-- User-defined slices of public routes, each with its own leaderboard.
+-- path_data, distance and bbox are computed by the api from the parent route.
+CREATE TABLE segments (
+    id SERIAL PRIMARY KEY,
+    route_id INTEGER NOT NULL REFERENCES routes(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    start_distance_meters REAL NOT NULL CHECK (start_distance_meters >= 0),
+    end_distance_meters REAL NOT NULL,
+    distance_meters REAL NOT NULL CHECK (distance_meters > 0),
+    path_data JSONB NOT NULL,
+    min_lat DOUBLE PRECISION NOT NULL,
+    min_lon DOUBLE PRECISION NOT NULL,
+    max_lat DOUBLE PRECISION NOT NULL,
+    max_lon DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW(),
+    CHECK (end_distance_meters > start_distance_meters)
+);
+
+CREATE INDEX idx_segments_route_id ON segments(route_id);
+CREATE INDEX idx_segments_bbox ON segments(min_lat, max_lat, min_lon, max_lon);
+
+-- Best pass of a score (run) over a segment, matched from its GPS sensor_data.
+CREATE TABLE segment_efforts (
+    id SERIAL PRIMARY KEY,
+    segment_id INTEGER NOT NULL REFERENCES segments(id) ON DELETE CASCADE,
+    score_id INTEGER NOT NULL REFERENCES scores(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    elapsed_seconds REAL NOT NULL CHECK (elapsed_seconds > 0),
+    start_offset_ms INTEGER NOT NULL,
+    end_offset_ms INTEGER NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW(),
+    UNIQUE (segment_id, score_id)
+);
+
+CREATE INDEX idx_segment_efforts_leaderboard ON segment_efforts(segment_id, elapsed_seconds);
+CREATE INDEX idx_segment_efforts_user ON segment_efforts(user_id, segment_id);
+CREATE INDEX idx_segment_efforts_score_id ON segment_efforts(score_id);
@@ -0,0 +1,42 @@
+-- Immutable revisions of a route's path. A new revision is written whenever path_data changes;
+-- scores reference the revision they were run on so leaderboards stay comparable.
+CREATE TABLE route_versions (
+    id SERIAL PRIMARY KEY,
+    route_id INTEGER NOT NULL REFERENCES routes(id) ON DELETE CASCADE,
+    version INTEGER NOT NULL CHECK (version > 0),
+    path_data JSONB NOT NULL,
+    distance_meters REAL,
+    elevation_gain_meters REAL,
+    elevation_loss_meters REAL,
+    created_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP DEFAULT NOW(),
+    UNIQUE (route_id, version)
+);
+
+CREATE FUNCTION forbid_route_version_update() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'route_versions rows are immutable';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER route_versions_immutable
+    BEFORE UPDATE ON route_versions
+    FOR EACH ROW EXECUTE FUNCTION forbid_route_version_update();
+
+ALTER TABLE routes ADD COLUMN current_version INTEGER NOT NULL DEFAULT 1;
+
+-- Existing routes become revision 1, and existing scores are attached to it
+INSERT INTO route_versions (route_id, version, path_data, distance_meters,
+                            elevation_gain_meters, elevation_loss_meters, created_by, created_at)
+SELECT id, 1, path_data, distance_meters, elevation_gain_meters, elevation_loss_meters,
+       user_id, COALESCE(updated_at, created_at, NOW())
+FROM routes;
+
+ALTER TABLE scores ADD COLUMN route_version INTEGER;
+UPDATE scores SET route_version = 1;
+ALTER TABLE scores
+    ALTER COLUMN route_version SET NOT NULL,
+    ADD CONSTRAINT scores_route_version_fkey
+        FOREIGN KEY (route_id, route_version) REFERENCES route_versions(route_id, version) ON DELETE CASCADE;
+
+CREATE INDEX idx_scores_route_version ON scores(route_id, route_version, time_seconds);
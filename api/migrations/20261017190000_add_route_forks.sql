@@ -0,0 +1,8 @@
+-- A fork is an independent copy of another user's route. The source route and revision are kept
+-- for attribution; the original author survives the source route being deleted.
+ALTER TABLE routes
+    ADD COLUMN forked_from INTEGER REFERENCES routes(id) ON DELETE SET NULL,
+    ADD COLUMN forked_from_version INTEGER,
+    ADD COLUMN forked_from_user_id INTEGER REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_routes_forked_from ON routes(forked_from) WHERE forked_from IS NOT NULL;
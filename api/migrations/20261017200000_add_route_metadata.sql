@@ -0,0 +1,16 @@
+-- Descriptive metadata for routes, and full-text search over name and description.
+-- The 'simple' configuration is used because route names mix languages and place names.
+ALTER TABLE routes
+    ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN surface TEXT CHECK (surface IN ('asphalt', 'gravel', 'dirt', 'trail', 'track', 'sand', 'mixed')),
+    ADD COLUMN difficulty TEXT CHECK (difficulty IN ('easy', 'moderate', 'hard', 'expert')),
+    ADD COLUMN is_loop BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN is_out_and_back BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', coalesce(name, '')), 'A') ||
+        setweight(to_tsvector('simple', coalesce(description, '')), 'B')
+    ) STORED;
+
+CREATE INDEX idx_routes_search_vector ON routes USING GIN (search_vector);
+CREATE INDEX idx_routes_tags ON routes USING GIN (tags);
+CREATE INDEX idx_routes_difficulty ON routes(difficulty) WHERE difficulty IS NOT NULL;
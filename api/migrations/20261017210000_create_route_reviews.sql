@@ -0,0 +1,28 @@
+-- Star ratings with optional text reviews (one per user and route), and favourite routes.
+-- The aggregates on routes are maintained by the API in the same transaction as the review.
+CREATE TABLE route_reviews (
+    id SERIAL PRIMARY KEY,
+    route_id INTEGER NOT NULL REFERENCES routes(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    rating INTEGER NOT NULL CHECK (rating BETWEEN 1 AND 5),
+    review TEXT,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW(),
+    UNIQUE (route_id, user_id)
+);
+
+CREATE INDEX idx_route_reviews_user_id ON route_reviews(user_id);
+
+CREATE TABLE route_favorites (
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    route_id INTEGER NOT NULL REFERENCES routes(id) ON DELETE CASCADE,
+    created_at TIMESTAMP DEFAULT NOW(),
+    PRIMARY KEY (user_id, route_id)
+);
+
+CREATE INDEX idx_route_favorites_route_id ON route_favorites(route_id);
+
+ALTER TABLE routes
+    ADD COLUMN rating_average REAL,
+    ADD COLUMN rating_count INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN review_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,25 @@
+-- Visibility levels replace the is_public flag:
+--   private  : owner only
+--   friends  : owner and accepted friends
+--   unlisted : owner, and anyone holding a share link
+--   public   : everyone
+ALTER TABLE routes ADD COLUMN visibility TEXT NOT NULL DEFAULT 'private'
+    CHECK (visibility IN ('private', 'friends', 'unlisted', 'public'));
+UPDATE routes SET visibility = 'public' WHERE is_public;
+
+-- is_public stays readable (and filterable) for existing clients, derived from visibility
+DROP INDEX IF EXISTS idx_routes_public;
+ALTER TABLE routes DROP COLUMN is_public;
+ALTER TABLE routes ADD COLUMN is_public BOOLEAN GENERATED ALWAYS AS (visibility = 'public') STORED;
+CREATE INDEX idx_routes_visibility ON routes(visibility);
+
+-- Revocable share links for unlisted routes (only the SHA-256 hash of the token is stored)
+CREATE TABLE route_share_links (
+    id SERIAL PRIMARY KEY,
+    route_id INTEGER NOT NULL REFERENCES routes(id) ON DELETE CASCADE,
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP DEFAULT NOW(),
+    revoked_at TIMESTAMP
+);
+
+CREATE INDEX idx_route_share_links_route_id ON route_share_links(route_id);
@@ -0,0 +1,16 @@
+-- Run-to-route matching: a score is verified once its GPS track (sensor_data) follows the
+-- route revision it was run on. Only verified scores count toward leaderboards.
+--   pending  : no GPS track uploaded yet (includes every score recorded before this migration)
+--   verified : start and finish reached, route followed in order
+--   rejected : see verification_reason
+ALTER TABLE scores
+    ADD COLUMN verification_status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (verification_status IN ('pending', 'verified', 'rejected')),
+    ADD COLUMN verification_reason TEXT,
+    -- Share of the route covered in order, between 0 and 1
+    ADD COLUMN route_coverage REAL,
+    ADD COLUMN max_deviation_meters REAL,
+    ADD COLUMN verified_at TIMESTAMP;
+
+CREATE INDEX idx_scores_verified ON scores(route_id, route_version, time_seconds)
+    WHERE verification_status = 'verified';
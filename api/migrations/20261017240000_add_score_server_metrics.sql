@@ -0,0 +1,14 @@
+-- Metrics recomputed by the server from the raw sensor_data samples, stored next to the values
+-- sent by the client (max_speed_kmh, avg_speed_kmh, ...). metric_discrepancies lists the client
+-- metrics that differ too much from the server ones.
+ALTER TABLE scores
+    ADD COLUMN server_max_speed_kmh REAL,
+    ADD COLUMN server_avg_speed_kmh REAL,
+    ADD COLUMN server_max_g_force REAL,
+    ADD COLUMN server_max_inclination_degrees REAL,
+    ADD COLUMN server_max_sound_db REAL,
+    ADD COLUMN metric_discrepancies TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN metrics_computed_at TIMESTAMP;
+
+CREATE INDEX idx_scores_metric_discrepancies ON scores(created_at)
+    WHERE cardinality(metric_discrepancies) > 0;
@@ -0,0 +1,18 @@
+-- Anti-cheat flags raised on scores by the rule engine (one row per score and rule), reviewed by
+-- moderators. A score with an open or confirmed flag is hidden from leaderboards; a dismissed
+-- flag is kept as a record and is not raised again for the same rule.
+CREATE TABLE score_flags (
+    id SERIAL PRIMARY KEY,
+    score_id INTEGER NOT NULL REFERENCES scores(id) ON DELETE CASCADE,
+    rule TEXT NOT NULL,
+    details JSONB NOT NULL DEFAULT '{}',
+    status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'dismissed', 'confirmed')),
+    review_note TEXT,
+    reviewed_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    reviewed_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW(),
+    UNIQUE (score_id, rule)
+);
+
+CREATE INDEX idx_score_flags_status ON score_flags(status, created_at);
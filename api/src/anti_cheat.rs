@@ -0,0 +1,248 @@
+//! Règles anti-triche évaluées sur chaque course soumise. Une règle déclenchée ouvre un
+//! signalement dans `score_flags`, que les modérateurs traitent depuis `/admin/score-flags` ;
+//! tant qu'il n'est pas écarté, la course n'apparaît pas dans les classements.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::{
+    db::DbPool,
+    geometry,
+    models::score::MAX_SPEED_KMH,
+    score_metrics::{self, Sample},
+};
+
+/// Records du monde sur route et sur piste : (distance en mètres, temps en secondes).
+const WORLD_RECORDS: [(f64, f64); 11] = [
+    (100.0, 9.58),
+    (200.0, 19.19),
+    (400.0, 43.03),
+    (800.0, 100.91),
+    (1_000.0, 131.96),
+    (1_500.0, 206.0),
+    (3_000.0, 440.67),
+    (5_000.0, 755.36),
+    (10_000.0, 1_571.0),
+    (21_097.5, 3_402.0),
+    (42_195.0, 7_235.0),
+];
+/// Marge sur le record, pour un tracé dessiné un peu plus long que la distance réellement courue.
+pub const PACE_MARGIN: f64 = 1.05;
+/// Un saut de position d'au moins cette distance, à une vitesse supérieure à [`MAX_SPEED_KMH`],
+/// est une téléportation. En deçà, c'est du bruit GPS entre deux relevés rapprochés.
+pub const TELEPORT_MIN_METERS: f64 = 200.0;
+/// Vitesse moyenne à partir de laquelle l'accéléromètre doit enregistrer les foulées.
+pub const MOVING_SPEED_KMH: f64 = 6.0;
+/// En deçà de cet écart type de la norme de l'accélération (m/s²), l'appareil est immobile.
+pub const STILL_ACCEL_STD_DEV: f64 = 0.5;
+/// Nombre minimal de relevés (accéléromètre, intervalles) pour juger une course.
+pub const MIN_SAMPLES: usize = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// Allure plus rapide que le record du monde sur la distance du parcours.
+    ImpossiblePace,
+    GpsTeleport,
+    /// Vitesse de course alors que l'accéléromètre ne bouge pas.
+    SpeedWithoutMotion,
+    /// Relevés tous espacés exactement du même intervalle, comme une trace générée.
+    RegularSampling,
+    /// Même temps et mêmes métriques qu'une course précédente du même utilisateur sur le parcours.
+    DuplicateSubmission,
+}
+
+impl Rule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rule::ImpossiblePace => "impossible_pace",
+            Rule::GpsTeleport => "gps_teleport",
+            Rule::SpeedWithoutMotion => "speed_without_motion",
+            Rule::RegularSampling => "regular_sampling",
+            Rule::DuplicateSubmission => "duplicate_submission",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Flag {
+    pub rule: Rule,
+    /// Mesures qui ont déclenché la règle.
+    pub details: serde_json::Value,
+}
+
+/// Ce qu'on sait de la course en dehors de ses relevés.
+#[derive(Debug, Clone, Default)]
+pub struct Submission {
+    pub time_seconds: f64,
+    /// Longueur de la révision du parcours courue.
+    pub distance_meters: Option<f64>,
+    /// Course précédente identique, le cas échéant.
+    pub duplicate_of: Option<i32>,
+}
+
+/// Règles déclenchées par la course, dans l'ordre de [`Rule`].
+pub fn evaluate(submission: &Submission, samples: &[Sample]) -> Vec<Flag> {
+    [
+        submission.distance_meters.and_then(|d| impossible_pace(d, submission.time_seconds)),
+        gps_teleport(samples),
+        speed_without_motion(samples),
+        regular_sampling(samples),
+        submission.duplicate_of.map(|id| Flag { rule: Rule::DuplicateSubmission, details: json!({ "duplicate_of": id }) }),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Vitesse du record du monde en m/s, interpolée entre les distances voisines sur une échelle
+/// logarithmique et bornée aux distances extrêmes.
+pub fn record_speed(distance_meters: f64) -> f64 {
+    let speed = |(d, t): (f64, f64)| d / t;
+    let first = WORLD_RECORDS[0];
+    let last = WORLD_RECORDS[WORLD_RECORDS.len() - 1];
+    if distance_meters <= first.0 {
+        return speed(first);
+    }
+    if distance_meters >= last.0 {
+        return speed(last);
+    }
+    let upper = WORLD_RECORDS.iter().position(|&(d, _)| d >= distance_meters).unwrap_or(WORLD_RECORDS.len() - 1);
+    let (a, b) = (WORLD_RECORDS[upper - 1], WORLD_RECORDS[upper]);
+    let t = (distance_meters.ln() - a.0.ln()) / (b.0.ln() - a.0.ln());
+    speed(a) + (speed(b) - speed(a)) * t
+}
+
+pub fn impossible_pace(distance_meters: f64, time_seconds: f64) -> Option<Flag> {
+    if distance_meters <= 0.0 || time_seconds <= 0.0 {
+        return None;
+    }
+    let speed = distance_meters / time_seconds;
+    let record = record_speed(distance_meters);
+    (speed > record * PACE_MARGIN).then(|| Flag {
+        rule: Rule::ImpossiblePace,
+        details: json!({
+            "distance_meters": distance_meters,
+            "time_seconds": time_seconds,
+            "speed_kmh": speed * 3.6,
+            "record_speed_kmh": record * 3.6,
+        }),
+    })
+}
+
+/// Sauts entre deux relevés GPS consécutifs d'au moins [`TELEPORT_MIN_METERS`] à plus de
+/// [`MAX_SPEED_KMH`].
+pub fn gps_teleport(samples: &[Sample]) -> Option<Flag> {
+    let positions: Vec<(i32, geometry::Position)> = samples.iter().filter_map(|s| s.position.map(|p| (s.offset_ms, p))).collect();
+    let jumps: Vec<f64> = positions
+        .windows(2)
+        .filter_map(|w| {
+            let meters = geometry::haversine_distance(&w[0].1, &w[1].1);
+            let elapsed_ms = w[1].0 - w[0].0;
+            let speed_kmh = if elapsed_ms > 0 { meters / elapsed_ms as f64 * 3_600.0 } else { f64::INFINITY };
+            (meters >= TELEPORT_MIN_METERS && speed_kmh > MAX_SPEED_KMH as f64).then_some(meters)
+        })
+        .collect();
+    let longest = jumps.iter().copied().reduce(f64::max)?;
+    Some(Flag {
+        rule: Rule::GpsTeleport,
+        details: json!({ "count": jumps.len(), "max_jump_meters": longest }),
+    })
+}
+
+/// Vitesse moyenne d'au moins [`MOVING_SPEED_KMH`] alors que la norme de l'accélération ne varie
+/// presque pas : le téléphone était posé pendant la course.
+pub fn speed_without_motion(samples: &[Sample]) -> Option<Flag> {
+    let magnitudes: Vec<f64> = samples.iter().filter_map(|s| s.accel.map(|(x, y, z)| (x * x + y * y + z * z).sqrt())).collect();
+    if magnitudes.len() < MIN_SAMPLES {
+        return None;
+    }
+    let avg_speed_kmh = score_metrics::compute(samples).avg_speed_kmh.filter(|&v| v >= MOVING_SPEED_KMH)?;
+
+    let mean = magnitudes.iter().sum::<f64>() / magnitudes.len() as f64;
+    let std_dev = (magnitudes.iter().map(|m| (m - mean).powi(2)).sum::<f64>() / magnitudes.len() as f64).sqrt();
+    (std_dev < STILL_ACCEL_STD_DEV).then(|| Flag {
+        rule: Rule::SpeedWithoutMotion,
+        details: json!({ "avg_speed_kmh": avg_speed_kmh, "accel_samples": magnitudes.len(), "accel_std_dev": std_dev }),
+    })
+}
+
+/// Au moins [`MIN_SAMPLES`] intervalles entre relevés, tous identiques à la milliseconde près :
+/// les capteurs d'un téléphone ont toujours un peu de gigue.
+pub fn regular_sampling(samples: &[Sample]) -> Option<Flag> {
+    let mut offsets: Vec<i32> = samples.iter().map(|s| s.offset_ms).collect();
+    offsets.sort_unstable();
+    offsets.dedup();
+    let intervals: Vec<i32> = offsets.windows(2).map(|w| w[1] - w[0]).collect();
+    if intervals.len() < MIN_SAMPLES || intervals.iter().any(|&i| i != intervals[0]) {
+        return None;
+    }
+    Some(Flag {
+        rule: Rule::RegularSampling,
+        details: json!({ "intervals": intervals.len(), "interval_ms": intervals[0] }),
+    })
+}
+
+/// Évalue la course `score_id` et met à jour ses signalements : une règle déclenchée ouvre ou
+/// met à jour le sien sans changer la décision d'un modérateur, une règle qui ne l'est plus
+/// retire le sien s'il est encore ouvert. `None` si le score n'existe pas.
+pub async fn evaluate_score(pool: &DbPool, score_id: i32) -> Result<Option<Vec<Flag>>, sqlx::Error> {
+    let submission = sqlx::query_as::<_, (f32, Option<f32>, Option<i32>)>(
+        "SELECT s.time_seconds, v.distance_meters,
+                (SELECT d.id FROM scores d
+                 WHERE d.user_id = s.user_id AND d.route_id = s.route_id AND d.id < s.id
+                   AND d.time_seconds = s.time_seconds
+                   AND d.max_speed_kmh IS NOT DISTINCT FROM s.max_speed_kmh
+                   AND d.avg_speed_kmh IS NOT DISTINCT FROM s.avg_speed_kmh
+                   AND d.max_g_force IS NOT DISTINCT FROM s.max_g_force
+                   AND d.max_inclination_degrees IS NOT DISTINCT FROM s.max_inclination_degrees
+                   AND d.max_sound_db IS NOT DISTINCT FROM s.max_sound_db
+                 ORDER BY d.id
+                 LIMIT 1)
+         FROM scores s
+         LEFT JOIN route_versions v ON v.route_id = s.route_id AND v.version = s.route_version
+         WHERE s.id = $1"
+    )
+    .bind(score_id)
+    .fetch_optional(pool)
+    .await?;
+    let Some((time_seconds, distance_meters, duplicate_of)) = submission else {
+        return Ok(None);
+    };
+    let submission = Submission {
+        time_seconds: time_seconds as f64,
+        distance_meters: distance_meters.map(f64::from),
+        duplicate_of,
+    };
+
+    let samples = score_metrics::load_samples(pool, score_id).await?;
+    let flags = evaluate(&submission, &samples);
+
+    let mut tx = pool.begin().await?;
+    for flag in &flags {
+        sqlx::query(
+            "INSERT INTO score_flags (score_id, rule, details) VALUES ($1, $2, $3)
+             ON CONFLICT (score_id, rule) DO UPDATE
+             SET details = EXCLUDED.details, updated_at = NOW()"
+        )
+        .bind(score_id)
+        .bind(flag.rule.as_str())
+        .bind(&flag.details)
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query("DELETE FROM score_flags WHERE score_id = $1 AND status = 'open' AND rule <> ALL($2)")
+        .bind(score_id)
+        .bind(flags.iter().map(|f| f.rule.as_str()).collect::<Vec<_>>())
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    if flags.is_empty() {
+        info!("Course {} : aucune règle anti-triche déclenchée", score_id);
+    } else {
+        warn!("Course {} signalée: {:?}", score_id, flags.iter().map(|f| f.rule.as_str()).collect::<Vec<_>>());
+    }
+    Ok(Some(flags))
+}
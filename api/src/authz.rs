@@ -0,0 +1,120 @@
+//! Contrôle d'accès. Le rôle est lu dans les `Claims` insérés par `auth_middleware` :
+//! un changement de rôle révoque les sessions de l'utilisateur pour prendre effet immédiatement.
+//!
+//! L'utilisateur qui agit est toujours celui des `Claims`, jamais un identifiant fourni par le
+//! client ; `authorize` vérifie qu'il possède (ou participe à) la ressource modifiée.
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+use shared::jwt::{Claims, Role};
+use std::marker::PhantomData;
+use shared::errors::AppError;
+use sqlx::QueryBuilder;
+use tracing::{error, warn};
+
+use crate::db::DbPool;
+
+/// Rôle minimum exigé par `RequireRole`.
+pub trait RoleRequirement {
+    const ROLE: Role;
+}
+
+pub struct Moderator;
+
+impl RoleRequirement for Moderator {
+    const ROLE: Role = Role::Moderator;
+}
+
+pub struct Admin;
+
+impl RoleRequirement for Admin {
+    const ROLE: Role = Role::Admin;
+}
+
+/// Extracteur refusant la requête (403) si l'appelant n'a pas au moins le rôle `R`.
+/// S'utilise sur une route protégée par `auth_middleware` :
+/// `RequireRole(claims, _): RequireRole<Admin>`.
+pub struct RequireRole<R: RoleRequirement>(pub Claims, pub PhantomData<R>);
+
+impl<S, R> FromRequestParts<S> for RequireRole<R>
+where
+    S: Send + Sync,
+    R: RoleRequirement,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let claims = parts.extensions.get::<Claims>().cloned().ok_or_else(|| {
+            error!("RequireRole utilisé sur une route sans auth_middleware");
+            AppError::Unauthorized("Authentication required".to_string())
+        })?;
+
+        if claims.role < R::ROLE {
+            warn!(
+                "Accès refusé à l'utilisateur {} (rôle {}, {} requis)",
+                claims.user_id,
+                claims.role.as_str(),
+                R::ROLE.as_str()
+            );
+            return Err(AppError::Forbidden(format!("The {} role is required", R::ROLE.as_str())));
+        }
+
+        Ok(RequireRole(claims, PhantomData))
+    }
+}
+
+/// Autorise l'appelant s'il fait partie de `owners` (propriétaire, participants...) ou s'il a
+/// au moins le rôle `bypass` (`None` : aucun rôle ne dispense d'être concerné).
+/// Refus en 403, journalisé avec la ressource concernée.
+pub fn authorize(claims: &Claims, owners: &[i32], bypass: Option<Role>, resource: &str) -> Result<(), AppError> {
+    if owners.contains(&claims.user_id) || bypass.is_some_and(|role| claims.role >= role) {
+        return Ok(());
+    }
+
+    warn!("Utilisateur {} non autorisé à modifier {}", claims.user_id, resource);
+    Err(AppError::Forbidden("You are not allowed to modify this resource".to_string()))
+}
+
+/// Condition ` AND (...)` sur `routes` : parcours publics, ceux de `user_id` et ceux de ses amis
+/// (amitié acceptée) en visibilité `friends`. Un parcours `unlisted` ne s'ouvre que par un lien de
+/// partage, jamais par son identifiant.
+pub fn push_route_visible_to(query: &mut QueryBuilder<'_, sqlx::Postgres>, user_id: i32) {
+    query
+        .push(" AND (routes.visibility = 'public' OR routes.user_id = ")
+        .push_bind(user_id)
+        .push(" OR (routes.visibility = 'friends' AND EXISTS (SELECT 1 FROM friendships f WHERE f.status = 'accepted' AND ((f.user_id = ")
+        .push_bind(user_id)
+        .push(" AND f.friend_id = routes.user_id) OR (f.friend_id = ")
+        .push_bind(user_id)
+        .push(" AND f.user_id = routes.user_id)))))");
+}
+
+/// Propriétaire du parcours `id` s'il est visible par `user_id`. Un parcours invisible renvoie
+/// 404 comme un parcours inexistant, pour ne pas révéler son existence.
+pub async fn visible_route_owner(pool: &DbPool, id: i32, user_id: i32) -> Result<i32, AppError> {
+    let mut query = QueryBuilder::new("SELECT routes.user_id FROM routes WHERE routes.id = ");
+    query.push_bind(id);
+    push_route_visible_to(&mut query, user_id);
+
+    query
+        .build_query_scalar::<i32>()
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la vérification de l'accès au parcours {}: {}", id, e);
+            AppError::from(e)
+        })?
+        .ok_or_else(|| {
+            warn!("Parcours {} introuvable ou invisible pour l'utilisateur {}", id, user_id);
+            AppError::NotFound("Route not found".to_string())
+        })
+}
+
+/// Rôle actuel de l'utilisateur en base (embarqué ensuite dans les access tokens).
+pub async fn user_role(pool: &DbPool, user_id: i32) -> Result<Role, sqlx::Error> {
+    let role = sqlx::query_scalar::<_, String>("SELECT role FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+    role.parse().map_err(|e: String| sqlx::Error::Decode(e.into()))
+}
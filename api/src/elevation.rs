@@ -0,0 +1,196 @@
+use serde::Serialize;
+
+use crate::geometry::{self, Position};
+
+/// Pente calculée sur au moins cette distance, pour lisser le bruit GPS/baromètre.
+pub const GRADE_WINDOW_METERS: f64 = 100.0;
+/// Nombre maximal de points du profil renvoyé (le calcul utilise toutes les positions).
+pub const MAX_PROFILE_POINTS: usize = 1_000;
+/// Une montée continue tant que l'altitude ne redescend pas de plus que cela sous son maximum.
+const CLIMB_DIP_TOLERANCE_METERS: f64 = 10.0;
+/// En dessous de cette pente moyenne, une montée n'est pas catégorisée.
+const CLIMB_MIN_GRADE_PERCENT: f64 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ProfilePoint {
+    pub distance_meters: f64,
+    pub elevation_meters: f64,
+}
+
+/// Catégorie d'une montée, de la plus facile (`cat4`) à la plus dure (`hc`, hors catégorie).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClimbCategory {
+    Cat4,
+    Cat3,
+    Cat2,
+    Cat1,
+    Hc,
+}
+
+impl ClimbCategory {
+    /// Barème « longueur (m) × pente moyenne (%) », celui des applications de course et de vélo.
+    fn from_score(score: f64) -> Option<ClimbCategory> {
+        match score {
+            s if s >= 80_000.0 => Some(ClimbCategory::Hc),
+            s if s >= 64_000.0 => Some(ClimbCategory::Cat1),
+            s if s >= 32_000.0 => Some(ClimbCategory::Cat2),
+            s if s >= 16_000.0 => Some(ClimbCategory::Cat3),
+            s if s >= 8_000.0 => Some(ClimbCategory::Cat4),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Climb {
+    pub start_distance_meters: f64,
+    pub end_distance_meters: f64,
+    pub elevation_gain_meters: f64,
+    pub avg_grade_percent: f64,
+    pub category: ClimbCategory,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ElevationProfile {
+    pub distance_meters: f64,
+    pub total_ascent_meters: f64,
+    pub total_descent_meters: f64,
+    pub min_elevation_meters: f64,
+    pub max_elevation_meters: f64,
+    /// Pente montante la plus forte, sur une fenêtre de [`GRADE_WINDOW_METERS`].
+    pub max_grade_percent: f64,
+    /// Pente descendante la plus forte (valeur négative), sur la même fenêtre.
+    pub min_grade_percent: f64,
+    /// Pente nette : (altitude d'arrivée − altitude de départ) / distance.
+    pub avg_grade_percent: f64,
+    pub climbs: Vec<Climb>,
+    pub points: Vec<ProfilePoint>,
+}
+
+/// Profil d'altitude d'un tracé. `None` s'il manque une altitude ou si le tracé a moins de 2 positions.
+pub fn profile(positions: &[Position]) -> Option<ElevationProfile> {
+    if positions.len() < 2 {
+        return None;
+    }
+    let elevations: Vec<f64> = positions.iter().map(|p| p.ele).collect::<Option<_>>()?;
+
+    let mut distances = Vec::with_capacity(positions.len());
+    let mut total = 0.0;
+    distances.push(0.0);
+    for w in positions.windows(2) {
+        total += geometry::haversine_distance(&w[0], &w[1]);
+        distances.push(total);
+    }
+    if total == 0.0 {
+        return None;
+    }
+
+    let (mut ascent, mut descent) = (0.0, 0.0);
+    for w in elevations.windows(2) {
+        let delta = w[1] - w[0];
+        if delta > 0.0 { ascent += delta } else { descent -= delta }
+    }
+
+    let (min_grade_percent, max_grade_percent) = grade_extremes(&distances, &elevations);
+
+    Some(ElevationProfile {
+        distance_meters: total,
+        total_ascent_meters: ascent,
+        total_descent_meters: descent,
+        min_elevation_meters: elevations.iter().copied().fold(f64::INFINITY, f64::min),
+        max_elevation_meters: elevations.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        max_grade_percent,
+        min_grade_percent,
+        avg_grade_percent: (elevations[elevations.len() - 1] - elevations[0]) / total * 100.0,
+        climbs: climbs(&distances, &elevations),
+        points: sample(&distances, &elevations),
+    })
+}
+
+/// Pentes extrêmes entre chaque point et le premier point situé au moins une fenêtre plus loin.
+/// Un tracé plus court que la fenêtre n'a qu'une pente : celle de bout en bout.
+fn grade_extremes(distances: &[f64], elevations: &[f64]) -> (f64, f64) {
+    let last = distances.len() - 1;
+    let grade = |i: usize, j: usize| (elevations[j] - elevations[i]) / (distances[j] - distances[i]) * 100.0;
+    if distances[last] < GRADE_WINDOW_METERS {
+        let g = grade(0, last);
+        return (g, g);
+    }
+
+    let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+    let mut j = 0;
+    for i in 0..last {
+        j = j.max(i + 1);
+        while j < last && distances[j] - distances[i] < GRADE_WINDOW_METERS {
+            j += 1;
+        }
+        if distances[j] - distances[i] < GRADE_WINDOW_METERS {
+            break;
+        }
+        let g = grade(i, j);
+        min = min.min(g);
+        max = max.max(g);
+    }
+    (min, max)
+}
+
+/// Découpe le profil en montées : chacune part d'un point bas et s'arrête à son sommet
+/// dès que l'altitude retombe de plus de [`CLIMB_DIP_TOLERANCE_METERS`].
+fn climbs(distances: &[f64], elevations: &[f64]) -> Vec<Climb> {
+    let mut climbs = Vec::new();
+    let (mut low, mut high) = (0, 0);
+
+    let mut close = |low: usize, high: usize| {
+        let length = distances[high] - distances[low];
+        let gain = elevations[high] - elevations[low];
+        if length <= 0.0 || gain <= 0.0 {
+            return;
+        }
+        let grade = gain / length * 100.0;
+        if grade < CLIMB_MIN_GRADE_PERCENT {
+            return;
+        }
+        if let Some(category) = ClimbCategory::from_score(length * grade) {
+            climbs.push(Climb {
+                start_distance_meters: distances[low],
+                end_distance_meters: distances[high],
+                elevation_gain_meters: gain,
+                avg_grade_percent: grade,
+                category,
+            });
+        }
+    };
+
+    for i in 1..elevations.len() {
+        if elevations[i] >= elevations[high] {
+            high = i;
+        } else if elevations[high] - elevations[i] > CLIMB_DIP_TOLERANCE_METERS {
+            close(low, high);
+            low = i;
+            high = i;
+        } else if high == low && elevations[i] < elevations[low] {
+            // Toujours en descente : le point bas recule
+            low = i;
+            high = i;
+        }
+    }
+    close(low, high);
+    climbs
+}
+
+/// Au plus [`MAX_PROFILE_POINTS`] points régulièrement répartis, le dernier toujours inclus.
+fn sample(distances: &[f64], elevations: &[f64]) -> Vec<ProfilePoint> {
+    let step = distances.len().div_ceil(MAX_PROFILE_POINTS).max(1);
+    let point = |i: usize| ProfilePoint { distance_meters: distances[i], elevation_meters: elevations[i] };
+
+    let mut points: Vec<ProfilePoint> = (0..distances.len()).step_by(step).map(point).collect();
+    let last = distances.len() - 1;
+    if !last.is_multiple_of(step) {
+        if points.len() == MAX_PROFILE_POINTS {
+            points.pop();
+        }
+        points.push(point(last));
+    }
+    points
+}
@@ -0,0 +1,257 @@
+use serde_json::Value;
+
+/// Rayon moyen de la Terre (IUGG), en mètres.
+pub const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+/// Au-delà, le tracé est refusé (un point par seconde pendant près de 14 h).
+pub const MAX_POSITIONS: usize = 50_000;
+
+/// Position GeoJSON : longitude, latitude et altitude optionnelle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub lon: f64,
+    pub lat: f64,
+    pub ele: Option<f64>,
+}
+
+/// Valeurs dérivées du tracé, calculées par le serveur et stockées sur `routes`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteMetrics {
+    pub distance_meters: f64,
+    pub min_lat: f64,
+    pub min_lon: f64,
+    pub max_lat: f64,
+    pub max_lon: f64,
+    pub start: Position,
+    pub end: Position,
+    /// `None` si au moins une position n'a pas d'altitude.
+    pub elevation_gain_meters: Option<f64>,
+    pub elevation_loss_meters: Option<f64>,
+}
+
+/// Lit `path_data` : LineString GeoJSON, Feature contenant une LineString,
+/// ou simple tableau de positions `[[lon, lat(, ele)], ...]`.
+pub fn parse_path(value: &Value) -> Result<Vec<Position>, String> {
+    let coordinates = match value {
+        Value::Array(_) => value,
+        Value::Object(object) => match object.get("type").and_then(Value::as_str) {
+            Some("LineString") => object.get("coordinates").ok_or("LineString without coordinates")?,
+            Some("Feature") => {
+                let geometry = object.get("geometry").filter(|g| g.is_object()).ok_or("Feature without geometry")?;
+                if geometry.get("type").and_then(Value::as_str) != Some("LineString") {
+                    return Err("Feature geometry must be a LineString".to_string());
+                }
+                geometry.get("coordinates").ok_or("LineString without coordinates")?
+            }
+            Some(other) => return Err(format!("unsupported geometry type '{}', expected LineString", other)),
+            None => return Err("must be a GeoJSON LineString, Feature or coordinate array".to_string()),
+        },
+        _ => return Err("must be a GeoJSON LineString, Feature or coordinate array".to_string()),
+    };
+
+    let coordinates = coordinates.as_array().ok_or("coordinates must be an array")?;
+    if coordinates.len() < 2 {
+        return Err("a LineString needs at least 2 positions".to_string());
+    }
+    if coordinates.len() > MAX_POSITIONS {
+        return Err(format!("at most {} positions are allowed", MAX_POSITIONS));
+    }
+
+    let positions = coordinates
+        .iter()
+        .enumerate()
+        .map(|(i, c)| parse_position(c).map_err(|e| format!("position {}: {}", i, e)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if path_length(&positions) == 0.0 {
+        return Err("the path has zero length".to_string());
+    }
+    Ok(positions)
+}
+
+fn parse_position(value: &Value) -> Result<Position, String> {
+    let items = value.as_array().ok_or("must be an array [lon, lat] or [lon, lat, ele]")?;
+    if items.len() < 2 {
+        return Err("must be an array [lon, lat] or [lon, lat, ele]".to_string());
+    }
+
+    let number = |v: &Value| v.as_f64().filter(|n| n.is_finite());
+    let lon = number(&items[0]).ok_or("longitude must be a number")?;
+    let lat = number(&items[1]).ok_or("latitude must be a number")?;
+    let ele = match items.get(2) {
+        None | Some(Value::Null) => None,
+        Some(v) => Some(number(v).ok_or("elevation must be a number")?),
+    };
+
+    if !(-180.0..=180.0).contains(&lon) {
+        return Err("longitude must be between -180 and 180".to_string());
+    }
+    if !(-90.0..=90.0).contains(&lat) {
+        return Err("latitude must be between -90 and 90".to_string());
+    }
+    Ok(Position { lon, lat, ele })
+}
+
+/// Distance orthodromique (formule de haversine), précise à ~0,5 % près.
+pub fn haversine_distance(a: &Position, b: &Position) -> f64 {
+    let (lat1, lat2) = (a.lat.to_radians(), b.lat.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (b.lon - a.lon).to_radians();
+
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * h.sqrt().min(1.0).asin()
+}
+
+pub fn path_length(positions: &[Position]) -> f64 {
+    positions.windows(2).map(|w| haversine_distance(&w[0], &w[1])).sum()
+}
+
+/// `positions` doit provenir de [`parse_path`] (au moins deux positions).
+pub fn compute_metrics(positions: &[Position]) -> RouteMetrics {
+    let start = positions[0];
+    let end = positions[positions.len() - 1];
+
+    let (mut min_lat, mut min_lon, mut max_lat, mut max_lon) = (start.lat, start.lon, start.lat, start.lon);
+    for p in positions {
+        min_lat = min_lat.min(p.lat);
+        min_lon = min_lon.min(p.lon);
+        max_lat = max_lat.max(p.lat);
+        max_lon = max_lon.max(p.lon);
+    }
+
+    let elevations: Option<Vec<f64>> = positions.iter().map(|p| p.ele).collect();
+    let (elevation_gain_meters, elevation_loss_meters) = match elevations {
+        Some(elevations) => {
+            let (gain, loss) = elevations.windows(2).fold((0.0, 0.0), |(gain, loss), w| {
+                let delta = w[1] - w[0];
+                if delta > 0.0 { (gain + delta, loss) } else { (gain, loss - delta) }
+            });
+            (Some(gain), Some(loss))
+        }
+        None => (None, None),
+    };
+
+    RouteMetrics {
+        distance_meters: path_length(positions),
+        min_lat,
+        min_lon,
+        max_lat,
+        max_lon,
+        start,
+        end,
+        elevation_gain_meters,
+        elevation_loss_meters,
+    }
+}
+
+/// Douglas–Peucker : retire les positions à moins de `tolerance_m` mètres du tracé simplifié.
+/// Les extrémités sont toujours conservées, avec leur altitude.
+pub fn simplify(positions: &[Position], tolerance_m: f64) -> Vec<Position> {
+    if positions.len() < 3 {
+        return positions.to_vec();
+    }
+
+    let mean_lat = positions.iter().map(|p| p.lat).sum::<f64>() / positions.len() as f64;
+    let projection = LocalProjection::new(mean_lat);
+    let points: Vec<(f64, f64)> = positions.iter().map(|p| projection.project(p)).collect();
+
+    let mut keep = vec![false; positions.len()];
+    keep[0] = true;
+    keep[positions.len() - 1] = true;
+
+    // Pile explicite : un tracé de 50 000 points dépasserait la pile en récursif
+    let mut ranges = vec![(0, positions.len() - 1)];
+    while let Some((first, last)) = ranges.pop() {
+        let (farthest, distance) = (first + 1..last)
+            .map(|i| (i, segment_distance(points[i], points[first], points[last])))
+            .fold((first, 0.0), |best, current| if current.1 > best.1 { current } else { best });
+
+        if distance > tolerance_m {
+            keep[farthest] = true;
+            ranges.push((first, farthest));
+            ranges.push((farthest, last));
+        }
+    }
+
+    positions.iter().zip(keep).filter(|(_, k)| *k).map(|(p, _)| *p).collect()
+}
+
+/// Projection équirectangulaire locale, en mètres : suffisante à l'échelle d'un parcours.
+#[derive(Debug, Clone, Copy)]
+pub struct LocalProjection {
+    scale_x: f64,
+}
+
+impl LocalProjection {
+    pub fn new(reference_lat: f64) -> LocalProjection {
+        LocalProjection { scale_x: EARTH_RADIUS_METERS * reference_lat.to_radians().cos() }
+    }
+
+    pub fn project(&self, p: &Position) -> (f64, f64) {
+        (p.lon.to_radians() * self.scale_x, p.lat.to_radians() * EARTH_RADIUS_METERS)
+    }
+}
+
+/// Distance (plane) du point `p` au segment `[a, b]`, points issus de [`LocalProjection`].
+pub fn segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_squared).clamp(0.0, 1.0)
+    };
+    ((p.0 - a.0 - t * dx).powi(2) + (p.1 - a.1 - t * dy).powi(2)).sqrt()
+}
+
+/// Portion du tracé entre deux distances depuis le départ, extrémités interpolées
+/// (altitude comprise si les deux positions encadrantes en ont une).
+pub fn slice_path(positions: &[Position], start_m: f64, end_m: f64) -> Vec<Position> {
+    let interpolate = |a: &Position, b: &Position, t: f64| Position {
+        lon: a.lon + (b.lon - a.lon) * t,
+        lat: a.lat + (b.lat - a.lat) * t,
+        ele: a.ele.zip(b.ele).map(|(ea, eb)| ea + (eb - ea) * t),
+    };
+
+    let mut slice = Vec::new();
+    let mut travelled = 0.0;
+    for w in positions.windows(2) {
+        let length = haversine_distance(&w[0], &w[1]);
+        let (from, to) = (travelled, travelled + length);
+        travelled = to;
+        if to < start_m || length == 0.0 {
+            continue;
+        }
+        if slice.is_empty() {
+            slice.push(interpolate(&w[0], &w[1], ((start_m - from) / length).clamp(0.0, 1.0)));
+        }
+        if to >= end_m {
+            slice.push(interpolate(&w[0], &w[1], ((end_m - from) / length).clamp(0.0, 1.0)));
+            break;
+        }
+        slice.push(w[1]);
+    }
+    slice
+}
+
+/// Encoded polyline (algorithme de Google, précision 1e-5) ; l'altitude n'y figure pas.
+pub fn encode_polyline(positions: &[Position]) -> String {
+    let mut out = String::new();
+    let (mut prev_lat, mut prev_lon) = (0i64, 0i64);
+    for p in positions {
+        let lat = (p.lat * 1e5).round() as i64;
+        let lon = (p.lon * 1e5).round() as i64;
+        encode_polyline_value(lat - prev_lat, &mut out);
+        encode_polyline_value(lon - prev_lon, &mut out);
+        (prev_lat, prev_lon) = (lat, lon);
+    }
+    out
+}
+
+fn encode_polyline_value(value: i64, out: &mut String) {
+    let mut value = if value < 0 { !(value << 1) } else { value << 1 };
+    while value >= 0x20 {
+        out.push(char::from((0x20 | (value & 0x1f)) as u8 + 63));
+        value >>= 5;
+    }
+    out.push(char::from(value as u8 + 63));
+}
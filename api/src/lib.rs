@@ -2,3 +2,20 @@ pub mod db;
 pub mod models;
 pub mod routes;
 pub mod middleware;
+pub mod sessions;
+pub mod mailer;
+pub mod user_tokens;
+pub mod totp;
+pub mod login_guard;
+pub mod authz;
+pub mod validation;
+pub mod pagination;
+pub mod geometry;
+pub mod route_formats;
+pub mod spatial;
+pub mod elevation;
+pub mod segments;
+pub mod thumbnail;
+pub mod verification;
+pub mod score_metrics;
+pub mod anti_cheat;
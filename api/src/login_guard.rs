@@ -0,0 +1,133 @@
+//! Protection du login contre le brute-force : échecs comptés par compte (email normalisé)
+//! et par IP dans `login_attempts`, délais progressifs et verrouillage temporaire.
+
+use axum::http::HeaderMap;
+use bcrypt::{hash, verify, DEFAULT_COST};
+use std::{net::SocketAddr, sync::LazyLock, time::Duration};
+
+use crate::db::DbPool;
+
+/// Fenêtre glissante sur laquelle les échecs sont comptés (et durée du verrouillage).
+pub const ATTEMPT_WINDOW_MINUTES: i32 = 15;
+pub const MAX_FAILURES_PER_ACCOUNT: i64 = 5;
+/// Plus élevé que par compte : plusieurs utilisateurs peuvent partager une IP (NAT, campus).
+pub const MAX_FAILURES_PER_IP: i64 = 20;
+
+const BASE_DELAY_MS: u64 = 250;
+const MAX_DELAY_MS: u64 = 4_000;
+
+/// Hash d'un mot de passe quelconque, vérifié quand l'email est inconnu pour que la réponse
+/// prenne le même temps qu'avec un vrai compte.
+static DUMMY_HASH: LazyLock<String> =
+    LazyLock::new(|| hash("rmce-dummy-password", DEFAULT_COST).expect("bcrypt hash"));
+
+#[derive(Clone, Copy)]
+pub enum AttemptOutcome {
+    Failure,
+    Success,
+    /// Déverrouillage via le lien reçu par email : remet le compteur du compte à zéro.
+    Unlock,
+}
+
+impl AttemptOutcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AttemptOutcome::Failure => "failure",
+            AttemptOutcome::Success => "success",
+            AttemptOutcome::Unlock => "unlock",
+        }
+    }
+}
+
+pub struct FailureCounts {
+    /// Échecs depuis la dernière connexion réussie ou le dernier déverrouillage.
+    pub account: i64,
+    pub ip: i64,
+}
+
+impl FailureCounts {
+    pub fn account_locked(&self) -> bool {
+        self.account >= MAX_FAILURES_PER_ACCOUNT
+    }
+
+    pub fn ip_blocked(&self) -> bool {
+        self.ip >= MAX_FAILURES_PER_IP
+    }
+}
+
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// IP du client. `X-Forwarded-For` n'est lu que derrière un reverse proxy de confiance
+/// (`TRUST_PROXY_HEADERS=true`), sinon n'importe quel client pourrait le falsifier. On prend
+/// la dernière entrée, celle ajoutée par notre proxy : les précédentes viennent du client.
+pub fn client_ip(headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<String> {
+    let trust_proxy = std::env::var("TRUST_PROXY_HEADERS").is_ok_and(|v| v == "true");
+    if trust_proxy
+        && let Some(forwarded) = headers.get("x-forwarded-for").and_then(|v| v.to_str().ok())
+        && let Some(last) = forwarded.rsplit(',').next().map(str::trim).filter(|ip| !ip.is_empty())
+    {
+        return Some(last.to_string());
+    }
+    peer.map(|addr| addr.ip().to_string())
+}
+
+pub async fn failure_counts(pool: &DbPool, email: &str, ip: Option<&str>) -> Result<FailureCounts, sqlx::Error> {
+    let account = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM login_attempts
+         WHERE email = $1
+           AND outcome = 'failure'
+           AND created_at > NOW() - make_interval(mins => $2)
+           AND created_at > COALESCE(
+               (SELECT MAX(created_at) FROM login_attempts WHERE email = $1 AND outcome <> 'failure'),
+               '-infinity'
+           )"
+    )
+    .bind(email)
+    .bind(ATTEMPT_WINDOW_MINUTES)
+    .fetch_one(pool)
+    .await?;
+
+    let ip = match ip {
+        Some(ip) => {
+            sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM login_attempts
+                 WHERE ip = $1
+                   AND outcome = 'failure'
+                   AND created_at > NOW() - make_interval(mins => $2)"
+            )
+            .bind(ip)
+            .bind(ATTEMPT_WINDOW_MINUTES)
+            .fetch_one(pool)
+            .await?
+        }
+        None => 0,
+    };
+
+    Ok(FailureCounts { account, ip })
+}
+
+pub async fn record(pool: &DbPool, email: &str, ip: Option<&str>, outcome: AttemptOutcome) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO login_attempts (email, ip, outcome) VALUES ($1, $2, $3)")
+        .bind(email)
+        .bind(ip)
+        .bind(outcome.as_str())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Délai imposé après le n-ième échec consécutif : 0, 0, 250 ms, 500 ms, 1 s... plafonné à 4 s.
+pub fn delay_after(failures: i64) -> Duration {
+    if failures < 2 {
+        return Duration::ZERO;
+    }
+    let exponent = (failures - 2).min(16) as u32;
+    Duration::from_millis((BASE_DELAY_MS << exponent).min(MAX_DELAY_MS))
+}
+
+/// Vérification bcrypt sans compte associé, pour une réponse à temps constant.
+pub fn dummy_verify(password: &str) {
+    let _ = verify(password, &DUMMY_HASH);
+}
@@ -0,0 +1,143 @@
+use std::{fmt, io::Write, path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use lettre::{
+    message::Mailbox,
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use serde::Serialize;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug)]
+pub struct MailerError(pub String);
+
+impl fmt::Display for MailerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Mailer error: {}", self.0)
+    }
+}
+
+impl std::error::Error for MailerError {}
+
+/// Envoi d'emails transactionnels (vérification d'adresse, réinitialisation de mot de passe).
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> Result<(), MailerError>;
+}
+
+pub type SharedMailer = Arc<dyn Mailer>;
+
+/// Envoi réel via un serveur SMTP (STARTTLS).
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(
+        host: &str,
+        port: u16,
+        credentials: Option<(String, String)>,
+        from: &str,
+    ) -> Result<Self, MailerError> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .map_err(|e| MailerError(e.to_string()))?
+            .port(port);
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(SmtpMailer {
+            transport: builder.build(),
+            from: from.parse().map_err(|e: lettre::address::AddressError| MailerError(e.to_string()))?,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: Email) -> Result<(), MailerError> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(email.to.parse().map_err(|e: lettre::address::AddressError| MailerError(e.to_string()))?)
+            .subject(email.subject)
+            .body(email.body)
+            .map_err(|e| MailerError(e.to_string()))?;
+
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| MailerError(e.to_string()))?;
+
+        info!("Email envoyé via SMTP à {}", email.to);
+        Ok(())
+    }
+}
+
+/// N'envoie rien : journalise les emails et, si `outbox` est défini, les ajoute
+/// (une ligne JSON par email) à ce fichier. Pour le développement local et les tests.
+pub struct LogMailer {
+    outbox: Option<PathBuf>,
+}
+
+impl LogMailer {
+    pub fn new(outbox: Option<PathBuf>) -> Self {
+        LogMailer { outbox }
+    }
+}
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: Email) -> Result<(), MailerError> {
+        info!("[mail] À: {} | Sujet: {}\n{}", email.to, email.subject, email.body);
+
+        if let Some(path) = &self.outbox {
+            let line = serde_json::to_string(&email).map_err(|e| MailerError(e.to_string()))?;
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| MailerError(e.to_string()))?;
+            writeln!(file, "{}", line).map_err(|e| MailerError(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Sélectionne l'implémentation selon `MAILER` (`smtp` ou `log`, par défaut `log`).
+pub fn from_env() -> SharedMailer {
+    let kind = std::env::var("MAILER").unwrap_or_else(|_| "log".to_string());
+
+    if kind == "smtp" {
+        let host = std::env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".to_string());
+        let port = std::env::var("SMTP_PORT")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(587);
+        let credentials = std::env::var("SMTP_USERNAME")
+            .ok()
+            .zip(std::env::var("SMTP_PASSWORD").ok());
+        let from = std::env::var("MAIL_FROM").unwrap_or_else(|_| "no-reply@rmce.local".to_string());
+
+        match SmtpMailer::new(&host, port, credentials, &from) {
+            Ok(mailer) => {
+                info!("Mailer SMTP configuré ({}:{})", host, port);
+                return Arc::new(mailer);
+            }
+            Err(e) => error!("Configuration SMTP invalide, repli sur le mailer de log: {}", e),
+        }
+    } else if kind != "log" {
+        warn!("MAILER inconnu '{}', utilisation du mailer de log", kind);
+    }
+
+    let outbox = std::env::var("MAIL_OUTBOX").ok().map(PathBuf::from);
+    Arc::new(LogMailer::new(outbox))
+}
@@ -1,4 +1,5 @@
 use dotenvy::dotenv;
+use std::net::SocketAddr;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 use tracing::{info, error};
 
@@ -48,7 +49,8 @@ async fn main() -> Result<(), sqlx::Error> {
     })?;
 
     info!("Application prête à recevoir des requêtes");
-    axum::serve(listener, app).await.map_err(|e| {
+    // L'adresse du client sert à la limitation des tentatives de connexion par IP
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.map_err(|e| {
         error!("Erreur du serveur: {}", e);
         sqlx::Error::PoolClosed
     })?;
@@ -1,17 +1,19 @@
 use axum::{
     middleware::Next,
-    http::Request,
+    http::{header::CONTENT_LENGTH, Request},
     body::Body,
     response::Response,
 };
-use tracing::error;
-use shared::jwt::verify_jwt;
+use tracing::{error, warn};
+use shared::{errors::{AppError, ErrorBody}, jwt::verify_jwt};
+
+use crate::{db::DbPool, sessions::is_session_active};
 
 /// Middleware pour extraire et vérifier le JWT token
 pub async fn auth_middleware(
     mut request: Request<Body>,
     next: Next,
-) -> Result<Response, axum::http::StatusCode> {
+) -> Result<Response, AppError> {
     let token = request
         .headers()
         .get("Authorization")
@@ -19,18 +21,63 @@ pub async fn auth_middleware(
         .and_then(|h| h.strip_prefix("Bearer "))
         .ok_or_else(|| {
             error!("Missing or invalid Authorization header");
-            axum::http::StatusCode::UNAUTHORIZED
+            AppError::Unauthorized("Missing or invalid Authorization header".to_string())
         })?;
 
     let claims = verify_jwt(token)
         .map_err(|e| {
             error!("Invalid JWT token: {}", e);
-            axum::http::StatusCode::UNAUTHORIZED
+            AppError::Unauthorized("Invalid or expired token".to_string())
+        })?;
+
+    // Vérifier que la session n'a pas été révoquée (logout, vol d'appareil...)
+    let pool = request
+        .extensions()
+        .get::<DbPool>()
+        .cloned()
+        .ok_or_else(|| {
+            error!("DbPool absent des extensions de la requête");
+            AppError::Internal("DbPool missing from request extensions".to_string())
+        })?;
+
+    let active = is_session_active(&pool, &claims.jti, claims.user_id)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la vérification de la session {}: {}", claims.jti, e);
+            AppError::from(e)
         })?;
 
+    if !active {
+        warn!("Session {} révoquée ou expirée pour l'utilisateur {}", claims.jti, claims.user_id);
+        return Err(AppError::Unauthorized("Session revoked or expired".to_string()));
+    }
+
     // Insérer les claims dans les extensions pour les utiliser dans les routes
     request.extensions_mut().insert(claims);
 
     Ok(next.run(request).await)
 }
 
+/// Complète le corps des réponses `AppError` avec l'identifiant de la requête (`x-request-id`),
+/// que le client peut communiquer au support pour retrouver les logs.
+pub async fn error_request_id_middleware(
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string);
+
+    let response = next.run(request).await;
+    let Some(mut body) = response.extensions().get::<ErrorBody>().cloned() else {
+        return response;
+    };
+    body.request_id = request_id;
+
+    let (mut parts, _) = response.into_parts();
+    parts.headers.remove(CONTENT_LENGTH);
+    let bytes = serde_json::to_vec(&body).unwrap_or_default();
+    Response::from_parts(parts, Body::from(bytes))
+}
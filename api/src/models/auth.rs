@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::validation::{Validate, ValidationErrors};
+
 #[derive(Serialize, Deserialize)]
 pub struct Login {
     pub email: String,
@@ -11,4 +13,119 @@ pub struct Register {
     pub username: String,
     pub email: String,
     pub password: String,
-}
\ No newline at end of file
+}
+#[derive(Serialize, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ForgotPassword {
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ResetPassword {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VerifyEmail {
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UnlockAccount {
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TotpCode {
+    pub code: String,
+}
+
+/// Second étape de connexion : `code` peut être un code TOTP ou un code de secours.
+#[derive(Serialize, Deserialize)]
+pub struct MfaVerify {
+    pub mfa_token: String,
+    pub code: String,
+}
+
+/// Pas de contrôle de robustesse au login : seuls des mots de passe déjà acceptés peuvent correspondre.
+impl Validate for Login {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.length("email", &self.email, 1, 254);
+        errors.length("password", &self.password, 1, 1024);
+        errors.into_result()
+    }
+}
+
+impl Validate for Register {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.username("username", &self.username);
+        errors.email("email", &self.email);
+        errors.password("password", &self.password);
+        errors.into_result()
+    }
+}
+
+impl Validate for RefreshRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.length("refresh_token", &self.refresh_token, 1, 256);
+        errors.into_result()
+    }
+}
+
+impl Validate for ForgotPassword {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.email("email", &self.email);
+        errors.into_result()
+    }
+}
+
+impl Validate for ResetPassword {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.length("token", &self.token, 1, 256);
+        errors.password("new_password", &self.new_password);
+        errors.into_result()
+    }
+}
+
+impl Validate for VerifyEmail {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.length("token", &self.token, 1, 256);
+        errors.into_result()
+    }
+}
+
+impl Validate for UnlockAccount {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.length("token", &self.token, 1, 256);
+        errors.into_result()
+    }
+}
+
+impl Validate for TotpCode {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.length("code", &self.code, 1, 32);
+        errors.into_result()
+    }
+}
+
+impl Validate for MfaVerify {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.length("mfa_token", &self.mfa_token, 1, 256);
+        errors.length("code", &self.code, 1, 32);
+        errors.into_result()
+    }
+}
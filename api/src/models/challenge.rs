@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::{
+    models::score::{MAX_TIME_SECONDS, MIN_TIME_SECONDS},
+    validation::{Validate, ValidationErrors},
+};
+
 #[derive(Serialize, Deserialize, FromRow)]
 pub struct Challenge {
     pub id: i32,
@@ -40,3 +45,20 @@ where
     }
 }
 
+impl Validate for CreateChallenge {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.range("route_id", self.route_id, 1, i32::MAX);
+        errors.optional_range("challenged_id", self.challenged_id, 1, i32::MAX);
+        errors.into_result()
+    }
+}
+
+impl Validate for UpdateChallenge {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.optional_range("challenger_time", self.challenger_time, MIN_TIME_SECONDS, MAX_TIME_SECONDS);
+        errors.optional_range("challenged_time", self.challenged_time, MIN_TIME_SECONDS, MAX_TIME_SECONDS);
+        errors.into_result()
+    }
+}
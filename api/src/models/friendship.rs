@@ -27,6 +27,8 @@ pub struct PendingRequest {
     pub username: String,
     pub email: String,
     pub status: String,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub created_at: Option<chrono::NaiveDateTime>,
 }
 
 fn serialize_datetime<S>(date: &Option<chrono::NaiveDateTime>, serializer: S) -> Result<S::Ok, S::Error>
@@ -6,3 +6,6 @@ pub mod score;
 pub mod friendship;
 pub mod challenge;
 pub mod sensor_data;
+pub mod segment;
+pub mod review;
+pub mod score_flag;
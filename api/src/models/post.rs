@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::validation::{Validate, ValidationErrors};
+
 #[derive(Serialize, Deserialize, FromRow)]
 pub struct Post {
     pub id: i32,
@@ -9,17 +11,36 @@ pub struct Post {
     pub body: String,
 }
 
+/// L'auteur est l'utilisateur connecté : un éventuel `user_id` envoyé par le client est ignoré.
 #[derive(Serialize, Deserialize)]
 pub struct CreatePost {
     pub title: String,
     pub body: String,
-    pub user_id: Option<i32>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct UpdatePost {
     pub title: String,
     pub body: String,
-    pub user_id: Option<i32>,
 }
 
+const TITLE_MAX_CHARS: usize = 200;
+const BODY_MAX_CHARS: usize = 10_000;
+
+impl Validate for CreatePost {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.length("title", self.title.trim(), 1, TITLE_MAX_CHARS);
+        errors.length("body", self.body.trim(), 1, BODY_MAX_CHARS);
+        errors.into_result()
+    }
+}
+
+impl Validate for UpdatePost {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.length("title", self.title.trim(), 1, TITLE_MAX_CHARS);
+        errors.length("body", self.body.trim(), 1, BODY_MAX_CHARS);
+        errors.into_result()
+    }
+}
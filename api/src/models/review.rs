@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::validation::{Validate, ValidationErrors};
+
+/// Colonnes lues pour construire un [`RouteReview`], depuis `route_reviews` joint à `users`.
+pub const REVIEW_COLUMNS: &str = "id, route_id, user_id, username, rating, review, created_at, updated_at";
+
+pub const REVIEW_MAX_CHARS: usize = 2_000;
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct RouteReview {
+    pub id: i32,
+    pub route_id: i32,
+    pub user_id: i32,
+    pub username: String,
+    /// De 1 à 5 étoiles.
+    pub rating: i32,
+    /// `None` pour une note sans commentaire.
+    pub review: Option<String>,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub updated_at: Option<chrono::NaiveDateTime>,
+}
+
+fn serialize_datetime<S>(date: &Option<chrono::NaiveDateTime>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match date {
+        Some(d) => serializer.serialize_str(&d.to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Note de l'utilisateur connecté ; la renvoyer remplace la précédente.
+#[derive(Serialize, Deserialize)]
+pub struct UpsertReview {
+    pub rating: i32,
+    pub review: Option<String>,
+}
+
+impl Validate for UpsertReview {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.range("rating", self.rating, 1, 5);
+        errors.optional_length("review", self.review.as_deref(), 0, REVIEW_MAX_CHARS);
+        errors.into_result()
+    }
+}
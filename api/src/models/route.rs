@@ -2,21 +2,77 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use serde_json::Value;
 
+use crate::{
+    geometry,
+    validation::{Validate, ValidationErrors},
+};
+
+/// Colonnes lues pour construire un [`Route`].
+pub const ROUTE_COLUMNS: &str = "id, user_id, name, description, is_public, visibility, path_data, distance_meters, \
+    min_lat, min_lon, max_lat, max_lon, start_lat, start_lon, end_lat, end_lon, \
+    elevation_gain_meters, elevation_loss_meters, current_version, \
+    forked_from, forked_from_version, forked_from_user_id, \
+    tags, surface, difficulty, is_loop, is_out_and_back, rating_average, rating_count, review_count, \
+    created_at, updated_at";
+
+/// Colonnes lues pour construire un [`RouteVersion`].
+pub const ROUTE_VERSION_COLUMNS: &str = "id, route_id, version, path_data, distance_meters, \
+    elevation_gain_meters, elevation_loss_meters, created_by, created_at";
+
 #[derive(Serialize, Deserialize, FromRow)]
 pub struct Route {
     pub id: i32,
     pub user_id: i32,
     pub name: String,
     pub description: Option<String>,
+    /// Vaut `visibility == "public"`, conservé pour les clients existants.
     pub is_public: bool,
+    /// Une des valeurs de [`VISIBILITIES`].
+    pub visibility: String,
     pub path_data: Value,
+    /// Calculée par le serveur à partir de `path_data`, comme les champs suivants.
     pub distance_meters: Option<f32>,
+    pub min_lat: Option<f64>,
+    pub min_lon: Option<f64>,
+    pub max_lat: Option<f64>,
+    pub max_lon: Option<f64>,
+    pub start_lat: Option<f64>,
+    pub start_lon: Option<f64>,
+    pub end_lat: Option<f64>,
+    pub end_lon: Option<f64>,
+    /// Absents si le tracé n'a pas d'altitude sur chaque position.
+    pub elevation_gain_meters: Option<f32>,
+    pub elevation_loss_meters: Option<f32>,
+    /// Révision courante du tracé (voir `route_versions`), incrémentée à chaque nouveau tracé.
+    pub current_version: i32,
+    /// Parcours copié par `POST /routes/{id}/fork`, `None` s'il a été supprimé depuis.
+    pub forked_from: Option<i32>,
+    /// Révision du parcours source au moment de la copie.
+    pub forked_from_version: Option<i32>,
+    /// Auteur du parcours source, conservé pour l'attribution.
+    pub forked_from_user_id: Option<i32>,
+    pub tags: Vec<String>,
+    /// Une des valeurs de [`SURFACES`].
+    pub surface: Option<String>,
+    /// Une des valeurs de [`DIFFICULTIES`].
+    pub difficulty: Option<String>,
+    pub is_loop: bool,
+    pub is_out_and_back: bool,
+    /// Moyenne des notes (1 à 5), `None` tant que le parcours n'a pas été noté.
+    pub rating_average: Option<f32>,
+    pub rating_count: i32,
+    /// Notes accompagnées d'un commentaire.
+    pub review_count: i32,
     #[serde(serialize_with = "serialize_datetime")]
     #[sqlx(rename = "created_at")]
     pub created_at: Option<chrono::NaiveDateTime>,
     #[serde(serialize_with = "serialize_datetime")]
     #[sqlx(rename = "updated_at")]
     pub updated_at: Option<chrono::NaiveDateTime>,
+    /// Tracé encodé, présent seulement avec `?geometry=polyline` (`path_data` vaut alors `null`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[sqlx(skip)]
+    pub polyline: Option<String>,
 }
 
 fn serialize_datetime<S>(date: &Option<chrono::NaiveDateTime>, serializer: S) -> Result<S::Ok, S::Error>
@@ -29,13 +85,119 @@ where
     }
 }
 
+/// Révision immuable du tracé d'un parcours.
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct RouteVersion {
+    pub id: i32,
+    pub route_id: i32,
+    pub version: i32,
+    pub path_data: Value,
+    pub distance_meters: Option<f32>,
+    pub elevation_gain_meters: Option<f32>,
+    pub elevation_loss_meters: Option<f32>,
+    /// `None` si l'auteur a supprimé son compte.
+    pub created_by: Option<i32>,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Entrée de `GET /routes/{id}/versions`, sans le tracé.
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct RouteVersionSummary {
+    pub version: i32,
+    pub distance_meters: Option<f32>,
+    pub elevation_gain_meters: Option<f32>,
+    pub elevation_loss_meters: Option<f32>,
+    pub created_by: Option<i32>,
+    /// Nombre de scores enregistrés sur cette révision.
+    pub score_count: i64,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Lien de partage d'un parcours `unlisted`. Le token n'est renvoyé qu'à la création.
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct RouteShareLink {
+    pub id: i32,
+    pub route_id: i32,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+    /// Un lien révoqué ne donne plus accès au parcours.
+    #[serde(serialize_with = "serialize_datetime")]
+    pub revoked_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct CreatedShareLink {
+    #[serde(flatten)]
+    pub link: RouteShareLink,
+    pub token: String,
+    /// Chemin de `GET /shared/routes/{token}`, accessible sans connexion.
+    pub path: String,
+}
+
+/// Parcours renvoyé par une recherche spatiale.
+#[derive(Serialize, FromRow)]
+pub struct NearbyRoute {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub route: Route,
+    /// Distance entre le point de recherche (ou le centre de l'emprise) et le départ du parcours.
+    pub distance_from_meters: f64,
+}
+
+/// Parcours renvoyé par `GET /routes/search`.
+#[derive(Serialize, FromRow)]
+pub struct RouteSearchHit {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub route: Route,
+    /// Pertinence de la recherche plein texte, 0 sans `q`.
+    pub rank: f32,
+}
+
+#[derive(Serialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+/// Répartition des parcours trouvés, calculée sur tous les résultats et pas seulement la page.
+#[derive(Serialize, Default)]
+pub struct SearchFacets {
+    pub tags: Vec<FacetCount>,
+    pub surface: Vec<FacetCount>,
+    pub difficulty: Vec<FacetCount>,
+}
+
+#[derive(Serialize)]
+pub struct RouteSearchResults {
+    pub total: i64,
+    pub items: Vec<RouteSearchHit>,
+    pub facets: SearchFacets,
+}
+
+/// `path_data` : LineString GeoJSON, Feature ou tableau de positions `[lon, lat(, ele)]`.
+/// La distance est calculée par le serveur ; un `distance_meters` envoyé par le client est ignoré.
 #[derive(Serialize, Deserialize)]
 pub struct CreateRoute {
     pub name: String,
     pub description: Option<String>,
-    pub is_public: bool,
+    /// Ancienne forme de `visibility` : `true` pour `public`, `false` pour `private`.
+    #[serde(default)]
+    pub is_public: Option<bool>,
+    /// `private` par défaut.
+    pub visibility: Option<String>,
     pub path_data: Value,
-    pub distance_meters: Option<f32>,
+    /// Normalisés en minuscules, sans doublon.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub surface: Option<String>,
+    pub difficulty: Option<String>,
+    #[serde(default)]
+    pub is_loop: bool,
+    #[serde(default)]
+    pub is_out_and_back: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -43,7 +205,119 @@ pub struct UpdateRoute {
     pub name: Option<String>,
     pub description: Option<String>,
     pub is_public: Option<bool>,
+    pub visibility: Option<String>,
     pub path_data: Option<Value>,
-    pub distance_meters: Option<f32>,
+    /// Remplace toutes les étiquettes du parcours.
+    pub tags: Option<Vec<String>>,
+    pub surface: Option<String>,
+    pub difficulty: Option<String>,
+    pub is_loop: Option<bool>,
+    pub is_out_and_back: Option<bool>,
+}
+
+pub const NAME_MAX_CHARS: usize = 100;
+pub const DESCRIPTION_MAX_CHARS: usize = 2_000;
+pub const MAX_TAGS: usize = 10;
+pub const TAG_MAX_CHARS: usize = 30;
+pub const SURFACES: &[&str] = &["asphalt", "gravel", "dirt", "trail", "track", "sand", "mixed"];
+pub const DIFFICULTIES: &[&str] = &["easy", "moderate", "hard", "expert"];
+pub const VISIBILITIES: &[&str] = &["private", "friends", "unlisted", "public"];
+
+/// Visibilité demandée : `visibility`, à défaut l'ancien booléen `is_public`.
+fn requested_visibility(visibility: Option<&str>, is_public: Option<bool>) -> Option<String> {
+    visibility
+        .map(str::to_string)
+        .or_else(|| is_public.map(|public| if public { "public" } else { "private" }.to_string()))
+}
+
+fn check_visibility(errors: &mut ValidationErrors, visibility: Option<&str>, is_public: Option<bool>) {
+    errors.optional_one_of("visibility", visibility, VISIBILITIES);
+    if let (Some(visibility), Some(is_public)) = (visibility, is_public)
+        && (visibility == "public") != is_public
+    {
+        errors.add("is_public", "conflicts with visibility");
+    }
+}
+
+impl CreateRoute {
+    pub fn visibility(&self) -> String {
+        requested_visibility(self.visibility.as_deref(), self.is_public).unwrap_or_else(|| "private".to_string())
+    }
+}
+
+impl UpdateRoute {
+    /// `None` si la visibilité ne change pas.
+    pub fn visibility(&self) -> Option<String> {
+        requested_visibility(self.visibility.as_deref(), self.is_public)
+    }
+}
+
+/// Forme stockée d'une étiquette : `" Trail "` devient `"trail"`.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
 }
 
+/// Étiquettes normalisées, dans l'ordre, sans doublon.
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags.iter().map(|t| normalize_tag(t)) {
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// Lettres, chiffres et tirets, ex. `trail` ou `bord-de-mer`.
+pub fn check_tag(errors: &mut ValidationErrors, field: &str, tag: &str) {
+    let tag = normalize_tag(tag);
+    errors.length(field, &tag, 1, TAG_MAX_CHARS);
+    if !tag.chars().all(|c| c.is_alphanumeric() || c == '-') {
+        errors.add(field, "may only contain letters, digits and '-'");
+    }
+}
+
+fn check_metadata(errors: &mut ValidationErrors, tags: Option<&[String]>, surface: Option<&str>, difficulty: Option<&str>) {
+    if let Some(tags) = tags {
+        if tags.len() > MAX_TAGS {
+            errors.add("tags", format!("must contain at most {} tags", MAX_TAGS));
+        }
+        for (i, tag) in tags.iter().enumerate() {
+            check_tag(errors, &format!("tags[{}]", i), tag);
+        }
+    }
+    errors.optional_one_of("surface", surface, SURFACES);
+    errors.optional_one_of("difficulty", difficulty, DIFFICULTIES);
+}
+
+fn check_path_data(errors: &mut ValidationErrors, path_data: &Value) {
+    if let Err(message) = geometry::parse_path(path_data) {
+        errors.add("path_data", message);
+    }
+}
+
+impl Validate for CreateRoute {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.length("name", self.name.trim(), 1, NAME_MAX_CHARS);
+        errors.optional_length("description", self.description.as_deref(), 0, DESCRIPTION_MAX_CHARS);
+        check_path_data(&mut errors, &self.path_data);
+        check_metadata(&mut errors, Some(&self.tags), self.surface.as_deref(), self.difficulty.as_deref());
+        check_visibility(&mut errors, self.visibility.as_deref(), self.is_public);
+        errors.into_result()
+    }
+}
+
+impl Validate for UpdateRoute {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.optional_length("name", self.name.as_deref().map(str::trim), 1, NAME_MAX_CHARS);
+        errors.optional_length("description", self.description.as_deref(), 0, DESCRIPTION_MAX_CHARS);
+        if let Some(path_data) = &self.path_data {
+            check_path_data(&mut errors, path_data);
+        }
+        check_metadata(&mut errors, self.tags.as_deref(), self.surface.as_deref(), self.difficulty.as_deref());
+        check_visibility(&mut errors, self.visibility.as_deref(), self.is_public);
+        errors.into_result()
+    }
+}
@@ -1,10 +1,20 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::validation::{Validate, ValidationErrors};
+
+pub const SCORE_COLUMNS: &str = "id, route_id, route_version, user_id, time_seconds, max_speed_kmh, avg_speed_kmh,
+    max_g_force, max_inclination_degrees, max_sound_db, created_at,
+    verification_status, verification_reason, route_coverage, max_deviation_meters, verified_at,
+    server_max_speed_kmh, server_avg_speed_kmh, server_max_g_force, server_max_inclination_degrees,
+    server_max_sound_db, metric_discrepancies, metrics_computed_at";
+
 #[derive(Serialize, Deserialize, FromRow)]
 pub struct Score {
     pub id: i32,
     pub route_id: i32,
+    /// Révision du parcours sur laquelle la course a été faite.
+    pub route_version: i32,
     pub user_id: i32,
     pub time_seconds: f32,
     pub max_speed_kmh: Option<f32>,
@@ -14,6 +24,24 @@ pub struct Score {
     pub max_sound_db: Option<f32>,
     #[serde(serialize_with = "serialize_datetime")]
     pub created_at: Option<chrono::NaiveDateTime>,
+    /// `pending` tant qu'aucune trace GPS n'a été envoyée, puis `verified` ou `rejected`.
+    pub verification_status: String,
+    pub verification_reason: Option<String>,
+    /// Part du tracé longée dans l'ordre, entre 0 et 1.
+    pub route_coverage: Option<f32>,
+    pub max_deviation_meters: Option<f32>,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub verified_at: Option<chrono::NaiveDateTime>,
+    /// Métriques recalculées à partir de `sensor_data` ; celles sans préfixe sont celles du client.
+    pub server_max_speed_kmh: Option<f32>,
+    pub server_avg_speed_kmh: Option<f32>,
+    pub server_max_g_force: Option<f32>,
+    pub server_max_inclination_degrees: Option<f32>,
+    pub server_max_sound_db: Option<f32>,
+    /// Métriques du client trop éloignées de celles du serveur.
+    pub metric_discrepancies: Vec<String>,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub metrics_computed_at: Option<chrono::NaiveDateTime>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -31,6 +59,7 @@ pub struct CreateScore {
 pub struct LeaderboardEntry {
     pub user_id: i32,
     pub username: String,
+    pub route_version: i32,
     pub time_seconds: f32,
     pub max_speed_kmh: Option<f32>,
     #[serde(serialize_with = "serialize_datetime")]
@@ -47,3 +76,30 @@ where
     }
 }
 
+// Bornes physiques larges : on rejette les valeurs absurdes, pas les performances exceptionnelles.
+pub const MIN_TIME_SECONDS: f32 = 0.001;
+/// Une semaine.
+pub const MAX_TIME_SECONDS: f32 = 604_800.0;
+pub const MAX_SPEED_KMH: f32 = 300.0;
+pub const MAX_G_FORCE: f32 = 20.0;
+pub const MAX_INCLINATION_DEGREES: f32 = 90.0;
+/// Seuil au-delà duquel une onde sonore n'est plus une variation de pression.
+pub const MAX_SOUND_DB: f32 = 194.0;
+
+impl Validate for CreateScore {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.range("time_seconds", self.time_seconds, MIN_TIME_SECONDS, MAX_TIME_SECONDS);
+        errors.optional_range("max_speed_kmh", self.max_speed_kmh, 0.0, MAX_SPEED_KMH);
+        errors.optional_range("avg_speed_kmh", self.avg_speed_kmh, 0.0, MAX_SPEED_KMH);
+        errors.optional_range("max_g_force", self.max_g_force, 0.0, MAX_G_FORCE);
+        errors.optional_range("max_inclination_degrees", self.max_inclination_degrees, -MAX_INCLINATION_DEGREES, MAX_INCLINATION_DEGREES);
+        errors.optional_range("max_sound_db", self.max_sound_db, 0.0, MAX_SOUND_DB);
+        if let (Some(avg), Some(max)) = (self.avg_speed_kmh, self.max_speed_kmh)
+            && avg > max
+        {
+            errors.add("avg_speed_kmh", "must not exceed max_speed_kmh");
+        }
+        errors.into_result()
+    }
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::validation::{Validate, ValidationErrors};
+
+/// Colonnes lues pour construire un [`ScoreFlag`], depuis `score_flags` joint à `scores` et `users`.
+pub const SCORE_FLAG_COLUMNS: &str = "id, score_id, rule, details, status, review_note, reviewed_by, reviewed_at,
+    created_at, updated_at, route_id, user_id, username, time_seconds";
+
+pub const FLAG_STATUSES: &[&str] = &["open", "dismissed", "confirmed"];
+/// Décisions possibles d'un modérateur.
+pub const REVIEW_STATUSES: &[&str] = &["dismissed", "confirmed"];
+pub const REVIEW_NOTE_MAX_CHARS: usize = 1_000;
+
+/// Signalement d'une règle anti-triche, avec la course concernée.
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct ScoreFlag {
+    pub id: i32,
+    pub score_id: i32,
+    pub rule: String,
+    /// Mesures qui ont déclenché la règle.
+    pub details: serde_json::Value,
+    /// `open` tant qu'un modérateur ne l'a pas traité, puis `dismissed` ou `confirmed`.
+    pub status: String,
+    pub review_note: Option<String>,
+    pub reviewed_by: Option<i32>,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub reviewed_at: Option<chrono::NaiveDateTime>,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub updated_at: Option<chrono::NaiveDateTime>,
+    pub route_id: i32,
+    pub user_id: i32,
+    pub username: String,
+    pub time_seconds: f32,
+}
+
+fn serialize_datetime<S>(date: &Option<chrono::NaiveDateTime>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match date {
+        Some(d) => serializer.serialize_str(&d.to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReviewScoreFlag {
+    pub status: String,
+    pub note: Option<String>,
+}
+
+impl Validate for ReviewScoreFlag {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.optional_one_of("status", Some(&self.status), REVIEW_STATUSES);
+        errors.optional_length("note", self.note.as_deref(), 0, REVIEW_NOTE_MAX_CHARS);
+        errors.into_result()
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+
+use crate::validation::{Validate, ValidationErrors};
+
+/// Colonnes lues pour construire un [`Segment`].
+pub const SEGMENT_COLUMNS: &str = "id, route_id, user_id, name, start_distance_meters, end_distance_meters, \
+    distance_meters, path_data, min_lat, min_lon, max_lat, max_lon, created_at";
+
+/// Longueur minimale d'un segment : en deçà, la précision GPS fausse le classement.
+pub const MIN_SEGMENT_METERS: f64 = 100.0;
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct Segment {
+    pub id: i32,
+    pub route_id: i32,
+    /// Créateur du segment.
+    pub user_id: i32,
+    pub name: String,
+    pub start_distance_meters: f32,
+    pub end_distance_meters: f32,
+    pub distance_meters: f32,
+    /// Portion du tracé du parcours, en LineString GeoJSON.
+    pub path_data: Value,
+    pub min_lat: f64,
+    pub min_lon: f64,
+    pub max_lat: f64,
+    pub max_lon: f64,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Portion d'un parcours public, délimitée par des distances depuis son départ.
+#[derive(Serialize, Deserialize)]
+pub struct CreateSegment {
+    pub route_id: i32,
+    pub name: String,
+    pub start_distance_meters: f64,
+    pub end_distance_meters: f64,
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct SegmentEffort {
+    pub id: i32,
+    pub segment_id: i32,
+    pub score_id: i32,
+    pub user_id: i32,
+    pub elapsed_seconds: f32,
+    pub start_offset_ms: i32,
+    pub end_offset_ms: i32,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Meilleur passage d'un utilisateur sur un segment.
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct SegmentLeaderboardEntry {
+    pub rank: i64,
+    pub user_id: i32,
+    pub username: String,
+    pub effort_id: i32,
+    pub score_id: i32,
+    pub elapsed_seconds: f32,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Record personnel d'un utilisateur sur un segment, avec son rang au classement.
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct SegmentPr {
+    pub segment_id: i32,
+    pub segment_name: String,
+    pub route_id: i32,
+    pub rank: i64,
+    pub effort_id: i32,
+    pub score_id: i32,
+    pub elapsed_seconds: f32,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+fn serialize_datetime<S>(date: &Option<chrono::NaiveDateTime>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match date {
+        Some(d) => serializer.serialize_str(&d.to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl Validate for CreateSegment {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.length("name", &self.name, 1, 100);
+        if !self.start_distance_meters.is_finite() || self.start_distance_meters < 0.0 {
+            errors.add("start_distance_meters", "must be a positive number");
+        }
+        if !self.end_distance_meters.is_finite()
+            || self.end_distance_meters - self.start_distance_meters < MIN_SEGMENT_METERS
+        {
+            errors.add(
+                "end_distance_meters",
+                format!("must be at least {} m after start_distance_meters", MIN_SEGMENT_METERS),
+            );
+        }
+        errors.into_result()
+    }
+}
@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::{
+    models::score::{MAX_G_FORCE, MAX_INCLINATION_DEGREES, MAX_SOUND_DB, MAX_SPEED_KMH, MAX_TIME_SECONDS},
+    validation::{Validate, ValidationErrors},
+};
+
 #[derive(Serialize, Deserialize, FromRow)]
 pub struct SensorData {
     pub id: i32,
@@ -53,3 +58,51 @@ pub struct BulkSensorData {
     pub data: Vec<CreateSensorData>,
 }
 
+/// Nombre maximal de points par envoi groupé (environ 3 h à 1 Hz).
+pub const MAX_BULK_POINTS: usize = 10_000;
+/// Accélération en m/s², vitesse angulaire en rad/s.
+const MAX_ACCEL: f32 = 200.0;
+const MAX_GYRO: f32 = 50.0;
+const MAX_NEARBY_DEVICES: i32 = 10_000;
+const MIN_ALTITUDE: f32 = -500.0;
+const MAX_ALTITUDE: f32 = 9_000.0;
+
+impl Validate for CreateSensorData {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.range("timestamp_offset_ms", self.timestamp_offset_ms, 0, (MAX_TIME_SECONDS * 1000.0) as i32);
+        for (field, value) in [("accel_x", self.accel_x), ("accel_y", self.accel_y), ("accel_z", self.accel_z)] {
+            errors.optional_range(field, value, -MAX_ACCEL, MAX_ACCEL);
+        }
+        for (field, value) in [("gyro_x", self.gyro_x), ("gyro_y", self.gyro_y), ("gyro_z", self.gyro_z)] {
+            errors.optional_range(field, value, -MAX_GYRO, MAX_GYRO);
+        }
+        errors.optional_range("orientation_azimuth", self.orientation_azimuth, 0.0, 360.0);
+        errors.optional_range("orientation_pitch", self.orientation_pitch, -180.0, 180.0);
+        errors.optional_range("orientation_roll", self.orientation_roll, -180.0, 180.0);
+        errors.optional_range("speed_kmh", self.speed_kmh, 0.0, MAX_SPEED_KMH);
+        errors.optional_range("g_force", self.g_force, 0.0, MAX_G_FORCE);
+        errors.optional_range("inclination_degrees", self.inclination_degrees, -MAX_INCLINATION_DEGREES, MAX_INCLINATION_DEGREES);
+        errors.optional_range("sound_db", self.sound_db, 0.0, MAX_SOUND_DB);
+        errors.optional_range("nearby_devices", self.nearby_devices, 0, MAX_NEARBY_DEVICES);
+        errors.optional_range("latitude", self.latitude, -90.0, 90.0);
+        errors.optional_range("longitude", self.longitude, -180.0, 180.0);
+        errors.optional_range("altitude", self.altitude, MIN_ALTITUDE, MAX_ALTITUDE);
+        if self.latitude.is_some() != self.longitude.is_some() {
+            errors.add("latitude", "latitude and longitude must be provided together");
+        }
+        errors.into_result()
+    }
+}
+
+impl Validate for BulkSensorData {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.range("score_id", self.score_id, 1, i32::MAX);
+        errors.range("data", self.data.len(), 1, MAX_BULK_POINTS);
+        for (i, point) in self.data.iter().enumerate() {
+            errors.nested(&format!("data[{}]", i), point.validate());
+        }
+        errors.into_result()
+    }
+}
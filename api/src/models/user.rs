@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
+use shared::jwt::Role;
 use sqlx::FromRow;
 
+use crate::validation::{Validate, ValidationErrors};
+
 #[derive(Serialize, Deserialize)]
 pub struct CreateUser {
     pub username: String,
@@ -12,5 +15,39 @@ pub struct User {
     pub id: i32,
     pub username: String,
     pub email: String,
+    /// `false` tant que l'utilisateur n'a pas confirmé son adresse email.
+    pub email_verified: bool,
+}
+
+
+/// Vue d'un compte pour l'administration : rôle et état de sécurité en plus du profil.
+#[derive(Serialize, Deserialize, FromRow)]
+pub struct UserAccount {
+    pub id: i32,
+    pub username: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub role: String,
+    pub totp_enabled: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UpdateRole {
+    pub role: Role,
 }
 
+impl Validate for CreateUser {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.username("username", &self.username);
+        errors.email("email", &self.email);
+        errors.into_result()
+    }
+}
+
+/// Le rôle est déjà contrôlé à la désérialisation.
+impl Validate for UpdateRole {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Ok(())
+    }
+}
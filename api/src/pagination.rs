@@ -0,0 +1,173 @@
+use data_encoding::BASE64URL_NOPAD;
+use serde::{Deserialize, Serialize};
+use shared::errors::AppError;
+use sqlx::{Postgres, QueryBuilder};
+
+pub const DEFAULT_LIMIT: i64 = 20;
+pub const MAX_LIMIT: i64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    fn sql(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+/// Tri proposé par un endpoint. `expr` ne doit jamais être NULL (utiliser `COALESCE`),
+/// sinon la comparaison de tuples du curseur exclurait des lignes.
+pub struct SortField {
+    /// Valeur du paramètre `sort`.
+    pub name: &'static str,
+    pub expr: &'static str,
+    /// Type Postgres vers lequel la clé du curseur (texte) est convertie.
+    pub sql_type: &'static str,
+    pub default_order: SortOrder,
+}
+
+/// Paramètres communs des listes : `?limit=20&cursor=...&sort=created_at&order=desc`.
+#[derive(Debug, Default, Deserialize)]
+pub struct PageParams {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub sort: Option<String>,
+    pub order: Option<SortOrder>,
+}
+
+/// Position de la dernière ligne renvoyée. Encodée en base64 : opaque pour le client.
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    sort: String,
+    order: SortOrder,
+    key: String,
+    id: i64,
+}
+
+pub struct PageRequest {
+    pub limit: i64,
+    pub sort: &'static SortField,
+    pub order: SortOrder,
+    after: Option<Cursor>,
+}
+
+/// Enveloppe des réponses paginées ; `next_cursor` vaut `null` sur la dernière page.
+#[derive(Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl PageParams {
+    /// Le premier élément de `fields` est le tri par défaut.
+    pub fn resolve(self, fields: &'static [SortField], max_limit: i64) -> Result<PageRequest, AppError> {
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT.min(max_limit));
+        if !(1..=max_limit).contains(&limit) {
+            return Err(AppError::validation(
+                "Invalid pagination parameters",
+                serde_json::json!({ "limit": [format!("must be between 1 and {}", max_limit)] }),
+            ));
+        }
+
+        let sort = match self.sort.as_deref() {
+            None => &fields[0],
+            Some(name) => fields.iter().find(|f| f.name == name).ok_or_else(|| {
+                let allowed: Vec<&str> = fields.iter().map(|f| f.name).collect();
+                AppError::validation(
+                    "Invalid pagination parameters",
+                    serde_json::json!({ "sort": [format!("must be one of: {}", allowed.join(", "))] }),
+                )
+            })?,
+        };
+        let order = self.order.unwrap_or(sort.default_order);
+
+        let after = match self.cursor.as_deref() {
+            None | Some("") => None,
+            Some(raw) => {
+                let cursor = decode_cursor(raw)?;
+                // Un curseur n'a de sens que pour le tri qui l'a produit
+                if cursor.sort != sort.name || cursor.order != order {
+                    return Err(AppError::BadRequest("Cursor does not match the requested sort".to_string()));
+                }
+                Some(cursor)
+            }
+        };
+
+        Ok(PageRequest { limit, sort, order, after })
+    }
+}
+
+impl PageRequest {
+    /// Ajoute la condition du curseur, le tri et la limite. La requête doit déjà contenir un `WHERE`.
+    pub fn push_sql(&self, query: &mut QueryBuilder<'_, Postgres>, id_column: &str) {
+        if let Some(after) = &self.after {
+            let op = match self.order {
+                SortOrder::Asc => ">",
+                SortOrder::Desc => "<",
+            };
+            query
+                .push(format!(" AND ({}, {}) {} (", self.sort.expr, id_column, op))
+                .push_bind(after.key.clone())
+                .push(format!("::{}, ", self.sort.sql_type))
+                .push_bind(after.id)
+                .push(")");
+        }
+
+        let dir = self.order.sql();
+        query
+            .push(format!(" ORDER BY {} {}, {} {} LIMIT ", self.sort.expr, dir, id_column, dir))
+            // Une ligne de plus pour savoir s'il existe une page suivante
+            .push_bind(self.limit + 1);
+    }
+
+    /// `key` renvoie, pour une ligne, la valeur de `sort.expr` sous forme texte et son identifiant.
+    pub fn into_page<T>(self, mut rows: Vec<T>, key: impl Fn(&T, &str) -> (String, i64)) -> Page<T> {
+        let has_more = rows.len() as i64 > self.limit;
+        rows.truncate(self.limit as usize);
+
+        let next_cursor = if has_more {
+            rows.last().map(|last| {
+                let (key, id) = key(last, self.sort.name);
+                encode_cursor(&Cursor {
+                    sort: self.sort.name.to_string(),
+                    order: self.order,
+                    key,
+                    id,
+                })
+            })
+        } else {
+            None
+        };
+
+        Page { items: rows, next_cursor }
+    }
+}
+
+/// Clé de curseur pour une date éventuellement absente, cohérente avec `COALESCE(col, 'epoch')`.
+pub fn timestamp_key(value: Option<chrono::NaiveDateTime>) -> String {
+    value.unwrap_or_default().to_string()
+}
+
+fn encode_cursor(cursor: &Cursor) -> String {
+    BASE64URL_NOPAD.encode(&serde_json::to_vec(cursor).unwrap_or_default())
+}
+
+fn decode_cursor(raw: &str) -> Result<Cursor, AppError> {
+    BASE64URL_NOPAD
+        .decode(raw.as_bytes())
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| AppError::BadRequest("Invalid cursor".to_string()))
+}
+
+/// Échappe `%`, `_` et `\` pour utiliser une saisie utilisateur dans un motif `LIKE`.
+pub fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
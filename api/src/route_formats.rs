@@ -0,0 +1,338 @@
+use std::{fmt::Write, str::FromStr};
+
+use roxmltree::{Document, Node};
+use serde_json::{json, Value};
+
+use crate::geometry::{self, Position};
+
+/// Formats d'échange de parcours. GeoJSON n'est proposé qu'à l'export :
+/// à l'import, `POST /routes` l'accepte directement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteFormat {
+    Gpx,
+    Kml,
+    Tcx,
+    Geojson,
+}
+
+impl RouteFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            RouteFormat::Gpx => "gpx",
+            RouteFormat::Kml => "kml",
+            RouteFormat::Tcx => "tcx",
+            RouteFormat::Geojson => "geojson",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            RouteFormat::Gpx => "application/gpx+xml",
+            RouteFormat::Kml => "application/vnd.google-earth.kml+xml",
+            RouteFormat::Tcx => "application/vnd.garmin.tcx+xml",
+            RouteFormat::Geojson => "application/geo+json",
+        }
+    }
+}
+
+impl FromStr for RouteFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "gpx" => Ok(RouteFormat::Gpx),
+            "kml" => Ok(RouteFormat::Kml),
+            "tcx" => Ok(RouteFormat::Tcx),
+            "geojson" | "json" => Ok(RouteFormat::Geojson),
+            other => Err(format!("unsupported format '{}', expected gpx, kml, tcx or geojson", other)),
+        }
+    }
+}
+
+/// Tracé lu dans un fichier importé.
+#[derive(Debug)]
+pub struct ImportedRoute {
+    pub format: RouteFormat,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub positions: Vec<Position>,
+}
+
+/// Ce qu'il faut d'un parcours stocké pour l'exporter.
+pub struct ExportedRoute<'a> {
+    pub name: &'a str,
+    pub description: Option<&'a str>,
+    pub distance_meters: Option<f32>,
+    pub positions: &'a [Position],
+}
+
+/// Détecte le format à partir de l'élément racine (`gpx`, `kml`, `TrainingCenterDatabase`).
+/// Les DTD sont refusées par le parseur, ce qui écarte les attaques par entités XML.
+pub fn import(content: &str) -> Result<ImportedRoute, String> {
+    let doc = Document::parse(content).map_err(|e| format!("invalid XML: {}", e))?;
+    let root = doc.root_element();
+
+    let imported = match root.tag_name().name() {
+        "gpx" => import_gpx(root),
+        "kml" => import_kml(root),
+        "TrainingCenterDatabase" => import_tcx(root),
+        other => return Err(format!("unsupported document '{}', expected GPX, KML or TCX", other)),
+    }?;
+
+    if imported.positions.len() < 2 {
+        return Err("the file contains fewer than 2 track points".to_string());
+    }
+    if imported.positions.len() > geometry::MAX_POSITIONS {
+        return Err(format!("at most {} track points are allowed", geometry::MAX_POSITIONS));
+    }
+    Ok(imported)
+}
+
+/// `path_data` stocké pour un tracé importé : LineString GeoJSON, altitude en 3e valeur si connue.
+pub fn to_path_data(positions: &[Position]) -> Value {
+    let coordinates: Vec<Value> = positions
+        .iter()
+        .map(|p| match p.ele {
+            Some(ele) => json!([p.lon, p.lat, ele]),
+            None => json!([p.lon, p.lat]),
+        })
+        .collect();
+    json!({ "type": "LineString", "coordinates": coordinates })
+}
+
+pub fn export(route: &ExportedRoute, format: RouteFormat) -> String {
+    match format {
+        RouteFormat::Gpx => export_gpx(route),
+        RouteFormat::Kml => export_kml(route),
+        RouteFormat::Tcx => export_tcx(route),
+        RouteFormat::Geojson => export_geojson(route),
+    }
+}
+
+// ============ Import ============
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|c| c.tag_name().name() == name)
+}
+
+fn child_text(node: Node, name: &str) -> Option<String> {
+    child(node, name)
+        .and_then(|c| c.text())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+}
+
+fn parse_number(text: &str, what: &str) -> Result<f64, String> {
+    text.trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite())
+        .ok_or_else(|| format!("invalid {} '{}'", what, text.trim()))
+}
+
+fn checked_position(lon: f64, lat: f64, ele: Option<f64>) -> Result<Position, String> {
+    if !(-180.0..=180.0).contains(&lon) || !(-90.0..=90.0).contains(&lat) {
+        return Err(format!("coordinates out of range ({}, {})", lon, lat));
+    }
+    Ok(Position { lon, lat, ele })
+}
+
+/// Points de trace (`trkpt`), à défaut points d'itinéraire (`rtept`), tous segments confondus.
+fn import_gpx(root: Node) -> Result<ImportedRoute, String> {
+    let point_tag = if root.descendants().any(|n| n.tag_name().name() == "trkpt") { "trkpt" } else { "rtept" };
+
+    let positions = root
+        .descendants()
+        .filter(|n| n.tag_name().name() == point_tag)
+        .map(|pt| {
+            let lat = parse_number(pt.attribute("lat").ok_or("track point without lat")?, "latitude")?;
+            let lon = parse_number(pt.attribute("lon").ok_or("track point without lon")?, "longitude")?;
+            let ele = child_text(pt, "ele").map(|e| parse_number(&e, "elevation")).transpose()?;
+            checked_position(lon, lat, ele)
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let container = root.descendants().find(|n| matches!(n.tag_name().name(), "trk" | "rte"));
+    let metadata = child(root, "metadata");
+    Ok(ImportedRoute {
+        format: RouteFormat::Gpx,
+        name: container.and_then(|c| child_text(c, "name")).or_else(|| metadata.and_then(|m| child_text(m, "name"))),
+        description: container.and_then(|c| child_text(c, "desc")).or_else(|| metadata.and_then(|m| child_text(m, "desc"))),
+        positions,
+    })
+}
+
+/// Première `LineString` du document, ou à défaut les `gx:coord` d'un `gx:Track`.
+fn import_kml(root: Node) -> Result<ImportedRoute, String> {
+    let positions = if let Some(line) = root.descendants().find(|n| n.tag_name().name() == "LineString") {
+        let text = child(line, "coordinates").and_then(|c| c.text()).ok_or("LineString without coordinates")?;
+        text.split_whitespace()
+            .map(|tuple| {
+                let values = tuple.split(',').map(|v| parse_number(v, "coordinate")).collect::<Result<Vec<_>, _>>()?;
+                if values.len() < 2 {
+                    return Err(format!("invalid coordinate tuple '{}'", tuple));
+                }
+                checked_position(values[0], values[1], values.get(2).copied())
+            })
+            .collect::<Result<Vec<_>, String>>()?
+    } else {
+        root.descendants()
+            .filter(|n| n.tag_name().name() == "coord")
+            .map(|coord| {
+                let values = coord
+                    .text()
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .map(|v| parse_number(v, "coordinate"))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if values.len() < 2 {
+                    return Err("invalid gx:coord".to_string());
+                }
+                checked_position(values[0], values[1], values.get(2).copied())
+            })
+            .collect::<Result<Vec<_>, String>>()?
+    };
+
+    let placemark = root.descendants().find(|n| n.tag_name().name() == "Placemark");
+    let document = root.descendants().find(|n| n.tag_name().name() == "Document");
+    Ok(ImportedRoute {
+        format: RouteFormat::Kml,
+        name: placemark.and_then(|p| child_text(p, "name")).or_else(|| document.and_then(|d| child_text(d, "name"))),
+        description: placemark.and_then(|p| child_text(p, "description")),
+        positions,
+    })
+}
+
+/// `Trackpoint` d'un `Course` ou d'une `Activity` ; les points sans position (pauses) sont ignorés.
+fn import_tcx(root: Node) -> Result<ImportedRoute, String> {
+    let mut positions = Vec::new();
+    for point in root.descendants().filter(|n| n.tag_name().name() == "Trackpoint") {
+        let Some(position) = child(point, "Position") else { continue };
+        let lat = parse_number(&child_text(position, "LatitudeDegrees").ok_or("Position without LatitudeDegrees")?, "latitude")?;
+        let lon = parse_number(&child_text(position, "LongitudeDegrees").ok_or("Position without LongitudeDegrees")?, "longitude")?;
+        let ele = child_text(point, "AltitudeMeters").map(|e| parse_number(&e, "altitude")).transpose()?;
+        positions.push(checked_position(lon, lat, ele)?);
+    }
+
+    let course = root.descendants().find(|n| n.tag_name().name() == "Course");
+    Ok(ImportedRoute {
+        format: RouteFormat::Tcx,
+        name: course.and_then(|c| child_text(c, "Name")),
+        description: course.and_then(|c| child_text(c, "Notes")),
+        positions,
+    })
+}
+
+// ============ Export ============
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn export_gpx(route: &ExportedRoute) -> String {
+    let name = escape_xml(route.name);
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<gpx version=\"1.1\" creator=\"RMCE\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n");
+    let _ = writeln!(out, "  <metadata><name>{}</name></metadata>", name);
+    out.push_str("  <trk>\n");
+    let _ = writeln!(out, "    <name>{}</name>", name);
+    if let Some(description) = route.description {
+        let _ = writeln!(out, "    <desc>{}</desc>", escape_xml(description));
+    }
+    out.push_str("    <trkseg>\n");
+    for p in route.positions {
+        match p.ele {
+            Some(ele) => { let _ = writeln!(out, "      <trkpt lat=\"{}\" lon=\"{}\"><ele>{}</ele></trkpt>", p.lat, p.lon, ele); }
+            None => { let _ = writeln!(out, "      <trkpt lat=\"{}\" lon=\"{}\"/>", p.lat, p.lon); }
+        }
+    }
+    out.push_str("    </trkseg>\n  </trk>\n</gpx>\n");
+    out
+}
+
+fn export_kml(route: &ExportedRoute) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n  <Document>\n");
+    let _ = writeln!(out, "    <name>{}</name>", escape_xml(route.name));
+    out.push_str("    <Placemark>\n");
+    let _ = writeln!(out, "      <name>{}</name>", escape_xml(route.name));
+    if let Some(description) = route.description {
+        let _ = writeln!(out, "      <description>{}</description>", escape_xml(description));
+    }
+    out.push_str("      <LineString>\n");
+    // Sans mode d'altitude explicite, KML plaque le tracé au sol et ignore l'altitude
+    if route.positions.iter().any(|p| p.ele.is_some()) {
+        out.push_str("        <altitudeMode>absolute</altitudeMode>\n");
+    }
+    out.push_str("        <coordinates>\n");
+    for p in route.positions {
+        match p.ele {
+            Some(ele) => { let _ = writeln!(out, "          {},{},{}", p.lon, p.lat, ele); }
+            None => { let _ = writeln!(out, "          {},{}", p.lon, p.lat); }
+        }
+    }
+    out.push_str("        </coordinates>\n      </LineString>\n    </Placemark>\n  </Document>\n</kml>\n");
+    out
+}
+
+/// Exporté comme `Course` : sans horodatage, puisqu'un parcours n'a pas de temps de passage.
+fn export_tcx(route: &ExportedRoute) -> String {
+    // Le schéma TCX limite le nom d'un parcours à 15 caractères
+    let name: String = route.name.chars().take(15).collect();
+    let name = escape_xml(name.trim());
+    let first = route.positions[0];
+    let last = route.positions[route.positions.len() - 1];
+    let total = route.distance_meters.map(f64::from).unwrap_or_else(|| geometry::path_length(route.positions));
+
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<TrainingCenterDatabase xmlns=\"http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2\">\n");
+    out.push_str("  <Courses>\n    <Course>\n");
+    let _ = writeln!(out, "      <Name>{}</Name>", name);
+    out.push_str("      <Lap>\n        <TotalTimeSeconds>0</TotalTimeSeconds>\n");
+    let _ = writeln!(out, "        <DistanceMeters>{:.1}</DistanceMeters>", total);
+    let _ = writeln!(out, "        <BeginPosition><LatitudeDegrees>{}</LatitudeDegrees><LongitudeDegrees>{}</LongitudeDegrees></BeginPosition>", first.lat, first.lon);
+    let _ = writeln!(out, "        <EndPosition><LatitudeDegrees>{}</LatitudeDegrees><LongitudeDegrees>{}</LongitudeDegrees></EndPosition>", last.lat, last.lon);
+    out.push_str("        <Intensity>Active</Intensity>\n      </Lap>\n      <Track>\n");
+
+    let mut distance = 0.0;
+    for (i, p) in route.positions.iter().enumerate() {
+        if i > 0 {
+            distance += geometry::haversine_distance(&route.positions[i - 1], p);
+        }
+        out.push_str("        <Trackpoint>\n");
+        let _ = writeln!(out, "          <Position><LatitudeDegrees>{}</LatitudeDegrees><LongitudeDegrees>{}</LongitudeDegrees></Position>", p.lat, p.lon);
+        if let Some(ele) = p.ele {
+            let _ = writeln!(out, "          <AltitudeMeters>{}</AltitudeMeters>", ele);
+        }
+        let _ = writeln!(out, "          <DistanceMeters>{:.1}</DistanceMeters>", distance);
+        out.push_str("        </Trackpoint>\n");
+    }
+    out.push_str("      </Track>\n");
+    if let Some(description) = route.description {
+        let _ = writeln!(out, "      <Notes>{}</Notes>", escape_xml(description));
+    }
+    out.push_str("    </Course>\n  </Courses>\n</TrainingCenterDatabase>\n");
+    out
+}
+
+fn export_geojson(route: &ExportedRoute) -> String {
+    json!({
+        "type": "Feature",
+        "properties": {
+            "name": route.name,
+            "description": route.description,
+            "distance_meters": route.distance_meters,
+        },
+        "geometry": to_path_data(route.positions),
+    })
+    .to_string()
+}
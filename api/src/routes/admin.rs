@@ -0,0 +1,281 @@
+use axum::{
+    Json, Router,
+    extract::Extension,
+    routing::{delete, get, put}
+};
+use serde::Deserialize;
+use shared::{errors::AppError, jwt::Role};
+use sqlx::QueryBuilder;
+use tracing::{info, warn, error};
+
+use crate::{
+    anti_cheat::Rule,
+    authz::{Admin, Moderator, RequireRole},
+    db::DbPool,
+    models::score_flag::{ReviewScoreFlag, ScoreFlag, FLAG_STATUSES, SCORE_FLAG_COLUMNS},
+    models::user::{UpdateRole, UserAccount},
+    pagination::{timestamp_key, Page, PageParams, SortField, SortOrder, MAX_LIMIT},
+    sessions,
+    validation::{Path, Query, ValidatedJson, ValidationErrors},
+};
+
+/// Modération des comptes, parcours, défis et courses signalées. Monté derrière `auth_middleware`.
+pub fn router() -> Router {
+    Router::new()
+        .route("/users", get(list_users))
+        .route("/users/{id}", delete(delete_user))
+        .route("/users/{id}/role", put(update_role))
+        .route("/routes/{id}", delete(delete_route))
+        .route("/challenges/{id}", delete(delete_challenge))
+        .route("/score-flags", get(list_score_flags))
+        .route("/score-flags/{id}", put(review_score_flag))
+}
+
+const ACCOUNT_SORTS: &[SortField] = &[
+    SortField { name: "id", expr: "id", sql_type: "int4", default_order: SortOrder::Asc },
+    SortField { name: "username", expr: "username", sql_type: "text", default_order: SortOrder::Asc },
+];
+
+#[derive(Deserialize)]
+struct AccountQuery {
+    role: Option<Role>,
+}
+
+async fn list_users(
+    Extension(pool): Extension<DbPool>,
+    RequireRole(claims, _): RequireRole<Moderator>,
+    Query(filters): Query<AccountQuery>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<UserAccount>>, AppError> {
+    info!("Liste des comptes demandée par l'utilisateur {}", claims.user_id);
+    let page = page.resolve(ACCOUNT_SORTS, MAX_LIMIT)?;
+
+    let mut query = QueryBuilder::new(
+        "SELECT id, username, email, email_verified, role, totp_enabled FROM users WHERE true"
+    );
+    if let Some(role) = filters.role {
+        query.push(" AND role = ").push_bind(role.as_str());
+    }
+    page.push_sql(&mut query, "id");
+
+    let users = query
+        .build_query_as::<UserAccount>()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération des comptes: {}", e);
+            AppError::from(e)
+        })?;
+
+    Ok(Json(page.into_page(users, |u, sort| match sort {
+        "username" => (u.username.clone(), u.id as i64),
+        _ => (u.id.to_string(), u.id as i64),
+    })))
+}
+
+async fn update_role(
+    Extension(pool): Extension<DbPool>,
+    RequireRole(claims, _): RequireRole<Admin>,
+    Path(id): Path<i32>,
+    ValidatedJson(req): ValidatedJson<UpdateRole>,
+) -> Result<Json<UserAccount>, AppError> {
+    info!("Changement du rôle de l'utilisateur {} en {} par l'utilisateur {}", id, req.role.as_str(), claims.user_id);
+
+    // Évite qu'un administrateur se retire ses propres droits par erreur
+    if id == claims.user_id {
+        warn!("L'utilisateur {} a tenté de modifier son propre rôle", claims.user_id);
+        return Err(AppError::BadRequest("You cannot change your own role".to_string()));
+    }
+
+    let user = sqlx::query_as::<_, UserAccount>(
+        "UPDATE users SET role = $1 WHERE id = $2
+         RETURNING id, username, email, email_verified, role, totp_enabled"
+    )
+    .bind(req.role.as_str())
+    .bind(id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors du changement de rôle de l'utilisateur {}: {}", id, e);
+        AppError::from(e)
+    })?
+    .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    // Les access tokens en cours portent l'ancien rôle : on force une reconnexion
+    let revoked = sessions::revoke_all_sessions(&pool, id).await.map_err(|e| {
+        error!("Erreur lors de la révocation des sessions: {}", e);
+        AppError::from(e)
+    })?;
+
+    info!("Rôle de l'utilisateur {} changé ({} sessions révoquées)", id, revoked);
+    Ok(Json(user))
+}
+
+async fn delete_user(
+    Extension(pool): Extension<DbPool>,
+    RequireRole(claims, _): RequireRole<Admin>,
+    Path(id): Path<i32>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Suppression du compte {} par l'administrateur {}", id, claims.user_id);
+
+    let result = sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la suppression de l'utilisateur {}: {}", id, e);
+            AppError::from(e)
+        })?;
+
+    if result.rows_affected() == 0 {
+        warn!("Utilisateur {} non trouvé pour la suppression", id);
+        return Err(AppError::NotFound("User not found".to_string()));
+    }
+
+    Ok(Json(serde_json::json!({
+        "message": "User deleted successfully"
+    })))
+}
+
+async fn delete_route(
+    Extension(pool): Extension<DbPool>,
+    RequireRole(claims, _): RequireRole<Moderator>,
+    Path(id): Path<i32>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Suppression du parcours {} par le modérateur {}", id, claims.user_id);
+
+    let result = sqlx::query("DELETE FROM routes WHERE id = $1")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la suppression du parcours {}: {}", id, e);
+            AppError::from(e)
+        })?;
+
+    if result.rows_affected() == 0 {
+        warn!("Parcours {} non trouvé pour la suppression", id);
+        return Err(AppError::NotFound("Route not found".to_string()));
+    }
+
+    Ok(Json(serde_json::json!({
+        "message": "Route deleted successfully"
+    })))
+}
+
+async fn delete_challenge(
+    Extension(pool): Extension<DbPool>,
+    RequireRole(claims, _): RequireRole<Moderator>,
+    Path(id): Path<i32>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Suppression du défi {} par le modérateur {}", id, claims.user_id);
+
+    let result = sqlx::query("DELETE FROM challenges WHERE id = $1")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la suppression du défi {}: {}", id, e);
+            AppError::from(e)
+        })?;
+
+    if result.rows_affected() == 0 {
+        warn!("Défi {} non trouvé pour la suppression", id);
+        return Err(AppError::NotFound("Challenge not found".to_string()));
+    }
+
+    Ok(Json(serde_json::json!({
+        "message": "Challenge deleted successfully"
+    })))
+}
+
+/// Signalements joints à la course et à son auteur.
+const SCORE_FLAG_SOURCE: &str = "(SELECT f.*, s.route_id, s.user_id, s.time_seconds, u.username
+     FROM score_flags f
+     JOIN scores s ON s.id = f.score_id
+     JOIN users u ON u.id = s.user_id) flags";
+
+const SCORE_FLAG_SORTS: &[SortField] = &[
+    SortField { name: "created_at", expr: "COALESCE(created_at, 'epoch')", sql_type: "timestamp", default_order: SortOrder::Asc },
+];
+
+#[derive(Deserialize)]
+struct ScoreFlagQuery {
+    /// `open` par défaut : la file d'attente des modérateurs.
+    status: Option<String>,
+    rule: Option<Rule>,
+}
+
+async fn list_score_flags(
+    Extension(pool): Extension<DbPool>,
+    RequireRole(claims, _): RequireRole<Moderator>,
+    Query(filters): Query<ScoreFlagQuery>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<ScoreFlag>>, AppError> {
+    info!("Liste des courses signalées demandée par le modérateur {}", claims.user_id);
+    let status = filters.status.as_deref().unwrap_or("open");
+    let mut errors = ValidationErrors::new();
+    errors.optional_one_of("status", Some(status), FLAG_STATUSES);
+    errors.into_result()?;
+    let page = page.resolve(SCORE_FLAG_SORTS, MAX_LIMIT)?;
+
+    let mut query = QueryBuilder::new(format!("SELECT {} FROM {} WHERE status = ", SCORE_FLAG_COLUMNS, SCORE_FLAG_SOURCE));
+    query.push_bind(status);
+    if let Some(rule) = filters.rule {
+        query.push(" AND rule = ").push_bind(rule.as_str());
+    }
+    page.push_sql(&mut query, "id");
+
+    let flags = query
+        .build_query_as::<ScoreFlag>()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération des courses signalées: {}", e);
+            AppError::from(e)
+        })?;
+
+    Ok(Json(page.into_page(flags, |f, _| (timestamp_key(f.created_at), f.id as i64))))
+}
+
+/// `dismissed` rend la course aux classements, `confirmed` l'en écarte définitivement.
+async fn review_score_flag(
+    Extension(pool): Extension<DbPool>,
+    RequireRole(claims, _): RequireRole<Moderator>,
+    Path(id): Path<i32>,
+    ValidatedJson(req): ValidatedJson<ReviewScoreFlag>,
+) -> Result<Json<ScoreFlag>, AppError> {
+    info!("Signalement {} marqué {} par le modérateur {}", id, req.status, claims.user_id);
+
+    let updated = sqlx::query(
+        "UPDATE score_flags
+         SET status = $1, review_note = $2, reviewed_by = $3, reviewed_at = NOW(), updated_at = NOW()
+         WHERE id = $4"
+    )
+    .bind(&req.status)
+    .bind(&req.note)
+    .bind(claims.user_id)
+    .bind(id)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la revue du signalement {}: {}", id, e);
+        AppError::from(e)
+    })?;
+
+    if updated.rows_affected() == 0 {
+        warn!("Signalement {} non trouvé", id);
+        return Err(AppError::NotFound("Score flag not found".to_string()));
+    }
+
+    let flag = sqlx::query_as::<_, ScoreFlag>(&format!("SELECT {} FROM {} WHERE id = $1", SCORE_FLAG_COLUMNS, SCORE_FLAG_SOURCE))
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération du signalement {}: {}", id, e);
+            AppError::from(e)
+        })?;
+
+    Ok(Json(flag))
+}
@@ -1,95 +1,199 @@
 use axum::{
     Json, Router,
-    extract::Extension,
-    http::StatusCode,
-    routing::post
+    extract::{ConnectInfo, Extension},
+    http::HeaderMap,
+    middleware,
+    routing::{get, post}
 };
 use bcrypt::{hash, verify, DEFAULT_COST};
+use shared::errors::AppError;
 use tracing::{info, warn, error};
 use serde::{Deserialize, Serialize};
-use shared::jwt::create_jwt;
+use shared::jwt::{create_jwt, Claims, ACCESS_TOKEN_TTL_SECS};
+use std::net::SocketAddr;
 
+use super::mfa;
 use crate::{
+    authz,
     db::DbPool,
-    models::auth::{Login, Register},
+    login_guard::{self, AttemptOutcome},
+    mailer::{Email, SharedMailer},
+    middleware::auth_middleware,
+    models::auth::{ForgotPassword, Login, RefreshRequest, Register, ResetPassword, UnlockAccount, VerifyEmail},
     models::user::User,
+    sessions::{self, RefreshOutcome},
+    user_tokens::{self, TokenPurpose},
+    validation::{Query, ValidatedJson},
 };
 
 #[derive(Serialize, Deserialize)]
 pub struct LoginResponse {
     pub token: String,
+    pub refresh_token: String,
+    pub expires_in: u64,
     pub user: User,
 }
 
+/// Retourné par `/auth/login` quand le compte a activé la double authentification.
+/// `mfa_token` s'échange contre un `LoginResponse` via `/auth/2fa/verify`.
+#[derive(Serialize, Deserialize)]
+pub struct MfaChallenge {
+    pub mfa_required: bool,
+    pub mfa_token: String,
+    pub expires_in: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LoginOutcome {
+    Authenticated(LoginResponse),
+    MfaRequired(MfaChallenge),
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TokenResponse {
+    pub token: String,
+    pub refresh_token: String,
+    pub expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct LogoutQuery {
+    all: Option<bool>,
+}
+
 pub fn router() -> Router {
+    // Routes nécessitant un access token valide
+    let authenticated = Router::new()
+        .route("/logout", post(logout))
+        .route("/session", get(get_session))
+        .layer(middleware::from_fn(auth_middleware));
+
     Router::new()
         .route("/login", post(login))
         .route("/register", post(register))
+        .route("/refresh", post(refresh))
+        .route("/forgot-password", post(forgot_password))
+        .route("/reset-password", post(reset_password))
+        .route("/verify-email", post(verify_email))
+        .route("/unlock-account", post(unlock_account))
+        .nest("/2fa", mfa::router())
+        .merge(authenticated)
 }
 
 async fn login(
     Extension(pool): Extension<DbPool>,
-    Json(login_req): Json<Login>,
-) -> Result<Json<LoginResponse>, StatusCode> {
-    info!("Tentative de connexion pour l'email: {}", login_req.email);
-    
+    Extension(mailer): Extension<SharedMailer>,
+    peer: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    ValidatedJson(login_req): ValidatedJson<Login>,
+) -> Result<Json<LoginOutcome>, AppError> {
+    // L'email saisi n'est pas journalisé : il peut s'agir d'un mot de passe tapé dans le mauvais champ
+    info!("Tentative de connexion");
+
+    let email_key = login_guard::normalize_email(&login_req.email);
+    let ip = login_guard::client_ip(&headers, peer.map(|Extension(ConnectInfo(addr))| addr));
+
+    let counts = login_guard::failure_counts(&pool, &email_key, ip.as_deref())
+        .await
+        .map_err(|e| {
+            error!("Erreur lors du comptage des tentatives de connexion: {}", e);
+            AppError::from(e)
+        })?;
+
+    if counts.ip_blocked() {
+        warn!("Trop d'échecs de connexion depuis l'IP {}", ip.as_deref().unwrap_or("inconnue"));
+        return Err(AppError::RateLimited("Too many failed login attempts from this address, try again later".to_string()));
+    }
+    // Même réponse que le compte existe ou non
+    if counts.account_locked() {
+        warn!("Connexion refusée : compte temporairement verrouillé");
+        return Err(AppError::RateLimited("Too many failed login attempts, try again later or use the unlock link sent by email".to_string()));
+    }
+
     let user = sqlx::query_as::<_, UserWithPassword>(
-        "SELECT id, username, email, password FROM users WHERE email = $1"
+        "SELECT id, username, email, email_verified, password, totp_enabled FROM users WHERE email = $1"
     )
     .bind(&login_req.email)
     .fetch_optional(&pool)
     .await
     .map_err(|e| {
         error!("Erreur lors de la requête de connexion: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
-    match user {
-        Some(u) => {
-            info!("Utilisateur trouvé: {}", u.username);
-            match verify(login_req.password, &u.password) {
-                Ok(true) => {
-                    let token = create_jwt(u.id, u.username.clone(), u.email.clone())
-                        .map_err(|e| {
-                            error!("Erreur lors de la création du token JWT: {}", e);
-                            StatusCode::INTERNAL_SERVER_ERROR
-                        })?;
-
-                    info!("Connexion réussie pour l'utilisateur: {}", u.username);
-                    Ok(Json(LoginResponse {
-                        token,
-                        user: User {
-                            id: u.id,
-                            username: u.username,
-                            email: u.email,
-                        },
-                    }))
-                }
-                Ok(false) => {
-                    warn!("Mot de passe incorrect pour l'utilisateur: {}", u.username);
-                    Err(StatusCode::UNAUTHORIZED)
-                }
-                Err(e) => {
-                    error!("Erreur lors de la vérification du mot de passe: {}", e);
-                    Err(StatusCode::INTERNAL_SERVER_ERROR)
+    let password_ok = match &user {
+        Some(u) => verify(&login_req.password, &u.password).map_err(|e| {
+            error!("Erreur lors de la vérification du mot de passe: {}", e);
+            AppError::Internal(e.to_string())
+        })?,
+        None => {
+            login_guard::dummy_verify(&login_req.password);
+            false
+        }
+    };
+
+    let u = match user {
+        Some(u) if password_ok => u,
+        user => {
+            record_attempt(&pool, &email_key, ip.as_deref(), AttemptOutcome::Failure).await?;
+            let failures = counts.account + 1;
+            match &user {
+                Some(u) => warn!("Mot de passe incorrect pour l'utilisateur {} ({} échecs)", u.id, failures),
+                None => warn!("Tentative de connexion avec un email inexistant"),
+            }
+
+            if failures == login_guard::MAX_FAILURES_PER_ACCOUNT && let Some(u) = &user {
+                warn!("Compte de l'utilisateur {} verrouillé pour {} minutes", u.id, login_guard::ATTEMPT_WINDOW_MINUTES);
+                if let Err(e) = send_unlock_email(&pool, &mailer, u.id, &u.username, &u.email).await {
+                    error!("Erreur lors de l'envoi de l'email de déverrouillage à l'utilisateur {}: {}", u.id, e);
                 }
             }
+
+            tokio::time::sleep(login_guard::delay_after(failures)).await;
+            return Err(AppError::Unauthorized("Invalid email or password".to_string()));
         }
-        None => {
-            warn!("Tentative de connexion avec un email inexistant: {}", login_req.email);
-            Err(StatusCode::UNAUTHORIZED)
-        }
+    };
+
+    // Avec la 2FA, le compteur d'échecs n'est remis à zéro qu'après le second facteur
+    if u.totp_enabled {
+        let mfa_token = user_tokens::issue(&pool, u.id, TokenPurpose::MfaPending)
+            .await
+            .map_err(|e| {
+                error!("Erreur lors de la création du token MFA: {}", e);
+                AppError::from(e)
+            })?;
+
+        info!("Mot de passe valide, second facteur requis pour l'utilisateur: {}", u.username);
+        return Ok(Json(LoginOutcome::MfaRequired(MfaChallenge {
+            mfa_required: true,
+            mfa_token,
+            expires_in: TokenPurpose::MfaPending.ttl_minutes() as u64 * 60,
+        })));
     }
+
+    record_attempt(&pool, &email_key, ip.as_deref(), AttemptOutcome::Success).await?;
+    let response = issue_login_tokens(&pool, User {
+        id: u.id,
+        username: u.username,
+        email: u.email,
+        email_verified: u.email_verified,
+    })
+    .await?;
+
+    info!("Connexion réussie pour l'utilisateur: {}", response.user.username);
+    Ok(Json(LoginOutcome::Authenticated(response)))
 }
 
 async fn register(
     Extension(pool): Extension<DbPool>,
-    Json(register_req): Json<Register>,
-) -> Result<Json<User>, StatusCode> {
+    Extension(mailer): Extension<SharedMailer>,
+    ValidatedJson(register_req): ValidatedJson<Register>,
+) -> Result<Json<User>, AppError> {
     info!("Tentative d'enregistrement pour l'utilisateur: {} ({})", register_req.username, register_req.email);
     
     let existing = sqlx::query_as::<_, UserWithPassword>(
-        "SELECT id, username, email, password FROM users WHERE email = $1 OR username = $2"
+        "SELECT id, username, email, email_verified, password, totp_enabled FROM users WHERE email = $1 OR username = $2"
     )
     .bind(&register_req.email)
     .bind(&register_req.username)
@@ -97,24 +201,24 @@ async fn register(
     .await
     .map_err(|e| {
         error!("Erreur lors de la vérification de l'utilisateur existant: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     if let Some(existing_user) = existing {
         warn!("Tentative d'enregistrement avec un email ou username existant: {} / {}", existing_user.email, existing_user.username);
-        return Err(StatusCode::CONFLICT);
+        return Err(AppError::Conflict("Email or username already taken".to_string()));
     }
 
     info!("Hachage du mot de passe pour l'utilisateur: {}", register_req.username);
     let hashed_password = hash(register_req.password, DEFAULT_COST)
         .map_err(|e| {
             error!("Erreur lors du hachage du mot de passe: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            AppError::Internal(e.to_string())
         })?;
 
     info!("Création de l'utilisateur dans la base de données: {}", register_req.username);
     let user = sqlx::query_as::<_, User>(
-        "INSERT INTO users (username, email, password) VALUES ($1, $2, $3) RETURNING id, username, email"
+        "INSERT INTO users (username, email, password) VALUES ($1, $2, $3) RETURNING id, username, email, email_verified"
     )
     .bind(&register_req.username)
     .bind(&register_req.email)
@@ -123,13 +227,397 @@ async fn register(
     .await
     .map_err(|e| {
         error!("Erreur lors de la création de l'utilisateur: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     info!("Utilisateur créé avec succès: {} (ID: {})", user.username, user.id);
+
+    // L'échec d'envoi n'empêche pas l'inscription : l'email peut être renvoyé plus tard
+    if let Err(e) = send_verification_email(&pool, &mailer, &user).await {
+        error!("Erreur lors de l'envoi de l'email de vérification à {}: {}", user.email, e);
+    }
+
     Ok(Json(user))
 }
 
+/// Ouvre une session et émet la paire access token / refresh token.
+pub(crate) async fn issue_login_tokens(pool: &DbPool, user: User) -> Result<LoginResponse, AppError> {
+    let role = authz::user_role(pool, user.id).await.map_err(|e| {
+        error!("Erreur lors de la récupération du rôle de l'utilisateur {}: {}", user.id, e);
+        AppError::from(e)
+    })?;
+
+    let (session_id, refresh_token) = sessions::create_session(pool, user.id)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la création de la session: {}", e);
+            AppError::from(e)
+        })?;
+
+    let token = create_jwt(user.id, user.username.clone(), user.email.clone(), session_id, role)
+        .map_err(|e| {
+            error!("Erreur lors de la création du token JWT: {}", e);
+            AppError::Internal(e.to_string())
+        })?;
+
+    Ok(LoginResponse {
+        token,
+        refresh_token,
+        expires_in: ACCESS_TOKEN_TTL_SECS,
+        user,
+    })
+}
+
+async fn refresh(
+    Extension(pool): Extension<DbPool>,
+    ValidatedJson(refresh_req): ValidatedJson<RefreshRequest>,
+) -> Result<Json<TokenResponse>, AppError> {
+    info!("Renouvellement d'un access token");
+
+    let outcome = sessions::rotate_refresh_token(&pool, &refresh_req.refresh_token)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la rotation du refresh token: {}", e);
+            AppError::from(e)
+        })?;
+
+    let (session_id, user_id, refresh_token) = match outcome {
+        RefreshOutcome::Rotated { session_id, user_id, refresh_token } => (session_id, user_id, refresh_token),
+        RefreshOutcome::Reused => {
+            warn!("Refresh token réutilisé, session révoquée");
+            return Err(AppError::Unauthorized("Refresh token reused, session revoked".to_string()));
+        }
+        RefreshOutcome::Invalid => {
+            warn!("Refresh token invalide, expiré ou révoqué");
+            return Err(AppError::Unauthorized("Invalid or expired refresh token".to_string()));
+        }
+    };
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT id, username, email, email_verified FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la récupération de l'utilisateur {}: {}", user_id, e);
+        AppError::from(e)
+    })?
+    .ok_or_else(|| AppError::Unauthorized("User no longer exists".to_string()))?;
+
+    // Le rôle est relu à chaque renouvellement
+    let role = authz::user_role(&pool, user.id).await.map_err(|e| {
+        error!("Erreur lors de la récupération du rôle de l'utilisateur {}: {}", user.id, e);
+        AppError::from(e)
+    })?;
+
+    let token = create_jwt(user.id, user.username, user.email, session_id, role)
+        .map_err(|e| {
+            error!("Erreur lors de la création du token JWT: {}", e);
+            AppError::Internal(e.to_string())
+        })?;
+
+    info!("Access token renouvelé pour l'utilisateur {}", user_id);
+    Ok(Json(TokenResponse {
+        token,
+        refresh_token,
+        expires_in: ACCESS_TOKEN_TTL_SECS,
+    }))
+}
+
+async fn logout(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<LogoutQuery>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let revoked = if params.all.unwrap_or(false) {
+        info!("Déconnexion de toutes les sessions de l'utilisateur {}", claims.user_id);
+        sessions::revoke_all_sessions(&pool, claims.user_id).await
+    } else {
+        info!("Déconnexion de la session {} de l'utilisateur {}", claims.jti, claims.user_id);
+        sessions::revoke_session(&pool, &claims.jti).await
+    }
+    .map_err(|e| {
+        error!("Erreur lors de la révocation de session: {}", e);
+        AppError::from(e)
+    })?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Logged out successfully",
+        "revoked_sessions": revoked
+    })))
+}
+
+/// Permet aux autres services (geo-service) de vérifier qu'un token n'est pas révoqué.
+async fn get_session(
+    Extension(claims): Extension<Claims>,
+) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "user_id": claims.user_id,
+        "session_id": claims.jti,
+        "role": claims.role,
+        "expires_at": claims.exp
+    }))
+}
+
+/// Toujours 200 : la réponse ne révèle pas si l'email correspond à un compte.
+async fn forgot_password(
+    Extension(pool): Extension<DbPool>,
+    Extension(mailer): Extension<SharedMailer>,
+    ValidatedJson(forgot_req): ValidatedJson<ForgotPassword>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Demande de réinitialisation de mot de passe");
+
+    let user = sqlx::query_as::<_, User>(
+        "SELECT id, username, email, email_verified FROM users WHERE email = $1"
+    )
+    .bind(&forgot_req.email)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la recherche de l'utilisateur: {}", e);
+        AppError::from(e)
+    })?;
+
+    if let Some(user) = user {
+        let token = user_tokens::issue(&pool, user.id, TokenPurpose::PasswordReset)
+            .await
+            .map_err(|e| {
+                error!("Erreur lors de la création du token de réinitialisation: {}", e);
+                AppError::from(e)
+            })?;
+
+        let email = Email {
+            to: user.email.clone(),
+            subject: "Réinitialisation de votre mot de passe".to_string(),
+            body: format!(
+                "Bonjour {},\n\nPour choisir un nouveau mot de passe, ouvrez ce lien (valable {} minutes) :\n{}/reset-password?token={}\n\nSi vous n'êtes pas à l'origine de cette demande, ignorez cet email.",
+                user.username,
+                TokenPurpose::PasswordReset.ttl_minutes(),
+                app_link_base_url(),
+                token
+            ),
+        };
+        if let Err(e) = mailer.send(email).await {
+            error!("Erreur lors de l'envoi de l'email de réinitialisation à l'utilisateur {}: {}", user.id, e);
+        } else {
+            info!("Email de réinitialisation envoyé à l'utilisateur {}", user.id);
+        }
+    }
+
+    Ok(Json(serde_json::json!({
+        "message": "If an account exists for this email, a reset link has been sent"
+    })))
+}
+
+async fn reset_password(
+    Extension(pool): Extension<DbPool>,
+    ValidatedJson(reset_req): ValidatedJson<ResetPassword>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Réinitialisation de mot de passe");
+
+    let hashed_password = hash(&reset_req.new_password, DEFAULT_COST)
+        .map_err(|e| {
+            error!("Erreur lors du hachage du mot de passe: {}", e);
+            AppError::Internal(e.to_string())
+        })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("Erreur lors du démarrage de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    let user_id = user_tokens::consume(&mut tx, &reset_req.token, TokenPurpose::PasswordReset)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la vérification du token de réinitialisation: {}", e);
+            AppError::from(e)
+        })?
+        .ok_or_else(|| {
+            warn!("Token de réinitialisation invalide, expiré ou déjà utilisé");
+            AppError::BadRequest("Invalid or expired reset token".to_string())
+        })?;
+
+    // Recevoir l'email de réinitialisation prouve aussi la possession de l'adresse
+    sqlx::query("UPDATE users SET password = $1, email_verified = true WHERE id = $2")
+        .bind(&hashed_password)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la mise à jour du mot de passe: {}", e);
+            AppError::from(e)
+        })?;
+
+    tx.commit().await.map_err(|e| {
+        error!("Erreur lors de la validation de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    // Un mot de passe compromis ne doit plus donner accès aux sessions existantes
+    let revoked = sessions::revoke_all_sessions(&pool, user_id).await.map_err(|e| {
+        error!("Erreur lors de la révocation des sessions: {}", e);
+        AppError::from(e)
+    })?;
+
+    info!("Mot de passe réinitialisé pour l'utilisateur {} ({} sessions révoquées)", user_id, revoked);
+    Ok(Json(serde_json::json!({
+        "message": "Password reset successfully"
+    })))
+}
+
+async fn verify_email(
+    Extension(pool): Extension<DbPool>,
+    ValidatedJson(verify_req): ValidatedJson<VerifyEmail>,
+) -> Result<Json<User>, AppError> {
+    info!("Vérification d'adresse email");
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("Erreur lors du démarrage de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    let user_id = user_tokens::consume(&mut tx, &verify_req.token, TokenPurpose::EmailVerification)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la vérification du token: {}", e);
+            AppError::from(e)
+        })?
+        .ok_or_else(|| {
+            warn!("Token de vérification invalide, expiré ou déjà utilisé");
+            AppError::BadRequest("Invalid or expired verification token".to_string())
+        })?;
+
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET email_verified = true WHERE id = $1
+         RETURNING id, username, email, email_verified"
+    )
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la vérification de l'email: {}", e);
+        AppError::from(e)
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        error!("Erreur lors de la validation de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    info!("Email vérifié pour l'utilisateur {}", user.id);
+    Ok(Json(user))
+}
+
+/// Lève le verrouillage déclenché par trop d'échecs de connexion.
+async fn unlock_account(
+    Extension(pool): Extension<DbPool>,
+    ValidatedJson(unlock_req): ValidatedJson<UnlockAccount>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Déverrouillage de compte");
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("Erreur lors du démarrage de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    let user_id = user_tokens::consume(&mut tx, &unlock_req.token, TokenPurpose::AccountUnlock)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la vérification du token de déverrouillage: {}", e);
+            AppError::from(e)
+        })?
+        .ok_or_else(|| {
+            warn!("Token de déverrouillage invalide, expiré ou déjà utilisé");
+            AppError::BadRequest("Invalid or expired unlock token".to_string())
+        })?;
+
+    let email = sqlx::query_scalar::<_, String>("SELECT email FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération de l'utilisateur {}: {}", user_id, e);
+            AppError::from(e)
+        })?;
+
+    tx.commit().await.map_err(|e| {
+        error!("Erreur lors de la validation de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    login_guard::record(&pool, &login_guard::normalize_email(&email), None, AttemptOutcome::Unlock)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors du déverrouillage du compte: {}", e);
+            AppError::from(e)
+        })?;
+
+    info!("Compte de l'utilisateur {} déverrouillé", user_id);
+    Ok(Json(serde_json::json!({
+        "message": "Account unlocked"
+    })))
+}
+
+pub(super) async fn record_attempt(pool: &DbPool, email_key: &str, ip: Option<&str>, outcome: AttemptOutcome) -> Result<(), AppError> {
+    login_guard::record(pool, email_key, ip, outcome).await.map_err(|e| {
+        error!("Erreur lors de l'enregistrement de la tentative de connexion: {}", e);
+        AppError::from(e)
+    })
+}
+
+pub(super) async fn send_unlock_email(
+    pool: &DbPool,
+    mailer: &SharedMailer,
+    user_id: i32,
+    username: &str,
+    email: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let token = user_tokens::issue(pool, user_id, TokenPurpose::AccountUnlock).await?;
+
+    mailer.send(Email {
+        to: email.to_string(),
+        subject: "Votre compte a été temporairement verrouillé".to_string(),
+        body: format!(
+            "Bonjour {},\n\nPlusieurs tentatives de connexion ont échoué, votre compte est verrouillé pendant {} minutes.\nSi c'était vous, débloquez-le immédiatement avec ce lien :\n{}/unlock-account?token={}\n\nSinon, nous vous conseillons de changer votre mot de passe.",
+            username,
+            login_guard::ATTEMPT_WINDOW_MINUTES,
+            app_link_base_url(),
+            token
+        ),
+    }).await?;
+
+    info!("Email de déverrouillage envoyé à l'utilisateur {}", user_id);
+    Ok(())
+}
+
+async fn send_verification_email(
+    pool: &DbPool,
+    mailer: &SharedMailer,
+    user: &User,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let token = user_tokens::issue(pool, user.id, TokenPurpose::EmailVerification).await?;
+
+    mailer.send(Email {
+        to: user.email.clone(),
+        subject: "Confirmez votre adresse email".to_string(),
+        body: format!(
+            "Bienvenue {} !\n\nConfirmez votre adresse email en ouvrant ce lien :\n{}/verify-email?token={}",
+            user.username,
+            app_link_base_url(),
+            token
+        ),
+    }).await?;
+
+    info!("Email de vérification envoyé à l'utilisateur {}", user.id);
+    Ok(())
+}
+
+/// Base des liens envoyés par email (deep link de l'application mobile par défaut).
+fn app_link_base_url() -> String {
+    std::env::var("APP_LINK_BASE_URL").unwrap_or_else(|_| "rmce://auth".to_string())
+}
+
 // Structure interne pour les requêtes avec mot de passe
 #[derive(sqlx::FromRow)]
 #[allow(dead_code)]
@@ -137,5 +625,7 @@ struct UserWithPassword {
     id: i32,
     username: String,
     email: String,
+    email_verified: bool,
     password: String,
+    totp_enabled: bool,
 }
\ No newline at end of file
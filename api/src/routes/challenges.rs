@@ -1,18 +1,23 @@
 use axum::{
     Json, Router,
-    extract::{Extension, Path},
-    http::StatusCode,
+    extract::Extension,
     routing::{get, post}
 };
+use serde::Deserialize;
+use shared::errors::AppError;
+use sqlx::QueryBuilder;
 use tracing::{info, warn, error};
 use shared::jwt::Claims;
 
 use crate::{
+    authz,
     db::DbPool,
     models::{
         challenge::{Challenge, CreateChallenge, UpdateChallenge},
         score::LeaderboardEntry,
     },
+    pagination::{timestamp_key, Page, PageParams, SortField, SortOrder, MAX_LIMIT},
+    validation::{Path, Query, ValidatedJson},
 };
 
 pub fn router() -> Router {
@@ -34,8 +39,8 @@ pub fn router() -> Router {
 async fn create_challenge(
     Extension(pool): Extension<DbPool>,
     Extension(claims): Extension<Claims>,
-    Json(new_challenge): Json<CreateChallenge>,
-) -> Result<Json<Challenge>, StatusCode> {
+    ValidatedJson(new_challenge): ValidatedJson<CreateChallenge>,
+) -> Result<Json<Challenge>, AppError> {
     let user_id = claims.user_id;
 
     info!("Création d'un défi sur le parcours {} par l'utilisateur {}", new_challenge.route_id, user_id);
@@ -52,7 +57,7 @@ async fn create_challenge(
     .await
     .map_err(|e| {
         error!("Erreur lors de la création du défi: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     info!("Défi créé avec succès (ID: {})", challenge.id);
@@ -62,7 +67,7 @@ async fn create_challenge(
 async fn get_challenge(
     Extension(pool): Extension<DbPool>,
     Path(id): Path<i32>,
-) -> Result<Json<Challenge>, StatusCode> {
+) -> Result<Json<Challenge>, AppError> {
     info!("Récupération du défi {}", id);
 
     let challenge = sqlx::query_as::<_, Challenge>(
@@ -74,7 +79,7 @@ async fn get_challenge(
     .await
     .map_err(|e| {
         error!("Erreur lors de la récupération du défi: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     match challenge {
@@ -84,7 +89,7 @@ async fn get_challenge(
         }
         None => {
             warn!("Défi {} non trouvé", id);
-            Err(StatusCode::NOT_FOUND)
+            Err(AppError::NotFound("Challenge not found".to_string()))
         }
     }
 }
@@ -93,7 +98,7 @@ async fn accept_challenge(
     Extension(pool): Extension<DbPool>,
     Extension(claims): Extension<Claims>,
     Path(id): Path<i32>,
-) -> Result<Json<Challenge>, StatusCode> {
+) -> Result<Json<Challenge>, AppError> {
     let user_id = claims.user_id;
 
     info!("Acceptation du défi {} par l'utilisateur {}", id, user_id);
@@ -110,7 +115,7 @@ async fn accept_challenge(
     .await
     .map_err(|e| {
         error!("Erreur lors de l'acceptation du défi: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     match challenge {
@@ -120,144 +125,280 @@ async fn accept_challenge(
         }
         None => {
             warn!("Défi {} non trouvé ou déjà accepté", id);
-            Err(StatusCode::NOT_FOUND)
+            Err(AppError::NotFound("Challenge not found or already accepted".to_string()))
         }
     }
 }
 
+/// Chaque participant enregistre son propre temps ; le gagnant est déduit des temps
+/// enregistrés et le défi est terminé dès que les deux temps sont connus ; à temps égaux, il
+/// n'y a pas de gagnant. Les temps ne sont acceptés qu'une fois le défi accepté (`active`). Le
+/// seul statut qu'un participant peut demander est `cancelled`, tant que le défi n'est pas terminé.
 async fn complete_challenge(
     Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
     Path(id): Path<i32>,
-    Json(update): Json<UpdateChallenge>,
-) -> Result<Json<Challenge>, StatusCode> {
-    info!("Complétion du défi {}", id);
-
-    // Determine winner based on times
-    let winner_query = "
-        SELECT
-            CASE
-                WHEN $1 IS NOT NULL AND $2 IS NOT NULL THEN
-                    CASE WHEN $1 < $2 THEN challenger_id ELSE challenged_id END
-                ELSE NULL
-            END as winner
-        FROM challenges WHERE id = $3";
-
-    let winner_id: Option<i32> = sqlx::query_scalar(winner_query)
-        .bind(update.challenger_time)
-        .bind(update.challenged_time)
-        .bind(id)
-        .fetch_optional(&pool)
-        .await
-        .map_err(|e| {
-            error!("Erreur lors du calcul du gagnant: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .flatten();
+    ValidatedJson(update): ValidatedJson<UpdateChallenge>,
+) -> Result<Json<Challenge>, AppError> {
+    info!("Complétion du défi {} par l'utilisateur {}", id, claims.user_id);
+
+    let (challenger_id, challenged_id, status): (i32, Option<i32>, String) = sqlx::query_as(
+        "SELECT challenger_id, challenged_id, status FROM challenges WHERE id = $1"
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la récupération du défi {}: {}", id, e);
+        AppError::from(e)
+    })?
+    .ok_or_else(|| {
+        warn!("Défi {} non trouvé", id);
+        AppError::NotFound("Challenge not found".to_string())
+    })?;
+
+    let participants: Vec<i32> = std::iter::once(challenger_id).chain(challenged_id).collect();
+    authz::authorize(&claims, &participants, None, &format!("le défi {}", id))?;
+
+    // Personne ne peut saisir le temps de son adversaire
+    let is_challenger = claims.user_id == challenger_id;
+    if (is_challenger && update.challenged_time.is_some())
+        || (!is_challenger && update.challenger_time.is_some())
+    {
+        warn!("Utilisateur {} a tenté de saisir le temps de son adversaire (défi {})", claims.user_id, id);
+        return Err(AppError::Forbidden("You can only submit your own time".to_string()));
+    }
+
+    // `completed` découle des deux temps enregistrés, jamais de la demande du client
+    if let Some(requested) = update.status.as_deref() {
+        if requested != "cancelled" {
+            warn!("Utilisateur {} a demandé le statut {} pour le défi {}", claims.user_id, requested, id);
+            return Err(AppError::validation(
+                "Invalid request body",
+                serde_json::json!({ "status": ["only 'cancelled' can be requested; a challenge completes once both times are recorded"] }),
+            ));
+        }
+        if update.challenger_time.is_some() || update.challenged_time.is_some() {
+            return Err(AppError::validation(
+                "Invalid request body",
+                serde_json::json!({ "status": ["cannot be combined with a time"] }),
+            ));
+        }
+    }
+
+    match status.as_str() {
+        "completed" => {
+            warn!("Défi {} déjà terminé", id);
+            return Err(AppError::Conflict("Challenge already completed".to_string()));
+        }
+        "cancelled" => {
+            warn!("Défi {} annulé", id);
+            return Err(AppError::Conflict("Challenge has been cancelled".to_string()));
+        }
+        "pending" if update.challenger_time.is_some() || update.challenged_time.is_some() => {
+            warn!("Temps saisi sur le défi {} avant son acceptation", id);
+            return Err(AppError::Conflict("Challenge has not been accepted yet".to_string()));
+        }
+        _ => {}
+    }
 
     let challenge = sqlx::query_as::<_, Challenge>(
         "UPDATE challenges
-         SET status = COALESCE($1, status),
-             challenger_time = COALESCE($2, challenger_time),
-             challenged_time = COALESCE($3, challenged_time),
-             winner_id = $4,
-             completed_at = CASE WHEN $1 = 'completed' THEN NOW() ELSE completed_at END
-         WHERE id = $5
+         SET challenger_time = COALESCE($1, challenger_time),
+             challenged_time = COALESCE($2, challenged_time),
+             winner_id = CASE
+                 WHEN COALESCE($1, challenger_time) IS NOT NULL AND COALESCE($2, challenged_time) IS NOT NULL THEN
+                      CASE WHEN COALESCE($1, challenger_time) < COALESCE($2, challenged_time) THEN challenger_id
+                          WHEN COALESCE($1, challenger_time) > COALESCE($2, challenged_time) THEN challenged_id
+                          ELSE NULL END
+                 ELSE NULL
+             END,
+             status = CASE
+                 WHEN $3 = 'cancelled' THEN 'cancelled'
+                 WHEN COALESCE($1, challenger_time) IS NOT NULL AND COALESCE($2, challenged_time) IS NOT NULL THEN 'completed'
+                 ELSE status
+             END,
+             completed_at = CASE
+                 WHEN COALESCE($1, challenger_time) IS NOT NULL AND COALESCE($2, challenged_time) IS NOT NULL THEN NOW()
+                 ELSE completed_at
+             END
+         WHERE id = $4
+           -- Le statut a pu changer depuis la lecture ci-dessus (requête concurrente)
+           AND (status = 'active' OR ($3 = 'cancelled' AND status = 'pending'))
          RETURNING id, route_id, challenger_id, challenged_id, status, challenger_time, challenged_time, winner_id, created_at, completed_at"
     )
-    .bind(update.status)
     .bind(update.challenger_time)
     .bind(update.challenged_time)
-    .bind(winner_id)
+    .bind(update.status)
     .bind(id)
     .fetch_optional(&pool)
     .await
     .map_err(|e| {
         error!("Erreur lors de la complétion du défi: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     match challenge {
         Some(c) => {
-            info!("Défi {} complété", id);
+            info!("Défi {} mis à jour (statut: {})", id, c.status);
             Ok(Json(c))
         }
         None => {
-            warn!("Défi {} non trouvé", id);
-            Err(StatusCode::NOT_FOUND)
+            warn!("Défi {} terminé ou annulé entre-temps", id);
+            Err(AppError::Conflict("Challenge is no longer open".to_string()))
         }
     }
 }
 
+const CHALLENGE_SORTS: &[SortField] = &[
+    SortField { name: "created_at", expr: "COALESCE(created_at, 'epoch')", sql_type: "timestamp", default_order: SortOrder::Desc },
+];
+
+#[derive(Deserialize)]
+struct AvailableChallengesQuery {
+    route_id: Option<i32>,
+}
+
 async fn get_available_challenges(
     Extension(pool): Extension<DbPool>,
-) -> Result<Json<Vec<Challenge>>, StatusCode> {
-    info!("Récupération des défis disponibles");
+    Query(filters): Query<AvailableChallengesQuery>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<Challenge>>, AppError> {
+    info!("Récupération des défis disponibles (parcours: {:?})", filters.route_id);
+    let page = page.resolve(CHALLENGE_SORTS, MAX_LIMIT)?;
 
-    let challenges = sqlx::query_as::<_, Challenge>(
+    let mut query = QueryBuilder::new(
         "SELECT id, route_id, challenger_id, challenged_id, status, challenger_time, challenged_time, winner_id, created_at, completed_at
          FROM challenges
-         WHERE status = 'pending' AND challenged_id IS NULL
-         ORDER BY created_at DESC"
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| {
-        error!("Erreur lors de la récupération des défis disponibles: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+         WHERE status = 'pending' AND challenged_id IS NULL"
+    );
+    if let Some(route_id) = filters.route_id {
+        query.push(" AND route_id = ").push_bind(route_id);
+    }
+    page.push_sql(&mut query, "id");
+
+    let challenges = query
+        .build_query_as::<Challenge>()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération des défis disponibles: {}", e);
+            AppError::from(e)
+        })?;
 
     info!("{} défis disponibles récupérés", challenges.len());
-    Ok(Json(challenges))
+    Ok(Json(page.into_page(challenges, |c, _| (timestamp_key(c.created_at), c.id as i64))))
 }
 
 // ============ Leaderboard Routes ============
 
+/// `?version=current` (défaut), `all`, ou un numéro de révision du parcours.
+#[derive(Deserialize)]
+struct LeaderboardQuery {
+    version: Option<String>,
+}
+
+enum VersionScope {
+    Current,
+    All,
+    Version(i32),
+}
+
+impl LeaderboardQuery {
+    fn scope(&self) -> Result<VersionScope, AppError> {
+        match self.version.as_deref() {
+            None | Some("current") => Ok(VersionScope::Current),
+            Some("all") => Ok(VersionScope::All),
+            Some(v) => v.parse::<i32>().ok().filter(|v| *v > 0).map(VersionScope::Version).ok_or_else(|| {
+                AppError::validation(
+                    "Invalid leaderboard parameters",
+                    serde_json::json!({ "version": ["must be current, all or a positive revision number"] }),
+                )
+            }),
+        }
+    }
+}
+
+/// Meilleur temps vérifié de chaque utilisateur. Par défaut seuls comptent les scores de la
+/// révision courante : un temps réalisé sur un ancien tracé n'est pas comparable.
 async fn get_route_leaderboard(
     Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
     Path(route_id): Path<i32>,
-) -> Result<Json<Vec<LeaderboardEntry>>, StatusCode> {
+    Query(params): Query<LeaderboardQuery>,
+) -> Result<Json<Vec<LeaderboardEntry>>, AppError> {
     info!("Récupération du classement pour le parcours {}", route_id);
+    let scope = params.scope()?;
+    authz::visible_route_owner(&pool, route_id, claims.user_id).await?;
 
-    let leaderboard = sqlx::query_as::<_, LeaderboardEntry>(
-        "SELECT DISTINCT ON (s.user_id)
-            s.user_id, u.username, s.time_seconds, s.max_speed_kmh, s.created_at
-         FROM scores s
-         JOIN users u ON u.id = s.user_id
-         WHERE s.route_id = $1
-         ORDER BY s.user_id, s.time_seconds ASC
+    let mut query = QueryBuilder::new(
+        "SELECT * FROM (
+             SELECT DISTINCT ON (s.user_id)
+                 s.user_id, u.username, s.route_version, s.time_seconds, s.max_speed_kmh, s.created_at
+             FROM scores s
+             JOIN users u ON u.id = s.user_id
+             WHERE s.verification_status = 'verified'
+               AND NOT EXISTS (SELECT 1 FROM score_flags f WHERE f.score_id = s.id AND f.status <> 'dismissed')
+               AND s.route_id = "
+    );
+    query.push_bind(route_id);
+    match scope {
+        VersionScope::Current => {
+            query.push(" AND s.route_version = (SELECT current_version FROM routes WHERE id = ").push_bind(route_id).push(")");
+        }
+        VersionScope::Version(version) => {
+            query.push(" AND s.route_version = ").push_bind(version);
+        }
+        VersionScope::All => {}
+    }
+    query.push(
+        " ORDER BY s.user_id, s.time_seconds ASC
+         ) best
+         ORDER BY best.time_seconds ASC
          LIMIT 100"
-    )
-    .bind(route_id)
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| {
-        error!("Erreur lors de la récupération du classement: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    );
+
+    let leaderboard = query
+        .build_query_as::<LeaderboardEntry>()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération du classement: {}", e);
+            AppError::from(e)
+        })?;
 
     info!("{} entrées récupérées pour le classement du parcours {}", leaderboard.len(), route_id);
     Ok(Json(leaderboard))
 }
 
+/// Meilleure vitesse de pointe de chaque utilisateur, recalculée par le serveur d'après la trace
+/// GPS : la valeur annoncée par le client n'est pas comparable. Une course dont les métriques du
+/// client s'écartent de celles du serveur n'est pas classée.
 async fn get_global_speed_leaderboard(
     Extension(pool): Extension<DbPool>,
-) -> Result<Json<Vec<LeaderboardEntry>>, StatusCode> {
+) -> Result<Json<Vec<LeaderboardEntry>>, AppError> {
     info!("Récupération du classement global des vitesses");
 
     let leaderboard = sqlx::query_as::<_, LeaderboardEntry>(
-        "SELECT DISTINCT ON (s.user_id)
-            s.user_id, u.username, s.time_seconds, s.max_speed_kmh, s.created_at
-         FROM scores s
-         JOIN users u ON u.id = s.user_id
-         WHERE s.max_speed_kmh IS NOT NULL
-         ORDER BY s.user_id, s.max_speed_kmh DESC
+        "SELECT * FROM (
+             SELECT DISTINCT ON (s.user_id)
+                 s.user_id, u.username, s.route_version, s.time_seconds, s.server_max_speed_kmh AS max_speed_kmh, s.created_at
+             FROM scores s
+             JOIN users u ON u.id = s.user_id
+             WHERE s.server_max_speed_kmh IS NOT NULL AND s.metrics_computed_at IS NOT NULL
+               AND cardinality(s.metric_discrepancies) = 0
+               AND s.verification_status = 'verified'
+               AND NOT EXISTS (SELECT 1 FROM score_flags f WHERE f.score_id = s.id AND f.status <> 'dismissed')
+             ORDER BY s.user_id, s.server_max_speed_kmh DESC
+         ) best
+         ORDER BY best.max_speed_kmh DESC
          LIMIT 100"
     )
     .fetch_all(&pool)
     .await
     .map_err(|e| {
         error!("Erreur lors de la récupération du classement des vitesses: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     info!("{} entrées récupérées pour le classement global des vitesses", leaderboard.len());
@@ -1,15 +1,19 @@
 use axum::{
     Json, Router,
-    extract::{Extension, Path},
-    http::StatusCode,
+    extract::Extension,
     routing::{get, post, put}
 };
+use shared::errors::AppError;
+use sqlx::QueryBuilder;
 use tracing::{info, warn, error};
 use shared::jwt::Claims;
 
 use crate::{
+    authz,
     db::DbPool,
     models::friendship::{Friendship, FriendInfo, PendingRequest},
+    pagination::{timestamp_key, Page, PageParams, SortField, SortOrder, MAX_LIMIT},
+    validation::{Path, Query},
 };
 
 pub fn router() -> Router {
@@ -21,43 +25,59 @@ pub fn router() -> Router {
         .route("/pending", get(get_pending_requests))
 }
 
+const FRIEND_SORTS: &[SortField] = &[
+    SortField { name: "username", expr: "username", sql_type: "text", default_order: SortOrder::Asc },
+];
+
+const PENDING_SORTS: &[SortField] = &[
+    SortField { name: "created_at", expr: "COALESCE(f.created_at, 'epoch')", sql_type: "timestamp", default_order: SortOrder::Desc },
+];
+
 async fn get_friends(
     Extension(pool): Extension<DbPool>,
     Extension(claims): Extension<Claims>,
-) -> Result<Json<Vec<FriendInfo>>, StatusCode> {
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<FriendInfo>>, AppError> {
     let user_id = claims.user_id;
     
     info!("Récupération des amis de l'utilisateur {}", user_id);
-    
-    let friends = sqlx::query_as::<_, FriendInfo>(
-        "SELECT u.id, u.username, u.email, f.status
-         FROM friendships f
-         JOIN users u ON u.id = f.friend_id
-         WHERE f.user_id = $1 AND f.status = 'accepted'
-         UNION
-         SELECT u.id, u.username, u.email, f.status
-         FROM friendships f
-         JOIN users u ON u.id = f.user_id
-         WHERE f.friend_id = $1 AND f.status = 'accepted'
-         ORDER BY username"
-    )
-    .bind(user_id)
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| {
-        error!("Erreur lors de la récupération des amis: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    let page = page.resolve(FRIEND_SORTS, MAX_LIMIT)?;
+
+    let mut query = QueryBuilder::new(
+        "SELECT id, username, email, status FROM (
+             SELECT u.id, u.username, u.email, f.status
+             FROM friendships f
+             JOIN users u ON u.id = f.friend_id
+             WHERE f.status = 'accepted' AND f.user_id = "
+    );
+    query.push_bind(user_id).push(
+        " UNION
+             SELECT u.id, u.username, u.email, f.status
+             FROM friendships f
+             JOIN users u ON u.id = f.user_id
+             WHERE f.status = 'accepted' AND f.friend_id = "
+    );
+    query.push_bind(user_id).push(") AS friends WHERE true");
+    page.push_sql(&mut query, "id");
+
+    let friends = query
+        .build_query_as::<FriendInfo>()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération des amis: {}", e);
+            AppError::from(e)
+        })?;
 
     info!("{} amis récupérés pour l'utilisateur {}", friends.len(), user_id);
-    Ok(Json(friends))
+    Ok(Json(page.into_page(friends, |f, _| (f.username.clone(), f.id as i64))))
 }
 
 async fn add_friend(
     Extension(pool): Extension<DbPool>,
     Extension(claims): Extension<Claims>,
     Path(username): Path<String>,
-) -> Result<Json<Friendship>, StatusCode> {
+) -> Result<Json<Friendship>, AppError> {
     let user_id = claims.user_id;
 
     info!("Ajout de l'ami '{}' à l'utilisateur {}", username, user_id);
@@ -70,11 +90,11 @@ async fn add_friend(
     .await
     .map_err(|e| {
         error!("Erreur lors de la recherche de l'utilisateur '{}': {}", username, e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?
     .ok_or_else(|| {
         warn!("Utilisateur '{}' non trouvé", username);
-        StatusCode::NOT_FOUND
+        AppError::NotFound("User not found".to_string())
     })?;
 
     let friendship = sqlx::query_as::<_, Friendship>(
@@ -89,7 +109,7 @@ async fn add_friend(
     .await
     .map_err(|e| {
         error!("Erreur lors de l'ajout de l'ami: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     info!("Demande d'ami envoyée avec succès à '{}' (id={})", username, friend_id);
@@ -98,9 +118,11 @@ async fn add_friend(
 
 async fn accept_friend(
     Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
     Path(friendship_id): Path<i32>,
-) -> Result<Json<Friendship>, StatusCode> {
-    info!("Acceptation de la demande d'ami {}", friendship_id);
+) -> Result<Json<Friendship>, AppError> {
+    info!("Acceptation de la demande d'ami {} par l'utilisateur {}", friendship_id, claims.user_id);
+    ensure_recipient(&pool, &claims, friendship_id).await?;
     
     let friendship = sqlx::query_as::<_, Friendship>(
         "UPDATE friendships
@@ -113,7 +135,7 @@ async fn accept_friend(
     .await
     .map_err(|e| {
         error!("Erreur lors de l'acceptation de la demande d'ami: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     match friendship {
@@ -123,16 +145,18 @@ async fn accept_friend(
         }
         None => {
             warn!("Demande d'ami {} non trouvée", friendship_id);
-            Err(StatusCode::NOT_FOUND)
+            Err(AppError::NotFound("Friend request not found".to_string()))
         }
     }
 }
 
 async fn reject_friend(
     Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
     Path(friendship_id): Path<i32>,
-) -> Result<Json<Friendship>, StatusCode> {
-    info!("Rejet de la demande d'ami {}", friendship_id);
+) -> Result<Json<Friendship>, AppError> {
+    info!("Rejet de la demande d'ami {} par l'utilisateur {}", friendship_id, claims.user_id);
+    ensure_recipient(&pool, &claims, friendship_id).await?;
     
     let friendship = sqlx::query_as::<_, Friendship>(
         "UPDATE friendships
@@ -145,7 +169,7 @@ async fn reject_friend(
     .await
     .map_err(|e| {
         error!("Erreur lors du rejet de la demande d'ami: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     match friendship {
@@ -155,7 +179,7 @@ async fn reject_friend(
         }
         None => {
             warn!("Demande d'ami {} non trouvée", friendship_id);
-            Err(StatusCode::NOT_FOUND)
+            Err(AppError::NotFound("Friend request not found".to_string()))
         }
     }
 }
@@ -163,27 +187,49 @@ async fn reject_friend(
 async fn get_pending_requests(
     Extension(pool): Extension<DbPool>,
     Extension(claims): Extension<Claims>,
-) -> Result<Json<Vec<PendingRequest>>, StatusCode> {
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<PendingRequest>>, AppError> {
     let user_id = claims.user_id;
 
     info!("Récupération des demandes d'ami en attente pour l'utilisateur {}", user_id);
+    let page = page.resolve(PENDING_SORTS, MAX_LIMIT)?;
 
-    let requests = sqlx::query_as::<_, PendingRequest>(
-        "SELECT f.id AS friendship_id, u.id, u.username, u.email, f.status
+    let mut query = QueryBuilder::new(
+        "SELECT f.id AS friendship_id, u.id, u.username, u.email, f.status, f.created_at
          FROM friendships f
          JOIN users u ON u.id = f.user_id
-         WHERE f.friend_id = $1 AND f.status = 'pending'
-         ORDER BY f.created_at DESC"
-    )
-    .bind(user_id)
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| {
-        error!("Erreur lors de la récupération des demandes d'ami: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+         WHERE f.status = 'pending' AND f.friend_id = "
+    );
+    query.push_bind(user_id);
+    page.push_sql(&mut query, "f.id");
+
+    let requests = query
+        .build_query_as::<PendingRequest>()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération des demandes d'ami: {}", e);
+            AppError::from(e)
+        })?;
 
     info!("{} demandes d'ami en attente", requests.len());
-    Ok(Json(requests))
+    Ok(Json(page.into_page(requests, |r, _| (timestamp_key(r.created_at), r.friendship_id as i64))))
 }
 
+/// Seul le destinataire d'une demande peut y répondre (ni l'expéditeur, ni un tiers).
+async fn ensure_recipient(pool: &DbPool, claims: &Claims, friendship_id: i32) -> Result<(), AppError> {
+    let recipient = sqlx::query_scalar::<_, i32>("SELECT friend_id FROM friendships WHERE id = $1")
+        .bind(friendship_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération de la demande d'ami {}: {}", friendship_id, e);
+            AppError::from(e)
+        })?
+        .ok_or_else(|| {
+            warn!("Demande d'ami {} non trouvée", friendship_id);
+            AppError::NotFound("Friend request not found".to_string())
+        })?;
+
+    authz::authorize(claims, &[recipient], None, &format!("la demande d'ami {}", friendship_id))
+}
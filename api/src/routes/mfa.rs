@@ -0,0 +1,424 @@
+use axum::{
+    Json, Router,
+    extract::{ConnectInfo, Extension},
+    http::HeaderMap,
+    middleware,
+    routing::post
+};
+use data_encoding::BASE32_NOPAD;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use shared::jwt::Claims;
+use shared::errors::AppError;
+use std::net::SocketAddr;
+use tracing::{info, warn, error};
+
+use super::auth::{issue_login_tokens, record_attempt, send_unlock_email, LoginResponse};
+use crate::{
+    db::DbPool,
+    login_guard::{self, AttemptOutcome, FailureCounts},
+    mailer::SharedMailer,
+    middleware::auth_middleware,
+    models::auth::{MfaVerify, TotpCode},
+    models::user::User,
+    sessions::hash_token,
+    totp,
+    user_tokens::{self, TokenPurpose},
+    validation::ValidatedJson,
+};
+
+const TOTP_ISSUER: &str = "RMCE";
+const RECOVERY_CODES_COUNT: usize = 10;
+/// Nombre de codes erronés tolérés avant d'invalider le token "MFA pending". Ils comptent aussi
+/// parmi les échecs de connexion du compte, verrouillé comme après des mots de passe erronés.
+const MAX_MFA_ATTEMPTS: i32 = 5;
+
+#[derive(Serialize, Deserialize)]
+pub struct TotpSetupResponse {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct TotpState {
+    totp_secret: Option<String>,
+    totp_enabled: bool,
+    totp_last_step: Option<i64>,
+}
+
+pub fn router() -> Router {
+    let authenticated = Router::new()
+        .route("/setup", post(setup))
+        .route("/confirm", post(confirm))
+        .route("/disable", post(disable))
+        .layer(middleware::from_fn(auth_middleware));
+
+    Router::new()
+        .route("/verify", post(verify))
+        .merge(authenticated)
+}
+
+/// Étape 1 de l'enrôlement : génère un secret, pas encore actif tant qu'il n'est pas confirmé.
+async fn setup(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<TotpSetupResponse>, AppError> {
+    info!("Enrôlement TOTP pour l'utilisateur {}", claims.user_id);
+
+    let state = load_totp_state(&pool, claims.user_id).await?;
+    if state.totp_enabled {
+        warn!("TOTP déjà activé pour l'utilisateur {}", claims.user_id);
+        return Err(AppError::Conflict("Two-factor authentication is already enabled".to_string()));
+    }
+
+    let secret = totp::generate_secret();
+    sqlx::query("UPDATE users SET totp_secret = $1, totp_last_step = NULL WHERE id = $2")
+        .bind(&secret)
+        .bind(claims.user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de l'enregistrement du secret TOTP: {}", e);
+            AppError::from(e)
+        })?;
+
+    Ok(Json(TotpSetupResponse {
+        otpauth_uri: totp::otpauth_uri(&secret, &claims.email, TOTP_ISSUER),
+        secret,
+    }))
+}
+
+/// Étape 2 : un premier code valide active le TOTP et génère les codes de secours.
+async fn confirm(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(req): ValidatedJson<TotpCode>,
+) -> Result<Json<RecoveryCodesResponse>, AppError> {
+    info!("Confirmation de l'enrôlement TOTP pour l'utilisateur {}", claims.user_id);
+
+    let state = load_totp_state(&pool, claims.user_id).await?;
+    if state.totp_enabled {
+        return Err(AppError::Conflict("Two-factor authentication is already enabled".to_string()));
+    }
+    let secret = state.totp_secret.ok_or_else(|| {
+        warn!("Aucun enrôlement TOTP en cours pour l'utilisateur {}", claims.user_id);
+        AppError::BadRequest("No two-factor setup in progress".to_string())
+    })?;
+
+    let step = totp::verify(&secret, &req.code, totp::current_step(), None).ok_or_else(|| {
+        warn!("Code TOTP de confirmation invalide pour l'utilisateur {}", claims.user_id);
+        AppError::BadRequest("Invalid code".to_string())
+    })?;
+
+    let recovery_codes: Vec<String> = (0..RECOVERY_CODES_COUNT).map(|_| generate_recovery_code()).collect();
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("Erreur lors du démarrage de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    sqlx::query("UPDATE users SET totp_enabled = true, totp_last_step = $1 WHERE id = $2")
+        .bind(step as i64)
+        .bind(claims.user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de l'activation du TOTP: {}", e);
+            AppError::from(e)
+        })?;
+
+    sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+        .bind(claims.user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la suppression des anciens codes de secours: {}", e);
+            AppError::from(e)
+        })?;
+
+    for code in &recovery_codes {
+        sqlx::query("INSERT INTO mfa_recovery_codes (user_id, code_hash) VALUES ($1, $2)")
+            .bind(claims.user_id)
+            .bind(hash_token(&normalize_recovery_code(code)))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("Erreur lors de l'enregistrement des codes de secours: {}", e);
+                AppError::from(e)
+            })?;
+    }
+
+    tx.commit().await.map_err(|e| {
+        error!("Erreur lors de la validation de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    info!("TOTP activé pour l'utilisateur {}", claims.user_id);
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// Soumis au même verrouillage que `verify` : un token d'accès volé ne suffit pas à deviner le
+/// code pour retirer le second facteur.
+async fn disable(
+    Extension(pool): Extension<DbPool>,
+    Extension(mailer): Extension<SharedMailer>,
+    Extension(claims): Extension<Claims>,
+    peer: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    ValidatedJson(req): ValidatedJson<TotpCode>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Désactivation du TOTP pour l'utilisateur {}", claims.user_id);
+
+    let state = load_totp_state(&pool, claims.user_id).await?;
+    if !state.totp_enabled {
+        return Err(AppError::BadRequest("Two-factor authentication is not enabled".to_string()));
+    }
+
+    let user = load_user(&pool, claims.user_id).await?;
+    let email_key = login_guard::normalize_email(&user.email);
+    let ip = login_guard::client_ip(&headers, peer.map(|Extension(ConnectInfo(addr))| addr));
+    let counts = guard_second_factor(&pool, user.id, &email_key, ip.as_deref()).await?;
+
+    if !check_second_factor(&pool, claims.user_id, &req.code).await? {
+        warn!("Code invalide lors de la désactivation du TOTP pour l'utilisateur {}", claims.user_id);
+        record_second_factor_failure(&pool, &mailer, &user, &email_key, ip.as_deref(), &counts).await?;
+        return Err(AppError::Unauthorized("Invalid code".to_string()));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("Erreur lors du démarrage de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    sqlx::query("UPDATE users SET totp_enabled = false, totp_secret = NULL, totp_last_step = NULL WHERE id = $1")
+        .bind(claims.user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la désactivation du TOTP: {}", e);
+            AppError::from(e)
+        })?;
+
+    sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+        .bind(claims.user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la suppression des codes de secours: {}", e);
+            AppError::from(e)
+        })?;
+
+    tx.commit().await.map_err(|e| {
+        error!("Erreur lors de la validation de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    info!("TOTP désactivé pour l'utilisateur {}", claims.user_id);
+    Ok(Json(serde_json::json!({
+        "message": "Two-factor authentication disabled"
+    })))
+}
+
+/// Seconde étape de connexion : échange le token "MFA pending" et un code contre les tokens de session.
+async fn verify(
+    Extension(pool): Extension<DbPool>,
+    Extension(mailer): Extension<SharedMailer>,
+    peer: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    ValidatedJson(req): ValidatedJson<MfaVerify>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let user_id = user_tokens::find_valid(&pool, &req.mfa_token, TokenPurpose::MfaPending)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la vérification du token MFA: {}", e);
+            AppError::from(e)
+        })?
+        .ok_or_else(|| {
+            warn!("Token MFA invalide, expiré ou déjà utilisé");
+            AppError::Unauthorized("Invalid or expired MFA token".to_string())
+        })?;
+
+    info!("Vérification du second facteur pour l'utilisateur {}", user_id);
+
+    let user = load_user(&pool, user_id).await?;
+
+    // Un nouveau login ne donne pas de nouveaux essais : les échecs sont comptés sur le compte
+    let email_key = login_guard::normalize_email(&user.email);
+    let ip = login_guard::client_ip(&headers, peer.map(|Extension(ConnectInfo(addr))| addr));
+    let counts = guard_second_factor(&pool, user_id, &email_key, ip.as_deref()).await?;
+
+    if !check_second_factor(&pool, user_id, &req.code).await? {
+        warn!("Code de second facteur invalide pour l'utilisateur {} ({} échecs)", user_id, counts.account + 1);
+        user_tokens::record_failed_attempt(&pool, &req.mfa_token, TokenPurpose::MfaPending, MAX_MFA_ATTEMPTS)
+            .await
+            .map_err(|e| {
+                error!("Erreur lors de l'enregistrement de l'échec MFA: {}", e);
+                AppError::from(e)
+            })?;
+        record_second_factor_failure(&pool, &mailer, &user, &email_key, ip.as_deref(), &counts).await?;
+        return Err(AppError::Unauthorized("Invalid code".to_string()));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("Erreur lors du démarrage de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    // Le token a pu être consommé entre-temps par une requête concurrente
+    user_tokens::consume(&mut tx, &req.mfa_token, TokenPurpose::MfaPending)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la consommation du token MFA: {}", e);
+            AppError::from(e)
+        })?
+        .ok_or_else(|| AppError::Unauthorized("Invalid or expired MFA token".to_string()))?;
+
+    tx.commit().await.map_err(|e| {
+        error!("Erreur lors de la validation de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    record_attempt(&pool, &email_key, ip.as_deref(), AttemptOutcome::Success).await?;
+    let response = issue_login_tokens(&pool, user).await?;
+    info!("Connexion réussie (2FA) pour l'utilisateur: {}", response.user.username);
+    Ok(Json(response))
+}
+
+async fn load_user(pool: &DbPool, user_id: i32) -> Result<User, AppError> {
+    sqlx::query_as::<_, User>(
+        "SELECT id, username, email, email_verified FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la récupération de l'utilisateur {}: {}", user_id, e);
+        AppError::from(e)
+    })
+}
+
+/// Échecs de connexion du compte et de l'IP : 429 si l'un ou l'autre a atteint sa limite.
+async fn guard_second_factor(pool: &DbPool, user_id: i32, email_key: &str, ip: Option<&str>) -> Result<FailureCounts, AppError> {
+    let counts = login_guard::failure_counts(pool, email_key, ip)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors du comptage des tentatives de connexion: {}", e);
+            AppError::from(e)
+        })?;
+    if counts.ip_blocked() {
+        warn!("Trop d'échecs de connexion depuis l'IP {}", ip.unwrap_or("inconnue"));
+        return Err(AppError::RateLimited("Too many failed login attempts from this address, try again later".to_string()));
+    }
+    if counts.account_locked() {
+        warn!("Second facteur refusé : compte de l'utilisateur {} temporairement verrouillé", user_id);
+        return Err(AppError::RateLimited("Too many failed login attempts, try again later or use the unlock link sent by email".to_string()));
+    }
+    Ok(counts)
+}
+
+/// Compte un code erroné parmi les échecs de connexion, envoie le lien de déverrouillage quand
+/// le compte se verrouille, puis applique le délai progressif.
+async fn record_second_factor_failure(
+    pool: &DbPool,
+    mailer: &SharedMailer,
+    user: &User,
+    email_key: &str,
+    ip: Option<&str>,
+    counts: &FailureCounts,
+) -> Result<(), AppError> {
+    record_attempt(pool, email_key, ip, AttemptOutcome::Failure).await?;
+
+    let failures = counts.account + 1;
+    if failures == login_guard::MAX_FAILURES_PER_ACCOUNT {
+        warn!("Compte de l'utilisateur {} verrouillé pour {} minutes", user.id, login_guard::ATTEMPT_WINDOW_MINUTES);
+        if let Err(e) = send_unlock_email(pool, mailer, user.id, &user.username, &user.email).await {
+            error!("Erreur lors de l'envoi de l'email de déverrouillage à l'utilisateur {}: {}", user.id, e);
+        }
+    }
+
+    tokio::time::sleep(login_guard::delay_after(failures)).await;
+    Ok(())
+}
+
+async fn load_totp_state(pool: &DbPool, user_id: i32) -> Result<TotpState, AppError> {
+    sqlx::query_as::<_, TotpState>(
+        "SELECT totp_secret, totp_enabled, totp_last_step FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la récupération de l'état TOTP: {}", e);
+        AppError::from(e)
+    })?
+    .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+}
+
+/// Accepte un code TOTP (jamais deux fois le même pas) ou consomme un code de secours.
+async fn check_second_factor(pool: &DbPool, user_id: i32, code: &str) -> Result<bool, AppError> {
+    let state = load_totp_state(pool, user_id).await?;
+    let (Some(secret), true) = (state.totp_secret, state.totp_enabled) else {
+        return Ok(false);
+    };
+
+    let last_step = state.totp_last_step.map(|s| s as u64);
+    if let Some(step) = totp::verify(&secret, code, totp::current_step(), last_step) {
+        // Mise à jour conditionnelle : deux requêtes simultanées ne peuvent pas utiliser le même code
+        let result = sqlx::query(
+            "UPDATE users SET totp_last_step = $1
+             WHERE id = $2 AND (totp_last_step IS NULL OR totp_last_step < $1)"
+        )
+        .bind(step as i64)
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de l'enregistrement du pas TOTP: {}", e);
+            AppError::from(e)
+        })?;
+        return Ok(result.rows_affected() > 0);
+    }
+
+    let used = sqlx::query_scalar::<_, i32>(
+        "UPDATE mfa_recovery_codes SET used_at = NOW()
+         WHERE id = (
+            SELECT id FROM mfa_recovery_codes
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            LIMIT 1
+         )
+         RETURNING id"
+    )
+    .bind(user_id)
+    .bind(hash_token(&normalize_recovery_code(code)))
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la vérification du code de secours: {}", e);
+        AppError::from(e)
+    })?;
+
+    if used.is_some() {
+        info!("Code de secours utilisé par l'utilisateur {}", user_id);
+    }
+    Ok(used.is_some())
+}
+
+/// Code de secours lisible, ex. `k3j9d-x7m2q` (50 bits d'entropie).
+fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; 7];
+    rand::rng().fill_bytes(&mut bytes);
+    let encoded = BASE32_NOPAD.encode(&bytes).to_lowercase();
+    format!("{}-{}", &encoded[..5], &encoded[5..10])
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
@@ -3,11 +3,16 @@ use axum::{
     middleware,
     Extension, Router
 };
-use tower_http::trace::TraceLayer;
+use shared::errors::AppError;
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
+};
 use tracing::Level;
 
 use crate::db::DbPool;
-use crate::middleware::auth_middleware;
+use crate::mailer::{self, SharedMailer};
+use crate::middleware::{auth_middleware, error_request_id_middleware};
 
 pub mod posts;
 pub mod users;
@@ -16,13 +21,23 @@ pub mod parcours;
 pub mod friends;
 pub mod challenges;
 pub mod sensor_data;
+pub mod well_known;
+pub mod mfa;
+pub mod admin;
+pub mod segments;
 
 pub fn create_app(pool: DbPool) -> Router {
+    create_app_with_mailer(pool, mailer::from_env())
+}
+
+pub fn create_app_with_mailer(pool: DbPool, mailer: SharedMailer) -> Router {
     let protected_routes = Router::new()
         .nest("/routes", parcours::router())
         .nest("/friends", friends::router())
         .nest("/api", challenges::router())
         .nest("/sensor-data", sensor_data::router())
+        .nest("/admin", admin::router())
+        .nest("/segments", segments::router())
         .layer(middleware::from_fn(auth_middleware));
 
     Router::new()
@@ -31,18 +46,29 @@ pub fn create_app(pool: DbPool) -> Router {
         .nest("/auth", auth::router())
         .nest("/posts", posts::router())
         .nest("/users", users::router())
+        .nest("/.well-known", well_known::router())
+        .nest("/shared", parcours::shared_router())
         // Protected routes
         .merge(protected_routes)
+        .fallback(|| async { AppError::NotFound("No such endpoint".to_string()) })
+        .layer(middleware::from_fn(error_request_id_middleware))
         .layer(Extension(pool))
+        .layer(Extension(mailer))
         .layer(
             TraceLayer::new_for_http()
                 .make_span_with(|request: &axum::http::Request<_>| {
+                    let request_id = request
+                        .headers()
+                        .get("x-request-id")
+                        .and_then(|h| h.to_str().ok())
+                        .unwrap_or_default();
                     tracing::span!(
                         Level::INFO,
                         "http_request",
                         method = %request.method(),
                         uri = %request.uri(),
                         version = ?request.version(),
+                        request_id = %request_id,
                     )
                 })
                 .on_request(|request: &axum::http::Request<_>, _span: &tracing::Span| {
@@ -67,5 +93,7 @@ pub fn create_app(pool: DbPool) -> Router {
                     );
                 })
         )
+        // Identifiant de requête généré (ou repris du client) et renvoyé dans `x-request-id`
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
 }
-
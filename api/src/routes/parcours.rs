@@ -1,240 +1,1482 @@
 use axum::{
     Json, Router,
-    extract::{Extension, Path, Query},
-    http::StatusCode,
-    routing::{get, post}
+    body::Bytes,
+    extract::{DefaultBodyLimit, Extension},
+    http::{
+        HeaderMap, StatusCode,
+        header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE, ETAG, IF_NONE_MATCH, LAST_MODIFIED},
+    },
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put}
 };
+use shared::errors::AppError;
 use tracing::{info, warn, error};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use sqlx::QueryBuilder;
 
 use shared::jwt::Claims;
 
 use crate::{
+    anti_cheat,
+    authz,
     db::DbPool,
-    models::route::{CreateRoute, Route, UpdateRoute},
-    models::score::{CreateScore, Score},
+    elevation::{self, ElevationProfile},
+    geometry::{self, Position, RouteMetrics},
+    models::route::{
+        self, CreateRoute, CreatedShareLink, FacetCount, NearbyRoute, Route, RouteSearchHit, RouteSearchResults, RouteVersion,
+        RouteShareLink, RouteVersionSummary, SearchFacets, UpdateRoute, DESCRIPTION_MAX_CHARS, DIFFICULTIES, NAME_MAX_CHARS,
+        ROUTE_COLUMNS, ROUTE_VERSION_COLUMNS, SURFACES, VISIBILITIES,
+    },
+    models::review::{RouteReview, UpsertReview, REVIEW_COLUMNS},
+    models::score::{CreateScore, Score, SCORE_COLUMNS},
+    route_formats::{self, ExportedRoute, RouteFormat},
+    sessions,
+    pagination::{escape_like, timestamp_key, Page, PageParams, SortField, SortOrder, DEFAULT_LIMIT, MAX_LIMIT},
+    spatial::{self, BoundingBox},
+    thumbnail::{self, ThumbnailFormat, ThumbnailOptions},
+    validation::{Path, Query, Validate, ValidatedJson, ValidationErrors},
 };
 
+/// Une trace GPX d'une journée complète dépasse facilement la limite par défaut de 2 Mo.
+const IMPORT_MAX_BYTES: usize = 10 * 1024 * 1024;
+
 pub fn router() -> Router {
     Router::new()
         .route("/", get(get_routes).post(create_route))
+        .route("/import", post(import_route).layer(DefaultBodyLimit::max(IMPORT_MAX_BYTES)))
+        .route("/{id}/export", get(export_route))
+        .route("/{id}/profile", get(get_route_profile))
+        .route("/{id}/thumbnail.{format}", get(get_route_thumbnail))
+        .route("/{id}/versions", get(get_route_versions))
+        .route("/{id}/versions/{version}", get(get_route_version))
+        .route("/{id}/fork", post(fork_route))
+        .route("/{id}/forks", get(get_route_forks))
+        .route("/{id}/review", put(upsert_review).delete(delete_review))
+        .route("/{id}/reviews", get(get_route_reviews))
+        .route("/{id}/favorite", post(add_favorite).delete(remove_favorite))
+        .route("/favorites", get(get_favorite_routes))
+        .route("/{id}/share-links", get(get_share_links).post(create_share_link))
+        .route("/{id}/share-links/{link_id}", delete(revoke_share_link))
+        .route("/nearby", get(get_nearby_routes))
+        .route("/within", get(get_routes_within))
+        .route("/search", get(search_routes))
         .route("/{id}", get(get_route).put(update_route).delete(delete_route))
         .route("/{id}/score", post(submit_score))
         .route("/user/{user_id}", get(get_user_routes))
         .route("/public", get(get_public_routes))
 }
 
+/// Accès sans connexion aux parcours `unlisted` par lien de partage, monté sous `/shared`.
+pub fn shared_router() -> Router {
+    Router::new()
+        .route("/routes/{token}", get(get_shared_route))
+        .route("/routes/{token}/thumbnail.{format}", get(get_shared_route_thumbnail))
+}
+
+/// Rayon maximal de `/routes/nearby`.
+const MAX_NEARBY_RADIUS_METERS: f64 = 200_000.0;
+
+const ROUTE_SORTS: &[SortField] = &[
+    SortField { name: "created_at", expr: "COALESCE(created_at, 'epoch')", sql_type: "timestamp", default_order: SortOrder::Desc },
+    SortField { name: "distance", expr: "COALESCE(distance_meters, 0)", sql_type: "real", default_order: SortOrder::Asc },
+    SortField { name: "name", expr: "name", sql_type: "text", default_order: SortOrder::Asc },
+];
+
 #[derive(Deserialize)]
 struct RouteQuery {
     user_id: Option<i32>,
     is_public: Option<bool>,
+    visibility: Option<String>,
+    /// Recherche sur le nom (contient, insensible à la casse).
+    q: Option<String>,
+    min_distance: Option<f32>,
+    max_distance: Option<f32>,
+    forked_from: Option<i32>,
+    /// Réservé à `/routes/favorites` : les favoris d'un autre utilisateur ne sont pas filtrables.
+    #[serde(skip)]
+    favorited_by: Option<i32>,
 }
 
 async fn create_route(
     Extension(pool): Extension<DbPool>,
     Extension(claims): Extension<Claims>,
-    Json(new_route): Json<CreateRoute>,
-) -> Result<Json<Route>, StatusCode> {
+    ValidatedJson(new_route): ValidatedJson<CreateRoute>,
+) -> Result<Json<Route>, AppError> {
     let user_id = claims.user_id;
 
     info!("Création d'un nouveau parcours: {} par l'utilisateur {}", new_route.name, user_id);
 
-    let route = sqlx::query_as::<_, Route>(
-        "INSERT INTO routes (user_id, name, description, is_public, path_data, distance_meters)
-         VALUES ($1, $2, $3, $4, $5, $6)
-         RETURNING id, user_id, name, description, is_public, path_data, distance_meters, created_at, updated_at"
-    )
-    .bind(user_id)
-    .bind(&new_route.name)
-    .bind(&new_route.description)
-    .bind(new_route.is_public)
-    .bind(&new_route.path_data)
-    .bind(new_route.distance_meters)
-    .fetch_one(&pool)
-    .await
-    .map_err(|e| {
+    let metrics = route_metrics(&new_route.path_data)?;
+    info!("Parcours de {:.0} m calculé à partir du tracé", metrics.distance_meters);
+
+    let route = insert_route(&pool, user_id, &new_route, &metrics, None).await.map_err(|e| {
         error!("Erreur lors de la création du parcours: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     info!("Parcours créé avec succès: {} (ID: {})", route.name, route.id);
     Ok(Json(route))
 }
 
-async fn get_routes(
+/// Valeurs qui remplacent celles du fichier importé ou du parcours copié.
+#[derive(Deserialize)]
+struct RouteOverrides {
+    name: Option<String>,
+    description: Option<String>,
+    is_public: Option<bool>,
+    visibility: Option<String>,
+}
+
+/// Le corps est le fichier GPX, KML ou TCX lui-même ; le format est détecté à partir du contenu.
+/// Nom et description sont repris du fichier sauf s'ils sont passés en paramètres.
+async fn import_route(
     Extension(pool): Extension<DbPool>,
-    Query(params): Query<RouteQuery>,
-) -> Result<Json<Vec<Route>>, StatusCode> {
-    info!("Récupération des parcours avec filtres: user_id={:?}, is_public={:?}", params.user_id, params.is_public);
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<RouteOverrides>,
+    body: Bytes,
+) -> Result<Json<Route>, AppError> {
+    let content = std::str::from_utf8(&body)
+        .map_err(|_| AppError::BadRequest("The file must be UTF-8 encoded".to_string()))?;
+
+    let imported = route_formats::import(content.trim_start_matches('\u{feff}')).map_err(|message| {
+        warn!("Import de parcours refusé pour l'utilisateur {}: {}", claims.user_id, message);
+        let mut errors = ValidationErrors::new();
+        errors.add("file", message);
+        AppError::from(errors)
+    })?;
+    info!("Import d'un fichier {} de {} points par l'utilisateur {}", imported.format.extension(), imported.positions.len(), claims.user_id);
+
+    let truncate = |value: String, max: usize| value.chars().take(max).collect::<String>();
+    let new_route = CreateRoute {
+        name: params
+            .name
+            .or_else(|| imported.name.map(|n| truncate(n, NAME_MAX_CHARS)))
+            .unwrap_or_else(|| "Imported route".to_string()),
+        description: params
+            .description
+            .or_else(|| imported.description.map(|d| truncate(d, DESCRIPTION_MAX_CHARS))),
+        is_public: params.is_public,
+        visibility: params.visibility,
+        path_data: route_formats::to_path_data(&imported.positions),
+        tags: Vec::new(),
+        surface: None,
+        difficulty: None,
+        is_loop: false,
+        is_out_and_back: false,
+    };
+    new_route.validate()?;
 
-    let mut query = "SELECT id, user_id, name, description, is_public, path_data, distance_meters, created_at, updated_at FROM routes WHERE 1=1".to_string();
+    let metrics = route_metrics(&new_route.path_data)?;
+    let route = insert_route(&pool, claims.user_id, &new_route, &metrics, None).await.map_err(|e| {
+        error!("Erreur lors de l'import du parcours: {}", e);
+        AppError::from(e)
+    })?;
+
+    info!("Parcours importé avec succès: {} (ID: {})", route.name, route.id);
+    Ok(Json(route))
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    format: Option<String>,
+}
 
-    if params.user_id.is_some() {
-        query.push_str(" AND user_id = $1");
+async fn export_route(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+    Query(params): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    let format = params
+        .format
+        .as_deref()
+        .unwrap_or("gpx")
+        .parse::<RouteFormat>()
+        .map_err(|message| AppError::validation("Invalid export format", serde_json::json!({ "format": [message] })))?;
+    info!("Export du parcours {} au format {}", id, format.extension());
+
+    let route = fetch_visible_route(&pool, id, claims.user_id).await?;
+
+    // Les parcours antérieurs à la validation du tracé peuvent contenir n'importe quoi
+    let positions = geometry::parse_path(&route.path_data).map_err(|message| {
+        warn!("Tracé du parcours {} inexploitable pour l'export: {}", id, message);
+        AppError::Unprocessable(format!("The stored path cannot be exported: {}", message))
+    })?;
+
+    let body = route_formats::export(&ExportedRoute {
+        name: &route.name,
+        description: route.description.as_deref(),
+        distance_meters: route.distance_meters,
+        positions: &positions,
+    }, format);
+
+    let disposition = format!("attachment; filename=\"route-{}.{}\"", id, format.extension());
+    Ok(([(CONTENT_TYPE, format.content_type().to_string()), (CONTENT_DISPOSITION, disposition)], body).into_response())
+}
+
+/// Origine des altitudes d'un profil.
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ProfileSource {
+    PathData,
+    SensorData,
+}
+
+#[derive(Serialize)]
+struct RouteProfile {
+    route_id: i32,
+    source: ProfileSource,
+    /// Course dont les relevés ont servi, si `source` vaut `sensor_data`.
+    score_id: Option<i32>,
+    #[serde(flatten)]
+    profile: ElevationProfile,
+}
+
+/// Profil d'altitude tiré de `path_data` ; à défaut d'altitude sur chaque position, de la course
+/// vérifiée et non signalée sur la révision courante qui a le plus de relevés GPS avec altitude.
+/// Les relevés des autres courses ne sont visibles que de leur auteur.
+async fn get_route_profile(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+) -> Result<Json<RouteProfile>, AppError> {
+    info!("Calcul du profil d'altitude du parcours {}", id);
+
+    let path_data = fetch_visible_route(&pool, id, claims.user_id).await?.path_data;
+
+    if let Some(profile) = geometry::parse_path(&path_data).ok().and_then(|positions| elevation::profile(&positions)) {
+        return Ok(Json(RouteProfile { route_id: id, source: ProfileSource::PathData, score_id: None, profile }));
     }
-    if params.is_public.is_some() {
-        query.push_str(" AND is_public = $2");
+
+    let score_id = sqlx::query_scalar::<_, i32>(
+        "SELECT sd.score_id FROM sensor_data sd
+         JOIN scores s ON s.id = sd.score_id
+         JOIN routes ON routes.id = s.route_id
+         WHERE s.route_id = $1 AND s.route_version = routes.current_version
+           AND s.verification_status = 'verified'
+           AND NOT EXISTS (SELECT 1 FROM score_flags f WHERE f.score_id = s.id AND f.status <> 'dismissed')
+           AND sd.latitude IS NOT NULL AND sd.longitude IS NOT NULL AND sd.altitude IS NOT NULL
+         GROUP BY sd.score_id
+         ORDER BY COUNT(*) DESC, sd.score_id
+         LIMIT 1"
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la recherche de relevés pour le parcours {}: {}", id, e);
+        AppError::from(e)
+    })?;
+
+    let profile = match score_id {
+        Some(score_id) => {
+            let rows = sqlx::query_as::<_, (f32, f32, f32)>(
+                "SELECT latitude, longitude, altitude FROM sensor_data
+                 WHERE score_id = $1 AND latitude IS NOT NULL AND longitude IS NOT NULL AND altitude IS NOT NULL
+                 ORDER BY timestamp_offset_ms, id"
+            )
+            .bind(score_id)
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| {
+                error!("Erreur lors de la récupération des relevés de la course {}: {}", score_id, e);
+                AppError::from(e)
+            })?;
+
+            let positions: Vec<Position> = rows
+                .into_iter()
+                .map(|(lat, lon, ele)| Position { lon: lon as f64, lat: lat as f64, ele: Some(ele as f64) })
+                .collect();
+            elevation::profile(&positions).map(|profile| (score_id, profile))
+        }
+        None => None,
+    };
+
+    match profile {
+        Some((score_id, profile)) => {
+            info!("Profil du parcours {} calculé à partir de la course {}", id, score_id);
+            Ok(Json(RouteProfile { route_id: id, source: ProfileSource::SensorData, score_id: Some(score_id), profile }))
+        }
+        None => {
+            warn!("Aucune altitude disponible pour le parcours {}", id);
+            Err(AppError::NotFound("No elevation data for this route".to_string()))
+        }
     }
-    query.push_str(" ORDER BY created_at DESC");
+}
 
-    let mut db_query = sqlx::query_as::<_, Route>(&query);
+async fn get_routes(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Query(filters): Query<RouteQuery>,
+    Query(page): Query<PageParams>,
+    Query(geometry): Query<GeometryQuery>,
+) -> Result<Json<Page<Route>>, AppError> {
+    info!("Récupération des parcours avec filtres: user_id={:?}, is_public={:?}", filters.user_id, filters.is_public);
+    list_routes(&pool, claims.user_id, filters, page, geometry).await
+}
 
-    if let Some(uid) = params.user_id {
-        db_query = db_query.bind(uid);
+/// Liste paginée commune à `/routes`, `/routes/user/{id}`, `/routes/public`, `/routes/{id}/forks`
+/// et `/routes/favorites`, restreinte aux parcours visibles par `viewer`.
+async fn list_routes(
+    pool: &DbPool,
+    viewer: i32,
+    filters: RouteQuery,
+    page: PageParams,
+    geometry: GeometryQuery,
+) -> Result<Json<Page<Route>>, AppError> {
+    let page = page.resolve(ROUTE_SORTS, MAX_LIMIT)?;
+    let mode = geometry.resolve()?;
+    let mut errors = ValidationErrors::new();
+    errors.optional_one_of("visibility", filters.visibility.as_deref(), VISIBILITIES);
+    errors.into_result()?;
+
+    let mut query = QueryBuilder::new(
+        format!("SELECT {} FROM routes WHERE true", ROUTE_COLUMNS)
+    );
+    if let Some(user_id) = filters.user_id {
+        query.push(" AND user_id = ").push_bind(user_id);
+    }
+    if let Some(is_public) = filters.is_public {
+        query.push(" AND is_public = ").push_bind(is_public);
+    }
+    if let Some(visibility) = filters.visibility {
+        query.push(" AND visibility = ").push_bind(visibility);
     }
-    if let Some(public) = params.is_public {
-        db_query = db_query.bind(public);
+    if let Some(q) = &filters.q {
+        query.push(" AND name ILIKE ").push_bind(format!("%{}%", escape_like(q)));
     }
+    if let Some(min) = filters.min_distance {
+        query.push(" AND distance_meters >= ").push_bind(min);
+    }
+    if let Some(max) = filters.max_distance {
+        query.push(" AND distance_meters <= ").push_bind(max);
+    }
+    if let Some(forked_from) = filters.forked_from {
+        query.push(" AND forked_from = ").push_bind(forked_from);
+    }
+    if let Some(user_id) = filters.favorited_by {
+        query.push(" AND id IN (SELECT route_id FROM route_favorites WHERE user_id = ").push_bind(user_id).push(")");
+    }
+    authz::push_route_visible_to(&mut query, viewer);
+    page.push_sql(&mut query, "id");
 
-    let routes = db_query
-        .fetch_all(&pool)
+    let mut routes = query
+        .build_query_as::<Route>()
+        .fetch_all(pool)
         .await
         .map_err(|e| {
             error!("Erreur lors de la récupération des parcours: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            AppError::from(e)
         })?;
 
     info!("{} parcours récupérés", routes.len());
-    Ok(Json(routes))
+    routes.iter_mut().for_each(|r| mode.apply(r));
+    Ok(Json(page.into_page(routes, |r, sort| {
+        let key = match sort {
+            "distance" => r.distance_meters.unwrap_or(0.0).to_string(),
+            "name" => r.name.clone(),
+            _ => timestamp_key(r.created_at),
+        };
+        (key, r.id as i64)
+    })))
 }
 
-async fn get_route(
+/// Tolérance de `?geometry=simplified` quand `tolerance_m` est absent.
+const DEFAULT_SIMPLIFY_TOLERANCE_METERS: f64 = 10.0;
+const MAX_SIMPLIFY_TOLERANCE_METERS: f64 = 1_000.0;
+
+/// `?geometry=full|simplified|polyline|none&tolerance_m=10` sur les lectures de parcours.
+#[derive(Deserialize)]
+struct GeometryQuery {
+    geometry: Option<String>,
+    tolerance_m: Option<f64>,
+}
+
+#[derive(Clone, Copy)]
+enum GeometryMode {
+    Full,
+    Simplified(f64),
+    /// Tracé encodé, simplifié seulement si `tolerance_m` est fourni.
+    Polyline(Option<f64>),
+    None,
+}
+
+impl GeometryQuery {
+    fn resolve(&self) -> Result<GeometryMode, AppError> {
+        let mut errors = ValidationErrors::new();
+        errors.optional_range("tolerance_m", self.tolerance_m, 0.1, MAX_SIMPLIFY_TOLERANCE_METERS);
+        let mode = match self.geometry.as_deref().unwrap_or("full") {
+            "full" => GeometryMode::Full,
+            "simplified" => GeometryMode::Simplified(self.tolerance_m.unwrap_or(DEFAULT_SIMPLIFY_TOLERANCE_METERS)),
+            "polyline" => GeometryMode::Polyline(self.tolerance_m),
+            "none" => GeometryMode::None,
+            _ => {
+                errors.add("geometry", "must be one of: full, simplified, polyline, none");
+                GeometryMode::Full
+            }
+        };
+        errors.into_result()?;
+        Ok(mode)
+    }
+}
+
+impl GeometryMode {
+    /// Réécrit `path_data` selon le mode. Un tracé illisible (antérieur à la validation) est laissé tel quel.
+    fn apply(self, route: &mut Route) {
+        let positions = match self {
+            GeometryMode::Full => return,
+            GeometryMode::None => {
+                route.path_data = serde_json::Value::Null;
+                return;
+            }
+            GeometryMode::Simplified(_) | GeometryMode::Polyline(_) => match geometry::parse_path(&route.path_data) {
+                Ok(positions) => positions,
+                Err(e) => {
+                    warn!("Tracé du parcours {} illisible, renvoyé complet: {}", route.id, e);
+                    return;
+                }
+            },
+        };
+
+        match self {
+            GeometryMode::Simplified(tolerance) => {
+                route.path_data = route_formats::to_path_data(&geometry::simplify(&positions, tolerance));
+            }
+            GeometryMode::Polyline(tolerance) => {
+                let positions = match tolerance {
+                    Some(tolerance) => geometry::simplify(&positions, tolerance),
+                    None => positions,
+                };
+                route.polyline = Some(geometry::encode_polyline(&positions));
+                route.path_data = serde_json::Value::Null;
+            }
+            GeometryMode::Full | GeometryMode::None => {}
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct NearbyQuery {
+    lat: Option<f64>,
+    lng: Option<f64>,
+    radius_m: Option<f64>,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct WithinQuery {
+    bbox: Option<String>,
+    limit: Option<i64>,
+}
+
+/// Parcours dont le départ est à moins de `radius_m` mètres, du plus proche au plus lointain.
+async fn get_nearby_routes(
     Extension(pool): Extension<DbPool>,
-    Path(id): Path<i32>,
-) -> Result<Json<Route>, StatusCode> {
-    info!("Récupération du parcours avec ID: {}", id);
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<NearbyQuery>,
+) -> Result<Json<Vec<NearbyRoute>>, AppError> {
+    let mut errors = ValidationErrors::new();
+    for (field, value) in [("lat", params.lat), ("lng", params.lng), ("radius_m", params.radius_m)] {
+        if value.is_none() {
+            errors.add(field, "is required");
+        }
+    }
+    errors.optional_range("lat", params.lat, -90.0, 90.0);
+    errors.optional_range("lng", params.lng, -180.0, 180.0);
+    errors.optional_range("radius_m", params.radius_m, 1.0, MAX_NEARBY_RADIUS_METERS);
+    errors.optional_range("limit", params.limit, 1, MAX_LIMIT);
+    errors.into_result()?;
+    let (Some(lat), Some(lng), Some(radius_m)) = (params.lat, params.lng, params.radius_m) else {
+        unreachable!("champs requis vérifiés ci-dessus");
+    };
+    info!("Recherche des parcours à moins de {} m de ({}, {})", radius_m, lat, lng);
 
-    let route = sqlx::query_as::<_, Route>(
-        "SELECT id, user_id, name, description, is_public, path_data, distance_meters, created_at, updated_at
-         FROM routes WHERE id = $1"
-    )
-    .bind(id)
-    .fetch_optional(&pool)
-    .await
-    .map_err(|e| {
-        error!("Erreur lors de la récupération du parcours {}: {}", id, e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    let postgis = spatial::postgis_enabled(&pool).await;
+    let mut query = QueryBuilder::new(format!("SELECT {}, ", ROUTE_COLUMNS));
+    spatial::push_distance_from_start(&mut query, lat, lng, postgis);
+    query.push(" AS distance_from_meters FROM routes WHERE start_lat IS NOT NULL");
+    authz::push_route_visible_to(&mut query, claims.user_id);
+    spatial::push_start_within(&mut query, lat, lng, radius_m, postgis);
+    query
+        .push(" ORDER BY distance_from_meters, id LIMIT ")
+        .push_bind(params.limit.unwrap_or(DEFAULT_LIMIT));
+
+    fetch_nearby(&pool, query).await
+}
+
+/// Parcours dont l'emprise recoupe `bbox`, du départ le plus proche du centre au plus lointain.
+async fn get_routes_within(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<WithinQuery>,
+) -> Result<Json<Vec<NearbyRoute>>, AppError> {
+    let mut errors = ValidationErrors::new();
+    let bbox = match params.bbox.as_deref().map(str::parse::<BoundingBox>) {
+        Some(Ok(bbox)) => Some(bbox),
+        Some(Err(message)) => {
+            errors.add("bbox", message);
+            None
+        }
+        None => {
+            errors.add("bbox", "is required");
+            None
+        }
+    };
+    errors.optional_range("limit", params.limit, 1, MAX_LIMIT);
+    errors.into_result()?;
+    let Some(bbox) = bbox else { unreachable!("bbox vérifiée ci-dessus") };
+    info!("Recherche des parcours dans l'emprise {:?}", bbox);
+
+    let postgis = spatial::postgis_enabled(&pool).await;
+    let (center_lat, center_lon) = bbox.center();
+    let mut query = QueryBuilder::new(format!("SELECT {}, ", ROUTE_COLUMNS));
+    spatial::push_distance_from_start(&mut query, center_lat, center_lon, postgis);
+    query.push(" AS distance_from_meters FROM routes WHERE start_lat IS NOT NULL");
+    authz::push_route_visible_to(&mut query, claims.user_id);
+    bbox.push_intersects(&mut query, postgis);
+    query
+        .push(" ORDER BY distance_from_meters, id LIMIT ")
+        .push_bind(params.limit.unwrap_or(DEFAULT_LIMIT));
+
+    fetch_nearby(&pool, query).await
+}
+
+/// `GET /routes/search` : les listes (`tags`, `difficulty`, `surface`) sont séparées par des virgules.
+#[derive(Deserialize)]
+struct SearchQuery {
+    /// Recherche plein texte sur le nom et la description (syntaxe `websearch_to_tsquery`).
+    q: Option<String>,
+    /// Le parcours doit avoir toutes ces étiquettes.
+    tags: Option<String>,
+    /// Le parcours doit avoir l'une de ces difficultés.
+    difficulty: Option<String>,
+    /// Le parcours doit avoir l'un de ces revêtements.
+    surface: Option<String>,
+    is_loop: Option<bool>,
+    is_out_and_back: Option<bool>,
+    min_distance: Option<f32>,
+    max_distance: Option<f32>,
+    limit: Option<i64>,
+}
+
+/// Filtres de recherche validés.
+struct SearchFilters {
+    text: Option<String>,
+    tags: Vec<String>,
+    difficulties: Vec<String>,
+    surfaces: Vec<String>,
+    is_loop: Option<bool>,
+    is_out_and_back: Option<bool>,
+    min_distance: Option<f32>,
+    max_distance: Option<f32>,
+}
+
+impl SearchQuery {
+    fn resolve(self) -> Result<SearchFilters, AppError> {
+        let list = |value: &Option<String>| -> Vec<String> {
+            value
+                .as_deref()
+                .map(|v| v.split(',').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string).collect())
+                .unwrap_or_default()
+        };
+        let (tags, difficulties, surfaces) = (list(&self.tags), list(&self.difficulty), list(&self.surface));
 
-    match route {
-        Some(r) => {
-            info!("Parcours {} trouvé: {}", id, r.name);
-            Ok(Json(r))
+        let mut errors = ValidationErrors::new();
+        for tag in &tags {
+            route::check_tag(&mut errors, "tags", tag);
+        }
+        for difficulty in &difficulties {
+            errors.optional_one_of("difficulty", Some(difficulty), DIFFICULTIES);
+        }
+        for surface in &surfaces {
+            errors.optional_one_of("surface", Some(surface), SURFACES);
+        }
+        errors.optional_range("limit", self.limit, 1, MAX_LIMIT);
+        errors.into_result()?;
+
+        Ok(SearchFilters {
+            text: self.q.map(|q| q.trim().to_string()).filter(|q| !q.is_empty()),
+            tags: route::normalize_tags(&tags),
+            difficulties,
+            surfaces,
+            is_loop: self.is_loop,
+            is_out_and_back: self.is_out_and_back,
+            min_distance: self.min_distance,
+            max_distance: self.max_distance,
+        })
+    }
+}
+
+impl SearchFilters {
+    /// Conditions communes aux résultats et aux facettes, après `WHERE true`.
+    fn push_sql(&self, query: &mut QueryBuilder<'_, sqlx::Postgres>, user_id: i32) {
+        authz::push_route_visible_to(query, user_id);
+        if let Some(text) = &self.text {
+            query.push(" AND routes.search_vector @@ websearch_to_tsquery('simple', ").push_bind(text.clone()).push(")");
+        }
+        if !self.tags.is_empty() {
+            query.push(" AND routes.tags @> ").push_bind(self.tags.clone());
+        }
+        if !self.difficulties.is_empty() {
+            query.push(" AND routes.difficulty = ANY(").push_bind(self.difficulties.clone()).push(")");
+        }
+        if !self.surfaces.is_empty() {
+            query.push(" AND routes.surface = ANY(").push_bind(self.surfaces.clone()).push(")");
+        }
+        if let Some(is_loop) = self.is_loop {
+            query.push(" AND routes.is_loop = ").push_bind(is_loop);
+        }
+        if let Some(is_out_and_back) = self.is_out_and_back {
+            query.push(" AND routes.is_out_and_back = ").push_bind(is_out_and_back);
+        }
+        if let Some(min) = self.min_distance {
+            query.push(" AND routes.distance_meters >= ").push_bind(min);
+        }
+        if let Some(max) = self.max_distance {
+            query.push(" AND routes.distance_meters <= ").push_bind(max);
+        }
+    }
+}
+
+/// Recherche plein texte et par facettes parmi les parcours visibles par l'utilisateur.
+/// Avec `q`, les résultats sont triés par pertinence ; sinon du plus récent au plus ancien.
+async fn search_routes(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<SearchQuery>,
+    Query(geometry): Query<GeometryQuery>,
+) -> Result<Json<RouteSearchResults>, AppError> {
+    let mode = geometry.resolve()?;
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+    let filters = params.resolve()?;
+    info!("Recherche de parcours: q={:?}, tags={:?}, difficulté={:?}", filters.text, filters.tags, filters.difficulties);
+
+    let mut query = QueryBuilder::new(format!("SELECT {}, ", ROUTE_COLUMNS));
+    match &filters.text {
+        Some(text) => {
+            query.push("ts_rank(search_vector, websearch_to_tsquery('simple', ").push_bind(text.clone()).push("))");
         }
         None => {
-            warn!("Parcours {} non trouvé", id);
-            Err(StatusCode::NOT_FOUND)
+            query.push("0::REAL");
         }
     }
+    query.push(" AS rank FROM routes WHERE true");
+    filters.push_sql(&mut query, claims.user_id);
+    query
+        .push(" ORDER BY rank DESC, COALESCE(created_at, 'epoch') DESC, id DESC LIMIT ")
+        .push_bind(limit);
+
+    let mut items = query
+        .build_query_as::<RouteSearchHit>()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la recherche de parcours: {}", e);
+            AppError::from(e)
+        })?;
+    items.iter_mut().for_each(|hit| mode.apply(&mut hit.route));
+
+    let mut query = QueryBuilder::new("WITH matched AS (SELECT tags, surface, difficulty FROM routes WHERE true");
+    filters.push_sql(&mut query, claims.user_id);
+    query.push(
+        ")
+         SELECT 'total' AS facet, NULL AS value, COUNT(*) AS count FROM matched
+         UNION ALL SELECT 'tags', tag, COUNT(*) FROM matched, unnest(tags) AS tag GROUP BY tag
+         UNION ALL SELECT 'surface', surface, COUNT(*) FROM matched WHERE surface IS NOT NULL GROUP BY surface
+         UNION ALL SELECT 'difficulty', difficulty, COUNT(*) FROM matched WHERE difficulty IS NOT NULL GROUP BY difficulty"
+    );
+    let rows = query
+        .build_query_as::<(String, Option<String>, i64)>()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors du calcul des facettes de recherche: {}", e);
+            AppError::from(e)
+        })?;
+
+    let mut total = 0;
+    let mut facets = SearchFacets::default();
+    for (facet, value, count) in rows {
+        let counts = match facet.as_str() {
+            "tags" => &mut facets.tags,
+            "surface" => &mut facets.surface,
+            "difficulty" => &mut facets.difficulty,
+            _ => {
+                total = count;
+                continue;
+            }
+        };
+        if let Some(value) = value {
+            counts.push(FacetCount { value, count });
+        }
+    }
+    for counts in [&mut facets.tags, &mut facets.surface, &mut facets.difficulty] {
+        counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    }
+
+    info!("{} parcours trouvés ({} affichés)", total, items.len());
+    Ok(Json(RouteSearchResults { total, items, facets }))
+}
+
+async fn fetch_nearby(pool: &DbPool, mut query: QueryBuilder<'_, sqlx::Postgres>) -> Result<Json<Vec<NearbyRoute>>, AppError> {
+    let routes = query
+        .build_query_as::<NearbyRoute>()
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la recherche spatiale de parcours: {}", e);
+            AppError::from(e)
+        })?;
+
+    info!("{} parcours trouvés", routes.len());
+    Ok(Json(routes))
+}
+
+async fn get_route(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+    Query(geometry): Query<GeometryQuery>,
+) -> Result<Json<Route>, AppError> {
+    info!("Récupération du parcours avec ID: {}", id);
+    let mode = geometry.resolve()?;
+
+    let mut route = fetch_visible_route(&pool, id, claims.user_id).await?;
+    info!("Parcours {} trouvé: {}", id, route.name);
+    mode.apply(&mut route);
+    Ok(Json(route))
+}
+
+/// Parcours `id` s'il est visible par `user_id` ; 404 sinon, comme s'il n'existait pas.
+async fn fetch_visible_route(pool: &DbPool, id: i32, user_id: i32) -> Result<Route, AppError> {
+    let mut query = QueryBuilder::new(format!("SELECT {} FROM routes WHERE id = ", ROUTE_COLUMNS));
+    query.push_bind(id);
+    authz::push_route_visible_to(&mut query, user_id);
+
+    query
+        .build_query_as::<Route>()
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération du parcours {}: {}", id, e);
+            AppError::from(e)
+        })?
+        .ok_or_else(|| {
+            warn!("Parcours {} non trouvé pour l'utilisateur {}", id, user_id);
+            AppError::NotFound("Route not found".to_string())
+        })
 }
 
 async fn get_user_routes(
     Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
     Path(user_id): Path<i32>,
-) -> Result<Json<Vec<Route>>, StatusCode> {
+    Query(filters): Query<RouteQuery>,
+    Query(page): Query<PageParams>,
+    Query(geometry): Query<GeometryQuery>,
+) -> Result<Json<Page<Route>>, AppError> {
     info!("Récupération des parcours de l'utilisateur {}", user_id);
+    list_routes(&pool, claims.user_id, RouteQuery { user_id: Some(user_id), ..filters }, page, geometry).await
+}
+
+async fn get_public_routes(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Query(filters): Query<RouteQuery>,
+    Query(page): Query<PageParams>,
+    Query(geometry): Query<GeometryQuery>,
+) -> Result<Json<Page<Route>>, AppError> {
+    info!("Récupération des parcours publics");
+    list_routes(&pool, claims.user_id, RouteQuery { is_public: Some(true), ..filters }, page, geometry).await
+}
+
+async fn update_route(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+    ValidatedJson(update): ValidatedJson<UpdateRoute>,
+) -> Result<Json<Route>, AppError> {
+    info!("Mise à jour du parcours {}", id);
+
+    let metrics = update.path_data.as_ref().map(route_metrics).transpose()?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("Erreur lors du démarrage de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    // Verrou sur la ligne : deux mises à jour simultanées ne créent pas la même révision
+    let (route_owner, current_path): (i32, serde_json::Value) =
+        sqlx::query_as("SELECT user_id, path_data FROM routes WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("Erreur lors de la vérification du propriétaire: {}", e);
+                AppError::from(e)
+            })?
+            .ok_or_else(|| AppError::NotFound("Route not found".to_string()))?;
+
+    if route_owner != claims.user_id {
+        warn!("Utilisateur {} a tenté de modifier le parcours {} de l'utilisateur {}", claims.user_id, id, route_owner);
+        return Err(AppError::Forbidden("You can only modify your own routes".to_string()));
+    }
+
+    let visibility = update.visibility();
+    let mut query = QueryBuilder::new("UPDATE routes SET name = COALESCE(");
+    query
+        .push_bind(update.name)
+        .push(", name), description = COALESCE(")
+        .push_bind(update.description)
+        .push(", description), visibility = COALESCE(")
+        .push_bind(visibility)
+        .push(", visibility), tags = COALESCE(")
+        .push_bind(update.tags.as_deref().map(route::normalize_tags))
+        .push(", tags), surface = COALESCE(")
+        .push_bind(update.surface)
+        .push(", surface), difficulty = COALESCE(")
+        .push_bind(update.difficulty)
+        .push(", difficulty), is_loop = COALESCE(")
+        .push_bind(update.is_loop)
+        .push(", is_loop), is_out_and_back = COALESCE(")
+        .push_bind(update.is_out_and_back)
+        .push(", is_out_and_back)");
+    // Un nouveau tracé remplace aussi toutes les valeurs qui en sont dérivées, dans une nouvelle révision
+    let new_revision = update.path_data.as_ref().is_some_and(|path| *path != current_path);
+    if new_revision && let (Some(path_data), Some(metrics)) = (update.path_data, metrics) {
+        query.push(", path_data = ").push_bind(path_data);
+        for (column, value) in derived_columns(&metrics) {
+            query.push(format!(", {} = ", column)).push_bind(value);
+        }
+        query.push(", current_version = current_version + 1");
+    }
+    query
+        .push(", updated_at = NOW() WHERE id = ")
+        .push_bind(id)
+        .push(format!(" RETURNING {}", ROUTE_COLUMNS));
+
+    let route = query
+        .build_query_as::<Route>()
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la mise à jour du parcours {}: {}", id, e);
+            AppError::from(e)
+        })?;
+
+    if new_revision {
+        insert_version(&mut tx, &route, claims.user_id).await.map_err(|e| {
+            error!("Erreur lors de l'enregistrement de la révision du parcours {}: {}", id, e);
+            AppError::from(e)
+        })?;
+    }
+
+    tx.commit().await.map_err(|e| {
+        error!("Erreur lors de la validation de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    info!("Parcours {} mis à jour avec succès (révision {})", id, route.current_version);
+    Ok(Json(route))
+}
+
+/// Révisions du tracé, de la plus récente à la plus ancienne.
+async fn get_route_versions(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<RouteVersionSummary>>, AppError> {
+    info!("Récupération des révisions du parcours {}", id);
+    authz::visible_route_owner(&pool, id, claims.user_id).await?;
 
-    let routes = sqlx::query_as::<_, Route>(
-        "SELECT id, user_id, name, description, is_public, path_data, distance_meters, created_at, updated_at
-         FROM routes WHERE user_id = $1 ORDER BY created_at DESC"
+    let versions = sqlx::query_as::<_, RouteVersionSummary>(
+        "SELECT v.version, v.distance_meters, v.elevation_gain_meters, v.elevation_loss_meters, v.created_by,
+                (SELECT COUNT(*) FROM scores s WHERE s.route_id = v.route_id AND s.route_version = v.version) AS score_count,
+                v.created_at
+         FROM route_versions v
+         WHERE v.route_id = $1
+         ORDER BY v.version DESC"
     )
-    .bind(user_id)
+    .bind(id)
     .fetch_all(&pool)
     .await
     .map_err(|e| {
-        error!("Erreur lors de la récupération des parcours de l'utilisateur {}: {}", user_id, e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        error!("Erreur lors de la récupération des révisions du parcours {}: {}", id, e);
+        AppError::from(e)
     })?;
 
-    info!("{} parcours récupérés pour l'utilisateur {}", routes.len(), user_id);
-    Ok(Json(routes))
+    info!("{} révisions récupérées pour le parcours {}", versions.len(), id);
+    Ok(Json(versions))
 }
 
-async fn get_public_routes(
+async fn get_route_version(
     Extension(pool): Extension<DbPool>,
-) -> Result<Json<Vec<Route>>, StatusCode> {
-    info!("Récupération des parcours publics");
+    Extension(claims): Extension<Claims>,
+    Path((id, version)): Path<(i32, i32)>,
+) -> Result<Json<RouteVersion>, AppError> {
+    info!("Récupération de la révision {} du parcours {}", version, id);
+    authz::visible_route_owner(&pool, id, claims.user_id).await?;
+
+    sqlx::query_as::<_, RouteVersion>(&format!(
+        "SELECT {} FROM route_versions WHERE route_id = $1 AND version = $2",
+        ROUTE_VERSION_COLUMNS
+    ))
+    .bind(id)
+    .bind(version)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la récupération de la révision {} du parcours {}: {}", version, id, e);
+        AppError::from(e)
+    })?
+    .map(Json)
+    .ok_or_else(|| AppError::NotFound("Route version not found".to_string()))
+}
+
+/// Copie un parcours public, ou l'un des siens, dans les parcours de l'utilisateur connecté.
+/// Un parcours visible seulement des amis ou par lien n'est pas copiable : la copie pourrait
+/// être publiée. La copie est privée par défaut et commence à la révision 1 ; la source reste attribuée.
+async fn fork_route(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+    Query(params): Query<RouteOverrides>,
+) -> Result<Json<Route>, AppError> {
+    info!("Copie du parcours {} par l'utilisateur {}", id, claims.user_id);
+
+    let source = fetch_visible_route(&pool, id, claims.user_id).await?;
+
+    if source.visibility != "public" && source.user_id != claims.user_id {
+        warn!("Utilisateur {} a tenté de copier le parcours non public {} de l'utilisateur {}", claims.user_id, id, source.user_id);
+        return Err(AppError::Forbidden("Only public routes can be forked".to_string()));
+    }
+
+    let fork = ForkSource { route_id: source.id, version: source.current_version, user_id: source.user_id };
+    let new_route = CreateRoute {
+        name: params.name.unwrap_or(source.name),
+        description: params.description.or(source.description),
+        is_public: params.is_public,
+        visibility: params.visibility,
+        path_data: source.path_data,
+        tags: source.tags,
+        surface: source.surface,
+        difficulty: source.difficulty,
+        is_loop: source.is_loop,
+        is_out_and_back: source.is_out_and_back,
+    };
+    new_route.validate()?;
+
+    let metrics = route_metrics(&new_route.path_data)?;
+    let route = insert_route(&pool, claims.user_id, &new_route, &metrics, Some(&fork)).await.map_err(|e| {
+        error!("Erreur lors de la copie du parcours {}: {}", id, e);
+        AppError::from(e)
+    })?;
+
+    info!("Parcours {} copié avec succès (ID: {})", id, route.id);
+    Ok(Json(route))
+}
 
-    let routes = sqlx::query_as::<_, Route>(
-        "SELECT id, user_id, name, description, is_public, path_data, distance_meters, created_at, updated_at
-         FROM routes WHERE is_public = true ORDER BY created_at DESC"
+/// Copies d'un parcours visibles par l'utilisateur connecté.
+async fn get_route_forks(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+    Query(filters): Query<RouteQuery>,
+    Query(page): Query<PageParams>,
+    Query(geometry): Query<GeometryQuery>,
+) -> Result<Json<Page<Route>>, AppError> {
+    info!("Récupération des copies du parcours {}", id);
+
+    authz::visible_route_owner(&pool, id, claims.user_id).await?;
+    list_routes(&pool, claims.user_id, RouteQuery { forked_from: Some(id), ..filters }, page, geometry).await
+}
+
+const REVIEW_SORTS: &[SortField] = &[
+    SortField { name: "created_at", expr: "COALESCE(created_at, 'epoch')", sql_type: "timestamp", default_order: SortOrder::Desc },
+    SortField { name: "rating", expr: "rating", sql_type: "integer", default_order: SortOrder::Desc },
+];
+
+/// Recalcule la note moyenne et les compteurs de `routes` après une modification des notes.
+async fn refresh_rating(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>, route_id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE routes SET
+             rating_average = (SELECT AVG(rating)::REAL FROM route_reviews WHERE route_id = $1),
+             rating_count = (SELECT COUNT(*) FROM route_reviews WHERE route_id = $1),
+             review_count = (SELECT COUNT(*) FROM route_reviews WHERE route_id = $1 AND review IS NOT NULL)
+         WHERE id = $1"
     )
-    .fetch_all(&pool)
+    .bind(route_id)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Note (et commentaire) de l'utilisateur connecté sur un parcours qu'il peut voir, hors les siens.
+/// Une seule note par utilisateur : en renvoyer une remplace la précédente.
+async fn upsert_review(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+    ValidatedJson(review): ValidatedJson<UpsertReview>,
+) -> Result<Json<RouteReview>, AppError> {
+    info!("Note de {} étoiles sur le parcours {} par l'utilisateur {}", review.rating, id, claims.user_id);
+
+    let owner = authz::visible_route_owner(&pool, id, claims.user_id).await?;
+    if owner == claims.user_id {
+        return Err(AppError::Forbidden("You cannot rate your own route".to_string()));
+    }
+
+    let text = review.review.map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("Erreur lors du démarrage de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    let saved = sqlx::query_as::<_, RouteReview>(&format!(
+        "WITH saved AS (
+             INSERT INTO route_reviews (route_id, user_id, rating, review)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (route_id, user_id) DO UPDATE
+             SET rating = EXCLUDED.rating, review = EXCLUDED.review, updated_at = NOW()
+             RETURNING *
+         )
+         SELECT {} FROM (SELECT saved.*, u.username FROM saved JOIN users u ON u.id = saved.user_id) r",
+        REVIEW_COLUMNS
+    ))
+    .bind(id)
+    .bind(claims.user_id)
+    .bind(review.rating)
+    .bind(text)
+    .fetch_one(&mut *tx)
     .await
     .map_err(|e| {
-        error!("Erreur lors de la récupération des parcours publics: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        error!("Erreur lors de l'enregistrement de la note: {}", e);
+        AppError::from(e)
     })?;
 
-    info!("{} parcours publics récupérés", routes.len());
-    Ok(Json(routes))
+    refresh_rating(&mut tx, id).await.map_err(|e| {
+        error!("Erreur lors du calcul de la note du parcours {}: {}", id, e);
+        AppError::from(e)
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        error!("Erreur lors de la validation de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    info!("Note {} enregistrée sur le parcours {}", saved.id, id);
+    Ok(Json(saved))
 }
 
-async fn update_route(
+async fn delete_review(
     Extension(pool): Extension<DbPool>,
     Extension(claims): Extension<Claims>,
     Path(id): Path<i32>,
-    Json(update): Json<UpdateRoute>,
-) -> Result<Json<Route>, StatusCode> {
-    info!("Mise à jour du parcours {}", id);
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Suppression de la note de l'utilisateur {} sur le parcours {}", claims.user_id, id);
 
-    // Verify user owns this route
-    let route_owner: i32 = sqlx::query_scalar("SELECT user_id FROM routes WHERE id = $1")
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("Erreur lors du démarrage de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    let result = sqlx::query("DELETE FROM route_reviews WHERE route_id = $1 AND user_id = $2")
         .bind(id)
-        .fetch_optional(&pool)
+        .bind(claims.user_id)
+        .execute(&mut *tx)
         .await
         .map_err(|e| {
-            error!("Erreur lors de la vérification du propriétaire: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            error!("Erreur lors de la suppression de la note: {}", e);
+            AppError::from(e)
+        })?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Review not found".to_string()));
+    }
+
+    refresh_rating(&mut tx, id).await.map_err(|e| {
+        error!("Erreur lors du calcul de la note du parcours {}: {}", id, e);
+        AppError::from(e)
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        error!("Erreur lors de la validation de la transaction: {}", e);
+        AppError::from(e)
+    })?;
+
+    info!("Note de l'utilisateur {} sur le parcours {} supprimée", claims.user_id, id);
+    Ok(Json(serde_json::json!({
+        "message": "Review deleted successfully"
+    })))
+}
+
+async fn get_route_reviews(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<RouteReview>>, AppError> {
+    info!("Récupération des notes du parcours {}", id);
+    let page = page.resolve(REVIEW_SORTS, MAX_LIMIT)?;
+
+    authz::visible_route_owner(&pool, id, claims.user_id).await?;
+
+    let mut query = QueryBuilder::new(format!(
+        "SELECT {} FROM (SELECT r.*, u.username FROM route_reviews r JOIN users u ON u.id = r.user_id) reviews
+         WHERE route_id = ",
+        REVIEW_COLUMNS
+    ));
+    query.push_bind(id);
+    page.push_sql(&mut query, "id");
+
+    let reviews = query
+        .build_query_as::<RouteReview>()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération des notes du parcours {}: {}", id, e);
+            AppError::from(e)
+        })?;
+
+    info!("{} notes récupérées pour le parcours {}", reviews.len(), id);
+    Ok(Json(page.into_page(reviews, |r, sort| {
+        let key = match sort {
+            "rating" => r.rating.to_string(),
+            _ => timestamp_key(r.created_at),
+        };
+        (key, r.id as i64)
+    })))
+}
+
+async fn add_favorite(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Ajout du parcours {} aux favoris de l'utilisateur {}", id, claims.user_id);
+
+    authz::visible_route_owner(&pool, id, claims.user_id).await?;
+
+    sqlx::query("INSERT INTO route_favorites (user_id, route_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(claims.user_id)
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de l'ajout du favori: {}", e);
+            AppError::from(e)
+        })?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Route added to favorites"
+    })))
+}
+
+async fn remove_favorite(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Retrait du parcours {} des favoris de l'utilisateur {}", id, claims.user_id);
+
+    let result = sqlx::query("DELETE FROM route_favorites WHERE user_id = $1 AND route_id = $2")
+        .bind(claims.user_id)
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors du retrait du favori: {}", e);
+            AppError::from(e)
+        })?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Favorite not found".to_string()));
+    }
+
+    Ok(Json(serde_json::json!({
+        "message": "Route removed from favorites"
+    })))
+}
+
+/// Favoris de l'utilisateur connecté qu'il peut encore voir.
+async fn get_favorite_routes(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Query(filters): Query<RouteQuery>,
+    Query(page): Query<PageParams>,
+    Query(geometry): Query<GeometryQuery>,
+) -> Result<Json<Page<Route>>, AppError> {
+    info!("Récupération des parcours favoris de l'utilisateur {}", claims.user_id);
+    let filters = RouteQuery { favorited_by: Some(claims.user_id), ..filters };
+    list_routes(&pool, claims.user_id, filters, page, geometry).await
+}
+
+/// Visibilité du parcours `id`, réservé à son propriétaire (404 s'il ne peut pas le voir).
+async fn owned_route_visibility(pool: &DbPool, claims: &Claims, id: i32) -> Result<String, AppError> {
+    let mut query = QueryBuilder::new("SELECT routes.user_id, routes.visibility FROM routes WHERE routes.id = ");
+    query.push_bind(id);
+    authz::push_route_visible_to(&mut query, claims.user_id);
+
+    let (owner, visibility) = query
+        .build_query_as::<(i32, String)>()
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération du parcours {}: {}", id, e);
+            AppError::from(e)
         })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+        .ok_or_else(|| AppError::NotFound("Route not found".to_string()))?;
 
-    if route_owner != claims.user_id {
-        warn!("Utilisateur {} a tenté de modifier le parcours {} de l'utilisateur {}", claims.user_id, id, route_owner);
-        return Err(StatusCode::FORBIDDEN);
-    }
-
-    let route = sqlx::query_as::<_, Route>(
-        "UPDATE routes
-         SET name = COALESCE($1, name),
-             description = COALESCE($2, description),
-             is_public = COALESCE($3, is_public),
-             path_data = COALESCE($4, path_data),
-             distance_meters = COALESCE($5, distance_meters),
-             updated_at = NOW()
-         WHERE id = $6
-         RETURNING id, user_id, name, description, is_public, path_data, distance_meters, created_at, updated_at"
+    authz::authorize(claims, &[owner], None, &format!("les liens de partage du parcours {}", id))?;
+    Ok(visibility)
+}
+
+/// Nouveau lien de partage d'un parcours `unlisted`. Seul le hash du token est conservé :
+/// le lien complet n'est renvoyé qu'ici.
+async fn create_share_link(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+) -> Result<Json<CreatedShareLink>, AppError> {
+    info!("Création d'un lien de partage pour le parcours {} par l'utilisateur {}", id, claims.user_id);
+
+    let visibility = owned_route_visibility(&pool, &claims, id).await?;
+    if visibility != "unlisted" {
+        return Err(AppError::Unprocessable("Share links can only be created for unlisted routes".to_string()));
+    }
+
+    let token = sessions::generate_token();
+    let link = sqlx::query_as::<_, RouteShareLink>(
+        "INSERT INTO route_share_links (route_id, token_hash) VALUES ($1, $2)
+         RETURNING id, route_id, created_at, revoked_at"
     )
-    .bind(update.name)
-    .bind(update.description)
-    .bind(update.is_public)
-    .bind(update.path_data)
-    .bind(update.distance_meters)
     .bind(id)
-    .fetch_optional(&pool)
+    .bind(sessions::hash_token(&token))
+    .fetch_one(&pool)
     .await
     .map_err(|e| {
-        error!("Erreur lors de la mise à jour du parcours {}: {}", id, e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        error!("Erreur lors de la création du lien de partage: {}", e);
+        AppError::from(e)
     })?;
 
-    match route {
-        Some(r) => {
-            info!("Parcours {} mis à jour avec succès", id);
-            Ok(Json(r))
-        }
-        None => {
-            warn!("Parcours {} non trouvé pour la mise à jour", id);
-            Err(StatusCode::NOT_FOUND)
-        }
+    info!("Lien de partage {} créé pour le parcours {}", link.id, id);
+    Ok(Json(CreatedShareLink { link, path: format!("/shared/routes/{}", token), token }))
+}
+
+async fn get_share_links(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<RouteShareLink>>, AppError> {
+    info!("Récupération des liens de partage du parcours {}", id);
+    owned_route_visibility(&pool, &claims, id).await?;
+
+    let links = sqlx::query_as::<_, RouteShareLink>(
+        "SELECT id, route_id, created_at, revoked_at FROM route_share_links
+         WHERE route_id = $1
+         ORDER BY created_at DESC, id DESC"
+    )
+    .bind(id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la récupération des liens de partage du parcours {}: {}", id, e);
+        AppError::from(e)
+    })?;
+
+    Ok(Json(links))
+}
+
+async fn revoke_share_link(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path((id, link_id)): Path<(i32, i32)>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Révocation du lien de partage {} du parcours {}", link_id, id);
+    owned_route_visibility(&pool, &claims, id).await?;
+
+    let result = sqlx::query(
+        "UPDATE route_share_links SET revoked_at = NOW()
+         WHERE id = $1 AND route_id = $2 AND revoked_at IS NULL"
+    )
+    .bind(link_id)
+    .bind(id)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la révocation du lien de partage {}: {}", link_id, e);
+        AppError::from(e)
+    })?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Share link not found".to_string()));
+    }
+
+    info!("Lien de partage {} révoqué", link_id);
+    Ok(Json(serde_json::json!({
+        "message": "Share link revoked successfully"
+    })))
+}
+
+/// Parcours ouvert par un lien de partage, sans connexion. Le lien cesse de fonctionner s'il est
+/// révoqué ou si le parcours redevient privé ou réservé aux amis.
+async fn get_shared_route(
+    Extension(pool): Extension<DbPool>,
+    Path(token): Path<String>,
+    Query(geometry): Query<GeometryQuery>,
+) -> Result<Json<Route>, AppError> {
+    let mode = geometry.resolve()?;
+
+    let mut route = fetch_shared_route(&pool, &token).await?;
+    info!("Parcours {} ouvert par lien de partage", route.id);
+    mode.apply(&mut route);
+    Ok(Json(route))
+}
+
+async fn fetch_shared_route(pool: &DbPool, token: &str) -> Result<Route, AppError> {
+    sqlx::query_as::<_, Route>(&format!(
+        "SELECT {} FROM routes
+         WHERE visibility IN ('unlisted', 'public')
+           AND id = (SELECT route_id FROM route_share_links WHERE token_hash = $1 AND revoked_at IS NULL)",
+        ROUTE_COLUMNS
+    ))
+    .bind(sessions::hash_token(token))
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la récupération d'un parcours partagé: {}", e);
+        AppError::from(e)
+    })?
+    .ok_or_else(|| {
+        warn!("Lien de partage inconnu, révoqué ou parcours devenu privé");
+        AppError::NotFound("Share link not found".to_string())
+    })
+}
+
+#[derive(Deserialize)]
+struct ThumbnailQuery {
+    width: Option<u32>,
+    height: Option<u32>,
+    /// Graduation de distance le long du tracé.
+    #[serde(default)]
+    ticks: bool,
+}
+
+impl ThumbnailQuery {
+    fn resolve(&self, format: &str) -> Result<(ThumbnailFormat, ThumbnailOptions), AppError> {
+        let format = format
+            .parse::<ThumbnailFormat>()
+            .map_err(|message| AppError::validation("Invalid thumbnail format", serde_json::json!({ "format": [message] })))?;
+
+        let mut errors = ValidationErrors::new();
+        errors.optional_range("width", self.width, thumbnail::MIN_SIZE, thumbnail::MAX_SIZE);
+        errors.optional_range("height", self.height, thumbnail::MIN_SIZE, thumbnail::MAX_SIZE);
+        errors.into_result()?;
+
+        let options = ThumbnailOptions {
+            width: self.width.unwrap_or(thumbnail::DEFAULT_WIDTH),
+            height: self.height.unwrap_or(thumbnail::DEFAULT_HEIGHT),
+            distance_ticks: self.ticks,
+        };
+        Ok((format, options))
+    }
+}
+
+/// Vignette SVG ou PNG du tracé, sans fond de carte.
+async fn get_route_thumbnail(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path((id, format)): Path<(i32, String)>,
+    Query(params): Query<ThumbnailQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let (format, options) = params.resolve(&format)?;
+    info!("Vignette {} {}x{} du parcours {}", format.extension(), options.width, options.height, id);
+
+    let route = fetch_visible_route(&pool, id, claims.user_id).await?;
+    thumbnail_response(&route, format, options, &headers, "private, max-age=300").await
+}
+
+/// Vignette d'un parcours ouvert par lien de partage, pour les aperçus des messageries et réseaux.
+async fn get_shared_route_thumbnail(
+    Extension(pool): Extension<DbPool>,
+    Path((token, format)): Path<(String, String)>,
+    Query(params): Query<ThumbnailQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let (format, options) = params.resolve(&format)?;
+
+    let route = fetch_shared_route(&pool, &token).await?;
+    info!("Vignette {} {}x{} du parcours {} par lien de partage", format.extension(), options.width, options.height, route.id);
+    thumbnail_response(&route, format, options, &headers, "public, max-age=300").await
+}
+
+/// Rendu (ou cache) de la vignette. L'ETag dépend d'`updated_at` : tant que le tracé n'a pas
+/// changé, un client qui renvoie `If-None-Match` reçoit un 304 sans corps. Le rendu, jusqu'à
+/// 1024×1024 px, tourne sur le pool bloquant de Tokio pour ne pas monopoliser un worker.
+async fn thumbnail_response(
+    route: &Route,
+    format: ThumbnailFormat,
+    options: ThumbnailOptions,
+    headers: &HeaderMap,
+    cache_control: &'static str,
+) -> Result<Response, AppError> {
+    let etag = format!(
+        "\"{}-{}-{}x{}{}.{}\"",
+        route.id,
+        route.updated_at.map(|t| t.and_utc().timestamp_micros()).unwrap_or(0),
+        options.width,
+        options.height,
+        if options.distance_ticks { "-ticks" } else { "" },
+        format.extension()
+    );
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(CACHE_CONTROL, cache_control.parse().expect("en-tête valide"));
+    response_headers.insert(ETAG, etag.parse().expect("en-tête valide"));
+    if let Some(updated_at) = route.updated_at
+        && let Ok(value) = updated_at.and_utc().format("%a, %d %b %Y %H:%M:%S GMT").to_string().parse()
+    {
+        response_headers.insert(LAST_MODIFIED, value);
+    }
+
+    let not_modified = headers
+        .get(IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|tag| tag.trim() == "*" || tag.trim() == etag));
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, response_headers).into_response());
     }
+
+    let key = thumbnail::CacheKey { route_id: route.id, updated_at: route.updated_at, format, options };
+    let (route_id, path_data) = (route.id, route.path_data.clone());
+    let body = tokio::task::spawn_blocking(move || {
+        thumbnail::cached(key, || {
+            // Les parcours antérieurs à la validation du tracé peuvent contenir n'importe quoi
+            let positions = geometry::parse_path(&path_data).map_err(|message| {
+                warn!("Tracé du parcours {} inexploitable pour la vignette: {}", route_id, message);
+                AppError::Unprocessable(format!("The stored path cannot be rendered: {}", message))
+            })?;
+            thumbnail::scene(&positions, &options)
+                .and_then(|scene| thumbnail::render(&scene, format))
+                .ok_or_else(|| {
+                    error!("Échec du rendu de la vignette du parcours {}", route_id);
+                    AppError::Internal("Thumbnail rendering failed".to_string())
+                })
+        })
+    })
+    .await
+    .map_err(|e| {
+        error!("Tâche de rendu de la vignette du parcours {} interrompue: {}", route_id, e);
+        AppError::Internal("Thumbnail rendering failed".to_string())
+    })??;
+
+    response_headers.insert(CONTENT_TYPE, format.content_type().parse().expect("en-tête valide"));
+    Ok((response_headers, body).into_response())
 }
 
 async fn delete_route(
     Extension(pool): Extension<DbPool>,
     Extension(claims): Extension<Claims>,
     Path(id): Path<i32>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, AppError> {
     info!("Suppression du parcours {}", id);
 
     // Verify user owns this route
@@ -244,13 +1486,13 @@ async fn delete_route(
         .await
         .map_err(|e| {
             error!("Erreur lors de la vérification du propriétaire: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            AppError::from(e)
         })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+        .ok_or_else(|| AppError::NotFound("Route not found".to_string()))?;
 
     if route_owner != claims.user_id {
         warn!("Utilisateur {} a tenté de supprimer le parcours {} de l'utilisateur {}", claims.user_id, id, route_owner);
-        return Err(StatusCode::FORBIDDEN);
+        return Err(AppError::Forbidden("You can only delete your own routes".to_string()));
     }
 
     let result = sqlx::query("DELETE FROM routes WHERE id = $1")
@@ -267,11 +1509,11 @@ async fn delete_route(
         }
         Ok(_) => {
             warn!("Parcours {} non trouvé pour la suppression", id);
-            Err(StatusCode::NOT_FOUND)
+            Err(AppError::NotFound("Route not found".to_string()))
         }
         Err(e) => {
             error!("Erreur lors de la suppression du parcours {}: {}", id, e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(AppError::from(e))
         }
     }
 }
@@ -280,34 +1522,39 @@ async fn submit_score(
     Extension(pool): Extension<DbPool>,
     Extension(claims): Extension<Claims>,
     Path(route_id): Path<i32>,
-    Json(new_score): Json<CreateScore>,
-) -> Result<Json<Score>, StatusCode> {
+    ValidatedJson(new_score): ValidatedJson<CreateScore>,
+) -> Result<Json<Score>, AppError> {
     let user_id = claims.user_id;
 
     info!("Soumission d'un score pour le parcours {} par l'utilisateur {}", route_id, user_id);
 
-    let route_exists = sqlx::query_scalar::<_, bool>(
-        "SELECT EXISTS(SELECT 1 FROM routes WHERE id = $1)"
-    )
-    .bind(route_id)
-    .fetch_one(&pool)
-    .await
-    .map_err(|e| {
-        error!("Erreur lors de la vérification du parcours: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    // Le score est rattaché à la révision courante du tracé
+    let mut query = QueryBuilder::new("SELECT current_version FROM routes WHERE id = ");
+    query.push_bind(route_id);
+    authz::push_route_visible_to(&mut query, user_id);
+    let route_version = query
+        .build_query_scalar::<i32>()
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la vérification du parcours: {}", e);
+            AppError::from(e)
+        })?;
 
-    if !route_exists {
+    let Some(route_version) = route_version else {
         warn!("Parcours {} non trouvé", route_id);
-        return Err(StatusCode::NOT_FOUND);
-    }
+        return Err(AppError::NotFound("Route not found".to_string()));
+    };
 
-    let score = sqlx::query_as::<_, Score>(
-        "INSERT INTO scores (route_id, user_id, time_seconds, max_speed_kmh, avg_speed_kmh, max_g_force, max_inclination_degrees, max_sound_db)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-         RETURNING id, route_id, user_id, time_seconds, max_speed_kmh, avg_speed_kmh, max_g_force, max_inclination_degrees, max_sound_db, created_at"
-    )
+    // En attente de vérification jusqu'à l'envoi de la trace GPS sur /sensor-data/bulk
+    let score = sqlx::query_as::<_, Score>(&format!(
+        "INSERT INTO scores (route_id, route_version, user_id, time_seconds, max_speed_kmh, avg_speed_kmh, max_g_force, max_inclination_degrees, max_sound_db)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+         RETURNING {}",
+        SCORE_COLUMNS
+    ))
     .bind(route_id)
+    .bind(route_version)
     .bind(user_id)
     .bind(new_score.time_seconds)
     .bind(new_score.max_speed_kmh)
@@ -319,9 +1566,115 @@ async fn submit_score(
     .await
     .map_err(|e| {
         error!("Erreur lors de la soumission du score: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
+    // Le score est enregistré : un échec de l'anti-triche ne doit pas faire échouer la soumission
+    if let Err(e) = anti_cheat::evaluate_score(&pool, score.id).await {
+        error!("Erreur lors de l'évaluation anti-triche du score {}: {}", score.id, e);
+    }
+
     info!("Score soumis avec succès: {} secondes (ID: {})", score.time_seconds, score.id);
     Ok(Json(score))
 }
+
+/// Le tracé a déjà été contrôlé par `Validate` ; une erreur ici reste une erreur de validation.
+fn route_metrics(path_data: &serde_json::Value) -> Result<RouteMetrics, AppError> {
+    let positions = geometry::parse_path(path_data).map_err(|message| {
+        let mut errors = ValidationErrors::new();
+        errors.add("path_data", message);
+        AppError::from(errors)
+    })?;
+    Ok(geometry::compute_metrics(&positions))
+}
+
+/// Parcours d'origine d'une copie.
+struct ForkSource {
+    route_id: i32,
+    version: i32,
+    user_id: i32,
+}
+
+async fn insert_route(
+    pool: &DbPool,
+    user_id: i32,
+    new_route: &CreateRoute,
+    metrics: &RouteMetrics,
+    fork: Option<&ForkSource>,
+) -> Result<Route, sqlx::Error> {
+    let mut query = QueryBuilder::new(
+        "INSERT INTO routes (user_id, name, description, visibility, path_data, tags, surface, difficulty, is_loop, is_out_and_back"
+    );
+    let derived = derived_columns(metrics);
+    for (column, _) in &derived {
+        query.push(", ").push(column);
+    }
+    query.push(", forked_from, forked_from_version, forked_from_user_id");
+    query.push(") VALUES (");
+    let mut values = query.separated(", ");
+    values
+        .push_bind(user_id)
+        .push_bind(&new_route.name)
+        .push_bind(&new_route.description)
+        .push_bind(new_route.visibility())
+        .push_bind(&new_route.path_data)
+        .push_bind(route::normalize_tags(&new_route.tags))
+        .push_bind(&new_route.surface)
+        .push_bind(&new_route.difficulty)
+        .push_bind(new_route.is_loop)
+        .push_bind(new_route.is_out_and_back);
+    for (_, value) in derived {
+        values.push_bind(value);
+    }
+    values
+        .push_bind(fork.map(|f| f.route_id))
+        .push_bind(fork.map(|f| f.version))
+        .push_bind(fork.map(|f| f.user_id));
+    query.push(format!(") RETURNING {}", ROUTE_COLUMNS));
+
+    let mut tx = pool.begin().await?;
+    let route = query.build_query_as::<Route>().fetch_one(&mut *tx).await?;
+    insert_version(&mut tx, &route, user_id).await?;
+    tx.commit().await?;
+    Ok(route)
+}
+
+/// Enregistre le tracé actuel de `route` comme révision `route.current_version`.
+async fn insert_version(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    route: &Route,
+    created_by: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO route_versions (route_id, version, path_data, distance_meters,
+                                     elevation_gain_meters, elevation_loss_meters, created_by)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)"
+    )
+    .bind(route.id)
+    .bind(route.current_version)
+    .bind(&route.path_data)
+    .bind(route.distance_meters)
+    .bind(route.elevation_gain_meters)
+    .bind(route.elevation_loss_meters)
+    .bind(created_by)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Colonnes de `routes` calculées à partir du tracé, avec leur valeur.
+fn derived_columns(metrics: &RouteMetrics) -> [(&'static str, Option<f64>); 11] {
+    [
+        ("distance_meters", Some(metrics.distance_meters)),
+        ("min_lat", Some(metrics.min_lat)),
+        ("min_lon", Some(metrics.min_lon)),
+        ("max_lat", Some(metrics.max_lat)),
+        ("max_lon", Some(metrics.max_lon)),
+        ("start_lat", Some(metrics.start.lat)),
+        ("start_lon", Some(metrics.start.lon)),
+        ("end_lat", Some(metrics.end.lat)),
+        ("end_lon", Some(metrics.end.lon)),
+        ("elevation_gain_meters", metrics.elevation_gain_meters),
+        ("elevation_loss_meters", metrics.elevation_loss_meters),
+    ]
+}
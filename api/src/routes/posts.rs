@@ -1,41 +1,81 @@
 use axum::{
-    extract::{Extension, Path},
-    http::StatusCode,
+    extract::Extension,
+    handler::Handler,
+    middleware,
     routing::get,
     Json, Router,
 };
-use sqlx::Error as SqlxError;
+use serde::Deserialize;
+use sqlx::{Error as SqlxError, QueryBuilder};
+use shared::errors::AppError;
 use tracing::{info, warn, error};
+use shared::jwt::{Claims, Role};
 
 use crate::{
+    authz,
     db::DbPool,
+    middleware::auth_middleware,
     models::post::{CreatePost, Post, UpdatePost},
+    pagination::{Page, PageParams, SortField, SortOrder, MAX_LIMIT},
+    validation::{Path, Query, ValidatedJson},
 };
 
 pub fn router() -> Router {
+    // Lecture publique ; l'auteur d'un post est l'utilisateur connecté,
+    // seul lui (ou la modération) peut le modifier ou le supprimer
+    let authenticated = middleware::from_fn(auth_middleware);
+
     Router::new()
-        .route("/", get(get_posts).post(create_post))
-        .route("/{id}",get(get_post).put(update_post).delete(delete_post))
+        .route("/", get(get_posts).post(create_post.layer(authenticated.clone())))
+        .route("/{id}", get(get_post)
+            .put(update_post.layer(authenticated.clone()))
+            .delete(delete_post.layer(authenticated)))
 }
 
-async fn get_posts(Extension(pool): Extension<DbPool>) -> Result<Json<Vec<Post>>, StatusCode> {
-    info!("Récupération de tous les posts");
-    let posts = sqlx::query_as::<_, Post>("SELECT id, user_id, title, body FROM posts")
+const POST_SORTS: &[SortField] = &[
+    SortField { name: "id", expr: "id", sql_type: "int4", default_order: SortOrder::Desc },
+    SortField { name: "title", expr: "title", sql_type: "text", default_order: SortOrder::Asc },
+];
+
+#[derive(Deserialize)]
+struct PostQuery {
+    user_id: Option<i32>,
+}
+
+async fn get_posts(
+    Extension(pool): Extension<DbPool>,
+    Query(filters): Query<PostQuery>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<Post>>, AppError> {
+    info!("Récupération des posts (auteur: {:?})", filters.user_id);
+    let page = page.resolve(POST_SORTS, MAX_LIMIT)?;
+
+    let mut query = QueryBuilder::new("SELECT id, user_id, title, body FROM posts WHERE true");
+    if let Some(user_id) = filters.user_id {
+        query.push(" AND user_id = ").push_bind(user_id);
+    }
+    page.push_sql(&mut query, "id");
+
+    let posts = query
+        .build_query_as::<Post>()
         .fetch_all(&pool)
         .await
         .map_err(|e| {
             error!("Erreur lors de la récupération des posts: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            AppError::from(e)
         })?;
 
     info!("{} posts récupérés", posts.len());
-    Ok(Json(posts))
+    Ok(Json(page.into_page(posts, |p, sort| match sort {
+        "title" => (p.title.clone(), p.id as i64),
+        _ => (p.id.to_string(), p.id as i64),
+    })))
 }
 
 async fn get_post(
     Extension(pool): Extension<DbPool>,
     Path(id): Path<i32>,
-) -> Result<Json<Post>, StatusCode> {
+) -> Result<Json<Post>, AppError> {
     info!("Récupération du post avec ID: {}", id);
     let opt = sqlx::query_as::<_, Post>(
         "SELECT id, user_id, title, body FROM posts WHERE id = $1"
@@ -45,7 +85,7 @@ async fn get_post(
     .await
     .map_err(|e| {
         error!("Erreur lors de la récupération du post {}: {}", id, e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     match opt {
@@ -55,27 +95,28 @@ async fn get_post(
         }
         None => {
             warn!("Post {} non trouvé", id);
-            Err(StatusCode::NOT_FOUND)
+            Err(AppError::NotFound("Post not found".to_string()))
         }
     }
 }
 
 async fn create_post(
     Extension(pool): Extension<DbPool>,
-    Json(new_post): Json<CreatePost>,
-) -> Result<Json<Post>, StatusCode> {
-    info!("Création d'un nouveau post: '{}' (user_id: {:?})", new_post.title, new_post.user_id);
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(new_post): ValidatedJson<CreatePost>,
+) -> Result<Json<Post>, AppError> {
+    info!("Création d'un nouveau post: '{}' par l'utilisateur {}", new_post.title, claims.user_id);
     let post = sqlx::query_as::<_, Post>(
         "INSERT INTO posts (user_id, title, body) VALUES ($1, $2, $3) RETURNING id, user_id, title, body"
     )
-    .bind(new_post.user_id)
+    .bind(claims.user_id)
     .bind(&new_post.title)
     .bind(&new_post.body)
     .fetch_one(&pool)
     .await
     .map_err(|e| {
         error!("Erreur lors de la création du post: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     info!("Post créé avec succès: ID {} - '{}'", post.id, post.title);
@@ -84,16 +125,19 @@ async fn create_post(
 
 async fn update_post(
     Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
     Path(id): Path<i32>,
-    Json(updated_post): Json<UpdatePost>,
-) -> Result<Json<Post>, StatusCode> {
-    info!("Mise à jour du post ID: {} - '{}'", id, updated_post.title);
+    ValidatedJson(updated_post): ValidatedJson<UpdatePost>,
+) -> Result<Json<Post>, AppError> {
+    info!("Mise à jour du post ID: {} - '{}' par l'utilisateur {}", id, updated_post.title, claims.user_id);
+    ensure_can_edit_post(&pool, &claims, id).await?;
+
+    // L'auteur n'est jamais modifiable
     let res = sqlx::query_as::<_, Post>(
-        "UPDATE posts SET title = $1, body = $2, user_id = $3 WHERE id = $4 RETURNING id, user_id, title, body"
+        "UPDATE posts SET title = $1, body = $2 WHERE id = $3 RETURNING id, user_id, title, body"
     )
     .bind(&updated_post.title)
     .bind(&updated_post.body)
-    .bind(updated_post.user_id)
     .bind(id)
     .fetch_one(&pool)
     .await;
@@ -105,20 +149,22 @@ async fn update_post(
         }
         Err(SqlxError::RowNotFound) => {
             warn!("Post {} non trouvé pour la mise à jour", id);
-            Err(StatusCode::NOT_FOUND)
+            Err(AppError::NotFound("Post not found".to_string()))
         }
         Err(e) => {
             error!("Erreur lors de la mise à jour du post {}: {}", id, e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(AppError::from(e))
         }
     }
 }
 
 async fn delete_post(
     Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
     Path(id): Path<i32>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    info!("Suppression du post ID: {}", id);
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Suppression du post ID: {} par l'utilisateur {}", id, claims.user_id);
+    ensure_can_edit_post(&pool, &claims, id).await?;
     let result = sqlx::query("DELETE FROM posts WHERE id = $1")
         .bind(id)
         .execute(&pool)
@@ -133,12 +179,32 @@ async fn delete_post(
         }
         Ok(_) => {
             warn!("Post {} non trouvé pour la suppression", id);
-            Err(StatusCode::NOT_FOUND)
+            Err(AppError::NotFound("Post not found".to_string()))
         }
         Err(e) => {
             error!("Erreur lors de la suppression du post {}: {}", id, e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(AppError::from(e))
         }
     }
 }
 
+
+/// 404 si le post n'existe pas, 403 si l'appelant n'en est ni l'auteur ni modérateur.
+async fn ensure_can_edit_post(pool: &DbPool, claims: &Claims, id: i32) -> Result<(), AppError> {
+    let author: Option<i32> = sqlx::query_scalar("SELECT user_id FROM posts WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la vérification de l'auteur du post {}: {}", id, e);
+            AppError::from(e)
+        })?
+        .ok_or_else(|| {
+            warn!("Post {} non trouvé", id);
+            AppError::NotFound("Post not found".to_string())
+        })?;
+
+    // Les posts anonymes (antérieurs à l'authentification) ne sont modifiables que par la modération
+    let owners: Vec<i32> = author.into_iter().collect();
+    authz::authorize(claims, &owners, Some(Role::Moderator), &format!("le post {}", id))
+}
@@ -0,0 +1,308 @@
+use axum::{
+    Json, Router,
+    extract::Extension,
+    routing::get
+};
+use serde::Deserialize;
+use shared::errors::AppError;
+use shared::jwt::{Claims, Role};
+use sqlx::QueryBuilder;
+use tracing::{info, warn, error};
+
+use crate::{
+    authz,
+    db::DbPool,
+    geometry,
+    models::segment::{CreateSegment, Segment, SegmentLeaderboardEntry, SegmentPr, SEGMENT_COLUMNS},
+    pagination::{timestamp_key, Page, PageParams, SortField, SortOrder, MAX_LIMIT},
+    route_formats,
+    validation::{Path, Query, ValidatedJson},
+};
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/", get(get_segments).post(create_segment))
+        .route("/prs", get(get_segment_prs))
+        .route("/{id}", get(get_segment).delete(delete_segment))
+        .route("/{id}/leaderboard", get(get_segment_leaderboard))
+}
+
+const SEGMENT_SORTS: &[SortField] = &[
+    SortField { name: "created_at", expr: "COALESCE(created_at, 'epoch')", sql_type: "timestamp", default_order: SortOrder::Desc },
+    SortField { name: "name", expr: "name", sql_type: "text", default_order: SortOrder::Asc },
+];
+
+#[derive(Deserialize)]
+struct SegmentQuery {
+    route_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct PrQuery {
+    /// Par défaut, l'utilisateur connecté.
+    user_id: Option<i32>,
+}
+
+/// Condition ` AND ...` : le parcours `route_id_expr` est visible par `user_id`. Un segment
+/// reprend une partie du tracé, il suit la visibilité de son parcours.
+fn push_route_visible(query: &mut QueryBuilder<'_, sqlx::Postgres>, route_id_expr: &str, user_id: i32) {
+    query.push(format!(" AND EXISTS (SELECT 1 FROM routes WHERE routes.id = {}", route_id_expr));
+    authz::push_route_visible_to(query, user_id);
+    query.push(")");
+}
+
+async fn create_segment(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(new_segment): ValidatedJson<CreateSegment>,
+) -> Result<Json<Segment>, AppError> {
+    info!("Création du segment '{}' sur le parcours {} par l'utilisateur {}", new_segment.name, new_segment.route_id, claims.user_id);
+
+    let (is_public, path_data) = sqlx::query_as::<_, (bool, serde_json::Value)>(
+        "SELECT is_public, path_data FROM routes WHERE id = $1"
+    )
+    .bind(new_segment.route_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la récupération du parcours {}: {}", new_segment.route_id, e);
+        AppError::from(e)
+    })?
+    .ok_or_else(|| AppError::NotFound("Route not found".to_string()))?;
+
+    if !is_public {
+        warn!("Segment refusé : le parcours {} n'est pas public", new_segment.route_id);
+        return Err(AppError::Unprocessable("Segments can only be created on public routes".to_string()));
+    }
+
+    let positions = geometry::parse_path(&path_data)
+        .map_err(|e| AppError::Unprocessable(format!("The route path cannot be used for a segment: {}", e)))?;
+    let route_length = geometry::path_length(&positions);
+    if new_segment.end_distance_meters > route_length {
+        return Err(AppError::validation(
+            "Invalid request body",
+            serde_json::json!({ "end_distance_meters": [format!("must not exceed the route length ({:.0} m)", route_length)] }),
+        ));
+    }
+
+    let slice = geometry::slice_path(&positions, new_segment.start_distance_meters, new_segment.end_distance_meters);
+    let metrics = geometry::compute_metrics(&slice);
+
+    let segment = sqlx::query_as::<_, Segment>(&format!(
+        "INSERT INTO segments (route_id, user_id, name, start_distance_meters, end_distance_meters,
+                               distance_meters, path_data, min_lat, min_lon, max_lat, max_lon)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+         RETURNING {}",
+        SEGMENT_COLUMNS
+    ))
+    .bind(new_segment.route_id)
+    .bind(claims.user_id)
+    .bind(&new_segment.name)
+    .bind(new_segment.start_distance_meters as f32)
+    .bind(new_segment.end_distance_meters as f32)
+    .bind(metrics.distance_meters as f32)
+    .bind(route_formats::to_path_data(&slice))
+    .bind(metrics.min_lat)
+    .bind(metrics.min_lon)
+    .bind(metrics.max_lat)
+    .bind(metrics.max_lon)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la création du segment: {}", e);
+        AppError::from(e)
+    })?;
+
+    info!("Segment créé avec succès: {} (ID: {})", segment.name, segment.id);
+    Ok(Json(segment))
+}
+
+async fn get_segments(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Query(filters): Query<SegmentQuery>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<Segment>>, AppError> {
+    info!("Récupération des segments (parcours {:?})", filters.route_id);
+    let page = page.resolve(SEGMENT_SORTS, MAX_LIMIT)?;
+
+    let mut query = QueryBuilder::new(format!("SELECT {} FROM segments WHERE true", SEGMENT_COLUMNS));
+    if let Some(route_id) = filters.route_id {
+        query.push(" AND route_id = ").push_bind(route_id);
+    }
+    push_route_visible(&mut query, "segments.route_id", claims.user_id);
+    page.push_sql(&mut query, "id");
+
+    let segments = query
+        .build_query_as::<Segment>()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération des segments: {}", e);
+            AppError::from(e)
+        })?;
+
+    info!("{} segments récupérés", segments.len());
+    Ok(Json(page.into_page(segments, |s, sort| {
+        let key = match sort {
+            "name" => s.name.clone(),
+            _ => timestamp_key(s.created_at),
+        };
+        (key, s.id as i64)
+    })))
+}
+
+async fn get_segment(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+) -> Result<Json<Segment>, AppError> {
+    info!("Récupération du segment {}", id);
+
+    let mut query = QueryBuilder::new(format!("SELECT {} FROM segments WHERE id = ", SEGMENT_COLUMNS));
+    query.push_bind(id);
+    push_route_visible(&mut query, "segments.route_id", claims.user_id);
+
+    query
+        .build_query_as::<Segment>()
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération du segment {}: {}", id, e);
+            AppError::from(e)
+        })?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound("Segment not found".to_string()))
+}
+
+async fn delete_segment(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Suppression du segment {}", id);
+
+    let creator: i32 = sqlx::query_scalar("SELECT user_id FROM segments WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la vérification du créateur du segment {}: {}", id, e);
+            AppError::from(e)
+        })?
+        .ok_or_else(|| AppError::NotFound("Segment not found".to_string()))?;
+
+    authz::authorize(&claims, &[creator], Some(Role::Moderator), &format!("le segment {}", id))?;
+
+    sqlx::query("DELETE FROM segments WHERE id = $1")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la suppression du segment {}: {}", id, e);
+            AppError::from(e)
+        })?;
+
+    info!("Segment {} supprimé avec succès", id);
+    Ok(Json(serde_json::json!({
+        "message": "Segment deleted successfully"
+    })))
+}
+
+/// Meilleur passage de chaque utilisateur, du plus rapide au plus lent. Comme le classement du
+/// parcours, seuls comptent les efforts de courses vérifiées et sans signalement en cours.
+async fn get_segment_leaderboard(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<SegmentLeaderboardEntry>>, AppError> {
+    info!("Récupération du classement du segment {}", id);
+
+    let mut query = QueryBuilder::new("SELECT EXISTS(SELECT 1 FROM segments WHERE id = ");
+    query.push_bind(id);
+    push_route_visible(&mut query, "segments.route_id", claims.user_id);
+    query.push(")");
+    let exists = query
+        .build_query_scalar::<bool>()
+        .fetch_one(&pool)
+        .await
+        .map_err(AppError::from)?;
+    if !exists {
+        return Err(AppError::NotFound("Segment not found".to_string()));
+    }
+
+    let leaderboard = sqlx::query_as::<_, SegmentLeaderboardEntry>(
+        "SELECT RANK() OVER (ORDER BY b.elapsed_seconds) AS rank,
+                b.user_id, u.username, b.id AS effort_id, b.score_id, b.elapsed_seconds, b.created_at
+         FROM (
+             SELECT DISTINCT ON (e.user_id) e.id, e.user_id, e.score_id, e.elapsed_seconds, e.created_at
+             FROM segment_efforts e
+             JOIN scores s ON s.id = e.score_id
+             WHERE e.segment_id = $1
+               AND s.verification_status = 'verified'
+               AND NOT EXISTS (SELECT 1 FROM score_flags f WHERE f.score_id = s.id AND f.status <> 'dismissed')
+             ORDER BY e.user_id, e.elapsed_seconds, e.id
+         ) b
+         JOIN users u ON u.id = b.user_id
+         ORDER BY b.elapsed_seconds, b.id
+         LIMIT 100"
+    )
+    .bind(id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        error!("Erreur lors de la récupération du classement du segment {}: {}", id, e);
+        AppError::from(e)
+    })?;
+
+    info!("{} entrées récupérées pour le classement du segment {}", leaderboard.len(), id);
+    Ok(Json(leaderboard))
+}
+
+/// Records personnels d'un utilisateur sur chaque segment qu'il a parcouru, avec le même filtre
+/// que [`get_segment_leaderboard`].
+async fn get_segment_prs(
+    Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<PrQuery>,
+) -> Result<Json<Vec<SegmentPr>>, AppError> {
+    let user_id = params.user_id.unwrap_or(claims.user_id);
+    info!("Récupération des records de segments de l'utilisateur {}", user_id);
+
+    let mut query = QueryBuilder::new(
+        "SELECT r.segment_id, s.name AS segment_name, s.route_id, r.rank,
+                r.id AS effort_id, r.score_id, r.elapsed_seconds, r.created_at
+         FROM (
+             SELECT b.*, RANK() OVER (PARTITION BY b.segment_id ORDER BY b.elapsed_seconds) AS rank
+             FROM (
+                 SELECT DISTINCT ON (e.segment_id, e.user_id) e.id, e.segment_id, e.user_id, e.score_id, e.elapsed_seconds, e.created_at
+                 FROM segment_efforts e
+                 JOIN scores sc ON sc.id = e.score_id
+                 WHERE sc.verification_status = 'verified'
+                   AND NOT EXISTS (SELECT 1 FROM score_flags f WHERE f.score_id = sc.id AND f.status <> 'dismissed')
+                   AND e.segment_id IN (SELECT segment_id FROM segment_efforts WHERE user_id = "
+    );
+    query.push_bind(user_id).push(
+        ")
+                 ORDER BY e.segment_id, e.user_id, e.elapsed_seconds, e.id
+             ) b
+         ) r
+         JOIN segments s ON s.id = r.segment_id
+         WHERE r.user_id = "
+    );
+    query.push_bind(user_id);
+    push_route_visible(&mut query, "s.route_id", claims.user_id);
+    query.push(" ORDER BY r.segment_id");
+
+    let prs = query
+        .build_query_as::<SegmentPr>()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération des records de l'utilisateur {}: {}", user_id, e);
+            AppError::from(e)
+        })?;
+
+    info!("{} records récupérés pour l'utilisateur {}", prs.len(), user_id);
+    Ok(Json(prs))
+}
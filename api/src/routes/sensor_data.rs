@@ -1,99 +1,65 @@
 use axum::{
     Json, Router,
-    extract::{Extension, Path},
-    http::StatusCode,
+    extract::Extension,
     routing::{get, post}
 };
-use tracing::{info, error};
+use shared::{errors::AppError, jwt::{Claims, Role}};
+use sqlx::QueryBuilder;
+use tracing::{info, warn, error};
 
 use crate::{
+    anti_cheat,
+    authz,
     db::DbPool,
-    models::sensor_data::{BulkSensorData, CreateSensorData, SensorData},
+    models::sensor_data::{BulkSensorData, SensorData},
+    pagination::{Page, PageParams, SortField, SortOrder},
+    score_metrics,
+    segments,
+    validation::{Path, Query, ValidatedJson},
+    verification,
 };
 
 pub fn router() -> Router {
     Router::new()
-        .route("/", post(upload_sensor_data))
         .route("/bulk", post(upload_bulk_sensor_data))
         .route("/score/{score_id}", get(get_sensor_data))
 }
 
-async fn upload_sensor_data(
-    Extension(pool): Extension<DbPool>,
-    Path(score_id): Path<i32>,
-    Json(data): Json<CreateSensorData>,
-) -> Result<Json<SensorData>, StatusCode> {
-    info!("Upload de données de capteur pour le score {}", score_id);
-    
-    let sensor_data = sqlx::query_as::<_, SensorData>(
-        "INSERT INTO sensor_data (
-            score_id, timestamp_offset_ms, accel_x, accel_y, accel_z,
-            gyro_x, gyro_y, gyro_z, orientation_azimuth, orientation_pitch, orientation_roll,
-            speed_kmh, g_force, inclination_degrees, sound_db, nearby_devices,
-            latitude, longitude, altitude
-        )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
-        RETURNING id, score_id, timestamp_offset_ms, accel_x, accel_y, accel_z,
-                  gyro_x, gyro_y, gyro_z, orientation_azimuth, orientation_pitch, orientation_roll,
-                  speed_kmh, g_force, inclination_degrees, sound_db, nearby_devices,
-                  latitude, longitude, altitude"
-    )
-    .bind(score_id)
-    .bind(data.timestamp_offset_ms)
-    .bind(data.accel_x)
-    .bind(data.accel_y)
-    .bind(data.accel_z)
-    .bind(data.gyro_x)
-    .bind(data.gyro_y)
-    .bind(data.gyro_z)
-    .bind(data.orientation_azimuth)
-    .bind(data.orientation_pitch)
-    .bind(data.orientation_roll)
-    .bind(data.speed_kmh)
-    .bind(data.g_force)
-    .bind(data.inclination_degrees)
-    .bind(data.sound_db)
-    .bind(data.nearby_devices)
-    .bind(data.latitude)
-    .bind(data.longitude)
-    .bind(data.altitude)
-    .fetch_one(&pool)
-    .await
-    .map_err(|e| {
-        error!("Erreur lors de l'upload des données de capteur: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+/// Auteur, statut de vérification et parcours du score `score_id`. 404 si le score n'existe pas.
+async fn fetch_score(pool: &DbPool, score_id: i32) -> Result<(i32, String, i32), AppError> {
+    sqlx::query_as::<_, (i32, String, i32)>("SELECT user_id, verification_status, route_id FROM scores WHERE id = $1")
+        .bind(score_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la vérification du score: {}", e);
+            AppError::from(e)
+        })?
+        .ok_or_else(|| {
+            warn!("Score {} non trouvé", score_id);
+            AppError::NotFound("Score not found".to_string())
+        })
+}
 
-    info!("Données de capteur uploadées avec succès (ID: {})", sensor_data.id);
-    Ok(Json(sensor_data))
+/// Seul l'auteur d'une course peut lui ajouter des relevés : ils décident de sa vérification,
+/// de ses métriques et des signalements anti-triche.
+async fn authorize_upload(pool: &DbPool, claims: &Claims, score_id: i32) -> Result<(), AppError> {
+    let (owner, _, _) = fetch_score(pool, score_id).await?;
+    authz::authorize(claims, &[owner], None, &format!("les relevés du score {}", score_id))
 }
 
 async fn upload_bulk_sensor_data(
     Extension(pool): Extension<DbPool>,
-    Json(bulk_data): Json<BulkSensorData>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(bulk_data): ValidatedJson<BulkSensorData>,
+) -> Result<Json<serde_json::Value>, AppError> {
     info!("Upload en masse de {} points de données pour le score {}", bulk_data.data.len(), bulk_data.score_id);
-    
-    // Verify score exists
-    let score_exists = sqlx::query_scalar::<_, bool>(
-        "SELECT EXISTS(SELECT 1 FROM scores WHERE id = $1)"
-    )
-    .bind(bulk_data.score_id)
-    .fetch_one(&pool)
-    .await
-    .map_err(|e| {
-        error!("Erreur lors de la vérification du score: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-    
-    if !score_exists {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    authorize_upload(&pool, &claims, bulk_data.score_id).await?;
     
     // Insert all sensor data in a transaction
     let mut tx = pool.begin().await.map_err(|e| {
         error!("Erreur lors du démarrage de la transaction: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
     
     let mut inserted_count = 0;
@@ -131,7 +97,7 @@ async fn upload_bulk_sensor_data(
         .await
         .map_err(|e| {
             error!("Erreur lors de l'insertion des données: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            AppError::from(e)
         })?;
         
         inserted_count += result.rows_affected();
@@ -139,40 +105,85 @@ async fn upload_bulk_sensor_data(
     
     tx.commit().await.map_err(|e| {
         error!("Erreur lors de la validation de la transaction: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
     
     info!("{} points de données insérés avec succès", inserted_count);
+
+    // Les relevés sont enregistrés : un échec du rapprochement ne doit pas faire échouer l'upload
+    let segment_efforts = segments::match_score(&pool, bulk_data.score_id).await.unwrap_or_else(|e| {
+        error!("Erreur lors du rapprochement du score {} avec les segments: {}", bulk_data.score_id, e);
+        0
+    });
+    // Toute la trace est revérifiée : un envoi en plusieurs fois peut compléter une course rejetée
+    let verification = verification::verify_score(&pool, bulk_data.score_id).await.unwrap_or_else(|e| {
+        error!("Erreur lors de la vérification du score {}: {}", bulk_data.score_id, e);
+        None
+    });
+    let metrics = score_metrics::process_score(&pool, bulk_data.score_id).await.unwrap_or_else(|e| {
+        error!("Erreur lors du calcul des métriques du score {}: {}", bulk_data.score_id, e);
+        None
+    });
+    // Les règles sur les relevés ne s'appliquent qu'une fois la trace reçue
+    if let Err(e) = anti_cheat::evaluate_score(&pool, bulk_data.score_id).await {
+        error!("Erreur lors de l'évaluation anti-triche du score {}: {}", bulk_data.score_id, e);
+    }
+
     Ok(Json(serde_json::json!({
         "message": "Sensor data uploaded successfully",
-        "inserted_count": inserted_count
+        "inserted_count": inserted_count,
+        "segment_efforts": segment_efforts,
+        "verification": verification,
+        "metrics": metrics
     })))
 }
 
+/// Un parcours d'une heure à 1 Hz tient en quelques pages.
+const SENSOR_MAX_LIMIT: i64 = 1_000;
+
+const SENSOR_SORTS: &[SortField] = &[
+    SortField { name: "timestamp", expr: "timestamp_offset_ms", sql_type: "int4", default_order: SortOrder::Asc },
+];
+
 async fn get_sensor_data(
     Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
     Path(score_id): Path<i32>,
-) -> Result<Json<Vec<SensorData>>, StatusCode> {
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<SensorData>>, AppError> {
     info!("Récupération des données de capteur pour le score {}", score_id);
-    
-    let sensor_data = sqlx::query_as::<_, SensorData>(
+    let page = page.resolve(SENSOR_SORTS, SENSOR_MAX_LIMIT)?;
+
+    // Trace GPS brute : l'auteur et les modérateurs, sinon une course vérifiée sur un parcours visible
+    let (owner, verification_status, route_id) = fetch_score(&pool, score_id).await?;
+    if authz::authorize(&claims, &[owner], Some(Role::Moderator), &format!("les relevés du score {}", score_id)).is_err() {
+        if verification_status != "verified" {
+            warn!("Relevés du score {} non vérifié refusés à l'utilisateur {}", score_id, claims.user_id);
+            return Err(AppError::Forbidden("You are not allowed to view this sensor data".to_string()));
+        }
+        authz::visible_route_owner(&pool, route_id, claims.user_id).await?;
+    }
+
+    let mut query = QueryBuilder::new(
         "SELECT id, score_id, timestamp_offset_ms, accel_x, accel_y, accel_z,
                 gyro_x, gyro_y, gyro_z, orientation_azimuth, orientation_pitch, orientation_roll,
                 speed_kmh, g_force, inclination_degrees, sound_db, nearby_devices,
                 latitude, longitude, altitude
          FROM sensor_data
-         WHERE score_id = $1
-         ORDER BY timestamp_offset_ms"
-    )
-    .bind(score_id)
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| {
-        error!("Erreur lors de la récupération des données de capteur: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+         WHERE score_id = "
+    );
+    query.push_bind(score_id);
+    page.push_sql(&mut query, "id");
+
+    let sensor_data = query
+        .build_query_as::<SensorData>()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération des données de capteur: {}", e);
+            AppError::from(e)
+        })?;
 
     info!("{} points de données récupérés pour le score {}", sensor_data.len(), score_id);
-    Ok(Json(sensor_data))
+    Ok(Json(page.into_page(sensor_data, |d, _| (d.timestamp_offset_ms.to_string(), d.id as i64))))
 }
-
@@ -1,30 +1,44 @@
 use axum::{
     Json, Router, 
-    extract::{Extension, Path}, 
-    http::{StatusCode}, 
+    extract::Extension,
+    handler::Handler,
+    middleware,
     routing::{get, post}
 };
+use serde::Deserialize;
+use shared::errors::AppError;
+use sqlx::QueryBuilder;
 use tracing::{info, warn, error};
+use shared::jwt::{Claims, Role};
 
 use crate::{
+    authz::{self, Admin, RequireRole},
     db::DbPool,
+    middleware::auth_middleware,
     models::user::{CreateUser, User},
+    pagination::{escape_like, Page, PageParams, SortField, SortOrder, MAX_LIMIT},
+    validation::{Path, Query, ValidatedJson},
 };
 
 pub fn router() -> Router {
+    // Lecture publique ; création de comptes réservée aux administrateurs,
+    // suppression et demandes d'ami au titulaire du compte (ou à un administrateur)
+    let authenticated = middleware::from_fn(auth_middleware);
+
     Router::new()
-        .route("/", get(get_users).post(create_user))
-        .route("/{id}", get(get_user).delete(delete_user))
-        .route("/{user_id}/friends/{friend_id}", post(add_friend))
+        .route("/", get(get_users).post(create_user.layer(authenticated.clone())))
+        .route("/{id}", get(get_user).delete(delete_user.layer(authenticated.clone())))
+        .route("/{user_id}/friends/{friend_id}", post(add_friend.layer(authenticated)))
 }
 
 async fn create_user(
     Extension(pool): Extension<DbPool>,
-    Json(new_user): Json<CreateUser>,
-) -> Result<Json<User>, StatusCode> {
-    info!("Création d'un nouvel utilisateur: {} ({})", new_user.username, new_user.email);
+    RequireRole(claims, _): RequireRole<Admin>,
+    ValidatedJson(new_user): ValidatedJson<CreateUser>,
+) -> Result<Json<User>, AppError> {
+    info!("Création d'un nouvel utilisateur: {} ({}) par l'administrateur {}", new_user.username, new_user.email, claims.user_id);
     let user = sqlx::query_as::<_, User>(
-        "INSERT INTO users (username, email) VALUES ($1, $2) RETURNING id, username, email"
+        "INSERT INTO users (username, email) VALUES ($1, $2) RETURNING id, username, email, email_verified"
     )
     .bind(&new_user.username)
     .bind(&new_user.email)
@@ -32,45 +46,68 @@ async fn create_user(
     .await
     .map_err(|e| {
         error!("Erreur lors de la création de l'utilisateur: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     info!("Utilisateur créé avec succès: {} (ID: {})", user.username, user.id);
     Ok(Json(user))
 }
 
+const USER_SORTS: &[SortField] = &[
+    SortField { name: "id", expr: "id", sql_type: "int4", default_order: SortOrder::Asc },
+    SortField { name: "username", expr: "username", sql_type: "text", default_order: SortOrder::Asc },
+];
+
+#[derive(Deserialize)]
+struct UserQuery {
+    /// Recherche sur le nom d'utilisateur (contient, insensible à la casse).
+    q: Option<String>,
+}
+
 async fn get_users(
-    Extension(pool): Extension<DbPool>
-) -> Result<Json<Vec<User>>, StatusCode> {
-    info!("Récupération de tous les utilisateurs");
-    let users = sqlx::query_as::<_, User>(
-        "SELECT id, username, email FROM users"
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| {
-        error!("Erreur lors de la récupération des utilisateurs: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    Extension(pool): Extension<DbPool>,
+    Query(filters): Query<UserQuery>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<User>>, AppError> {
+    info!("Récupération des utilisateurs (recherche: {:?})", filters.q);
+    let page = page.resolve(USER_SORTS, MAX_LIMIT)?;
+
+    let mut query = QueryBuilder::new("SELECT id, username, email, email_verified FROM users WHERE true");
+    if let Some(q) = &filters.q {
+        query.push(" AND username ILIKE ").push_bind(format!("%{}%", escape_like(q)));
+    }
+    page.push_sql(&mut query, "id");
+
+    let users = query
+        .build_query_as::<User>()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            error!("Erreur lors de la récupération des utilisateurs: {}", e);
+            AppError::from(e)
+        })?;
 
     info!("{} utilisateurs récupérés", users.len());
-    Ok(Json(users))
+    Ok(Json(page.into_page(users, |u, sort| match sort {
+        "username" => (u.username.clone(), u.id as i64),
+        _ => (u.id.to_string(), u.id as i64),
+    })))
 }
 
 async fn get_user(
     Extension(pool): Extension<DbPool>,
     Path(id): Path<i32>,
-) -> Result<Json<User>, StatusCode> {
+) -> Result<Json<User>, AppError> {
     info!("Récupération de l'utilisateur avec ID: {}", id);
     let opt = sqlx::query_as::<_, User>(
-        "SELECT id, username, email FROM users WHERE id = $1"
+        "SELECT id, username, email, email_verified FROM users WHERE id = $1"
     )
     .bind(id)
     .fetch_optional(&pool)
     .await
     .map_err(|e| {
         error!("Erreur lors de la récupération de l'utilisateur {}: {}", id, e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     match opt {
@@ -80,16 +117,18 @@ async fn get_user(
         }
         None => {
             warn!("Utilisateur {} non trouvé", id);
-            Err(StatusCode::NOT_FOUND)
+            Err(AppError::NotFound("User not found".to_string()))
         }
     }
 }
 
 async fn delete_user(
     Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
     Path(id): Path<i32>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    info!("Suppression de l'utilisateur ID: {}", id);
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("Suppression de l'utilisateur ID: {} par l'utilisateur {}", id, claims.user_id);
+    authz::authorize(&claims, &[id], Some(Role::Admin), &format!("le compte {}", id))?;
     let result = sqlx::query("DELETE FROM users WHERE id = $1")
         .bind(id)
         .execute(&pool)
@@ -104,20 +143,22 @@ async fn delete_user(
         }
         Ok(_) => {
             warn!("Utilisateur {} non trouvé pour la suppression", id);
-            Err(StatusCode::NOT_FOUND)
+            Err(AppError::NotFound("User not found".to_string()))
         }
         Err(e) => {
             error!("Erreur lors de la suppression de l'utilisateur {}: {}", id, e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(AppError::from(e))
         }
     }
 }
 
 async fn add_friend(
     Extension(pool): Extension<DbPool>,
+    Extension(claims): Extension<Claims>,
     Path((user_id, friend_id)): Path<(i32, i32)>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, AppError> {
     info!("Ajout de l'ami {} à l'utilisateur {}", friend_id, user_id);
+    authz::authorize(&claims, &[user_id], Some(Role::Admin), &format!("les amis de l'utilisateur {}", user_id))?;
 
     // Check if both users exist
     let user_exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
@@ -126,7 +167,7 @@ async fn add_friend(
         .await
         .map_err(|e| {
             error!("Erreur lors de la vérification de l'utilisateur: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            AppError::from(e)
         })?;
 
     let friend_exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
@@ -135,12 +176,12 @@ async fn add_friend(
         .await
         .map_err(|e| {
             error!("Erreur lors de la vérification de l'ami: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            AppError::from(e)
         })?;
 
     if !user_exists || !friend_exists {
         warn!("Utilisateur ou ami non trouvé: user={}, friend={}", user_id, friend_id);
-        return Err(StatusCode::NOT_FOUND);
+        return Err(AppError::NotFound("User or friend not found".to_string()));
     }
 
     // Add friendship
@@ -154,7 +195,7 @@ async fn add_friend(
     .await
     .map_err(|e| {
         error!("Erreur lors de l'ajout de l'ami: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        AppError::from(e)
     })?;
 
     if result.rows_affected() > 0 {
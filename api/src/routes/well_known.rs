@@ -0,0 +1,15 @@
+use axum::{Json, Router, routing::get};
+use jsonwebtoken::jwk::JwkSet;
+use tracing::info;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/jwks.json", get(get_jwks))
+}
+
+/// Clés publiques de vérification des JWT, consommables par n'importe quel service.
+async fn get_jwks() -> Json<JwkSet> {
+    let jwks = shared::jwt::jwks();
+    info!("Publication de {} clé(s) publique(s) JWKS", jwks.keys.len());
+    Json(jwks)
+}
@@ -0,0 +1,300 @@
+//! Métriques d'une course recalculées par le serveur à partir des relevés bruts (`sensor_data`),
+//! puis comparées à celles que le client a envoyées avec le score.
+
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{
+    db::DbPool,
+    geometry::{self, Position},
+    models::score::MAX_SPEED_KMH,
+};
+
+/// Accélération de la pesanteur, en m/s².
+pub const STANDARD_GRAVITY: f64 = 9.806_65;
+/// Vitesse de pointe mesurée sur au moins cette durée, pour lisser le bruit GPS.
+pub const SPEED_WINDOW_MS: i32 = 5_000;
+/// Un relevé GPS qui impliquerait une vitesse supérieure depuis le précédent retenu est un saut
+/// de position : il est ignoré.
+const GPS_JUMP_SPEED_KMH: f64 = MAX_SPEED_KMH as f64;
+/// Autant de sauts d'affilée, cohérents entre eux, désignent plutôt le relevé retenu comme
+/// aberrant (typiquement le premier fix, avant que le GPS ne se cale) : la trace repart d'eux.
+const ANCHOR_RESET_FIXES: usize = 3;
+/// Médiane glissante sur autant de relevés : un pic d'un ou deux relevés disparaît.
+const SPIKE_FILTER_WINDOW: usize = 5;
+
+/// Écart toléré entre la valeur du client et celle du serveur : le plus grand des deux seuils.
+struct Tolerance {
+    metric: &'static str,
+    absolute: f64,
+    relative: f64,
+}
+
+const TOLERANCES: [Tolerance; 5] = [
+    Tolerance { metric: "max_speed_kmh", absolute: 3.0, relative: 0.15 },
+    Tolerance { metric: "avg_speed_kmh", absolute: 1.0, relative: 0.10 },
+    Tolerance { metric: "max_g_force", absolute: 0.3, relative: 0.25 },
+    Tolerance { metric: "max_inclination_degrees", absolute: 5.0, relative: 0.0 },
+    Tolerance { metric: "max_sound_db", absolute: 6.0, relative: 0.0 },
+];
+
+/// Relevé brut d'une course.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sample {
+    pub offset_ms: i32,
+    pub position: Option<Position>,
+    /// Accélération (x, y, z) en m/s².
+    pub accel: Option<(f64, f64, f64)>,
+    pub speed_kmh: Option<f64>,
+    pub g_force: Option<f64>,
+    pub inclination_degrees: Option<f64>,
+    pub sound_db: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ScoreMetrics {
+    pub max_speed_kmh: Option<f64>,
+    pub avg_speed_kmh: Option<f64>,
+    pub max_g_force: Option<f64>,
+    pub max_inclination_degrees: Option<f64>,
+    pub max_sound_db: Option<f64>,
+}
+
+impl ScoreMetrics {
+    fn values(&self) -> [Option<f64>; 5] {
+        [self.max_speed_kmh, self.avg_speed_kmh, self.max_g_force, self.max_inclination_degrees, self.max_sound_db]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Discrepancy {
+    pub metric: &'static str,
+    pub client: f64,
+    pub server: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricsReport {
+    pub server: ScoreMetrics,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+/// Métriques tirées de `samples` (triés par temps).
+///
+/// Les vitesses viennent de la trace GPS, sauts de position écartés ; sans trace, des vitesses
+/// mesurées par l'appareil. L'accélération vient de l'accéléromètre (sa norme, en g), à défaut de
+/// `g_force`. Accélération, inclinaison, son et vitesses de l'appareil passent par une médiane
+/// glissante qui écarte les pics isolés.
+pub fn compute(samples: &[Sample]) -> ScoreMetrics {
+    let (max_speed_kmh, avg_speed_kmh) = match gps_speeds(samples) {
+        Some(speeds) => speeds,
+        None => {
+            let speeds = despike(&samples.iter().filter_map(|s| s.speed_kmh).collect::<Vec<_>>());
+            let avg = (!speeds.is_empty()).then(|| speeds.iter().sum::<f64>() / speeds.len() as f64);
+            (max(&speeds), avg)
+        }
+    };
+
+    let g_forces: Vec<f64> = samples
+        .iter()
+        .filter_map(|s| match s.accel {
+            Some((x, y, z)) => Some((x * x + y * y + z * z).sqrt() / STANDARD_GRAVITY),
+            None => s.g_force,
+        })
+        .collect();
+
+    ScoreMetrics {
+        max_speed_kmh,
+        avg_speed_kmh,
+        max_g_force: max(&despike(&g_forces)),
+        max_inclination_degrees: max(&despike(&samples.iter().filter_map(|s| s.inclination_degrees).collect::<Vec<_>>())),
+        max_sound_db: max(&despike(&samples.iter().filter_map(|s| s.sound_db).collect::<Vec<_>>())),
+    }
+}
+
+/// (vitesse de pointe, vitesse moyenne) en km/h d'après la trace GPS. `None` sans au moins
+/// 2 relevés GPS retenus à des instants différents.
+fn gps_speeds(samples: &[Sample]) -> Option<(Option<f64>, Option<f64>)> {
+    let is_jump = |(from_ms, from): (i32, Position), (to_ms, to): (i32, Position)| {
+        geometry::haversine_distance(&from, &to) / (to_ms - from_ms) as f64 * 3_600.0 > GPS_JUMP_SPEED_KMH
+    };
+    // (instant, distance cumulée, position) des relevés retenus
+    let mut kept: Vec<(i32, f64, Position)> = Vec::new();
+    // Sauts consécutifs par rapport au dernier relevé retenu, cohérents entre eux
+    let mut jumps: Vec<(i32, Position)> = Vec::new();
+    for sample in samples {
+        let Some(position) = sample.position else { continue };
+        let fix = (sample.offset_ms, position);
+        let Some(&(previous_ms, _, previous)) = kept.last() else {
+            kept.push((fix.0, 0.0, position));
+            continue;
+        };
+        if fix.0 <= previous_ms {
+            continue;
+        }
+        if !is_jump((previous_ms, previous), fix) {
+            jumps.clear();
+            push_fix(&mut kept, fix);
+            continue;
+        }
+
+        if jumps.last().is_some_and(|&last| fix.0 <= last.0 || is_jump(last, fix)) {
+            jumps.clear();
+        }
+        jumps.push(fix);
+        if jumps.len() >= ANCHOR_RESET_FIXES {
+            while kept.last().is_some_and(|&(ms, _, p)| is_jump((ms, p), jumps[0])) {
+                kept.pop();
+            }
+            for fix in jumps.drain(..) {
+                push_fix(&mut kept, fix);
+            }
+        }
+    }
+    if kept.len() < 2 {
+        return None;
+    }
+
+    let speed = |i: usize, j: usize| (kept[j].1 - kept[i].1) / (kept[j].0 - kept[i].0) as f64 * 3_600.0;
+    let last = kept.len() - 1;
+    let avg = speed(0, last);
+    if kept[last].0 - kept[0].0 < SPEED_WINDOW_MS {
+        return Some((Some(avg), Some(avg)));
+    }
+
+    let mut max_speed = 0.0_f64;
+    let mut j = 0;
+    for i in 0..last {
+        j = j.max(i + 1);
+        while j < last && kept[j].0 - kept[i].0 < SPEED_WINDOW_MS {
+            j += 1;
+        }
+        if kept[j].0 - kept[i].0 < SPEED_WINDOW_MS {
+            break;
+        }
+        max_speed = max_speed.max(speed(i, j));
+    }
+    Some((Some(max_speed), Some(avg)))
+}
+
+/// Ajoute un relevé à la trace retenue, avec la distance parcourue depuis le précédent.
+fn push_fix(kept: &mut Vec<(i32, f64, Position)>, (offset_ms, position): (i32, Position)) {
+    let travelled = kept.last().map_or(0.0, |(_, travelled, previous)| travelled + geometry::haversine_distance(previous, &position));
+    kept.push((offset_ms, travelled, position));
+}
+
+/// Médiane glissante centrée sur [`SPIKE_FILTER_WINDOW`] valeurs (tronquée aux extrémités).
+pub fn despike(values: &[f64]) -> Vec<f64> {
+    let half = SPIKE_FILTER_WINDOW / 2;
+    (0..values.len())
+        .map(|i| {
+            let mut window = values[i.saturating_sub(half)..(i + half + 1).min(values.len())].to_vec();
+            window.sort_by(f64::total_cmp);
+            let mid = window.len() / 2;
+            if window.len() % 2 == 1 { window[mid] } else { (window[mid - 1] + window[mid]) / 2.0 }
+        })
+        .collect()
+}
+
+fn max(values: &[f64]) -> Option<f64> {
+    values.iter().copied().reduce(f64::max)
+}
+
+/// Métriques du client trop éloignées de celles du serveur. Une métrique absente d'un côté
+/// n'est pas comparée.
+pub fn discrepancies(client: &ScoreMetrics, server: &ScoreMetrics) -> Vec<Discrepancy> {
+    TOLERANCES
+        .iter()
+        .zip(client.values().into_iter().zip(server.values()))
+        .filter_map(|(tolerance, values)| match values {
+            (Some(client), Some(server))
+                if (client - server).abs() > tolerance.absolute.max(tolerance.relative * server.abs()) =>
+            {
+                Some(Discrepancy { metric: tolerance.metric, client, server })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+type SampleRow = (i32, Option<f32>, Option<f32>, Option<f32>, Option<f32>, Option<f32>, Option<f32>, Option<f32>, Option<f32>, Option<f32>);
+
+/// Relevés bruts de la course `score_id`, triés par temps.
+pub async fn load_samples(pool: &DbPool, score_id: i32) -> Result<Vec<Sample>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, SampleRow>(
+        "SELECT timestamp_offset_ms, latitude, longitude, accel_x, accel_y, accel_z,
+                speed_kmh, g_force, inclination_degrees, sound_db
+         FROM sensor_data
+         WHERE score_id = $1
+         ORDER BY timestamp_offset_ms, id"
+    )
+    .bind(score_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(offset_ms, lat, lon, ax, ay, az, speed_kmh, g_force, inclination_degrees, sound_db)| Sample {
+            offset_ms,
+            position: lat.zip(lon).map(|(lat, lon)| Position { lon: lon as f64, lat: lat as f64, ele: None }),
+            accel: match (ax, ay, az) {
+                (Some(x), Some(y), Some(z)) => Some((x as f64, y as f64, z as f64)),
+                _ => None,
+            },
+            speed_kmh: speed_kmh.map(f64::from),
+            g_force: g_force.map(f64::from),
+            inclination_degrees: inclination_degrees.map(f64::from),
+            sound_db: sound_db.map(f64::from),
+        })
+        .collect())
+}
+
+/// Recalcule les métriques de la course `score_id` à partir de tous ses relevés et les enregistre
+/// à côté de celles du client. `None` si le score n'existe pas.
+pub async fn process_score(pool: &DbPool, score_id: i32) -> Result<Option<MetricsReport>, sqlx::Error> {
+    let client = sqlx::query_as::<_, (Option<f32>, Option<f32>, Option<f32>, Option<f32>, Option<f32>)>(
+        "SELECT max_speed_kmh, avg_speed_kmh, max_g_force, max_inclination_degrees, max_sound_db
+         FROM scores WHERE id = $1"
+    )
+    .bind(score_id)
+    .fetch_optional(pool)
+    .await?;
+    let Some((max_speed_kmh, avg_speed_kmh, max_g_force, max_inclination_degrees, max_sound_db)) = client else {
+        return Ok(None);
+    };
+    let client = ScoreMetrics {
+        max_speed_kmh: max_speed_kmh.map(f64::from),
+        avg_speed_kmh: avg_speed_kmh.map(f64::from),
+        max_g_force: max_g_force.map(f64::from),
+        max_inclination_degrees: max_inclination_degrees.map(f64::from),
+        max_sound_db: max_sound_db.map(f64::from),
+    };
+
+    let samples = load_samples(pool, score_id).await?;
+    let server = compute(&samples);
+    let discrepancies = discrepancies(&client, &server);
+
+    sqlx::query(
+        "UPDATE scores
+         SET server_max_speed_kmh = $1, server_avg_speed_kmh = $2, server_max_g_force = $3,
+             server_max_inclination_degrees = $4, server_max_sound_db = $5,
+             metric_discrepancies = $6, metrics_computed_at = NOW()
+         WHERE id = $7"
+    )
+    .bind(server.max_speed_kmh.map(|v| v as f32))
+    .bind(server.avg_speed_kmh.map(|v| v as f32))
+    .bind(server.max_g_force.map(|v| v as f32))
+    .bind(server.max_inclination_degrees.map(|v| v as f32))
+    .bind(server.max_sound_db.map(|v| v as f32))
+    .bind(discrepancies.iter().map(|d| d.metric).collect::<Vec<_>>())
+    .bind(score_id)
+    .execute(pool)
+    .await?;
+
+    if discrepancies.is_empty() {
+        info!("Course {} : métriques recalculées, conformes à celles du client", score_id);
+    } else {
+        warn!("Course {} : métriques du client éloignées de celles du serveur: {:?}", score_id, discrepancies);
+    }
+    Ok(Some(MetricsReport { server, discrepancies }))
+}
@@ -0,0 +1,192 @@
+//! Rapprochement des courses avec les segments. Une course (`scores`) dont les relevés
+//! `sensor_data` ont des coordonnées GPS est comparée aux segments situés dans son emprise ;
+//! chaque passage reconnu donne un `segment_efforts`, le plus rapide par couple course/segment.
+
+use tracing::{info, warn};
+
+use crate::{
+    db::DbPool,
+    geometry::{self, LocalProjection, Position, EARTH_RADIUS_METERS},
+};
+
+/// Écart toléré entre la trace GPS et le segment (précision d'un téléphone en ville).
+pub const MATCH_RADIUS_METERS: f64 = 25.0;
+/// Le segment est vérifié tous les tant de mètres, pour qu'une ligne droite ne puisse pas être coupée.
+const COVERAGE_STEP_METERS: f64 = 20.0;
+/// Au-delà de ce rapport entre la distance courue et celle du segment, le passage est un détour.
+const MAX_DETOUR_RATIO: f64 = 1.5;
+
+/// Relevé GPS d'une course.
+#[derive(Debug, Clone, Copy)]
+pub struct TrackPoint {
+    pub offset_ms: i32,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentMatch {
+    pub start_offset_ms: i32,
+    pub end_offset_ms: i32,
+}
+
+impl SegmentMatch {
+    pub fn elapsed_seconds(&self) -> f64 {
+        (self.end_offset_ms - self.start_offset_ms) as f64 / 1000.0
+    }
+}
+
+/// Passage le plus rapide de `track` (trié par temps) sur `segment`.
+///
+/// Un passage part du relevé le plus proche du début du segment, parmi une série de relevés
+/// proches de celui-ci, et s'arrête au plus proche de sa fin dans la série suivante proche
+/// de la fin. Entre les deux, la trace doit longer tout le segment sans trop s'en écarter.
+pub fn best_effort(segment: &[Position], track: &[TrackPoint]) -> Option<SegmentMatch> {
+    if segment.len() < 2 || track.len() < 2 {
+        return None;
+    }
+
+    let projection = LocalProjection::new(segment[0].lat);
+    let checkpoints = densify(&segment.iter().map(|p| projection.project(p)).collect::<Vec<_>>(), COVERAGE_STEP_METERS);
+    let points: Vec<(f64, f64)> = track.iter().map(|t| projection.project(&t.position)).collect();
+    let (start, end) = (checkpoints[0], checkpoints[checkpoints.len() - 1]);
+    let max_length = geometry::path_length(segment) * MAX_DETOUR_RATIO + 2.0 * MATCH_RADIUS_METERS;
+
+    // Série de relevés consécutifs à moins de MATCH_RADIUS_METERS de `target`, à partir de `from` :
+    // (indice du plus proche, indice suivant la série)
+    let closest_in_run = |from: usize, target: (f64, f64)| -> Option<(usize, usize)> {
+        let first = (from..points.len()).find(|&i| distance(points[i], target) <= MATCH_RADIUS_METERS)?;
+        let after = (first..points.len()).find(|&i| distance(points[i], target) > MATCH_RADIUS_METERS).unwrap_or(points.len());
+        let closest = (first..after).min_by(|&a, &b| distance(points[a], target).total_cmp(&distance(points[b], target)))?;
+        Some((closest, after))
+    };
+
+    let mut best: Option<SegmentMatch> = None;
+    let mut from = 0;
+    while let Some((i, after_start)) = closest_in_run(from, start) {
+        from = after_start;
+        let Some((j, _)) = closest_in_run(after_start, end) else { break };
+
+        let slice = &points[i..=j];
+        let length: f64 = slice.windows(2).map(|w| distance(w[0], w[1])).sum();
+        if length > max_length {
+            continue;
+        }
+        let covered = checkpoints.iter().all(|c| {
+            slice.windows(2).any(|w| geometry::segment_distance(*c, w[0], w[1]) <= MATCH_RADIUS_METERS)
+        });
+        if !covered {
+            continue;
+        }
+
+        let candidate = SegmentMatch { start_offset_ms: track[i].offset_ms, end_offset_ms: track[j].offset_ms };
+        if candidate.end_offset_ms > candidate.start_offset_ms
+            && best.is_none_or(|b| candidate.elapsed_seconds() < b.elapsed_seconds())
+        {
+            best = Some(candidate);
+        }
+    }
+    best
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// Points de la ligne tous les `step` mètres au plus.
+pub(crate) fn densify(points: &[(f64, f64)], step: f64) -> Vec<(f64, f64)> {
+    let mut out = vec![points[0]];
+    for w in points.windows(2) {
+        let steps = (distance(w[0], w[1]) / step).ceil().max(1.0) as usize;
+        for k in 1..=steps {
+            let t = k as f64 / steps as f64;
+            out.push((w[0].0 + (w[1].0 - w[0].0) * t, w[0].1 + (w[1].1 - w[0].1) * t));
+        }
+    }
+    out
+}
+
+/// Trace GPS de la course `score_id`, triée par temps, sans les coordonnées hors limites.
+pub async fn load_track(pool: &DbPool, score_id: i32) -> Result<Vec<TrackPoint>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (i32, f32, f32)>(
+        "SELECT timestamp_offset_ms, latitude, longitude FROM sensor_data
+         WHERE score_id = $1 AND latitude IS NOT NULL AND longitude IS NOT NULL
+         ORDER BY timestamp_offset_ms, id"
+    )
+    .bind(score_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(offset_ms, lat, lon)| TrackPoint { offset_ms, position: Position { lon: lon as f64, lat: lat as f64, ele: None } })
+        .filter(|t| (-90.0..=90.0).contains(&t.position.lat) && (-180.0..=180.0).contains(&t.position.lon))
+        .collect())
+}
+
+/// Recalcule les passages de la course `score_id` sur tous les segments compris dans l'emprise
+/// de sa trace GPS. Renvoie le nombre de passages enregistrés.
+pub async fn match_score(pool: &DbPool, score_id: i32) -> Result<usize, sqlx::Error> {
+    let track = load_track(pool, score_id).await?;
+    if track.len() < 2 {
+        return Ok(0);
+    }
+
+    let positions: Vec<Position> = track.iter().map(|t| t.position).collect();
+    let bounds = geometry::compute_metrics(&positions);
+    let margin_lat = (MATCH_RADIUS_METERS / EARTH_RADIUS_METERS).to_degrees();
+    let margin_lon = margin_lat / bounds.max_lat.abs().max(bounds.min_lat.abs()).to_radians().cos().max(1e-6);
+
+    let segments = sqlx::query_as::<_, (i32, serde_json::Value)>(
+        "SELECT id, path_data FROM segments
+         WHERE min_lat >= $1 AND max_lat <= $2 AND min_lon >= $3 AND max_lon <= $4"
+    )
+    .bind(bounds.min_lat - margin_lat)
+    .bind(bounds.max_lat + margin_lat)
+    .bind(bounds.min_lon - margin_lon)
+    .bind(bounds.max_lon + margin_lon)
+    .fetch_all(pool)
+    .await?;
+
+    let mut matched = 0;
+    for (segment_id, path_data) in segments {
+        let segment = match geometry::parse_path(&path_data) {
+            Ok(segment) => segment,
+            Err(e) => {
+                warn!("Tracé du segment {} illisible: {}", segment_id, e);
+                continue;
+            }
+        };
+
+        match best_effort(&segment, &track) {
+            Some(effort) => {
+                sqlx::query(
+                    "INSERT INTO segment_efforts (segment_id, score_id, user_id, elapsed_seconds, start_offset_ms, end_offset_ms)
+                     SELECT $1, id, user_id, $2, $3, $4 FROM scores WHERE id = $5
+                     ON CONFLICT (segment_id, score_id) DO UPDATE
+                     SET elapsed_seconds = EXCLUDED.elapsed_seconds,
+                         start_offset_ms = EXCLUDED.start_offset_ms,
+                         end_offset_ms = EXCLUDED.end_offset_ms"
+                )
+                .bind(segment_id)
+                .bind(effort.elapsed_seconds() as f32)
+                .bind(effort.start_offset_ms)
+                .bind(effort.end_offset_ms)
+                .bind(score_id)
+                .execute(pool)
+                .await?;
+                matched += 1;
+            }
+            // Des relevés ajoutés ou corrigés peuvent invalider un passage précédent
+            None => {
+                sqlx::query("DELETE FROM segment_efforts WHERE segment_id = $1 AND score_id = $2")
+                    .bind(segment_id)
+                    .bind(score_id)
+                    .execute(pool)
+                    .await?;
+            }
+        }
+    }
+
+    info!("Course {} : {} passage(s) de segment reconnu(s)", score_id, matched);
+    Ok(matched)
+}
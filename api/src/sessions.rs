@@ -0,0 +1,169 @@
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+use tracing::{info, warn};
+
+use crate::db::DbPool;
+
+/// Durée de vie d'une session (et de chaque refresh token émis pour elle).
+pub const REFRESH_TOKEN_TTL_DAYS: i32 = 30;
+
+/// Résultat d'une tentative de rotation de refresh token.
+pub enum RefreshOutcome {
+    Rotated {
+        session_id: String,
+        user_id: i32,
+        refresh_token: String,
+    },
+    /// Le token avait déjà été utilisé : la session est révoquée par précaution.
+    Reused,
+    Invalid,
+}
+
+/// Génère un token opaque aléatoire (256 bits, encodé en hexadécimal).
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Hash SHA-256 d'un token : seul ce hash est stocké en base.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Ouvre une nouvelle session et retourne `(session_id, refresh_token)`.
+pub async fn create_session(pool: &DbPool, user_id: i32) -> Result<(String, String), sqlx::Error> {
+    let session_id = generate_token();
+    let refresh_token = generate_token();
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "INSERT INTO sessions (id, user_id, expires_at)
+         VALUES ($1, $2, NOW() + make_interval(days => $3))"
+    )
+    .bind(&session_id)
+    .bind(user_id)
+    .bind(REFRESH_TOKEN_TTL_DAYS)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO refresh_tokens (session_id, token_hash, expires_at)
+         VALUES ($1, $2, NOW() + make_interval(days => $3))"
+    )
+    .bind(&session_id)
+    .bind(hash_token(&refresh_token))
+    .bind(REFRESH_TOKEN_TTL_DAYS)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    info!("Session {} ouverte pour l'utilisateur {}", session_id, user_id);
+    Ok((session_id, refresh_token))
+}
+
+/// Consomme un refresh token et en émet un nouveau pour la même session.
+///
+/// Un token déjà consommé signale un vol probable : toute la session est révoquée.
+pub async fn rotate_refresh_token(pool: &DbPool, refresh_token: &str) -> Result<RefreshOutcome, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let row = sqlx::query(
+        "SELECT rt.id, rt.session_id, s.user_id,
+                rt.used_at IS NOT NULL AS used,
+                rt.expires_at > NOW() AND s.expires_at > NOW() AND s.revoked_at IS NULL AS valid
+         FROM refresh_tokens rt
+         JOIN sessions s ON s.id = rt.session_id
+         WHERE rt.token_hash = $1
+         FOR UPDATE OF rt"
+    )
+    .bind(hash_token(refresh_token))
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(RefreshOutcome::Invalid);
+    };
+
+    let token_id: i32 = row.get("id");
+    let session_id: String = row.get("session_id");
+    let user_id: i32 = row.get("user_id");
+
+    if row.get::<bool, _>("used") {
+        warn!("Réutilisation d'un refresh token détectée, révocation de la session {}", session_id);
+        sqlx::query("UPDATE sessions SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+            .bind(&session_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        return Ok(RefreshOutcome::Reused);
+    }
+
+    if !row.get::<bool, _>("valid") {
+        return Ok(RefreshOutcome::Invalid);
+    }
+
+    sqlx::query("UPDATE refresh_tokens SET used_at = NOW() WHERE id = $1")
+        .bind(token_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let new_refresh_token = generate_token();
+    sqlx::query(
+        "INSERT INTO refresh_tokens (session_id, token_hash, expires_at)
+         VALUES ($1, $2, NOW() + make_interval(days => $3))"
+    )
+    .bind(&session_id)
+    .bind(hash_token(&new_refresh_token))
+    .bind(REFRESH_TOKEN_TTL_DAYS)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE sessions SET expires_at = NOW() + make_interval(days => $2) WHERE id = $1")
+        .bind(&session_id)
+        .bind(REFRESH_TOKEN_TTL_DAYS)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(RefreshOutcome::Rotated {
+        session_id,
+        user_id,
+        refresh_token: new_refresh_token,
+    })
+}
+
+/// Vérifie qu'une session existe, appartient à l'utilisateur, et n'est ni expirée ni révoquée.
+pub async fn is_session_active(pool: &DbPool, session_id: &str, user_id: i32) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(
+            SELECT 1 FROM sessions
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW()
+         )"
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn revoke_session(pool: &DbPool, session_id: &str) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("UPDATE sessions SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// Révoque toutes les sessions d'un utilisateur (déconnexion de tous les appareils).
+pub async fn revoke_all_sessions(pool: &DbPool, user_id: i32) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("UPDATE sessions SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
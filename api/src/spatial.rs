@@ -0,0 +1,198 @@
+use std::str::FromStr;
+
+use sqlx::{Postgres, QueryBuilder};
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
+
+use crate::{db::DbPool, geometry::EARTH_RADIUS_METERS};
+
+static POSTGIS: OnceCell<bool> = OnceCell::const_new();
+
+/// Vrai si l'extension PostGIS est installée. Vérifié une seule fois par processus :
+/// la migration des index spatiaux l'active quand elle est disponible.
+pub async fn postgis_enabled(pool: &DbPool) -> bool {
+    *POSTGIS
+        .get_or_init(|| async {
+            let enabled = sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'postgis')")
+                .fetch_one(pool)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Impossible de détecter PostGIS, recherche spatiale en SQL pur: {}", e);
+                    false
+                });
+            info!("Recherche spatiale: {}", if enabled { "PostGIS" } else { "SQL pur" });
+            enabled
+        })
+        .await
+}
+
+/// Emprise `min_lon,min_lat,max_lon,max_lat` (ordre du `bbox` GeoJSON).
+/// `min_lon > max_lon` désigne une emprise qui traverse l'antiméridien.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_lon: f64,
+    pub min_lat: f64,
+    pub max_lon: f64,
+    pub max_lat: f64,
+}
+
+impl FromStr for BoundingBox {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|v| v.trim().parse::<f64>().ok().filter(|n| n.is_finite()))
+            .collect::<Option<Vec<_>>>()
+            .filter(|v| v.len() == 4)
+            .ok_or("must be min_lon,min_lat,max_lon,max_lat")?;
+
+        let bbox = BoundingBox { min_lon: values[0], min_lat: values[1], max_lon: values[2], max_lat: values[3] };
+        if !(-180.0..=180.0).contains(&bbox.min_lon) || !(-180.0..=180.0).contains(&bbox.max_lon) {
+            return Err("longitudes must be between -180 and 180".to_string());
+        }
+        if !(-90.0..=90.0).contains(&bbox.min_lat) || !(-90.0..=90.0).contains(&bbox.max_lat) {
+            return Err("latitudes must be between -90 and 90".to_string());
+        }
+        if bbox.min_lat > bbox.max_lat {
+            return Err("min_lat must not be greater than max_lat".to_string());
+        }
+        Ok(bbox)
+    }
+}
+
+impl BoundingBox {
+    /// Emprise contenant le cercle de rayon `radius_m` autour du point ; sert de préfiltre indexé.
+    pub fn around(lat: f64, lon: f64, radius_m: f64) -> BoundingBox {
+        let dlat = (radius_m / EARTH_RADIUS_METERS).to_degrees();
+        let min_lat = (lat - dlat).max(-90.0);
+        let max_lat = (lat + dlat).min(90.0);
+
+        // Près des pôles le cercle couvre toutes les longitudes
+        let cos = lat.abs().max(min_lat.abs()).max(max_lat.abs()).to_radians().cos();
+        let dlon = if cos > 1e-6 { dlat / cos } else { 180.0 };
+        if dlon >= 180.0 {
+            return BoundingBox { min_lon: -180.0, min_lat, max_lon: 180.0, max_lat };
+        }
+
+        let wrap = |lon: f64| if lon > 180.0 { lon - 360.0 } else if lon < -180.0 { lon + 360.0 } else { lon };
+        BoundingBox { min_lon: wrap(lon - dlon), min_lat, max_lon: wrap(lon + dlon), max_lat }
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        let lat = (self.min_lat + self.max_lat) / 2.0;
+        let lon = if self.min_lon <= self.max_lon {
+            (self.min_lon + self.max_lon) / 2.0
+        } else {
+            let lon = (self.min_lon + self.max_lon + 360.0) / 2.0;
+            if lon > 180.0 { lon - 360.0 } else { lon }
+        };
+        (lat, lon)
+    }
+
+    /// Condition « l'emprise de la ligne recoupe celle-ci » sur les colonnes `min_*`/`max_*`.
+    pub fn push_intersects(&self, query: &mut QueryBuilder<'_, Postgres>, postgis: bool) {
+        if postgis && self.min_lon <= self.max_lon {
+            // Même expression que l'index idx_routes_bbox_geom
+            query
+                .push(" AND ST_MakeEnvelope(min_lon, min_lat, max_lon, max_lat, 4326) && ST_MakeEnvelope(")
+                .push_bind(self.min_lon)
+                .push(", ")
+                .push_bind(self.min_lat)
+                .push(", ")
+                .push_bind(self.max_lon)
+                .push(", ")
+                .push_bind(self.max_lat)
+                .push(", 4326)");
+            return;
+        }
+
+        query
+            .push(" AND min_lat <= ")
+            .push_bind(self.max_lat)
+            .push(" AND max_lat >= ")
+            .push_bind(self.min_lat);
+        if self.min_lon <= self.max_lon {
+            query
+                .push(" AND min_lon <= ")
+                .push_bind(self.max_lon)
+                .push(" AND max_lon >= ")
+                .push_bind(self.min_lon);
+        } else {
+            query
+                .push(" AND (max_lon >= ")
+                .push_bind(self.min_lon)
+                .push(" OR min_lon <= ")
+                .push_bind(self.max_lon)
+                .push(")");
+        }
+    }
+
+    /// Condition « le point de départ est dans l'emprise ».
+    fn push_contains_start(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        query
+            .push(" AND start_lat BETWEEN ")
+            .push_bind(self.min_lat)
+            .push(" AND ")
+            .push_bind(self.max_lat);
+        if self.min_lon <= self.max_lon {
+            query
+                .push(" AND start_lon BETWEEN ")
+                .push_bind(self.min_lon)
+                .push(" AND ")
+                .push_bind(self.max_lon);
+        } else {
+            query
+                .push(" AND (start_lon >= ")
+                .push_bind(self.min_lon)
+                .push(" OR start_lon <= ")
+                .push_bind(self.max_lon)
+                .push(")");
+        }
+    }
+}
+
+/// Distance en mètres entre le départ du parcours et le point donné (DOUBLE PRECISION).
+pub fn push_distance_from_start(query: &mut QueryBuilder<'_, Postgres>, lat: f64, lon: f64, postgis: bool) {
+    if postgis {
+        // Même expression que l'index idx_routes_start_geog
+        query
+            .push("ST_Distance(ST_SetSRID(ST_MakePoint(start_lon, start_lat), 4326)::geography, ST_SetSRID(ST_MakePoint(")
+            .push_bind(lon)
+            .push(", ")
+            .push_bind(lat)
+            .push("), 4326)::geography)");
+        return;
+    }
+
+    // Haversine, comme geometry::haversine_distance
+    query
+        .push(format!("(2 * {} * asin(least(1, sqrt(power(sin(radians(start_lat - ", EARTH_RADIUS_METERS))
+        .push_bind(lat)
+        .push(") / 2), 2) + cos(radians(")
+        .push_bind(lat)
+        .push(")) * cos(radians(start_lat)) * power(sin(radians(start_lon - ")
+        .push_bind(lon)
+        .push(") / 2), 2)))))");
+}
+
+/// Condition « départ du parcours à moins de `radius_m` mètres du point ».
+pub fn push_start_within(query: &mut QueryBuilder<'_, Postgres>, lat: f64, lon: f64, radius_m: f64, postgis: bool) {
+    if postgis {
+        query
+            .push(" AND ST_DWithin(ST_SetSRID(ST_MakePoint(start_lon, start_lat), 4326)::geography, ST_SetSRID(ST_MakePoint(")
+            .push_bind(lon)
+            .push(", ")
+            .push_bind(lat)
+            .push("), 4326)::geography, ")
+            .push_bind(radius_m)
+            .push(")");
+        return;
+    }
+
+    // Préfiltre sur idx_routes_start_point, puis distance exacte
+    BoundingBox::around(lat, lon, radius_m).push_contains_start(query);
+    query.push(" AND ");
+    push_distance_from_start(query, lat, lon, false);
+    query.push(" <= ").push_bind(radius_m);
+}
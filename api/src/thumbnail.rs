@@ -0,0 +1,353 @@
+//! Vignettes statiques des parcours, pour les fils d'actualité et les cartes de partage :
+//! le tracé projeté en Web Mercator sur un fond uni (aucun serveur de tuiles), avec les
+//! marqueurs de départ et d'arrivée et, en option, une graduation de distance.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    f64::consts::PI,
+    str::FromStr,
+    sync::{LazyLock, Mutex},
+};
+
+use axum::body::Bytes;
+use tiny_skia::{FillRule, LineCap, LineJoin, Paint, PathBuilder, Pixmap, Stroke, Transform};
+
+use crate::geometry::{self, Position};
+
+pub const DEFAULT_WIDTH: u32 = 400;
+pub const DEFAULT_HEIGHT: u32 = 300;
+pub const MIN_SIZE: u32 = 32;
+pub const MAX_SIZE: u32 = 1024;
+/// Au-delà, Web Mercator part à l'infini : les latitudes sont ramenées dans cette limite.
+const MAX_MERCATOR_LAT: f64 = 85.051_128_78;
+/// Pas de graduation possibles ; le plus petit qui donne au plus [`MAX_TICKS`] graduations est retenu.
+const TICK_STEPS_METERS: &[f64] = &[100.0, 200.0, 500.0, 1_000.0, 2_000.0, 5_000.0, 10_000.0, 20_000.0, 50_000.0, 100_000.0];
+const MAX_TICKS: usize = 20;
+/// Un point plus proche que cela (en pixels) du précédent n'ajoute rien au dessin.
+const MIN_SEGMENT_PIXELS: f64 = 0.5;
+/// Nombre de vignettes gardées en mémoire, toutes tailles et formats confondus.
+const CACHE_CAPACITY: usize = 128;
+
+#[derive(Clone, Copy)]
+struct Rgb(u8, u8, u8);
+
+impl Rgb {
+    fn hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+
+    fn paint(self) -> Paint<'static> {
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(self.0, self.1, self.2, 255);
+        paint.anti_alias = true;
+        paint
+    }
+}
+
+const BACKGROUND: Rgb = Rgb(0xf4, 0xf1, 0xea);
+const LINE: Rgb = Rgb(0xe4, 0x57, 0x2e);
+const TICK: Rgb = Rgb(0x1f, 0x29, 0x33);
+const START: Rgb = Rgb(0x2e, 0x7d, 0x32);
+const FINISH: Rgb = Rgb(0xc6, 0x28, 0x28);
+const MARKER_OUTLINE: Rgb = Rgb(0xff, 0xff, 0xff);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ThumbnailFormat {
+    Svg,
+    Png,
+}
+
+impl ThumbnailFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ThumbnailFormat::Svg => "svg",
+            ThumbnailFormat::Png => "png",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ThumbnailFormat::Svg => "image/svg+xml",
+            ThumbnailFormat::Png => "image/png",
+        }
+    }
+}
+
+impl FromStr for ThumbnailFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "svg" => Ok(ThumbnailFormat::Svg),
+            "png" => Ok(ThumbnailFormat::Png),
+            other => Err(format!("unsupported format '{}', expected svg or png", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ThumbnailOptions {
+    pub width: u32,
+    pub height: u32,
+    pub distance_ticks: bool,
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        ThumbnailOptions { width: DEFAULT_WIDTH, height: DEFAULT_HEIGHT, distance_ticks: false }
+    }
+}
+
+/// Graduation perpendiculaire au tracé, à `distance_meters` du départ.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tick {
+    pub distance_meters: f64,
+    pub from: (f64, f64),
+    pub to: (f64, f64),
+}
+
+/// Tracé mis à l'échelle de la vignette, en pixels (origine en haut à gauche).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub width: u32,
+    pub height: u32,
+    pub line: Vec<(f64, f64)>,
+    pub ticks: Vec<Tick>,
+    pub start: (f64, f64),
+    pub finish: (f64, f64),
+    stroke_width: f64,
+    marker_radius: f64,
+}
+
+/// Projection Web Mercator normalisée : x et y dans [0, 1], y croissant vers le sud.
+pub fn mercator(position: &Position) -> (f64, f64) {
+    let lat = position.lat.clamp(-MAX_MERCATOR_LAT, MAX_MERCATOR_LAT).to_radians();
+    let x = (position.lon + 180.0) / 360.0;
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0;
+    (x, y)
+}
+
+/// Met le tracé à l'échelle : centré, proportions conservées, avec une marge pour les marqueurs.
+/// `None` s'il a moins de 2 positions.
+pub fn scene(positions: &[Position], options: &ThumbnailOptions) -> Option<Scene> {
+    if positions.len() < 2 {
+        return None;
+    }
+
+    let (width, height) = (options.width as f64, options.height as f64);
+    let stroke_width = (width.min(height) / 100.0).clamp(1.5, 6.0);
+    let marker_radius = stroke_width * 1.8;
+    let padding = (width.min(height) * 0.08).max(marker_radius * 2.0);
+
+    let projected: Vec<(f64, f64)> = positions.iter().map(mercator).collect();
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for &(x, y) in &projected {
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    let (span_x, span_y) = ((max_x - min_x).max(f64::EPSILON), (max_y - min_y).max(f64::EPSILON));
+    let scale = ((width - 2.0 * padding).max(1.0) / span_x).min((height - 2.0 * padding).max(1.0) / span_y);
+    let offset_x = (width - (max_x - min_x) * scale) / 2.0;
+    let offset_y = (height - (max_y - min_y) * scale) / 2.0;
+    let pixels: Vec<(f64, f64)> = projected
+        .iter()
+        .map(|&(x, y)| (offset_x + (x - min_x) * scale, offset_y + (y - min_y) * scale))
+        .collect();
+
+    let ticks = if options.distance_ticks { ticks(positions, &pixels, stroke_width * 2.0) } else { Vec::new() };
+
+    let mut line = vec![pixels[0]];
+    for &p in &pixels[1..pixels.len() - 1] {
+        if distance(*line.last().unwrap(), p) >= MIN_SEGMENT_PIXELS {
+            line.push(p);
+        }
+    }
+    line.push(pixels[pixels.len() - 1]);
+
+    Some(Scene {
+        width: options.width,
+        height: options.height,
+        start: line[0],
+        finish: line[line.len() - 1],
+        line,
+        ticks,
+        stroke_width,
+        marker_radius,
+    })
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// Pas de graduation adapté à la longueur du tracé.
+pub fn tick_step(total_meters: f64) -> f64 {
+    TICK_STEPS_METERS
+        .iter()
+        .copied()
+        .find(|step| total_meters / step <= MAX_TICKS as f64)
+        .unwrap_or(TICK_STEPS_METERS[TICK_STEPS_METERS.len() - 1])
+}
+
+/// Graduations placées sur la distance réelle (haversine), dessinées sur le tracé projeté.
+fn ticks(positions: &[Position], pixels: &[(f64, f64)], half_length: f64) -> Vec<Tick> {
+    let total = geometry::path_length(positions);
+    let step = tick_step(total);
+
+    // Une graduation collée à l'arrivée serait cachée par son marqueur
+    let last = total - step / 4.0;
+
+    let mut ticks = Vec::new();
+    let mut next = step;
+    let mut travelled = 0.0;
+    for i in 1..positions.len() {
+        let length = geometry::haversine_distance(&positions[i - 1], &positions[i]);
+        let (a, b) = (pixels[i - 1], pixels[i]);
+        let pixel_length = distance(a, b);
+        while next < last && next <= travelled + length {
+            if length > 0.0 && pixel_length > 0.0 {
+                let t = (next - travelled) / length;
+                let at = (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+                let normal = (-(b.1 - a.1) / pixel_length * half_length, (b.0 - a.0) / pixel_length * half_length);
+                ticks.push(Tick {
+                    distance_meters: next,
+                    from: (at.0 - normal.0, at.1 - normal.1),
+                    to: (at.0 + normal.0, at.1 + normal.1),
+                });
+            }
+            next += step;
+        }
+        travelled += length;
+    }
+    ticks
+}
+
+pub fn render(scene: &Scene, format: ThumbnailFormat) -> Option<Vec<u8>> {
+    match format {
+        ThumbnailFormat::Svg => Some(render_svg(scene).into_bytes()),
+        ThumbnailFormat::Png => render_png(scene),
+    }
+}
+
+pub fn render_svg(scene: &Scene) -> String {
+    let points = scene.line.iter().map(|(x, y)| format!("{:.1},{:.1}", x, y)).collect::<Vec<_>>().join(" ");
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = scene.width,
+        h = scene.height
+    );
+    svg.push_str(&format!(r#"<rect width="{}" height="{}" fill="{}"/>"#, scene.width, scene.height, BACKGROUND.hex()));
+    svg.push_str(&format!(
+        r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{:.1}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+        points,
+        LINE.hex(),
+        scene.stroke_width
+    ));
+    if !scene.ticks.is_empty() {
+        svg.push_str(&format!(r#"<g stroke="{}" stroke-width="{:.1}" stroke-linecap="round">"#, TICK.hex(), scene.stroke_width / 2.0));
+        for tick in &scene.ticks {
+            svg.push_str(&format!(
+                r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}"/>"#,
+                tick.from.0, tick.from.1, tick.to.0, tick.to.1
+            ));
+        }
+        svg.push_str("</g>");
+    }
+    // Départ dessiné en dernier : sur une boucle, il recouvre l'arrivée
+    for (center, color) in [(scene.finish, FINISH), (scene.start, START)] {
+        svg.push_str(&format!(
+            r#"<circle cx="{:.1}" cy="{:.1}" r="{:.1}" fill="{}" stroke="{}" stroke-width="{:.1}"/>"#,
+            center.0,
+            center.1,
+            scene.marker_radius,
+            color.hex(),
+            MARKER_OUTLINE.hex(),
+            scene.stroke_width / 2.0
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// PNG rastérisé et anti-crénelé. `None` si la taille est invalide ou l'encodage échoue.
+pub fn render_png(scene: &Scene) -> Option<Vec<u8>> {
+    let mut pixmap = Pixmap::new(scene.width, scene.height)?;
+    pixmap.fill(tiny_skia::Color::from_rgba8(BACKGROUND.0, BACKGROUND.1, BACKGROUND.2, 255));
+
+    let stroke = |width: f64| Stroke {
+        width: width as f32,
+        line_cap: LineCap::Round,
+        line_join: LineJoin::Round,
+        ..Stroke::default()
+    };
+
+    let mut builder = PathBuilder::new();
+    builder.move_to(scene.line[0].0 as f32, scene.line[0].1 as f32);
+    for &(x, y) in &scene.line[1..] {
+        builder.line_to(x as f32, y as f32);
+    }
+    if let Some(path) = builder.finish() {
+        pixmap.stroke_path(&path, &LINE.paint(), &stroke(scene.stroke_width), Transform::identity(), None);
+    }
+
+    if !scene.ticks.is_empty() {
+        let mut builder = PathBuilder::new();
+        for tick in &scene.ticks {
+            builder.move_to(tick.from.0 as f32, tick.from.1 as f32);
+            builder.line_to(tick.to.0 as f32, tick.to.1 as f32);
+        }
+        if let Some(path) = builder.finish() {
+            pixmap.stroke_path(&path, &TICK.paint(), &stroke(scene.stroke_width / 2.0), Transform::identity(), None);
+        }
+    }
+
+    for (center, color) in [(scene.finish, FINISH), (scene.start, START)] {
+        if let Some(circle) = PathBuilder::from_circle(center.0 as f32, center.1 as f32, scene.marker_radius as f32) {
+            pixmap.fill_path(&circle, &color.paint(), FillRule::Winding, Transform::identity(), None);
+            pixmap.stroke_path(&circle, &MARKER_OUTLINE.paint(), &stroke(scene.stroke_width / 2.0), Transform::identity(), None);
+        }
+    }
+
+    pixmap.encode_png().ok()
+}
+
+/// Clé du cache : un parcours modifié change d'`updated_at`, ses anciennes vignettes ne sont
+/// plus jamais demandées et finissent évincées.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub route_id: i32,
+    pub updated_at: Option<chrono::NaiveDateTime>,
+    pub format: ThumbnailFormat,
+    pub options: ThumbnailOptions,
+}
+
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<CacheKey, Bytes>,
+    /// Ordre d'insertion : la plus ancienne vignette est évincée en premier.
+    order: VecDeque<CacheKey>,
+}
+
+static CACHE: LazyLock<Mutex<Cache>> = LazyLock::new(|| Mutex::new(Cache::default()));
+
+/// Vignette en cache pour `key`, sinon rendue par `render` puis mise en cache.
+pub fn cached<E>(key: CacheKey, render: impl FnOnce() -> Result<Vec<u8>, E>) -> Result<Bytes, E> {
+    if let Some(bytes) = CACHE.lock().unwrap().entries.get(&key) {
+        return Ok(bytes.clone());
+    }
+
+    let bytes = Bytes::from(render()?);
+    let mut cache = CACHE.lock().unwrap();
+    if cache.entries.insert(key.clone(), bytes.clone()).is_none() {
+        cache.order.push_back(key);
+        while cache.order.len() > CACHE_CAPACITY {
+            if let Some(oldest) = cache.order.pop_front() {
+                cache.entries.remove(&oldest);
+            }
+        }
+    }
+    Ok(bytes)
+}
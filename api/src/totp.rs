@@ -0,0 +1,87 @@
+//! Mots de passe à usage unique basés sur le temps (RFC 6238, HMAC-SHA1, 6 chiffres, pas de 30 s),
+//! compatibles avec Google Authenticator, Aegis, 1Password, etc.
+
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DIGITS: u32 = 6;
+pub const STEP_SECS: u64 = 30;
+/// Nombre de pas acceptés avant/après l'instant courant (dérive d'horloge du téléphone).
+pub const ALLOWED_SKEW: u64 = 1;
+
+/// Génère un secret de 160 bits encodé en base32 (format attendu par les applications).
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    rand::rng().fill_bytes(&mut bytes);
+    BASE32_NOPAD.encode(&bytes)
+}
+
+/// URI `otpauth://` à afficher sous forme de QR code lors de l'enrôlement.
+pub fn otpauth_uri(secret: &str, account: &str, issuer: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        percent_encode(issuer),
+        percent_encode(account),
+        secret,
+        percent_encode(issuer),
+        DIGITS,
+        STEP_SECS
+    )
+}
+
+/// Code HOTP pour un compteur donné (RFC 4226).
+pub fn code_at(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepte toute taille de clé");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = ((hash[offset] as u32 & 0x7f) << 24)
+        | ((hash[offset + 1] as u32) << 16)
+        | ((hash[offset + 2] as u32) << 8)
+        | (hash[offset + 3] as u32);
+
+    binary % 10u32.pow(DIGITS)
+}
+
+pub fn current_step() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        / STEP_SECS
+}
+
+/// Code attendu à l'instant courant pour un secret base32.
+pub fn current_code(secret: &str) -> Option<String> {
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    Some(format!("{:0width$}", code_at(&key, current_step()), width = DIGITS as usize))
+}
+
+/// Vérifie un code autour du pas `step` et retourne le pas correspondant.
+/// Les pas inférieurs ou égaux à `last_used_step` sont refusés (anti-rejeu).
+pub fn verify(secret: &str, code: &str, step: u64, last_used_step: Option<u64>) -> Option<u64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let expected: u32 = code.parse().ok()?;
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+
+    (step.saturating_sub(ALLOWED_SKEW)..=step + ALLOWED_SKEW)
+        .filter(|s| last_used_step.is_none_or(|last| *s > last))
+        .find(|s| code_at(&key, *s) == expected)
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
@@ -0,0 +1,119 @@
+use sqlx::{Postgres, Transaction};
+
+use crate::{
+    db::DbPool,
+    sessions::{generate_token, hash_token},
+};
+
+/// Usage d'un token à usage unique envoyé par email.
+#[derive(Clone, Copy)]
+pub enum TokenPurpose {
+    PasswordReset,
+    EmailVerification,
+    /// Mot de passe validé, second facteur (TOTP) encore attendu.
+    MfaPending,
+    /// Lien envoyé quand le compte est verrouillé après trop d'échecs de connexion.
+    AccountUnlock,
+}
+
+impl TokenPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenPurpose::PasswordReset => "password_reset",
+            TokenPurpose::EmailVerification => "email_verification",
+            TokenPurpose::MfaPending => "mfa_pending",
+            TokenPurpose::AccountUnlock => "account_unlock",
+        }
+    }
+
+    /// Durée de validité en minutes.
+    pub fn ttl_minutes(&self) -> i32 {
+        match self {
+            TokenPurpose::PasswordReset => 60,
+            TokenPurpose::EmailVerification => 48 * 60,
+            TokenPurpose::MfaPending => 5,
+            TokenPurpose::AccountUnlock => 60,
+        }
+    }
+}
+
+/// Émet un nouveau token et invalide les précédents tokens non utilisés du même usage.
+pub async fn issue(pool: &DbPool, user_id: i32, purpose: TokenPurpose) -> Result<String, sqlx::Error> {
+    let token = generate_token();
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "UPDATE user_tokens SET used_at = NOW()
+         WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(purpose.as_str())
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO user_tokens (user_id, purpose, token_hash, expires_at)
+         VALUES ($1, $2, $3, NOW() + make_interval(mins => $4))"
+    )
+    .bind(user_id)
+    .bind(purpose.as_str())
+    .bind(hash_token(&token))
+    .bind(purpose.ttl_minutes())
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(token)
+}
+
+/// Consomme un token valide dans la transaction fournie et retourne l'utilisateur associé.
+/// Retourne `None` si le token est inconnu, expiré, déjà utilisé ou d'un autre usage.
+pub async fn consume(
+    tx: &mut Transaction<'_, Postgres>,
+    token: &str,
+    purpose: TokenPurpose,
+) -> Result<Option<i32>, sqlx::Error> {
+    sqlx::query_scalar::<_, i32>(
+        "UPDATE user_tokens SET used_at = NOW()
+         WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > NOW()
+         RETURNING user_id"
+    )
+    .bind(hash_token(token))
+    .bind(purpose.as_str())
+    .fetch_optional(&mut **tx)
+    .await
+}
+
+/// Retourne l'utilisateur associé à un token valide, sans le consommer.
+pub async fn find_valid(pool: &DbPool, token: &str, purpose: TokenPurpose) -> Result<Option<i32>, sqlx::Error> {
+    sqlx::query_scalar::<_, i32>(
+        "SELECT user_id FROM user_tokens
+         WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > NOW()"
+    )
+    .bind(hash_token(token))
+    .bind(purpose.as_str())
+    .fetch_optional(pool)
+    .await
+}
+
+/// Compte un essai infructueux ; le token est invalidé au bout de `max_attempts` échecs.
+pub async fn record_failed_attempt(
+    pool: &DbPool,
+    token: &str,
+    purpose: TokenPurpose,
+    max_attempts: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE user_tokens
+         SET attempts = attempts + 1,
+             used_at = CASE WHEN attempts + 1 >= $3 THEN NOW() ELSE used_at END
+         WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL"
+    )
+    .bind(hash_token(token))
+    .bind(purpose.as_str())
+    .bind(max_attempts)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
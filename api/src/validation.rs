@@ -0,0 +1,213 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use axum::{
+    Json,
+    extract::{FromRequest, FromRequestParts, Request, rejection::JsonRejection},
+    http::{StatusCode, request::Parts},
+};
+use serde::de::DeserializeOwned;
+use shared::errors::AppError;
+
+/// Taille maximale d'un mot de passe : bcrypt ignore silencieusement au-delà de 72 octets.
+pub const PASSWORD_MAX_BYTES: usize = 72;
+pub const PASSWORD_MIN_CHARS: usize = 8;
+const EMAIL_MAX_CHARS: usize = 254;
+
+/// Contrôles métier d'un corps de requête, appliqués par [`ValidatedJson`].
+pub trait Validate {
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+/// Erreurs de validation regroupées par champ, renvoyées dans `details` :
+/// `{"time_seconds": ["must be between 0.001 and 604800"]}`.
+#[derive(Debug, Default)]
+pub struct ValidationErrors {
+    fields: BTreeMap<String, Vec<String>>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.fields.entry(field.to_string()).or_default().push(message.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn into_result(self) -> Result<(), ValidationErrors> {
+        if self.is_empty() { Ok(()) } else { Err(self) }
+    }
+
+    /// Rattache les erreurs d'un élément imbriqué, ex. `data[3].latitude`.
+    pub fn nested(&mut self, prefix: &str, result: Result<(), ValidationErrors>) {
+        if let Err(nested) = result {
+            for (field, messages) in nested.fields {
+                self.fields.entry(format!("{}.{}", prefix, field)).or_default().extend(messages);
+            }
+        }
+    }
+
+    /// Longueur en caractères (et non en octets), bornes incluses.
+    pub fn length(&mut self, field: &str, value: &str, min: usize, max: usize) {
+        let len = value.chars().count();
+        if len < min || len > max {
+            if min == 0 {
+                self.add(field, format!("must be at most {} characters", max));
+            } else {
+                self.add(field, format!("must be between {} and {} characters", min, max));
+            }
+        }
+    }
+
+    pub fn optional_length(&mut self, field: &str, value: Option<&str>, min: usize, max: usize) {
+        if let Some(v) = value {
+            self.length(field, v, min, max);
+        }
+    }
+
+    /// Rejette aussi NaN, qui n'est contenu dans aucun intervalle.
+    pub fn range<T: PartialOrd + Display + Copy>(&mut self, field: &str, value: T, min: T, max: T) {
+        if !(min..=max).contains(&value) {
+            self.add(field, format!("must be between {} and {}", min, max));
+        }
+    }
+
+    pub fn optional_range<T: PartialOrd + Display + Copy>(&mut self, field: &str, value: Option<T>, min: T, max: T) {
+        if let Some(v) = value {
+            self.range(field, v, min, max);
+        }
+    }
+
+    pub fn optional_one_of(&mut self, field: &str, value: Option<&str>, allowed: &[&str]) {
+        if let Some(v) = value
+            && !allowed.contains(&v)
+        {
+            self.add(field, format!("must be one of: {}", allowed.join(", ")));
+        }
+    }
+
+    pub fn email(&mut self, field: &str, value: &str) {
+        if value.chars().count() > EMAIL_MAX_CHARS || !is_valid_email(value) {
+            self.add(field, "must be a valid email address");
+        }
+    }
+
+    /// Au moins 8 caractères dont une lettre et un chiffre, au plus 72 octets.
+    pub fn password(&mut self, field: &str, value: &str) {
+        if value.chars().count() < PASSWORD_MIN_CHARS {
+            self.add(field, format!("must be at least {} characters", PASSWORD_MIN_CHARS));
+        }
+        if value.len() > PASSWORD_MAX_BYTES {
+            self.add(field, format!("must be at most {} bytes", PASSWORD_MAX_BYTES));
+        }
+        if !value.chars().any(char::is_alphabetic) || !value.chars().any(|c| c.is_ascii_digit()) {
+            self.add(field, "must contain at least one letter and one digit");
+        }
+    }
+
+    pub fn username(&mut self, field: &str, value: &str) {
+        self.length(field, value, 3, 64);
+        if !value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')) {
+            self.add(field, "may only contain letters, digits, '_', '-' and '.'");
+        }
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> Self {
+        AppError::validation("Invalid request body", serde_json::json!(errors.fields))
+    }
+}
+
+/// Vérification volontairement simple : une partie locale, un `@`, un domaine avec un point.
+/// La confirmation par email reste la seule preuve que l'adresse existe.
+fn is_valid_email(value: &str) -> bool {
+    let Some((local, domain)) = value.split_once('@') else {
+        return false;
+    };
+
+    !local.is_empty()
+        && local.len() <= 64
+        && !domain.contains('@')
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !domain.contains("..")
+        && !value.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+/// Remplace `Json<T>` pour les corps de requête : désérialise puis appelle [`Validate::validate`].
+/// Les erreurs sont renvoyées en `AppError` (400 si le JSON est illisible, 422 sinon).
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| match rejection {
+                JsonRejection::JsonDataError(e) => AppError::Validation {
+                    message: e.body_text(),
+                    details: None,
+                },
+                other => AppError::BadRequest(other.body_text()),
+            })?;
+
+        value.validate()?;
+        Ok(ValidatedJson(value))
+    }
+}
+
+/// Remplace `axum::extract::Query` : une chaîne de requête illisible (paramètre inconnu d'une
+/// énumération, nombre invalide...) renvoie le corps d'erreur JSON habituel en 422.
+pub struct Query<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Query(value) = axum::extract::Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| AppError::Validation {
+                message: rejection.body_text(),
+                details: None,
+            })?;
+        Ok(Query(value))
+    }
+}
+
+/// Remplace `axum::extract::Path` : un segment de chemin invalide (`/routes/abc`) renvoie le
+/// corps d'erreur JSON habituel en 400.
+pub struct Path<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Path(value) = axum::extract::Path::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| match rejection.status() {
+                // Paramètre absent de la route : erreur de programmation, pas du client
+                StatusCode::INTERNAL_SERVER_ERROR => AppError::Internal(rejection.body_text()),
+                _ => AppError::BadRequest(rejection.body_text()),
+            })?;
+        Ok(Path(value))
+    }
+}
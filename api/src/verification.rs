@@ -0,0 +1,216 @@
+//! Vérification des courses : la trace GPS d'un score (`sensor_data`) est rapprochée du tracé
+//! de la révision du parcours sur laquelle il a été soumis. Seuls les scores `verified` comptent
+//! dans les classements.
+
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{
+    db::DbPool,
+    geometry::{self, LocalProjection, Position},
+    segments::{self, TrackPoint},
+};
+
+/// Rayon des zones de départ et d'arrivée autour des extrémités du tracé.
+pub const GEOFENCE_RADIUS_METERS: f64 = 50.0;
+/// Au-delà, un relevé entre le départ et l'arrivée est hors parcours.
+pub const MAX_DEVIATION_METERS: f64 = 100.0;
+/// Part minimale du tracé longée, dans l'ordre.
+pub const MIN_COVERAGE: f64 = 0.9;
+/// Un point de contrôle est couvert si la trace passe à moins de cette distance.
+const COVERAGE_TOLERANCE_METERS: f64 = 30.0;
+/// Le tracé est vérifié tous les tant de mètres.
+const CHECKPOINT_STEP_METERS: f64 = 20.0;
+/// Écart toléré entre le temps annoncé et celui de la trace : le plus grand des deux seuils.
+const TIME_TOLERANCE_SECONDS: f64 = 2.0;
+const TIME_TOLERANCE_RATIO: f64 = 0.02;
+/// Simplification du tracé pour le calcul de l'écart, bien en deçà de la précision GPS.
+const SIMPLIFY_TOLERANCE_METERS: f64 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    Pending,
+    Verified,
+    Rejected,
+}
+
+impl VerificationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationStatus::Pending => "pending",
+            VerificationStatus::Verified => "verified",
+            VerificationStatus::Rejected => "rejected",
+        }
+    }
+}
+
+/// Motif de rejet, du plus au moins élémentaire : seul le premier rencontré est retenu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectionReason {
+    /// Moins de 2 relevés GPS.
+    InsufficientGps,
+    MissedStart,
+    /// Aucun passage dans la zone d'arrivée après le départ.
+    MissedFinish,
+    OffRoute,
+    Incomplete,
+    /// Tracé couvert, mais pas dans le bon sens ou le bon ordre.
+    WrongOrder,
+    /// Temps annoncé plus court que celui mis par la trace entre le départ et l'arrivée.
+    TimeMismatch,
+}
+
+impl RejectionReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectionReason::InsufficientGps => "insufficient_gps",
+            RejectionReason::MissedStart => "missed_start",
+            RejectionReason::MissedFinish => "missed_finish",
+            RejectionReason::OffRoute => "off_route",
+            RejectionReason::Incomplete => "incomplete",
+            RejectionReason::WrongOrder => "wrong_order",
+            RejectionReason::TimeMismatch => "time_mismatch",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RouteMatch {
+    pub status: VerificationStatus,
+    pub reason: Option<RejectionReason>,
+    /// Part des points de contrôle du tracé longés dans l'ordre, entre 0 et 1.
+    pub coverage: f64,
+    /// Plus grand écart au tracé entre le départ et l'arrivée. `None` faute de départ et d'arrivée.
+    pub max_deviation_meters: Option<f64>,
+    /// Temps mis par la trace entre la sortie de la zone de départ et l'entrée dans celle d'arrivée.
+    pub track_seconds: Option<f64>,
+}
+
+impl RouteMatch {
+    fn rejected(reason: RejectionReason, coverage: f64, max_deviation_meters: Option<f64>) -> RouteMatch {
+        RouteMatch { status: VerificationStatus::Rejected, reason: Some(reason), coverage, max_deviation_meters, track_seconds: None }
+    }
+}
+
+/// Rapproche `track` (trié par temps) de `route`.
+///
+/// La course part du premier relevé dans la zone de départ et s'arrête au dernier relevé dans
+/// la zone d'arrivée. Entre les deux, aucun relevé ne doit s'écarter du tracé de plus de
+/// [`MAX_DEVIATION_METERS`], et la trace doit longer au moins [`MIN_COVERAGE`] des points de
+/// contrôle du tracé, dans l'ordre. Enfin, `time_seconds` ne peut pas être plus court que le
+/// temps mis par la trace entre son dernier relevé dans la zone de départ et son premier relevé
+/// dans la zone d'arrivée : une borne basse, qui ne compte ni l'attente au départ ni celle à l'arrivée.
+pub fn match_route(route: &[Position], track: &[TrackPoint], time_seconds: f64) -> RouteMatch {
+    if route.len() < 2 || track.len() < 2 {
+        return RouteMatch::rejected(RejectionReason::InsufficientGps, 0.0, None);
+    }
+
+    let projection = LocalProjection::new(route[0].lat);
+    let line: Vec<(f64, f64)> = route.iter().map(|p| projection.project(p)).collect();
+    let checkpoints = segments::densify(&line, CHECKPOINT_STEP_METERS);
+    let points: Vec<(f64, f64)> = track.iter().map(|t| projection.project(&t.position)).collect();
+    let (start, finish) = (line[0], line[line.len() - 1]);
+
+    let Some(first) = points.iter().position(|&p| distance(p, start) <= GEOFENCE_RADIUS_METERS) else {
+        return RouteMatch::rejected(RejectionReason::MissedStart, 0.0, None);
+    };
+    let Some(last) = points.iter().rposition(|&p| distance(p, finish) <= GEOFENCE_RADIUS_METERS).filter(|&last| last > first) else {
+        return RouteMatch::rejected(RejectionReason::MissedFinish, 0.0, None);
+    };
+    let run = &points[first..=last];
+
+    let left_start = (first..=last).find(|&i| distance(points[i], start) > GEOFENCE_RADIUS_METERS).map_or(last, |i| i - 1);
+    let reached_finish = (left_start + 1..=last).find(|&i| distance(points[i], finish) <= GEOFENCE_RADIUS_METERS).unwrap_or(left_start);
+    let track_seconds = (track[reached_finish].offset_ms - track[left_start].offset_ms) as f64 / 1000.0;
+
+    let simplified: Vec<(f64, f64)> = geometry::simplify(route, SIMPLIFY_TOLERANCE_METERS).iter().map(|p| projection.project(p)).collect();
+    let max_deviation = run
+        .iter()
+        .map(|&p| simplified.windows(2).map(|w| geometry::segment_distance(p, w[0], w[1])).fold(f64::INFINITY, f64::min))
+        .fold(0.0, f64::max);
+
+    let near = |c: (f64, f64), k: usize| geometry::segment_distance(c, run[k], run[k + 1]) <= COVERAGE_TOLERANCE_METERS;
+    let covered = checkpoints.iter().filter(|&&c| (0..run.len() - 1).any(|k| near(c, k))).count();
+
+    // Dans l'ordre : chaque point de contrôle est cherché à partir du dernier tronçon retenu
+    let mut in_order = 0;
+    let mut from = 0;
+    for &c in &checkpoints {
+        if let Some(k) = (from..run.len() - 1).find(|&k| near(c, k)) {
+            in_order += 1;
+            from = k;
+        }
+    }
+
+    let total = checkpoints.len() as f64;
+    let coverage = in_order as f64 / total;
+    let reason = if max_deviation > MAX_DEVIATION_METERS {
+        Some(RejectionReason::OffRoute)
+    } else if (covered as f64 / total) < MIN_COVERAGE {
+        Some(RejectionReason::Incomplete)
+    } else if coverage < MIN_COVERAGE {
+        Some(RejectionReason::WrongOrder)
+    } else if time_seconds + TIME_TOLERANCE_SECONDS.max(TIME_TOLERANCE_RATIO * track_seconds) < track_seconds {
+        Some(RejectionReason::TimeMismatch)
+    } else {
+        None
+    };
+
+    RouteMatch {
+        status: if reason.is_some() { VerificationStatus::Rejected } else { VerificationStatus::Verified },
+        reason,
+        coverage,
+        max_deviation_meters: Some(max_deviation),
+        track_seconds: Some(track_seconds),
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// Vérifie la course `score_id` sur le tracé de sa révision et enregistre le résultat.
+/// `None` si le score n'existe pas ou si le tracé stocké est illisible (le score reste alors en attente).
+pub async fn verify_score(pool: &DbPool, score_id: i32) -> Result<Option<RouteMatch>, sqlx::Error> {
+    let score = sqlx::query_as::<_, (serde_json::Value, f32)>(
+        "SELECT v.path_data, s.time_seconds FROM scores s
+         JOIN route_versions v ON v.route_id = s.route_id AND v.version = s.route_version
+         WHERE s.id = $1"
+    )
+    .bind(score_id)
+    .fetch_optional(pool)
+    .await?;
+    let Some((path_data, time_seconds)) = score else {
+        return Ok(None);
+    };
+
+    let route = match geometry::parse_path(&path_data) {
+        Ok(route) => route,
+        Err(e) => {
+            warn!("Tracé illisible pour la vérification de la course {}: {}", score_id, e);
+            return Ok(None);
+        }
+    };
+
+    let track = segments::load_track(pool, score_id).await?;
+    let result = match_route(&route, &track, time_seconds as f64);
+
+    sqlx::query(
+        "UPDATE scores
+         SET verification_status = $1, verification_reason = $2, route_coverage = $3,
+             max_deviation_meters = $4, verified_at = NOW()
+         WHERE id = $5"
+    )
+    .bind(result.status.as_str())
+    .bind(result.reason.map(|r| r.as_str()))
+    .bind(result.coverage as f32)
+    .bind(result.max_deviation_meters.map(|d| d as f32))
+    .bind(score_id)
+    .execute(pool)
+    .await?;
+
+    info!("Course {} : {} ({:?})", score_id, result.status.as_str(), result.reason);
+    Ok(Some(result))
+}
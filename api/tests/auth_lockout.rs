@@ -0,0 +1,166 @@
+use std::{env, net::SocketAddr, path::{Path, PathBuf}};
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{Request, StatusCode};
+use serde_json::json;
+use tower::ServiceExt;
+
+mod common;
+
+use common::{build_app_with_outbox, unique_username};
+
+fn outbox_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("{}_{}.jsonl", unique_username(name), std::process::id()))
+}
+
+/// IP de test propre à chaque exécution, pour ne pas hériter des échecs d'une exécution précédente.
+fn unique_ip() -> SocketAddr {
+    let n = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u32;
+    SocketAddr::from(([10, (n >> 16) as u8, (n >> 8) as u8, n as u8], 40000))
+}
+
+async fn post_json(
+    app: &axum::Router,
+    uri: &str,
+    peer: Option<SocketAddr>,
+    body: serde_json::Value,
+) -> Result<(StatusCode, serde_json::Value), Box<dyn std::error::Error>> {
+    let mut request = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(serde_json::to_vec(&body)?))?;
+    if let Some(addr) = peer {
+        request.extensions_mut().insert(ConnectInfo(addr));
+    }
+
+    let response = app.clone().oneshot(request).await?;
+    let status = response.status();
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let value = serde_json::from_slice(&body_bytes).unwrap_or(serde_json::Value::Null);
+    Ok((status, value))
+}
+
+fn last_token_sent_to(outbox: &Path, to: &str) -> Option<String> {
+    let content = std::fs::read_to_string(outbox).ok()?;
+    content
+        .lines()
+        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+        .rfind(|m| m["to"] == to && m["body"].as_str().is_some_and(|b| b.contains("unlock-account")))
+        .and_then(|m| {
+            let body = m["body"].as_str()?.to_string();
+            let start = body.find("token=")? + "token=".len();
+            Some(body[start..].split_whitespace().next()?.to_string())
+        })
+}
+
+async fn register(app: &axum::Router, base: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let username = unique_username(base);
+    let email = format!("{}@test.com", username);
+    let (status, _) = post_json(app, "/auth/register", None, json!({
+        "username": username,
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    Ok(email)
+}
+
+async fn login(app: &axum::Router, email: &str, password: &str) -> Result<StatusCode, Box<dyn std::error::Error>> {
+    let (status, _) = post_json(app, "/auth/login", None, json!({
+        "email": email,
+        "password": password
+    })).await?;
+    Ok(status)
+}
+
+#[tokio::test]
+async fn account_is_locked_after_repeated_failures_and_unlocked_by_email() -> Result<(), Box<dyn std::error::Error>> {
+    let outbox = outbox_path("lockout");
+    let Some(app) = build_app_with_outbox(&outbox).await? else { return Ok(()) };
+    let email = register(&app, "lockout_user").await?;
+
+    for _ in 0..5 {
+        assert_eq!(login(&app, &email, "WrongPass!").await?, StatusCode::UNAUTHORIZED);
+    }
+
+    // Verrouillé, même avec le bon mot de passe (et quelle que soit la casse de l'email)
+    assert_eq!(login(&app, &email, "SecurePass123!").await?, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(login(&app, &email.to_uppercase(), "SecurePass123!").await?, StatusCode::TOO_MANY_REQUESTS);
+
+    let token = last_token_sent_to(&outbox, &email).expect("Unlock email should be sent");
+    let (status, _) = post_json(&app, "/auth/unlock-account", None, json!({ "token": token })).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    assert_eq!(login(&app, &email, "SecurePass123!").await?, StatusCode::OK);
+
+    // Le lien est à usage unique
+    let (status, _) = post_json(&app, "/auth/unlock-account", None, json!({ "token": token })).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let _ = std::fs::remove_file(&outbox);
+    Ok(())
+}
+
+#[tokio::test]
+async fn successful_login_resets_failure_count() -> Result<(), Box<dyn std::error::Error>> {
+    let outbox = outbox_path("reset_count");
+    let Some(app) = build_app_with_outbox(&outbox).await? else { return Ok(()) };
+    let email = register(&app, "reset_count_user").await?;
+
+    for _ in 0..2 {
+        for _ in 0..4 {
+            assert_eq!(login(&app, &email, "WrongPass!").await?, StatusCode::UNAUTHORIZED);
+        }
+        assert_eq!(login(&app, &email, "SecurePass123!").await?, StatusCode::OK);
+    }
+
+    let _ = std::fs::remove_file(&outbox);
+    Ok(())
+}
+
+#[tokio::test]
+async fn unknown_email_is_locked_like_a_real_account() -> Result<(), Box<dyn std::error::Error>> {
+    let outbox = outbox_path("unknown_lock");
+    let Some(app) = build_app_with_outbox(&outbox).await? else { return Ok(()) };
+    let email = format!("{}@test.com", unique_username("ghost"));
+
+    for _ in 0..5 {
+        assert_eq!(login(&app, &email, "WrongPass!").await?, StatusCode::UNAUTHORIZED);
+    }
+    assert_eq!(login(&app, &email, "WrongPass!").await?, StatusCode::TOO_MANY_REQUESTS);
+    assert!(last_token_sent_to(&outbox, &email).is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn ip_is_blocked_after_failures_across_accounts() -> Result<(), Box<dyn std::error::Error>> {
+    let outbox = outbox_path("ip_block");
+    let Some(app) = build_app_with_outbox(&outbox).await? else { return Ok(()) };
+    let email = register(&app, "ip_block_user").await?;
+    let attacker = unique_ip();
+
+    // Un échec par email : aucun compte n'atteint son propre seuil
+    for i in 0..20 {
+        let (status, _) = post_json(&app, "/auth/login", Some(attacker), json!({
+            "email": format!("{}_{}@test.com", unique_username("spray"), i),
+            "password": "WrongPass!"
+        })).await?;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    let credentials = json!({ "email": email, "password": "SecurePass123!" });
+    let (status, _) = post_json(&app, "/auth/login", Some(attacker), credentials.clone()).await?;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // Les autres clients ne sont pas affectés
+    let (status, _) = post_json(&app, "/auth/login", Some(unique_ip()), credentials).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let _ = std::fs::remove_file(&outbox);
+    Ok(())
+}
@@ -0,0 +1,249 @@
+use axum::http::StatusCode;
+use data_encoding::BASE32_NOPAD;
+use rust_rmce_api::totp;
+use serde_json::json;
+
+mod common;
+
+use common::{build_app, unique_username, send_json};
+
+/// Code TOTP pour un pas donné (les tests ont besoin du pas suivant, le courant étant déjà consommé).
+fn code_for_step(secret: &str, step: u64) -> String {
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).unwrap();
+    format!("{:06}", totp::code_at(&key, step))
+}
+
+/// Inscrit un utilisateur et active la 2FA ; retourne (email, secret, codes de secours).
+async fn register_with_totp(
+    app: &axum::Router,
+    base: &str,
+) -> Result<(String, String, Vec<String>), Box<dyn std::error::Error>> {
+    let username = unique_username(base);
+    let email = format!("{}@test.com", username);
+    send_json(app, "POST", "/auth/register", None, json!({
+        "username": username,
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+    let (_, login) = send_json(app, "POST", "/auth/login", None, json!({
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+    let token = login["token"].as_str().unwrap().to_string();
+
+    let (status, setup) = send_json(app, "POST", "/auth/2fa/setup", Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let secret = setup["secret"].as_str().unwrap().to_string();
+    assert!(setup["otpauth_uri"].as_str().unwrap().starts_with("otpauth://totp/RMCE:"));
+
+    let (status, _) = send_json(app, "POST", "/auth/2fa/confirm", Some(&token), json!({ "code": "000000x" })).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let code = totp::current_code(&secret).unwrap();
+    let (status, confirmed) = send_json(app, "POST", "/auth/2fa/confirm", Some(&token), json!({ "code": code })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let recovery_codes: Vec<String> = confirmed["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c.as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(recovery_codes.len(), 10);
+
+    Ok((email, secret, recovery_codes))
+}
+
+async fn login_challenge(app: &axum::Router, email: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let (status, body) = send_json(app, "POST", "/auth/login", None, json!({
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["mfa_required"], true);
+    assert!(body.get("token").is_none(), "No access token before the second factor");
+    Ok(body["mfa_token"].as_str().unwrap().to_string())
+}
+
+#[test]
+fn totp_matches_rfc6238_vector() {
+    // RFC 6238, annexe B : T = 59 s, SHA1 -> 94287082 (8 chiffres), soit 287082 sur 6 chiffres
+    assert_eq!(totp::code_at(b"12345678901234567890", 59 / totp::STEP_SECS), 287082);
+}
+
+#[tokio::test]
+async fn login_requires_totp_once_enabled() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let (email, secret, _) = register_with_totp(&app, "mfa_login").await?;
+
+    let mfa_token = login_challenge(&app, &email).await?;
+
+    let (status, _) = send_json(&app, "POST", "/auth/2fa/verify", None, json!({
+        "mfa_token": mfa_token,
+        "code": "123456"
+    })).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Le pas courant a été consommé à la confirmation : on utilise le suivant (dérive tolérée)
+    let code = code_for_step(&secret, totp::current_step() + 1);
+    let (status, body) = send_json(&app, "POST", "/auth/2fa/verify", None, json!({
+        "mfa_token": mfa_token,
+        "code": code
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["token"].is_string());
+    assert!(body["refresh_token"].is_string());
+
+    // Le token MFA est à usage unique
+    let (status, _) = send_json(&app, "POST", "/auth/2fa/verify", None, json!({
+        "mfa_token": mfa_token,
+        "code": code
+    })).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Un code déjà utilisé ne peut pas être rejoué sur une nouvelle connexion
+    let mfa_token = login_challenge(&app, &email).await?;
+    let (status, _) = send_json(&app, "POST", "/auth/2fa/verify", None, json!({
+        "mfa_token": mfa_token,
+        "code": code
+    })).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn recovery_code_works_once() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let (email, _, recovery_codes) = register_with_totp(&app, "mfa_recovery").await?;
+
+    let mfa_token = login_challenge(&app, &email).await?;
+    let (status, body) = send_json(&app, "POST", "/auth/2fa/verify", None, json!({
+        "mfa_token": mfa_token,
+        "code": recovery_codes[0].to_uppercase()
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let token = body["token"].as_str().unwrap().to_string();
+
+    let mfa_token = login_challenge(&app, &email).await?;
+    let (status, _) = send_json(&app, "POST", "/auth/2fa/verify", None, json!({
+        "mfa_token": mfa_token,
+        "code": recovery_codes[0]
+    })).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Désactivation avec un autre code de secours : la connexion redevient directe
+    let (status, _) = send_json(&app, "POST", "/auth/2fa/disable", Some(&token), json!({
+        "code": recovery_codes[1]
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = send_json(&app, "POST", "/auth/login", None, json!({
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["token"].is_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn mfa_token_is_invalidated_after_too_many_failures() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let (email, secret, _) = register_with_totp(&app, "mfa_bruteforce").await?;
+
+    let mfa_token = login_challenge(&app, &email).await?;
+    for _ in 0..5 {
+        let (status, _) = send_json(&app, "POST", "/auth/2fa/verify", None, json!({
+            "mfa_token": mfa_token,
+            "code": "000000"
+        })).await?;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    let code = code_for_step(&secret, totp::current_step() + 1);
+    let (status, _) = send_json(&app, "POST", "/auth/2fa/verify", None, json!({
+        "mfa_token": mfa_token,
+        "code": code
+    })).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn relogging_in_does_not_reset_second_factor_failures() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let (email, _, _) = register_with_totp(&app, "mfa_relogin").await?;
+
+    // Le bon mot de passe ne remet pas le compteur à zéro tant que le second facteur manque
+    for attempts in [3, 2] {
+        let mfa_token = login_challenge(&app, &email).await?;
+        for _ in 0..attempts {
+            let (status, _) = send_json(&app, "POST", "/auth/2fa/verify", None, json!({
+                "mfa_token": mfa_token,
+                "code": "000000"
+            })).await?;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    let (status, _) = send_json(&app, "POST", "/auth/login", None, json!({
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn locked_account_rejects_second_factor() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let (email, secret, _) = register_with_totp(&app, "mfa_locked").await?;
+
+    let first = login_challenge(&app, &email).await?;
+    for _ in 0..4 {
+        let (status, _) = send_json(&app, "POST", "/auth/2fa/verify", None, json!({ "mfa_token": first, "code": "000000" })).await?;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    // Le nouveau token n'a qu'un échec, mais le compte en a cinq : même le bon code est refusé
+    let second = login_challenge(&app, &email).await?;
+    let (status, _) = send_json(&app, "POST", "/auth/2fa/verify", None, json!({ "mfa_token": second, "code": "000000" })).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let code = code_for_step(&secret, totp::current_step() + 1);
+    let (status, _) = send_json(&app, "POST", "/auth/2fa/verify", None, json!({ "mfa_token": second, "code": code })).await?;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn disabling_two_factor_counts_toward_the_lockout() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let (email, _, recovery_codes) = register_with_totp(&app, "mfa_disable_guard").await?;
+
+    let mfa_token = login_challenge(&app, &email).await?;
+    let (status, body) = send_json(&app, "POST", "/auth/2fa/verify", None, json!({
+        "mfa_token": mfa_token,
+        "code": recovery_codes[0]
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let token = body["token"].as_str().unwrap().to_string();
+
+    // Un token d'accès volé ne permet pas de deviner le code indéfiniment
+    for _ in 0..5 {
+        let (status, _) = send_json(&app, "POST", "/auth/2fa/disable", Some(&token), json!({ "code": "000000" })).await?;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    let (status, _) = send_json(&app, "POST", "/auth/2fa/disable", Some(&token), json!({ "code": recovery_codes[1] })).await?;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    let (status, _) = send_json(&app, "POST", "/auth/login", None, json!({
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use std::{env, path::{Path, PathBuf}};
+use axum::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+use common::{build_app_with_outbox, unique_username, send_json};
+
+fn outbox_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("{}_{}.jsonl", unique_username(name), std::process::id()))
+}
+
+/// Dernier token envoyé par email à `to` (extrait du lien `...?token=`).
+fn last_token_sent_to(outbox: &Path, to: &str) -> Option<String> {
+    let content = std::fs::read_to_string(outbox).ok()?;
+    content
+        .lines()
+        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+        .rfind(|m| m["to"] == to)
+        .and_then(|m| {
+            let body = m["body"].as_str()?.to_string();
+            let start = body.find("token=")? + "token=".len();
+            Some(body[start..].split_whitespace().next()?.to_string())
+        })
+}
+
+#[tokio::test]
+async fn registration_sends_verification_email() -> Result<(), Box<dyn std::error::Error>> {
+    let outbox = outbox_path("verify");
+    let Some(app) = build_app_with_outbox(&outbox).await? else { return Ok(()) };
+
+    let username = unique_username("verify_user");
+    let email = format!("{}@test.com", username);
+    let (status, user) = send_json(&app, "POST", "/auth/register", None, json!({
+        "username": username,
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(user["email_verified"], false, "New users should be unverified");
+
+    let token = last_token_sent_to(&outbox, &email).expect("Verification email should be sent");
+
+    let (status, user) = send_json(&app, "POST", "/auth/verify-email", None, json!({ "token": token })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(user["email_verified"], true);
+
+    // Usage unique
+    let (status, _) = send_json(&app, "POST", "/auth/verify-email", None, json!({ "token": token })).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let _ = std::fs::remove_file(&outbox);
+    Ok(())
+}
+
+#[tokio::test]
+async fn password_reset_flow() -> Result<(), Box<dyn std::error::Error>> {
+    let outbox = outbox_path("reset");
+    let Some(app) = build_app_with_outbox(&outbox).await? else { return Ok(()) };
+
+    let username = unique_username("reset_user");
+    let email = format!("{}@test.com", username);
+    send_json(&app, "POST", "/auth/register", None, json!({
+        "username": username,
+        "email": email,
+        "password": "OldPass123!"
+    })).await?;
+
+    let (status, login) = send_json(&app, "POST", "/auth/login", None, json!({
+        "email": email,
+        "password": "OldPass123!"
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let old_refresh = login["refresh_token"].as_str().unwrap().to_string();
+
+    let (status, _) = send_json(&app, "POST", "/auth/forgot-password", None, json!({ "email": email })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let token = last_token_sent_to(&outbox, &email)
+        .filter(|_| std::fs::read_to_string(&outbox).unwrap_or_default().contains("reset-password"))
+        .expect("Reset email should be sent");
+
+    let (status, _) = send_json(&app, "POST", "/auth/reset-password", None, json!({
+        "token": token,
+        "new_password": "NewPass456!"
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // L'ancien mot de passe et les anciennes sessions ne fonctionnent plus
+    let (status, _) = send_json(&app, "POST", "/auth/login", None, json!({
+        "email": email,
+        "password": "OldPass123!"
+    })).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send_json(&app, "POST", "/auth/refresh", None, json!({ "refresh_token": old_refresh })).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = send_json(&app, "POST", "/auth/login", None, json!({
+        "email": email,
+        "password": "NewPass456!"
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // Le token de réinitialisation ne peut pas être rejoué
+    let (status, _) = send_json(&app, "POST", "/auth/reset-password", None, json!({
+        "token": token,
+        "new_password": "Another789!"
+    })).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let _ = std::fs::remove_file(&outbox);
+    Ok(())
+}
+
+#[tokio::test]
+async fn forgot_password_does_not_reveal_unknown_email() -> Result<(), Box<dyn std::error::Error>> {
+    let outbox = outbox_path("unknown");
+    let Some(app) = build_app_with_outbox(&outbox).await? else { return Ok(()) };
+
+    let email = format!("{}@test.com", unique_username("nobody"));
+    let (status, _) = send_json(&app, "POST", "/auth/forgot-password", None, json!({ "email": email })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(last_token_sent_to(&outbox, &email).is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn verification_token_cannot_reset_password() -> Result<(), Box<dyn std::error::Error>> {
+    let outbox = outbox_path("purpose");
+    let Some(app) = build_app_with_outbox(&outbox).await? else { return Ok(()) };
+
+    let username = unique_username("purpose_user");
+    let email = format!("{}@test.com", username);
+    send_json(&app, "POST", "/auth/register", None, json!({
+        "username": username,
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+    let token = last_token_sent_to(&outbox, &email).expect("Verification email should be sent");
+
+    let (status, _) = send_json(&app, "POST", "/auth/reset-password", None, json!({
+        "token": token,
+        "new_password": "Hijacked123!"
+    })).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let _ = std::fs::remove_file(&outbox);
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::json;
+use tower::ServiceExt;
+
+mod common;
+
+use common::{build_app, unique_username, send_json};
+
+/// Inscrit un utilisateur puis se connecte ; retourne la réponse complète du login.
+async fn register_and_login(
+    app: &axum::Router,
+    base: &str,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let username = unique_username(base);
+    let email = format!("{}@test.com", username);
+
+    send_json(app, "POST", "/auth/register", None, json!({
+        "username": username,
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+
+    let (status, body) = send_json(app, "POST", "/auth/login", None, json!({
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "Login should succeed");
+    Ok(body)
+}
+
+async fn get_friends_status(app: &axum::Router, token: &str) -> Result<StatusCode, Box<dyn std::error::Error>> {
+    let request = Request::builder()
+        .method("GET")
+        .uri("/friends")
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::empty())?;
+    Ok(app.clone().oneshot(request).await?.status())
+}
+
+#[tokio::test]
+async fn login_returns_refresh_token() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+
+    let login = register_and_login(&app, "session_login").await?;
+    assert!(login["token"].as_str().is_some_and(|t| !t.is_empty()));
+    assert!(login["refresh_token"].as_str().is_some_and(|t| !t.is_empty()));
+    assert!(login["expires_in"].as_u64().is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn refresh_rotates_token_and_detects_reuse() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+
+    let login = register_and_login(&app, "session_refresh").await?;
+    let refresh_token = login["refresh_token"].as_str().unwrap().to_string();
+
+    let (status, body) = send_json(&app, "POST", "/auth/refresh", None, json!({
+        "refresh_token": refresh_token
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "Refresh should succeed");
+    let new_token = body["token"].as_str().unwrap().to_string();
+    let new_refresh = body["refresh_token"].as_str().unwrap().to_string();
+    assert_ne!(new_refresh, refresh_token, "Refresh token should rotate");
+    assert_eq!(get_friends_status(&app, &new_token).await?, StatusCode::OK);
+
+    // Rejouer l'ancien refresh token révoque toute la session
+    let (status, _) = send_json(&app, "POST", "/auth/refresh", None, json!({
+        "refresh_token": refresh_token
+    })).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "Reused refresh token should be rejected");
+
+    let (status, _) = send_json(&app, "POST", "/auth/refresh", None, json!({
+        "refresh_token": new_refresh
+    })).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "Session should be revoked after reuse");
+    assert_eq!(get_friends_status(&app, &new_token).await?, StatusCode::UNAUTHORIZED);
+    Ok(())
+}
+
+#[tokio::test]
+async fn refresh_with_unknown_token_returns_401() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+
+    let (status, _) = send_json(&app, "POST", "/auth/refresh", None, json!({
+        "refresh_token": "not-a-real-token"
+    })).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    Ok(())
+}
+
+#[tokio::test]
+async fn logout_revokes_access_and_refresh_tokens() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+
+    let login = register_and_login(&app, "session_logout").await?;
+    let token = login["token"].as_str().unwrap();
+    let refresh_token = login["refresh_token"].as_str().unwrap();
+
+    let (status, _) = send_json(&app, "POST", "/auth/logout", Some(token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    assert_eq!(get_friends_status(&app, token).await?, StatusCode::UNAUTHORIZED);
+    let (status, _) = send_json(&app, "POST", "/auth/refresh", None, json!({
+        "refresh_token": refresh_token
+    })).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    Ok(())
+}
+
+#[tokio::test]
+async fn logout_all_revokes_every_session() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+
+    let first = register_and_login(&app, "session_logout_all").await?;
+    let email = first["user"]["email"].as_str().unwrap().to_string();
+    let token_phone = first["token"].as_str().unwrap().to_string();
+
+    let (_, second) = send_json(&app, "POST", "/auth/login", None, json!({
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+    let token_laptop = second["token"].as_str().unwrap().to_string();
+
+    let (status, body) = send_json(&app, "POST", "/auth/logout?all=true", Some(&token_laptop), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["revoked_sessions"].as_u64(), Some(2));
+
+    assert_eq!(get_friends_status(&app, &token_phone).await?, StatusCode::UNAUTHORIZED);
+    assert_eq!(get_friends_status(&app, &token_laptop).await?, StatusCode::UNAUTHORIZED);
+    Ok(())
+}
+
+#[tokio::test]
+async fn session_endpoint_requires_active_session() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+
+    let login = register_and_login(&app, "session_check").await?;
+    let token = login["token"].as_str().unwrap();
+
+    let request = Request::builder()
+        .method("GET")
+        .uri("/auth/session")
+        .header("Authorization", format!("Bearer {}", token))
+        .body(Body::empty())?;
+    assert_eq!(app.clone().oneshot(request).await?.status(), StatusCode::OK);
+
+    let request = Request::builder()
+        .method("GET")
+        .uri("/auth/session")
+        .body(Body::empty())?;
+    assert_eq!(app.clone().oneshot(request).await?.status(), StatusCode::UNAUTHORIZED);
+    Ok(())
+}
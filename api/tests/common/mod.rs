@@ -0,0 +1,130 @@
+//! Fixtures partagées par les tests d'intégration. Chaque fichier de `tests/` est compilé à part
+//! et n'en utilise qu'une partie, d'où le `dead_code` toléré.
+#![allow(dead_code)]
+
+use std::{env, path::Path, sync::Arc};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use dotenvy::dotenv;
+use rust_rmce_api::{db::{self, DbPool}, mailer::LogMailer, routes};
+use serde_json::json;
+use tower::ServiceExt;
+
+/// Pool sur `DATABASE_URL` ; `None` si la variable n'est pas définie : les tests sont ignorés.
+async fn test_pool() -> Result<Option<DbPool>, Box<dyn std::error::Error>> {
+    dotenv().ok();
+
+    let url = match env::var("DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => {
+            eprintln!("DATABASE_URL non définie, les tests d'intégration sont ignorés.");
+            return Ok(None);
+        }
+    };
+
+    Ok(Some(db::create_pool(&url).await?))
+}
+
+pub async fn build_app() -> Result<Option<axum::Router>, Box<dyn std::error::Error>> {
+    Ok(test_pool().await?.map(routes::create_app))
+}
+
+/// Pour les tests qui préparent des données directement en base (rôles, signalements...).
+pub async fn build_app_with_pool() -> Result<Option<(axum::Router, DbPool)>, Box<dyn std::error::Error>> {
+    Ok(test_pool().await?.map(|pool| (routes::create_app(pool.clone()), pool)))
+}
+
+/// Les emails envoyés sont ajoutés, un par ligne en JSON, au fichier `outbox`.
+pub async fn build_app_with_outbox(outbox: &Path) -> Result<Option<axum::Router>, Box<dyn std::error::Error>> {
+    Ok(test_pool()
+        .await?
+        .map(|pool| routes::create_app_with_mailer(pool, Arc::new(LogMailer::new(Some(outbox.to_path_buf()))))))
+}
+
+pub fn unique_username(base: &str) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    format!("{}_{}", base, now)
+}
+
+pub async fn send_json(
+    app: &axum::Router,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: serde_json::Value,
+) -> Result<(StatusCode, serde_json::Value), Box<dyn std::error::Error>> {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json");
+    if let Some(t) = token {
+        builder = builder.header("Authorization", format!("Bearer {}", t));
+    }
+    let request = builder.body(Body::from(serde_json::to_vec(&body)?))?;
+
+    let response = app.clone().oneshot(request).await?;
+    let status = response.status();
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let value = serde_json::from_slice(&body_bytes).unwrap_or(serde_json::Value::Null);
+    Ok((status, value))
+}
+
+pub struct TestUser {
+    pub id: i64,
+    pub username: String,
+    pub token: String,
+}
+
+/// Inscrit et connecte un utilisateur `<base>_<horodatage>`, mot de passe `SecurePass123!`.
+pub async fn new_user(app: &axum::Router, base: &str) -> Result<TestUser, Box<dyn std::error::Error>> {
+    let username = unique_username(base);
+    let email = format!("{}@test.com", username);
+    let (_, user) = send_json(app, "POST", "/auth/register", None, json!({
+        "username": username,
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+    let (status, login) = send_json(app, "POST", "/auth/login", None, json!({
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    Ok(TestUser {
+        id: user["id"].as_i64().unwrap(),
+        username,
+        token: login["token"].as_str().unwrap().to_string(),
+    })
+}
+
+/// Inscrit un utilisateur avec le rôle donné (attribué directement en base) ; retourne (id, token).
+pub async fn user_with_role(
+    app: &axum::Router,
+    pool: &DbPool,
+    role: &str,
+) -> Result<(i64, String), Box<dyn std::error::Error>> {
+    let username = unique_username(role);
+    let email = format!("{}@test.com", username);
+    let (_, user) = send_json(app, "POST", "/auth/register", None, json!({
+        "username": username,
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+    let id = user["id"].as_i64().unwrap();
+
+    sqlx::query("UPDATE users SET role = $1 WHERE id = $2")
+        .bind(role)
+        .bind(id as i32)
+        .execute(pool)
+        .await?;
+
+    let (status, login) = send_json(app, "POST", "/auth/login", None, json!({
+        "email": email,
+        "password": "SecurePass123!"
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    Ok((id, login["token"].as_str().unwrap().to_string()))
+}
@@ -0,0 +1,100 @@
+use rust_rmce_api::elevation::{self, ClimbCategory, MAX_PROFILE_POINTS};
+use rust_rmce_api::geometry::{Position, EARTH_RADIUS_METERS};
+
+/// Positions espacées de `step_m` mètres vers le nord, avec les altitudes données.
+fn along_meridian(step_m: f64, elevations: &[f64]) -> Vec<Position> {
+    let step_deg = (step_m / EARTH_RADIUS_METERS).to_degrees();
+    elevations
+        .iter()
+        .enumerate()
+        .map(|(i, ele)| Position { lon: 6.0, lat: 45.0 + i as f64 * step_deg, ele: Some(*ele) })
+        .collect()
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-6, "expected {}, got {}", expected, actual);
+}
+
+#[test]
+fn constant_grade_climb() {
+    // 2 km à 5 % : 2 000 × 5 = 10 000, quatrième catégorie
+    let elevations: Vec<f64> = (0..=20).map(|i| 100.0 + 5.0 * i as f64).collect();
+    let profile = elevation::profile(&along_meridian(100.0, &elevations)).unwrap();
+
+    assert_close(profile.distance_meters, 2_000.0);
+    assert_close(profile.total_ascent_meters, 100.0);
+    assert_close(profile.total_descent_meters, 0.0);
+    assert_close(profile.min_elevation_meters, 100.0);
+    assert_close(profile.max_elevation_meters, 200.0);
+    assert_close(profile.max_grade_percent, 5.0);
+    assert_close(profile.min_grade_percent, 5.0);
+    assert_close(profile.avg_grade_percent, 5.0);
+
+    assert_eq!(profile.climbs.len(), 1);
+    let climb = &profile.climbs[0];
+    assert_eq!(climb.category, ClimbCategory::Cat4);
+    assert_close(climb.start_distance_meters, 0.0);
+    assert_close(climb.end_distance_meters, 2_000.0);
+    assert_close(climb.elevation_gain_meters, 100.0);
+
+    assert_eq!(profile.points.len(), 21);
+    assert_close(profile.points[20].distance_meters, 2_000.0);
+    assert_close(profile.points[20].elevation_meters, 200.0);
+}
+
+#[test]
+fn climbs_are_split_on_significant_descents() {
+    // Montée de 1 km à 10 %, replat descendant de 5 m, montée de 1 km à 10 % : une seule montée
+    let mut elevations: Vec<f64> = (0..=10).map(|i| 10.0 * i as f64).collect();
+    elevations.push(95.0);
+    elevations.extend((1..=10).map(|i| 95.0 + 10.0 * i as f64));
+    let profile = elevation::profile(&along_meridian(100.0, &elevations)).unwrap();
+    assert_eq!(profile.climbs.len(), 1);
+    assert_close(profile.climbs[0].elevation_gain_meters, 195.0);
+    assert_eq!(profile.climbs[0].category, ClimbCategory::Cat3);
+
+    // Même profil avec une descente de 40 m au milieu : deux montées distinctes
+    let mut elevations: Vec<f64> = (0..=10).map(|i| 8.0 * i as f64).collect();
+    elevations.push(40.0);
+    elevations.extend((1..=10).map(|i| 40.0 + 8.0 * i as f64));
+    let profile = elevation::profile(&along_meridian(100.0, &elevations)).unwrap();
+    assert_eq!(profile.climbs.len(), 2);
+    assert_close(profile.climbs[1].start_distance_meters, 1_100.0);
+    assert!(profile.climbs.iter().all(|c| c.category == ClimbCategory::Cat4));
+    assert_close(profile.total_descent_meters, 40.0);
+    assert!(profile.min_grade_percent < -10.0);
+}
+
+#[test]
+fn long_steep_climb_is_hors_categorie() {
+    let elevations: Vec<f64> = (0..=100).map(|i| 500.0 + 8.0 * i as f64).collect();
+    let profile = elevation::profile(&along_meridian(100.0, &elevations)).unwrap();
+    assert_eq!(profile.climbs.len(), 1);
+    assert_eq!(profile.climbs[0].category, ClimbCategory::Hc);
+
+    // Faux plat : pas de montée catégorisée
+    let elevations: Vec<f64> = (0..=100).map(|i| 500.0 + 2.0 * i as f64).collect();
+    assert!(elevation::profile(&along_meridian(100.0, &elevations)).unwrap().climbs.is_empty());
+}
+
+#[test]
+fn short_or_incomplete_tracks() {
+    // Plus court que la fenêtre de pente : une seule pente, de bout en bout
+    let profile = elevation::profile(&along_meridian(20.0, &[10.0, 11.0, 12.0])).unwrap();
+    assert_close(profile.max_grade_percent, 5.0);
+    assert_close(profile.min_grade_percent, 5.0);
+
+    let mut positions = along_meridian(100.0, &[10.0, 11.0, 12.0]);
+    positions[1].ele = None;
+    assert!(elevation::profile(&positions).is_none());
+    assert!(elevation::profile(&positions[..1]).is_none());
+}
+
+#[test]
+fn long_profiles_are_sampled() {
+    let elevations: Vec<f64> = (0..5_001).map(|i| (i as f64 / 100.0).sin() * 50.0).collect();
+    let profile = elevation::profile(&along_meridian(10.0, &elevations)).unwrap();
+    assert!(profile.points.len() <= MAX_PROFILE_POINTS);
+    assert_close(profile.points[0].distance_meters, 0.0);
+    assert_close(profile.points.last().unwrap().distance_meters, 50_000.0);
+}
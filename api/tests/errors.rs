@@ -0,0 +1,161 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::json;
+use tower::ServiceExt;
+
+mod common;
+
+use common::{build_app_with_pool, TestUser, new_user};
+
+/// Retourne le statut, l'en-tête `x-request-id` et le corps JSON de la réponse.
+async fn send_with_request_id(
+    app: &axum::Router,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: serde_json::Value,
+) -> Result<(StatusCode, Option<String>, serde_json::Value), Box<dyn std::error::Error>> {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json");
+    if let Some(t) = token {
+        builder = builder.header("Authorization", format!("Bearer {}", t));
+    }
+    let request = builder.body(Body::from(serde_json::to_vec(&body)?))?;
+
+    let response = app.clone().oneshot(request).await?;
+    let status = response.status();
+    let request_id = response
+        .headers()
+        .get("x-request-id")
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let value = serde_json::from_slice(&body_bytes).unwrap_or(serde_json::Value::Null);
+    Ok((status, request_id, value))
+}
+
+#[tokio::test]
+async fn errors_have_stable_json_body_with_request_id() -> Result<(), Box<dyn std::error::Error>> {
+    let Some((app, _)) = build_app_with_pool().await? else { return Ok(()) };
+
+    let (status, request_id, body) = send_with_request_id(&app, "GET", "/users/99999999", None, json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "not_found");
+    assert_eq!(body["message"], "User not found");
+    assert!(body["details"].is_null());
+    let request_id = request_id.expect("x-request-id header");
+    assert_eq!(body["request_id"], request_id.as_str());
+
+    let (status, _, body) = send_with_request_id(&app, "GET", "/no/such/endpoint", None, json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "not_found");
+
+    let (status, _, body) = send_with_request_id(&app, "GET", "/friends", None, json!({})).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "unauthorized");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_request_id_is_echoed() -> Result<(), Box<dyn std::error::Error>> {
+    let Some((app, _)) = build_app_with_pool().await? else { return Ok(()) };
+
+    let request = Request::builder()
+        .uri("/posts/99999999")
+        .header("x-request-id", "mobile-42")
+        .body(Body::empty())?;
+    let response = app.oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.headers()["x-request-id"], "mobile-42");
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let body: serde_json::Value = serde_json::from_slice(&bytes)?;
+    assert_eq!(body["request_id"], "mobile-42");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn unique_violation_maps_to_conflict() -> Result<(), Box<dyn std::error::Error>> {
+    let Some((app, pool)) = build_app_with_pool().await? else { return Ok(()) };
+    let username = new_user(&app, "err_dup").await?.username;
+
+    let (status, _, body) = send_with_request_id(&app, "POST", "/auth/register", None, json!({
+        "username": username,
+        "email": format!("{}@test.com", username),
+        "password": "SecurePass123!"
+    })).await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "conflict");
+
+    // Création directe par un administrateur : la violation d'unicité remonte de Postgres
+    let admin_name = new_user(&app, "err_admin").await?.username;
+    sqlx::query("UPDATE users SET role = 'admin' WHERE username = $1")
+        .bind(&admin_name)
+        .execute(&pool)
+        .await?;
+    let (status, _, login) = send_with_request_id(&app, "POST", "/auth/login", None, json!({
+        "email": format!("{}@test.com", admin_name),
+        "password": "SecurePass123!"
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let admin_token = login["token"].as_str().unwrap().to_string();
+
+    let (status, _, body) = send_with_request_id(&app, "POST", "/users", Some(&admin_token), json!({
+        "username": username,
+        "email": "other@test.com"
+    })).await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "conflict");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn foreign_key_violation_maps_to_unprocessable() -> Result<(), Box<dyn std::error::Error>> {
+    let Some((app, _)) = build_app_with_pool().await? else { return Ok(()) };
+    let token = new_user(&app, "err_fk").await?.token;
+
+    let (status, _, body) = send_with_request_id(&app, "POST", "/api/challenges", Some(&token), json!({
+        "route_id": 99999999,
+        "challenged_id": null
+    })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "unprocessable");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_and_path_rejections_use_the_json_body() -> Result<(), Box<dyn std::error::Error>> {
+    let Some((app, pool)) = build_app_with_pool().await? else { return Ok(()) };
+    let TestUser { username: moderator, token, .. } = new_user(&app, "err_extract").await?;
+    sqlx::query("UPDATE users SET role = 'moderator' WHERE username = $1")
+        .bind(&moderator)
+        .execute(&pool)
+        .await?;
+    let (status, _, login) = send_with_request_id(&app, "POST", "/auth/login", None, json!({
+        "email": format!("{}@test.com", moderator),
+        "password": "SecurePass123!"
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let moderator_token = login["token"].as_str().unwrap().to_string();
+
+    let cases = [
+        ("/friends?limit=abc", &token, StatusCode::UNPROCESSABLE_ENTITY, "validation_failed"),
+        ("/admin/score-flags?rule=no_such_rule", &moderator_token, StatusCode::UNPROCESSABLE_ENTITY, "validation_failed"),
+        ("/routes/abc", &token, StatusCode::BAD_REQUEST, "bad_request"),
+        ("/segments/abc/leaderboard", &token, StatusCode::BAD_REQUEST, "bad_request"),
+    ];
+    for (uri, token, expected, code) in cases {
+        let (status, request_id, body) = send_with_request_id(&app, "GET", uri, Some(token), json!({})).await?;
+        assert_eq!(status, expected, "{}", uri);
+        assert_eq!(body["code"], code, "{}", uri);
+        assert!(body["message"].as_str().is_some_and(|m| !m.is_empty()), "{}", uri);
+        assert_eq!(body["request_id"], request_id.expect("x-request-id header").as_str());
+    }
+
+    Ok(())
+}
@@ -1,36 +1,11 @@
-use std::env;
 use axum::body::Body;
 use axum::http::{Request, StatusCode};
-use dotenvy::dotenv;
-use rust_rmce_api::{db, routes};
 use serde_json::json;
 use tower::ServiceExt;
 
-async fn build_app() -> Result<Option<axum::Router>, Box<dyn std::error::Error>> {
-    dotenv().ok();
+mod common;
 
-    let url = match env::var("DATABASE_URL") {
-        Ok(v) => v,
-        Err(_) => {
-            eprintln!("DATABASE_URL non définie, les tests sont ignorés.");
-            return Ok(None);
-        }
-    };
-
-    let pool = db::create_pool(&url).await?;
-    let app = routes::create_app(pool);
-
-    Ok(Some(app))
-}
-
-// Generate unique username with timestamp
-fn unique_username(base: &str) -> String {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis();
-    format!("{}_{}", base, now)
-}
+use common::{build_app, unique_username};
 
 // ============ USER STORIES & INTEGRATION TESTS ============
 
@@ -159,7 +134,7 @@ async fn user_story_02_create_and_manage_routes() -> Result<(), Box<dyn std::err
     // Story: L'utilisateur visualise le parcours créé
     let request = Request::builder()
         .method("GET")
-        .uri(&format!("/routes/{}", route_id))
+        .uri(format!("/routes/{}", route_id))
         .header("Authorization", format!("Bearer {}", token))
         .body(Body::empty())?;
 
@@ -174,7 +149,7 @@ async fn user_story_02_create_and_manage_routes() -> Result<(), Box<dyn std::err
 
     let request = Request::builder()
         .method("PUT")
-        .uri(&format!("/routes/{}", route_id))
+        .uri(format!("/routes/{}", route_id))
         .header("content-type", "application/json")
         .header("Authorization", format!("Bearer {}", token))
         .body(Body::from(serde_json::to_vec(&update_route_body)?))?;
@@ -263,7 +238,7 @@ async fn user_story_03_submit_score_after_run() -> Result<(), Box<dyn std::error
 
     let request = Request::builder()
         .method("POST")
-        .uri(&format!("/routes/{}/score", route_id))
+        .uri(format!("/routes/{}/score", route_id))
         .header("content-type", "application/json")
         .header("Authorization", format!("Bearer {}", token))
         .body(Body::from(serde_json::to_vec(&submit_score_body)?))?;
@@ -352,7 +327,7 @@ async fn user_story_04_add_friend_and_manage_requests() -> Result<(), Box<dyn st
     // Story: Alice ajoute Bob comme ami
     let request = Request::builder()
         .method("POST")
-        .uri(&format!("/friends/add/{}", user2_name))
+        .uri(format!("/friends/add/{}", user2_name))
         .header("Authorization", format!("Bearer {}", token1))
         .body(Body::empty())?;
 
@@ -439,7 +414,7 @@ async fn user_story_05_view_leaderboard() -> Result<(), Box<dyn std::error::Erro
 
     let request = Request::builder()
         .method("POST")
-        .uri(&format!("/routes/{}/score", route_id))
+        .uri(format!("/routes/{}/score", route_id))
         .header("content-type", "application/json")
         .header("Authorization", format!("Bearer {}", token))
         .body(Body::from(serde_json::to_vec(&submit_score_body)?))?;
@@ -449,7 +424,7 @@ async fn user_story_05_view_leaderboard() -> Result<(), Box<dyn std::error::Erro
     // Story: L'utilisateur consulte le classement du parcours
     let request = Request::builder()
         .method("GET")
-        .uri(&format!("/api/leaderboard/route/{}", route_id))
+        .uri(format!("/api/leaderboard/route/{}", route_id))
         .header("Authorization", format!("Bearer {}", token))
         .body(Body::empty())?;
 
@@ -536,7 +511,7 @@ async fn user_story_06_upload_sensor_data() -> Result<(), Box<dyn std::error::Er
 
     let request = Request::builder()
         .method("POST")
-        .uri(&format!("/routes/{}/score", route_id))
+        .uri(format!("/routes/{}/score", route_id))
         .header("content-type", "application/json")
         .header("Authorization", format!("Bearer {}", token))
         .body(Body::from(serde_json::to_vec(&submit_score_body)?))?;
@@ -704,7 +679,7 @@ async fn security_test_user_cannot_modify_others_route() -> Result<(), Box<dyn s
         "name": "My secret route",
         "description": "Only mine",
         "is_public": false,
-        "path_data": {"type": "LineString", "coordinates": [[0.0, 0.0]]},
+        "path_data": {"type": "LineString", "coordinates": [[0.0, 0.0], [0.01, 0.01]]},
         "distance_meters": 5000.0
     });
 
@@ -759,7 +734,7 @@ async fn security_test_user_cannot_modify_others_route() -> Result<(), Box<dyn s
 
     let request = Request::builder()
         .method("PUT")
-        .uri(&format!("/routes/{}", route_id))
+        .uri(format!("/routes/{}", route_id))
         .header("content-type", "application/json")
         .header("Authorization", format!("Bearer {}", token2))
         .body(Body::from(serde_json::to_vec(&update_route_body)?))?;
@@ -0,0 +1,218 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+use common::{build_app, send_json, new_user};
+
+#[tokio::test]
+async fn users_can_only_delete_and_befriend_as_themselves() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let alice = new_user(&app, "own_alice").await?;
+    let bob = new_user(&app, "own_bob").await?;
+
+    let (status, _) = send_json(&app, "DELETE", &format!("/users/{}", bob.id), Some(&alice.token), json!({})).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Envoyer une demande d'ami au nom de Bob
+    let (status, _) = send_json(&app, "POST", &format!("/users/{}/friends/{}", bob.id, alice.id), Some(&alice.token), json!({})).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send_json(&app, "POST", &format!("/users/{}/friends/{}", bob.id, alice.id), None, json!({})).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = send_json(&app, "POST", &format!("/users/{}/friends/{}", alice.id, bob.id), Some(&alice.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // Suppression de son propre compte
+    let (status, _) = send_json(&app, "DELETE", &format!("/users/{}", alice.id), Some(&alice.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn posts_belong_to_their_author() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let alice = new_user(&app, "post_alice").await?;
+    let bob = new_user(&app, "post_bob").await?;
+
+    // Le user_id fourni par le client est ignoré
+    let (status, post) = send_json(&app, "POST", "/posts", Some(&alice.token), json!({
+        "title": "Mon post",
+        "body": "Contenu",
+        "user_id": bob.id
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(post["user_id"], alice.id);
+    let uri = format!("/posts/{}", post["id"]);
+
+    let (status, _) = send_json(&app, "PUT", &uri, Some(&bob.token), json!({ "title": "Piraté", "body": "x" })).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send_json(&app, "DELETE", &uri, Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // L'auteur modifie son post sans pouvoir le transférer
+    let (status, updated) = send_json(&app, "PUT", &uri, Some(&alice.token), json!({
+        "title": "Modifié",
+        "body": "Nouveau contenu",
+        "user_id": bob.id
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["title"], "Modifié");
+    assert_eq!(updated["user_id"], alice.id);
+
+    let (status, _) = send_json(&app, "DELETE", &uri, Some(&alice.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn only_the_recipient_can_answer_a_friend_request() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let alice = new_user(&app, "friend_alice").await?;
+    let bob = new_user(&app, "friend_bob").await?;
+    let mallory = new_user(&app, "friend_mallory").await?;
+
+    let (status, friendship) = send_json(&app, "POST", &format!("/friends/add/{}", bob.username), Some(&alice.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let id = friendship["id"].as_i64().unwrap();
+
+    // Ni l'expéditeur ni un tiers ne peuvent répondre
+    for token in [&alice.token, &mallory.token] {
+        for action in ["accept", "reject"] {
+            let (status, _) = send_json(&app, "PUT", &format!("/friends/{}/{}", action, id), Some(token), json!({})).await?;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{} by non-recipient should be forbidden", action);
+        }
+    }
+
+    let (status, _) = send_json(&app, "PUT", "/friends/accept/999999", Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, accepted) = send_json(&app, "PUT", &format!("/friends/accept/{}", id), Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(accepted["status"], "accepted");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn only_participants_complete_a_challenge_with_their_own_time() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let alice = new_user(&app, "chal_alice").await?;
+    let bob = new_user(&app, "chal_bob").await?;
+    let mallory = new_user(&app, "chal_mallory").await?;
+
+    let (_, route) = send_json(&app, "POST", "/routes", Some(&alice.token), json!({
+        "name": "Route du défi",
+        "is_public": true,
+        "path_data": { "type": "LineString", "coordinates": [[2.35, 48.85], [2.36, 48.86]] }
+    })).await?;
+    let (status, challenge) = send_json(&app, "POST", "/api/challenges", Some(&alice.token), json!({
+        "route_id": route["id"],
+        "challenged_id": bob.id
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/api/challenges/{}/complete", challenge["id"]);
+    let (status, _) = send_json(&app, "POST", &format!("/api/challenges/{}/accept", challenge["id"]), Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // Un tiers ne peut ni saisir de temps ni terminer le défi
+    let (status, _) = send_json(&app, "POST", &uri, Some(&mallory.token), json!({ "challenger_time": 1.0, "challenged_time": 2.0 })).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send_json(&app, "POST", &uri, Some(&mallory.token), json!({ "status": "completed" })).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Chaque participant ne saisit que son propre temps
+    let (status, _) = send_json(&app, "POST", &uri, Some(&alice.token), json!({ "challenged_time": 999.0 })).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send_json(&app, "POST", &uri, Some(&bob.token), json!({ "challenger_time": 999.0 })).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, partial) = send_json(&app, "POST", &uri, Some(&alice.token), json!({ "challenger_time": 120.0 })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(partial["status"], "active");
+    assert!(partial["winner_id"].is_null());
+
+    let (status, done) = send_json(&app, "POST", &uri, Some(&bob.token), json!({ "challenged_time": 100.0 })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(done["status"], "completed");
+    assert_eq!(done["winner_id"], bob.id);
+
+    // Les temps ne peuvent plus être modifiés une fois le défi terminé
+    let (status, _) = send_json(&app, "POST", &uri, Some(&alice.token), json!({ "challenger_time": 50.0 })).await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn participants_cannot_force_a_challenge_status() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let alice = new_user(&app, "chal_status_alice").await?;
+    let bob = new_user(&app, "chal_status_bob").await?;
+
+    let (_, route) = send_json(&app, "POST", "/routes", Some(&alice.token), json!({
+        "name": "Route du défi",
+        "is_public": true,
+        "path_data": { "type": "LineString", "coordinates": [[2.35, 48.85], [2.36, 48.86]] }
+    })).await?;
+    let (_, challenge) = send_json(&app, "POST", "/api/challenges", Some(&alice.token), json!({
+        "route_id": route["id"],
+        "challenged_id": bob.id
+    })).await?;
+    let uri = format!("/api/challenges/{}/complete", challenge["id"]);
+
+    // Pas de résultat tant que le défi n'a pas été accepté
+    let (status, _) = send_json(&app, "POST", &uri, Some(&bob.token), json!({ "challenged_time": 80.0 })).await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = send_json(&app, "POST", &format!("/api/challenges/{}/accept", challenge["id"]), Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // Un défi ne se termine qu'avec les deux temps, sans gagnant désigné par un participant
+    let (status, body) = send_json(&app, "POST", &uri, Some(&alice.token), json!({ "status": "completed" })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["status"].is_array());
+    let (status, _) = send_json(&app, "POST", &uri, Some(&alice.token), json!({ "status": "cancelled", "challenger_time": 90.0 })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, cancelled) = send_json(&app, "POST", &uri, Some(&bob.token), json!({ "status": "cancelled" })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(cancelled["status"], "cancelled");
+    assert!(cancelled["winner_id"].is_null());
+    assert!(cancelled["completed_at"].is_null());
+
+    let (status, _) = send_json(&app, "POST", &uri, Some(&alice.token), json!({ "challenger_time": 90.0 })).await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn tied_challenge_has_no_winner() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let alice = new_user(&app, "chal_tie_alice").await?;
+    let bob = new_user(&app, "chal_tie_bob").await?;
+
+    let (_, route) = send_json(&app, "POST", "/routes", Some(&alice.token), json!({
+        "name": "Route du défi",
+        "is_public": true,
+        "path_data": { "type": "LineString", "coordinates": [[2.35, 48.85], [2.36, 48.86]] }
+    })).await?;
+    let (_, challenge) = send_json(&app, "POST", "/api/challenges", Some(&alice.token), json!({
+        "route_id": route["id"],
+        "challenged_id": bob.id
+    })).await?;
+    let (status, _) = send_json(&app, "POST", &format!("/api/challenges/{}/accept", challenge["id"]), Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/api/challenges/{}/complete", challenge["id"]);
+
+    let (status, _) = send_json(&app, "POST", &uri, Some(&alice.token), json!({ "challenger_time": 100.0 })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, done) = send_json(&app, "POST", &uri, Some(&bob.token), json!({ "challenged_time": 100.0 })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(done["status"], "completed");
+    assert!(done["winner_id"].is_null());
+
+    Ok(())
+}
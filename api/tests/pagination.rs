@@ -0,0 +1,137 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+use common::{build_app, send_json, TestUser, new_user};
+
+/// Parcourt toutes les pages de `uri` et retourne les éléments dans l'ordre reçu.
+async fn collect_pages(
+    app: &axum::Router,
+    uri: &str,
+    token: &str,
+) -> Result<(Vec<serde_json::Value>, usize), Box<dyn std::error::Error>> {
+    let mut items = Vec::new();
+    let mut pages = 0;
+    let mut cursor: Option<String> = None;
+    loop {
+        let page_uri = match &cursor {
+            Some(c) => format!("{}&cursor={}", uri, c),
+            None => uri.to_string(),
+        };
+        let (status, page) = send_json(app, "GET", &page_uri, Some(token), json!({})).await?;
+        assert_eq!(status, StatusCode::OK, "{}: {}", page_uri, page);
+        pages += 1;
+        items.extend(page["items"].as_array().unwrap().iter().cloned());
+        match page["next_cursor"].as_str() {
+            Some(c) => cursor = Some(c.to_string()),
+            None => break,
+        }
+        assert!(pages < 50, "pagination sans fin");
+    }
+    Ok((items, pages))
+}
+
+#[tokio::test]
+async fn routes_are_paginated_without_gaps_or_duplicates() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let TestUser { id: user_id, token, .. } = new_user(&app, "page_routes").await?;
+
+    // Tracés vers le nord, d'environ 1,1 km par centième de degré
+    let lengths = [3.0, 1.0, 5.0, 1.0, 2.0];
+    for (i, length) in lengths.iter().enumerate() {
+        let (status, _) = send_json(&app, "POST", "/routes", Some(&token), json!({
+            "name": format!("Parcours {}", i),
+            "is_public": i % 2 == 0,
+            "path_data": { "type": "LineString", "coordinates": [[2.35, 48.85], [2.35, 48.85 + length * 0.01]] }
+        })).await?;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    // Tri par défaut : du plus récent au plus ancien
+    let (items, pages) = collect_pages(&app, &format!("/routes/user/{}?limit=2", user_id), &token).await?;
+    assert_eq!(pages, 3);
+    let names: Vec<&str> = items.iter().map(|r| r["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["Parcours 4", "Parcours 3", "Parcours 2", "Parcours 1", "Parcours 0"]);
+
+    // Tri par distance avec ex aequo : départagés par l'identifiant
+    let (items, _) = collect_pages(&app, &format!("/routes?user_id={}&sort=distance&limit=2", user_id), &token).await?;
+    let names: Vec<&str> = items.iter().map(|r| r["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["Parcours 1", "Parcours 3", "Parcours 4", "Parcours 0", "Parcours 2"]);
+
+    // Filtres combinés
+    let (items, _) = collect_pages(&app, &format!("/routes?user_id={}&is_public=true&min_distance=2500", user_id), &token).await?;
+    let names: Vec<&str> = items.iter().map(|r| r["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["Parcours 2", "Parcours 0"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_pagination_parameters_are_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let TestUser { id: user_id, token, .. } = new_user(&app, "page_errors").await?;
+    for i in 0..2 {
+        send_json(&app, "POST", "/routes", Some(&token), json!({
+            "name": format!("R{}", i),
+            "is_public": true,
+            "path_data": { "type": "LineString", "coordinates": [[2.35, 48.85], [2.36, 48.86]] }
+        })).await?;
+    }
+
+    let (status, body) = send_json(&app, "GET", "/routes?limit=0", Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["limit"].is_array());
+
+    let (status, body) = send_json(&app, "GET", "/routes?sort=password", Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["sort"].is_array());
+
+    let (status, _) = send_json(&app, "GET", "/routes?cursor=not-a-cursor", Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Un curseur ne peut pas être réutilisé avec un autre tri
+    let (_, page) = send_json(&app, "GET", &format!("/routes?user_id={}&limit=1", user_id), Some(&token), json!({})).await?;
+    let cursor = page["next_cursor"].as_str().unwrap();
+    let (status, _) = send_json(&app, "GET", &format!("/routes?user_id={}&limit=1&sort=name&cursor={}", user_id, cursor), Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn users_and_sensor_data_are_paginated() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let TestUser { username, token, .. } = new_user(&app, "page_search").await?;
+
+    let (status, page) = send_json(&app, "GET", &format!("/users?q={}", username), None, json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["items"].as_array().unwrap().len(), 1);
+    assert_eq!(page["items"][0]["username"], username.as_str());
+    assert!(page["next_cursor"].is_null());
+
+    let (_, route) = send_json(&app, "POST", "/routes", Some(&token), json!({
+        "name": "Capteurs",
+        "is_public": true,
+        "path_data": { "type": "LineString", "coordinates": [[2.35, 48.85], [2.36, 48.86]] }
+    })).await?;
+    let (_, score) = send_json(&app, "POST", &format!("/routes/{}/score", route["id"]), Some(&token), json!({
+        "time_seconds": 60.0
+    })).await?;
+    let points: Vec<_> = [4000, 0, 3000, 1000, 2000]
+        .iter()
+        .map(|t| json!({ "timestamp_offset_ms": t }))
+        .collect();
+    let (status, _) = send_json(&app, "POST", "/sensor-data/bulk", Some(&token), json!({
+        "score_id": score["id"],
+        "data": points
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (items, pages) = collect_pages(&app, &format!("/sensor-data/score/{}?limit=2", score["id"]), &token).await?;
+    assert_eq!(pages, 3);
+    let offsets: Vec<i64> = items.iter().map(|d| d["timestamp_offset_ms"].as_i64().unwrap()).collect();
+    assert_eq!(offsets, [0, 1000, 2000, 3000, 4000]);
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+use common::{build_app_with_pool, unique_username, send_json, user_with_role};
+
+#[tokio::test]
+async fn regular_user_cannot_use_privileged_endpoints() -> Result<(), Box<dyn std::error::Error>> {
+    let Some((app, pool)) = build_app_with_pool().await? else { return Ok(()) };
+    let (user_id, token) = user_with_role(&app, &pool, "user").await?;
+    let (victim_id, victim_token) = user_with_role(&app, &pool, "user").await?;
+    let (_, post) = send_json(&app, "POST", "/posts", Some(&victim_token), json!({ "title": "t", "body": "b" })).await?;
+    let post_id = post["id"].as_i64().unwrap();
+
+    let (status, session) = send_json(&app, "GET", "/auth/session", Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(session["role"], "user");
+
+    let forbidden = [
+        ("POST", "/users".to_string(), json!({ "username": unique_username("x"), "email": "x@test.com" })),
+        ("DELETE", format!("/users/{}", victim_id), json!({})),
+        ("PUT", format!("/posts/{}", post_id), json!({ "title": "t", "body": "b" })),
+        ("DELETE", format!("/posts/{}", post_id), json!({})),
+        ("GET", "/admin/users".to_string(), json!({})),
+        ("PUT", format!("/admin/users/{}/role", user_id), json!({ "role": "admin" })),
+        ("DELETE", format!("/admin/users/{}", victim_id), json!({})),
+        ("DELETE", "/admin/routes/1".to_string(), json!({})),
+        ("DELETE", "/admin/challenges/1".to_string(), json!({})),
+    ];
+    for (method, uri, body) in forbidden {
+        let (status, _) = send_json(&app, method, &uri, Some(&token), body).await?;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{} {} should be forbidden", method, uri);
+    }
+
+    // La victime existe toujours
+    let (status, _) = send_json(&app, "GET", &format!("/users/{}", victim_id), None, json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn moderator_can_moderate_but_not_administer() -> Result<(), Box<dyn std::error::Error>> {
+    let Some((app, pool)) = build_app_with_pool().await? else { return Ok(()) };
+    let (moderator_id, token) = user_with_role(&app, &pool, "moderator").await?;
+    let (user_id, user_token) = user_with_role(&app, &pool, "user").await?;
+
+    let (status, users) = send_json(&app, "GET", "/admin/users?role=moderator&sort=id&order=desc&limit=100", Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let users = users["items"].as_array().unwrap();
+    assert!(users.iter().all(|u| u["role"] == "moderator"));
+    assert!(users.iter().any(|u| u["id"] == moderator_id));
+
+    // Parcours d'un autre utilisateur supprimé par la modération
+    let (status, route) = send_json(&app, "POST", "/routes", Some(&user_token), json!({
+        "name": "Route à modérer",
+        "is_public": true,
+        "path_data": { "type": "LineString", "coordinates": [[2.35, 48.85], [2.36, 48.86]] }
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let route_id = route["id"].as_i64().unwrap();
+    let (status, _) = send_json(&app, "DELETE", &format!("/admin/routes/{}", route_id), Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_json(&app, "DELETE", &format!("/admin/routes/{}", route_id), Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = send_json(&app, "DELETE", "/posts/99999", Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, post) = send_json(&app, "POST", "/posts", Some(&user_token), json!({ "title": "t", "body": "b" })).await?;
+    let (status, _) = send_json(&app, "DELETE", &format!("/posts/{}", post["id"]), Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = send_json(&app, "PUT", &format!("/admin/users/{}/role", user_id), Some(&token), json!({ "role": "admin" })).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send_json(&app, "DELETE", &format!("/users/{}", user_id), Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_role_change_revokes_sessions() -> Result<(), Box<dyn std::error::Error>> {
+    let Some((app, pool)) = build_app_with_pool().await? else { return Ok(()) };
+    let (admin_id, admin_token) = user_with_role(&app, &pool, "admin").await?;
+    let (user_id, user_token) = user_with_role(&app, &pool, "user").await?;
+
+    // Un administrateur ne peut pas changer son propre rôle
+    let (status, _) = send_json(&app, "PUT", &format!("/admin/users/{}/role", admin_id), Some(&admin_token), json!({ "role": "user" })).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = send_json(&app, "PUT", &format!("/admin/users/{}/role", user_id), Some(&admin_token), json!({ "role": "superuser" })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, updated) = send_json(&app, "PUT", &format!("/admin/users/{}/role", user_id), Some(&admin_token), json!({ "role": "moderator" })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["role"], "moderator");
+
+    // L'ancien token (rôle "user") n'est plus accepté
+    let (status, _) = send_json(&app, "GET", "/auth/session", Some(&user_token), json!({})).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = send_json(&app, "DELETE", "/users/99999", Some(&admin_token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send_json(&app, "DELETE", &format!("/admin/users/{}", user_id), Some(&admin_token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_json(&app, "GET", &format!("/users/{}", user_id), None, json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
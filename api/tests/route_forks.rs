@@ -0,0 +1,102 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+use common::{build_app, send_json, new_user};
+
+#[tokio::test]
+async fn fork_copies_public_routes_with_attribution() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let author = new_user(&app, "fork_author").await?;
+    let forker = new_user(&app, "fork_forker").await?;
+    let other = new_user(&app, "fork_other").await?;
+
+    let (status, source) = send_json(&app, "POST", "/routes", Some(&author.token), json!({
+        "name": "Tour du lac",
+        "description": "Boucle de 3 km",
+        "is_public": true,
+        "path_data": [[6.14, 46.20], [6.15, 46.21], [6.16, 46.20]]
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let source_id = source["id"].as_i64().unwrap();
+    assert!(source["forked_from"].is_null());
+
+    // La copie reprend la révision courante du tracé
+    let (status, _) = send_json(&app, "PUT", &format!("/routes/{}", source_id), Some(&author.token), json!({
+        "path_data": [[6.14, 46.20], [6.15, 46.22], [6.16, 46.20]]
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, fork) = send_json(&app, "POST", &format!("/routes/{}/fork", source_id), Some(&forker.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK, "{}", fork);
+    assert_ne!(fork["id"], source["id"]);
+    assert_ne!(fork["user_id"], source["user_id"]);
+    assert_eq!(fork["name"], "Tour du lac");
+    assert_eq!(fork["description"], "Boucle de 3 km");
+    assert_eq!(fork["is_public"], false);
+    assert_eq!(fork["path_data"], json!([[6.14, 46.20], [6.15, 46.22], [6.16, 46.20]]));
+    assert_eq!(fork["current_version"], 1);
+    assert_eq!(fork["forked_from"], source_id);
+    assert_eq!(fork["forked_from_version"], 2);
+    assert_eq!(fork["forked_from_user_id"], source["user_id"]);
+
+    // La copie appartient à l'utilisateur qui l'a faite
+    let (status, _) = send_json(&app, "PUT", &format!("/routes/{}", fork["id"]), Some(&forker.token), json!({
+        "name": "Mon tour du lac"
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, public_fork) = send_json(&app, "POST", &format!("/routes/{}/fork?name=Variante&is_public=true", source_id), Some(&other.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(public_fork["name"], "Variante");
+    assert_eq!(public_fork["is_public"], true);
+
+    // Les copies privées des autres n'apparaissent pas
+    let forks_uri = format!("/routes/{}/forks", source_id);
+    let fork_ids = |body: &serde_json::Value| -> Vec<serde_json::Value> {
+        body["items"].as_array().unwrap().iter().map(|r| r["id"].clone()).collect()
+    };
+    let (status, listed) = send_json(&app, "GET", &forks_uri, Some(&author.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK, "{}", listed);
+    assert_eq!(fork_ids(&listed), vec![public_fork["id"].clone()]);
+    let (_, listed) = send_json(&app, "GET", &forks_uri, Some(&forker.token), json!({})).await?;
+    assert_eq!(fork_ids(&listed), vec![public_fork["id"].clone(), fork["id"].clone()]);
+
+    let (status, _) = send_json(&app, "GET", "/routes/999999999/forks", Some(&author.token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send_json(&app, "POST", "/routes/999999999/fork", Some(&forker.token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Supprimer la source garde l'attribution à son auteur
+    let (status, _) = send_json(&app, "DELETE", &format!("/routes/{}", source_id), Some(&author.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (_, kept) = send_json(&app, "GET", &format!("/routes/{}", fork["id"]), Some(&forker.token), json!({})).await?;
+    assert!(kept["forked_from"].is_null());
+    assert_eq!(kept["forked_from_user_id"], source["user_id"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn private_routes_can_only_be_forked_by_their_owner() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let owner = new_user(&app, "fork_private_owner").await?;
+    let stranger = new_user(&app, "fork_private_stranger").await?;
+
+    let (_, route) = send_json(&app, "POST", "/routes", Some(&owner.token), json!({
+        "name": "Secret",
+        "is_public": false,
+        "path_data": [[2.35, 48.85], [2.36, 48.86]]
+    })).await?;
+    let uri = format!("/routes/{}/fork", route["id"]);
+
+    let (status, _) = send_json(&app, "POST", &uri, Some(&stranger.token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, copy) = send_json(&app, "POST", &uri, Some(&owner.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(copy["forked_from"], route["id"]);
+
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use rust_rmce_api::geometry::{self, Position};
+use rust_rmce_api::route_formats::{self, ExportedRoute, RouteFormat};
+use tower::ServiceExt;
+
+mod common;
+
+use common::{build_app, new_user};
+
+const SAMPLE_GPX: &str = include_str!("fixtures/sample.gpx");
+const SAMPLE_KML: &str = include_str!("fixtures/sample.kml");
+const SAMPLE_TCX: &str = include_str!("fixtures/sample.tcx");
+
+const FORMATS: [RouteFormat; 4] = [RouteFormat::Gpx, RouteFormat::Kml, RouteFormat::Tcx, RouteFormat::Geojson];
+
+async fn send_raw(
+    app: &axum::Router,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    content_type: &str,
+    body: Vec<u8>,
+) -> Result<(StatusCode, Option<String>, String), Box<dyn std::error::Error>> {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", content_type);
+    if let Some(t) = token {
+        builder = builder.header("Authorization", format!("Bearer {}", t));
+    }
+    let request = builder.body(Body::from(body))?;
+
+    let response = app.clone().oneshot(request).await?;
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok((status, content_type, String::from_utf8(body_bytes.to_vec())?))
+}
+
+fn assert_same_positions(actual: &[Position], expected: &[Position]) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a.lon - e.lon).abs() < 1e-9 && (a.lat - e.lat).abs() < 1e-9, "{:?} != {:?}", a, e);
+        match (a.ele, e.ele) {
+            (Some(a), Some(e)) => assert!((a - e).abs() < 1e-6, "elevation {} != {}", a, e),
+            (a, e) => assert_eq!(a, e),
+        }
+    }
+}
+
+/// Réimporte un export ; le GeoJSON passe par `path_data` comme le ferait `POST /routes`.
+fn reimport(content: &str, format: RouteFormat) -> Vec<Position> {
+    match format {
+        RouteFormat::Geojson => geometry::parse_path(&serde_json::from_str(content).unwrap()).unwrap(),
+        _ => {
+            let imported = route_formats::import(content).unwrap();
+            assert_eq!(imported.format, format);
+            imported.positions
+        }
+    }
+}
+
+#[test]
+fn sample_files_are_parsed() {
+    let gpx = route_formats::import(SAMPLE_GPX).unwrap();
+    assert_eq!(gpx.format, RouteFormat::Gpx);
+    assert_eq!(gpx.name.as_deref(), Some("Montée du col"));
+    assert_eq!(gpx.description.as_deref(), Some("Boucle d'essai avec altitude"));
+    // Les deux segments sont mis bout à bout
+    assert_eq!(gpx.positions.len(), 5);
+    assert_eq!(gpx.positions[0], Position { lon: 6.8652, lat: 45.8326, ele: Some(1035.0) });
+
+    let kml = route_formats::import(SAMPLE_KML).unwrap();
+    assert_eq!(kml.format, RouteFormat::Kml);
+    assert_eq!(kml.name.as_deref(), Some("Quai Magellan"));
+    assert_eq!(kml.positions.len(), 4);
+    assert_eq!(kml.positions[3], Position { lon: -1.5398, lat: 47.2119, ele: Some(12.0) });
+
+    let tcx = route_formats::import(SAMPLE_TCX).unwrap();
+    assert_eq!(tcx.format, RouteFormat::Tcx);
+    assert_eq!(tcx.name.as_deref(), Some("Tour du lac"));
+    assert_eq!(tcx.description.as_deref(), Some("Rive ouest"));
+    // Le point sans position est ignoré
+    assert_eq!(tcx.positions.len(), 3);
+    assert_eq!(tcx.positions[2].ele, Some(452.0));
+}
+
+#[test]
+fn exports_round_trip_coordinates_and_elevation() {
+    for sample in [SAMPLE_GPX, SAMPLE_KML, SAMPLE_TCX] {
+        let imported = route_formats::import(sample).unwrap();
+        let route = ExportedRoute {
+            name: "Parcours <test> & co",
+            description: Some("Export \"complet\""),
+            distance_meters: None,
+            positions: &imported.positions,
+        };
+
+        for format in FORMATS {
+            let exported = route_formats::export(&route, format);
+            assert_same_positions(&reimport(&exported, format), &imported.positions);
+        }
+    }
+}
+
+#[test]
+fn positions_without_elevation_stay_without_elevation() {
+    let positions = [
+        Position { lon: 2.3522, lat: 48.8566, ele: None },
+        Position { lon: 2.2945, lat: 48.8584, ele: None },
+    ];
+    let route = ExportedRoute { name: "Paris", description: None, distance_meters: Some(4_300.0), positions: &positions };
+
+    for format in FORMATS {
+        let exported = route_formats::export(&route, format);
+        assert_same_positions(&reimport(&exported, format), &positions);
+    }
+    assert!(!route_formats::export(&route, RouteFormat::Kml).contains("altitudeMode"));
+}
+
+#[test]
+fn invalid_files_are_rejected() {
+    assert!(route_formats::import("not xml").is_err());
+    assert!(route_formats::import("<html><body/></html>").is_err());
+    // Un seul point ne forme pas un tracé
+    assert!(route_formats::import(r#"<gpx><trk><trkseg><trkpt lat="45" lon="6"/></trkseg></trk></gpx>"#).is_err());
+    assert!(route_formats::import(r#"<gpx><trk><trkseg><trkpt lat="95" lon="6"/><trkpt lat="45" lon="6"/></trkseg></trk></gpx>"#).is_err());
+    // Les entités externes ne sont jamais résolues
+    let xxe = r#"<?xml version="1.0"?><!DOCTYPE gpx [<!ENTITY x SYSTEM "file:///etc/passwd">]><gpx><trk><name>&x;</name></trk></gpx>"#;
+    assert!(route_formats::import(xxe).is_err());
+}
+
+#[tokio::test]
+async fn import_then_export_through_the_api() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let token = new_user(&app, "formats_user").await?.token;
+
+    let (status, _, body) = send_raw(&app, "POST", "/routes/import", Some(&token), "application/gpx+xml", SAMPLE_GPX.as_bytes().to_vec()).await?;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let route: serde_json::Value = serde_json::from_str(&body)?;
+    assert_eq!(route["name"], "Montée du col");
+    assert_eq!(route["is_public"], false);
+    assert_eq!(route["path_data"]["type"], "LineString");
+    assert!(route["distance_meters"].as_f64().unwrap() > 0.0);
+    assert!(route["elevation_gain_meters"].as_f64().unwrap() > 0.0);
+    let id = route["id"].as_i64().unwrap();
+
+    let expected = route_formats::import(SAMPLE_GPX)?.positions;
+    for (format, content_type) in [
+        ("gpx", "application/gpx+xml"),
+        ("kml", "application/vnd.google-earth.kml+xml"),
+        ("tcx", "application/vnd.garmin.tcx+xml"),
+        ("geojson", "application/geo+json"),
+    ] {
+        let uri = format!("/routes/{}/export?format={}", id, format);
+        let (status, actual_type, body) = send_raw(&app, "GET", &uri, Some(&token), "text/plain", Vec::new()).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(actual_type.as_deref(), Some(content_type));
+        assert_same_positions(&reimport(&body, format.parse()?), &expected);
+    }
+
+    // Les paramètres priment sur le nom du fichier
+    let (status, _, body) = send_raw(&app, "POST", "/routes/import?name=Lac&is_public=true", Some(&token), "application/xml", SAMPLE_TCX.as_bytes().to_vec()).await?;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let route: serde_json::Value = serde_json::from_str(&body)?;
+    assert_eq!(route["name"], "Lac");
+    assert_eq!(route["is_public"], true);
+    assert_eq!(route["description"], "Rive ouest");
+
+    let (status, _, body) = send_raw(&app, "POST", "/routes/import", Some(&token), "application/xml", b"<gpx></gpx>".to_vec()).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let error: serde_json::Value = serde_json::from_str(&body)?;
+    assert!(error["details"]["file"].is_array());
+
+    let uri = format!("/routes/{}/export?format=shp", id);
+    let (status, _, _) = send_raw(&app, "GET", &uri, Some(&token), "text/plain", Vec::new()).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, _, _) = send_raw(&app, "GET", "/routes/999999999/export", Some(&token), "text/plain", Vec::new()).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
@@ -0,0 +1,214 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+use common::{build_app, send_json, new_user};
+
+fn assert_close(actual: &serde_json::Value, expected: f64, tolerance: f64) {
+    let actual = actual.as_f64().unwrap_or_else(|| panic!("{} is not a number", actual));
+    assert!((actual - expected).abs() <= tolerance, "expected {} ± {}, got {}", expected, tolerance, actual);
+}
+
+#[tokio::test]
+async fn derived_geometry_is_computed_by_the_server() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let token = new_user(&app, "geometry_user").await?.token;
+
+    // Un degré de latitude le long du méridien de Greenwich, avec dénivelé
+    let (status, route) = send_json(&app, "POST", "/routes", Some(&token), json!({
+        "name": "Méridien",
+        "is_public": true,
+        "path_data": { "type": "LineString", "coordinates": [[0.0, 45.0, 100.0], [0.0, 45.5, 250.0], [0.0, 46.0, 200.0]] },
+        "distance_meters": 42.0
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", route);
+    assert_close(&route["distance_meters"], 111_195.0, 1.0);
+    assert_close(&route["min_lat"], 45.0, 1e-9);
+    assert_close(&route["max_lat"], 46.0, 1e-9);
+    assert_close(&route["min_lon"], 0.0, 1e-9);
+    assert_close(&route["start_lat"], 45.0, 1e-9);
+    assert_close(&route["end_lat"], 46.0, 1e-9);
+    assert_close(&route["elevation_gain_meters"], 150.0, 1e-3);
+    assert_close(&route["elevation_loss_meters"], 50.0, 1e-3);
+
+    // Un nouveau tracé recalcule toutes les valeurs dérivées
+    let (status, updated) = send_json(&app, "PUT", &format!("/routes/{}", route["id"]), Some(&token), json!({
+        "path_data": [[10.0, 0.0], [11.0, 0.0]]
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", updated);
+    assert_close(&updated["distance_meters"], 111_195.0, 1.0);
+    assert_close(&updated["start_lon"], 10.0, 1e-9);
+    assert_close(&updated["max_lon"], 11.0, 1e-9);
+    assert!(updated["elevation_gain_meters"].is_null());
+    assert_eq!(updated["name"], "Méridien");
+
+    // Renommer sans toucher au tracé conserve la géométrie
+    let (status, renamed) = send_json(&app, "PUT", &format!("/routes/{}", route["id"]), Some(&token), json!({
+        "name": "Équateur"
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(renamed["distance_meters"], updated["distance_meters"]);
+    assert_eq!(renamed["path_data"], updated["path_data"]);
+
+    // Une Feature GeoJSON est acceptée
+    let (status, feature) = send_json(&app, "POST", "/routes", Some(&token), json!({
+        "name": "Feature",
+        "is_public": false,
+        "path_data": {
+            "type": "Feature",
+            "properties": { "source": "app" },
+            "geometry": { "type": "LineString", "coordinates": [[2.35, 48.85], [2.36, 48.85]] }
+        }
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", feature);
+    assert_close(&feature["distance_meters"], 731.0, 5.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_geometry_is_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let token = new_user(&app, "geometry_user").await?.token;
+
+    let invalid = [
+        json!({ "type": "LineString", "coordinates": [[2.35, 48.85]] }),
+        json!({ "type": "LineString", "coordinates": [[2.35, 95.0], [2.36, 48.85]] }),
+        json!({ "type": "LineString", "coordinates": [[2.35, 48.85], [2.35, 48.85]] }),
+        json!({ "type": "LineString", "coordinates": [[2.35, 48.85], ["2.36", 48.85]] }),
+        json!({ "type": "Polygon", "coordinates": [[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [0.0, 0.0]]] }),
+        json!({ "type": "Feature", "geometry": null }),
+        json!([[2.35]]),
+    ];
+    for path_data in invalid {
+        let (status, body) = send_json(&app, "POST", "/routes", Some(&token), json!({
+            "name": "Invalide",
+            "is_public": true,
+            "path_data": path_data
+        })).await?;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{} should be rejected", path_data);
+        assert!(body["details"]["path_data"].is_array(), "{}", body);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn geometry_parameter_controls_path_data() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let token = new_user(&app, "geometry_user").await?.token;
+
+    // Ligne droite échantillonnée tous les ~11 m : la simplification garde les extrémités
+    let coordinates: Vec<serde_json::Value> = (0..=100).map(|i| json!([5.0 + i as f64 * 1e-4, 44.0, 300.0 + i as f64])).collect();
+    let (status, route) = send_json(&app, "POST", "/routes", Some(&token), json!({
+        "name": "Droite",
+        "is_public": true,
+        "path_data": { "type": "LineString", "coordinates": coordinates }
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", route);
+    let id = route["id"].as_i64().unwrap();
+
+    let (_, full) = send_json(&app, "GET", &format!("/routes/{}", id), Some(&token), json!({})).await?;
+    assert_eq!(full["path_data"]["coordinates"].as_array().unwrap().len(), 101);
+    assert!(full.get("polyline").is_none());
+
+    let (status, simplified) = send_json(&app, "GET", &format!("/routes/{}?geometry=simplified", id), Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(simplified["path_data"]["coordinates"], json!([[5.0, 44.0, 300.0], [5.01, 44.0, 400.0]]));
+
+    let (_, polyline) = send_json(&app, "GET", &format!("/routes/{}?geometry=polyline&tolerance_m=5", id), Some(&token), json!({})).await?;
+    assert!(polyline["path_data"].is_null());
+    assert_eq!(polyline["polyline"], "_wpkG_qo]?o}@");
+
+    let (_, none) = send_json(&app, "GET", &format!("/routes/{}?geometry=none", id), Some(&token), json!({})).await?;
+    assert!(none["path_data"].is_null());
+    assert_eq!(none["distance_meters"], full["distance_meters"]);
+
+    // Les listes acceptent le même paramètre
+    let (status, page) = send_json(&app, "GET", "/routes/public?q=Droite&geometry=none&limit=100", Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page["items"].as_array().unwrap().iter().all(|r| r["path_data"].is_null()));
+
+    for query in ["geometry=compressed", "geometry=simplified&tolerance_m=0", "geometry=simplified&tolerance_m=5000"] {
+        let (status, body) = send_json(&app, "GET", &format!("/routes/{}?{}", id, query), Some(&token), json!({})).await?;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", query);
+        assert!(body["details"].is_object());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn elevation_profile_from_path_or_sensor_data() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let token = new_user(&app, "geometry_user").await?.token;
+
+    // ~1,1 km vers le nord, 55 m de montée
+    let (_, route) = send_json(&app, "POST", "/routes", Some(&token), json!({
+        "name": "Côte",
+        "is_public": true,
+        "path_data": [[6.0, 45.0, 200.0], [6.0, 45.005, 230.0], [6.0, 45.01, 255.0]]
+    })).await?;
+    let (status, profile) = send_json(&app, "GET", &format!("/routes/{}/profile", route["id"]), Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK, "{}", profile);
+    assert_eq!(profile["source"], "path_data");
+    assert!(profile["score_id"].is_null());
+    assert_close(&profile["total_ascent_meters"], 55.0, 1e-6);
+    assert_close(&profile["distance_meters"], 1_112.0, 1.0);
+    assert_eq!(profile["points"].as_array().unwrap().len(), 3);
+    assert!(profile["climbs"].is_array());
+
+    // Sans altitude dans le tracé, les relevés d'une course prennent le relais
+    let (_, flat) = send_json(&app, "POST", "/routes", Some(&token), json!({
+        "name": "Sans altitude",
+        "is_public": true,
+        "path_data": [[6.0, 45.0], [6.0, 45.01]]
+    })).await?;
+    let uri = format!("/routes/{}/profile", flat["id"]);
+    let (status, _) = send_json(&app, "GET", &uri, Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Une course rejetée (à 1,5 km du tracé) : ses positions ne sont pas exposées
+    let track = |longitude: f64| -> Vec<serde_json::Value> {
+        (0..=4)
+            .map(|i| json!({
+                "timestamp_offset_ms": i * 75_000,
+                "latitude": 45.0 + i as f64 * 0.0025,
+                "longitude": longitude,
+                "altitude": 300.0 + i as f64 * 10.0
+            }))
+            .chain([json!({ "timestamp_offset_ms": 37_500, "altitude": 999.0 })])
+            .collect()
+    };
+    let mut scores = Vec::new();
+    for (longitude, time, expected) in [(6.02, 310.0, "rejected"), (6.0, 300.0, "verified")] {
+        let (_, score) = send_json(&app, "POST", &format!("/routes/{}/score", flat["id"]), Some(&token), json!({
+            "time_seconds": time
+        })).await?;
+        let (status, upload) = send_json(&app, "POST", "/sensor-data/bulk", Some(&token), json!({
+            "score_id": score["id"],
+            "data": track(longitude)
+        })).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(upload["verification"]["status"], expected, "{}", upload);
+        if expected == "rejected" {
+            let (status, _) = send_json(&app, "GET", &uri, Some(&token), json!({})).await?;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+        }
+        scores.push(score);
+    }
+    let score = &scores[1];
+
+    let (status, profile) = send_json(&app, "GET", &uri, Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK, "{}", profile);
+    assert_eq!(profile["source"], "sensor_data");
+    assert_eq!(profile["score_id"], score["id"]);
+    assert_close(&profile["total_ascent_meters"], 40.0, 1e-3);
+    assert_close(&profile["max_elevation_meters"], 340.0, 1e-3);
+
+    let (status, _) = send_json(&app, "GET", "/routes/999999999/profile", Some(&token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
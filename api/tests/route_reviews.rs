@@ -0,0 +1,121 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+use common::{build_app, send_json, TestUser, new_user};
+
+async fn create_route(app: &axum::Router, user: &TestUser, name: &str, is_public: bool) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let (status, route) = send_json(app, "POST", "/routes", Some(&user.token), json!({
+        "name": name,
+        "is_public": is_public,
+        "path_data": [[5.37, 43.29], [5.38, 43.30]]
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", route);
+    Ok(route)
+}
+
+#[tokio::test]
+async fn one_rating_per_user_with_aggregates_on_route() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let owner = new_user(&app, "review_owner").await?;
+    let alice = new_user(&app, "review_alice").await?;
+    let bob = new_user(&app, "review_bob").await?;
+
+    let route = create_route(&app, &owner, "Calanques", true).await?;
+    assert!(route["rating_average"].is_null());
+    assert_eq!(route["rating_count"], 0);
+    let review_uri = format!("/routes/{}/review", route["id"]);
+    let route_uri = format!("/routes/{}", route["id"]);
+
+    let (status, _) = send_json(&app, "PUT", &review_uri, Some(&owner.token), json!({ "rating": 5 })).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, body) = send_json(&app, "PUT", &review_uri, Some(&alice.token), json!({ "rating": 6 })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["rating"].is_array());
+
+    let (status, review) = send_json(&app, "PUT", &review_uri, Some(&alice.token), json!({
+        "rating": 2,
+        "review": "  Trop de monde  "
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", review);
+    assert_eq!(review["username"], alice.username.as_str());
+    assert_eq!(review["review"], "Trop de monde");
+
+    // Une seconde note du même utilisateur remplace la première
+    let (status, updated) = send_json(&app, "PUT", &review_uri, Some(&alice.token), json!({ "rating": 4, "review": "Superbe au lever du jour" })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["id"], review["id"]);
+    let (status, _) = send_json(&app, "PUT", &review_uri, Some(&bob.token), json!({ "rating": 5, "review": " " })).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, rated) = send_json(&app, "GET", &route_uri, Some(&bob.token), json!({})).await?;
+    assert_eq!(rated["rating_count"], 2);
+    assert_eq!(rated["review_count"], 1);
+    assert_eq!(rated["rating_average"], 4.5);
+
+    let (status, reviews) = send_json(&app, "GET", &format!("{}s?sort=rating", review_uri), Some(&owner.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK, "{}", reviews);
+    let ratings: Vec<i64> = reviews["items"].as_array().unwrap().iter().map(|r| r["rating"].as_i64().unwrap()).collect();
+    assert_eq!(ratings, vec![5, 4]);
+    assert!(reviews["items"][0]["review"].is_null());
+
+    let (status, _) = send_json(&app, "DELETE", &review_uri, Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_json(&app, "DELETE", &review_uri, Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, rated) = send_json(&app, "GET", &route_uri, Some(&bob.token), json!({})).await?;
+    assert_eq!(rated["rating_count"], 1);
+    assert_eq!(rated["rating_average"], 4.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn favorites_are_listed_for_the_current_user() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let owner = new_user(&app, "favorite_owner").await?;
+    let fan = new_user(&app, "favorite_fan").await?;
+
+    let first = create_route(&app, &owner, "Vieux-Port", true).await?;
+    let second = create_route(&app, &owner, "Corniche", true).await?;
+    let private = create_route(&app, &owner, "Jardin", false).await?;
+
+    for route in [&first, &second] {
+        let uri = format!("/routes/{}/favorite", route["id"]);
+        let (status, _) = send_json(&app, "POST", &uri, Some(&fan.token), json!({})).await?;
+        assert_eq!(status, StatusCode::OK);
+    }
+    // Ajouter deux fois ne crée pas de doublon
+    let (status, _) = send_json(&app, "POST", &format!("/routes/{}/favorite", first["id"]), Some(&fan.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_json(&app, "POST", &format!("/routes/{}/favorite", private["id"]), Some(&fan.token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send_json(&app, "POST", "/routes/999999999/favorite", Some(&fan.token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let ids = |body: &serde_json::Value| -> Vec<serde_json::Value> {
+        body["items"].as_array().unwrap().iter().map(|r| r["id"].clone()).collect()
+    };
+    let (status, favorites) = send_json(&app, "GET", "/routes/favorites", Some(&fan.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK, "{}", favorites);
+    assert_eq!(ids(&favorites), vec![second["id"].clone(), first["id"].clone()]);
+
+    // Les favoris d'un autre utilisateur ne sont pas exposés
+    let (_, theirs) = send_json(&app, "GET", "/routes/favorites", Some(&owner.token), json!({})).await?;
+    assert!(ids(&theirs).is_empty());
+
+    // Un parcours redevenu privé disparaît des favoris
+    let (status, _) = send_json(&app, "PUT", &format!("/routes/{}", first["id"]), Some(&owner.token), json!({ "is_public": false })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (_, favorites) = send_json(&app, "GET", "/routes/favorites", Some(&fan.token), json!({})).await?;
+    assert_eq!(ids(&favorites), vec![second["id"].clone()]);
+
+    let uri = format!("/routes/{}/favorite", second["id"]);
+    let (status, _) = send_json(&app, "DELETE", &uri, Some(&fan.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_json(&app, "DELETE", &uri, Some(&fan.token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
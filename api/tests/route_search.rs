@@ -0,0 +1,144 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+use common::{build_app, send_json, new_user};
+
+/// Mot absent des autres parcours de la base, pour isoler chaque exécution.
+fn unique_word(prefix: &str) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    format!("{}{}", prefix, now)
+}
+
+fn names(body: &serde_json::Value) -> Vec<String> {
+    body["items"]
+        .as_array()
+        .unwrap_or_else(|| panic!("expected search results, got {}", body))
+        .iter()
+        .map(|r| r["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+fn facet(body: &serde_json::Value, name: &str) -> Vec<(String, i64)> {
+    body["facets"][name]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| (f["value"].as_str().unwrap().to_string(), f["count"].as_i64().unwrap()))
+        .collect()
+}
+
+#[tokio::test]
+async fn route_metadata_is_validated_and_normalized() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let user = new_user(&app, "meta_user").await?;
+    let path = json!([[2.35, 48.85], [2.36, 48.86]]);
+
+    let (status, body) = send_json(&app, "POST", "/routes", Some(&user.token), json!({
+        "name": "Invalide",
+        "is_public": true,
+        "path_data": path,
+        "tags": ["ok", "pas bon!"],
+        "surface": "lava",
+        "difficulty": "extreme"
+    })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["tags[1]"].is_array(), "{}", body);
+    assert!(body["details"]["surface"].is_array());
+    assert!(body["details"]["difficulty"].is_array());
+
+    let (status, route) = send_json(&app, "POST", "/routes", Some(&user.token), json!({
+        "name": "Normalisé",
+        "is_public": true,
+        "path_data": path,
+        "tags": [" Trail", "trail", "Bord-de-Mer"],
+        "surface": "gravel",
+        "is_loop": true
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", route);
+    assert_eq!(route["tags"], json!(["trail", "bord-de-mer"]));
+    assert_eq!(route["surface"], "gravel");
+    assert!(route["difficulty"].is_null());
+    assert_eq!(route["is_loop"], true);
+    assert_eq!(route["is_out_and_back"], false);
+
+    let (status, updated) = send_json(&app, "PUT", &format!("/routes/{}", route["id"]), Some(&user.token), json!({
+        "tags": ["urban"],
+        "difficulty": "moderate"
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["tags"], json!(["urban"]));
+    assert_eq!(updated["surface"], "gravel");
+    assert_eq!(updated["difficulty"], "moderate");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_combines_full_text_facets_and_visibility() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let author = new_user(&app, "search_author").await?;
+    let searcher = new_user(&app, "search_searcher").await?;
+    let word = unique_word("w");
+    let tag = unique_word("t");
+    let path = json!([[4.83, 45.76], [4.84, 45.77]]);
+
+    for (token, name, description, is_public, tags, surface, difficulty, is_loop) in [
+        (&author.token, format!("{} forest run", word), "Shaded", true, json!(["Trail", tag]), "dirt", "hard", true),
+        (&author.token, format!("{} city", word), "Along the forest edge", true, json!([tag, "urban"]), "asphalt", "easy", false),
+        (&author.token, format!("{} private", word), "Forest", false, json!([tag]), "dirt", "easy", false),
+    ] {
+        let (status, body) = send_json(&app, "POST", "/routes", Some(token), json!({
+            "name": name,
+            "description": description,
+            "is_public": is_public,
+            "path_data": path,
+            "tags": tags,
+            "surface": surface,
+            "difficulty": difficulty,
+            "is_loop": is_loop
+        })).await?;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+
+    let (status, all) = send_json(&app, "GET", &format!("/routes/search?tags={}", tag), Some(&searcher.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK, "{}", all);
+    assert_eq!(all["total"], 2);
+    assert_eq!(facet(&all, "tags"), vec![(tag.clone(), 2), ("trail".to_string(), 1), ("urban".to_string(), 1)]);
+    assert_eq!(facet(&all, "difficulty"), vec![("easy".to_string(), 1), ("hard".to_string(), 1)]);
+    assert_eq!(facet(&all, "surface"), vec![("asphalt".to_string(), 1), ("dirt".to_string(), 1)]);
+
+    // Le propriétaire voit aussi son parcours privé
+    let (_, own) = send_json(&app, "GET", &format!("/routes/search?tags={}", tag), Some(&author.token), json!({})).await?;
+    assert_eq!(own["total"], 3);
+
+    // Le nom pèse plus que la description
+    let (_, ranked) = send_json(&app, "GET", &format!("/routes/search?q={}%20forest", word), Some(&searcher.token), json!({})).await?;
+    assert_eq!(names(&ranked), vec![format!("{} forest run", word), format!("{} city", word)]);
+    assert!(ranked["items"][0]["rank"].as_f64().unwrap() > ranked["items"][1]["rank"].as_f64().unwrap());
+
+    let (_, trail) = send_json(&app, "GET", &format!("/routes/search?tags={},TRAIL", tag), Some(&searcher.token), json!({})).await?;
+    assert_eq!(names(&trail), vec![format!("{} forest run", word)]);
+
+    let (_, easy) = send_json(&app, "GET", &format!("/routes/search?tags={}&difficulty=easy,moderate", tag), Some(&searcher.token), json!({})).await?;
+    assert_eq!(names(&easy), vec![format!("{} city", word)]);
+
+    let (_, loops) = send_json(&app, "GET", &format!("/routes/search?q={}&is_loop=true&surface=dirt", word), Some(&searcher.token), json!({})).await?;
+    assert_eq!(names(&loops), vec![format!("{} forest run", word)]);
+
+    let (_, limited) = send_json(&app, "GET", &format!("/routes/search?tags={}&limit=1&geometry=none", tag), Some(&searcher.token), json!({})).await?;
+    assert_eq!(limited["items"].as_array().unwrap().len(), 1);
+    assert_eq!(limited["total"], 2);
+    assert!(limited["items"][0]["path_data"].is_null());
+
+    let (status, body) = send_json(&app, "GET", "/routes/search?difficulty=impossible&tags=pas%20bon", Some(&searcher.token), json!({})).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["difficulty"].is_array());
+    assert!(body["details"]["tags"].is_array());
+
+    Ok(())
+}
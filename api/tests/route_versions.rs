@@ -0,0 +1,126 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+use common::{build_app, send_json, TestUser, new_user};
+
+/// Relevés GPS le long de `path` (`[[lon, lat], ...]`), un tous les 0,0001° environ.
+fn track_along(path: &serde_json::Value) -> Vec<serde_json::Value> {
+    let coordinates: Vec<(f64, f64)> = path
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| (c[0].as_f64().unwrap(), c[1].as_f64().unwrap()))
+        .collect();
+    let mut points = vec![coordinates[0]];
+    for w in coordinates.windows(2) {
+        let steps = (((w[1].0 - w[0].0).abs().max((w[1].1 - w[0].1).abs())) / 0.0001).ceil() as usize;
+        for k in 1..=steps {
+            let t = k as f64 / steps as f64;
+            points.push((w[0].0 + (w[1].0 - w[0].0) * t, w[0].1 + (w[1].1 - w[0].1) * t));
+        }
+    }
+    points
+        .iter()
+        .enumerate()
+        // Un peu de gigue, comme un vrai GPS : des intervalles tous égaux sont signalés
+        .map(|(i, (lon, lat))| json!({ "timestamp_offset_ms": i * 1000 + i % 7 * 13, "latitude": lat, "longitude": lon }))
+        .collect()
+}
+
+/// Score suivi de sa trace GPS le long de `path`, pour qu'il soit vérifié et classé.
+async fn submit(app: &axum::Router, user: &TestUser, route_id: &serde_json::Value, path: &serde_json::Value, time: f64) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let (status, score) = send_json(app, "POST", &format!("/routes/{}/score", route_id), Some(&user.token), json!({
+        "time_seconds": time
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", score);
+    let (status, upload) = send_json(app, "POST", "/sensor-data/bulk", Some(&user.token), json!({
+        "score_id": score["id"],
+        "data": track_along(path)
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", upload);
+    assert_eq!(upload["verification"]["status"], "verified", "{}", upload);
+    Ok(score)
+}
+
+fn leaderboard_users(body: &serde_json::Value) -> Vec<(String, i64)> {
+    body.as_array()
+        .unwrap_or_else(|| panic!("expected an array, got {}", body))
+        .iter()
+        .map(|e| (e["username"].as_str().unwrap().to_string(), e["route_version"].as_i64().unwrap()))
+        .collect()
+}
+
+#[tokio::test]
+async fn path_changes_create_revisions_and_scope_leaderboards() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let alice = new_user(&app, "version_alice").await?;
+    let bob = new_user(&app, "version_bob").await?;
+
+    let first_path = json!([[4.80, 45.75], [4.81, 45.75]]);
+    let (status, route) = send_json(&app, "POST", "/routes", Some(&alice.token), json!({
+        "name": "Quais",
+        "is_public": true,
+        "path_data": first_path
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(route["current_version"], 1);
+    let id = route["id"].clone();
+
+    let alice_v1 = submit(&app, &alice, &id, &first_path, 120.0).await?;
+    assert_eq!(alice_v1["route_version"], 1);
+
+    // Renommer ou renvoyer le même tracé ne crée pas de révision
+    let (status, renamed) = send_json(&app, "PUT", &format!("/routes/{}", id), Some(&alice.token), json!({
+        "name": "Quais de Saône",
+        "path_data": first_path
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(renamed["current_version"], 1);
+
+    let second_path = json!([[4.80, 45.75], [4.80, 45.76], [4.81, 45.76]]);
+    let (status, updated) = send_json(&app, "PUT", &format!("/routes/{}", id), Some(&alice.token), json!({
+        "path_data": second_path
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["current_version"], 2);
+
+    let bob_v2 = submit(&app, &bob, &id, &second_path, 450.0).await?;
+    assert_eq!(bob_v2["route_version"], 2);
+
+    let (status, versions) = send_json(&app, "GET", &format!("/routes/{}/versions", id), Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let versions = versions.as_array().unwrap();
+    assert_eq!(versions.len(), 2);
+    assert_eq!(versions[0]["version"], 2);
+    assert_eq!(versions[0]["score_count"], 1);
+    assert!(versions[0].get("path_data").is_none());
+    assert!(versions[1]["distance_meters"].as_f64().unwrap() < versions[0]["distance_meters"].as_f64().unwrap());
+
+    // L'ancienne révision garde son tracé
+    let (status, v1) = send_json(&app, "GET", &format!("/routes/{}/versions/1", id), Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(v1["path_data"], first_path);
+    let (status, _) = send_json(&app, "GET", &format!("/routes/{}/versions/9", id), Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let uri = format!("/api/leaderboard/route/{}", id);
+    let (status, current) = send_json(&app, "GET", &uri, Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(leaderboard_users(&current), vec![(bob.username.clone(), 2)]);
+
+    let (_, all) = send_json(&app, "GET", &format!("{}?version=all", uri), Some(&bob.token), json!({})).await?;
+    assert_eq!(leaderboard_users(&all), vec![(alice.username.clone(), 1), (bob.username.clone(), 2)]);
+
+    let (_, first) = send_json(&app, "GET", &format!("{}?version=1", uri), Some(&bob.token), json!({})).await?;
+    assert_eq!(leaderboard_users(&first), vec![(alice.username.clone(), 1)]);
+
+    let (status, _) = send_json(&app, "GET", &format!("{}?version=latest", uri), Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, _) = send_json(&app, "GET", "/routes/999999999/versions", Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
@@ -0,0 +1,216 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+use common::{build_app, send_json, TestUser, new_user};
+
+async fn create_route(app: &axum::Router, user: &TestUser, visibility: &str) -> Result<i64, Box<dyn std::error::Error>> {
+    let (status, route) = send_json(app, "POST", "/routes", Some(&user.token), json!({
+        "name": format!("Parcours {}", visibility),
+        "visibility": visibility,
+        "path_data": [[-1.55, 47.21], [-1.54, 47.22]]
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", route);
+    assert_eq!(route["visibility"], visibility);
+    assert_eq!(route["is_public"], visibility == "public");
+    Ok(route["id"].as_i64().unwrap())
+}
+
+async fn befriend(app: &axum::Router, a: &TestUser, b: &TestUser) -> Result<(), Box<dyn std::error::Error>> {
+    let (status, friendship) = send_json(app, "POST", &format!("/friends/add/{}", b.username), Some(&a.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_json(app, "PUT", &format!("/friends/accept/{}", friendship["id"]), Some(&b.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    Ok(())
+}
+
+#[tokio::test]
+async fn visibility_is_enforced_on_route_reads() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let owner = new_user(&app, "vis_owner").await?;
+    let friend = new_user(&app, "vis_friend").await?;
+    let stranger = new_user(&app, "vis_stranger").await?;
+    befriend(&app, &owner, &friend).await?;
+
+    let private = create_route(&app, &owner, "private").await?;
+    let friends = create_route(&app, &owner, "friends").await?;
+    let unlisted = create_route(&app, &owner, "unlisted").await?;
+    let public = create_route(&app, &owner, "public").await?;
+    let owner_id = owner_id(&app, &owner, public).await?;
+
+    // Un parcours non listé ne s'ouvre pas par son identifiant, seulement par lien de partage
+    for (viewer, visible) in [
+        (&owner, vec![private, friends, unlisted, public]),
+        (&friend, vec![friends, public]),
+        (&stranger, vec![public]),
+    ] {
+        for id in [private, friends, unlisted, public] {
+            let (status, _) = send_json(&app, "GET", &format!("/routes/{}", id), Some(&viewer.token), json!({})).await?;
+            let expected = if visible.contains(&id) { StatusCode::OK } else { StatusCode::NOT_FOUND };
+            assert_eq!(status, expected, "{} reading route {}", viewer.username, id);
+        }
+
+        let (status, page) = send_json(&app, "GET", &format!("/routes/user/{}?limit=100", owner_id), Some(&viewer.token), json!({})).await?;
+        assert_eq!(status, StatusCode::OK);
+        let mut listed: Vec<i64> = page["items"].as_array().unwrap().iter().map(|r| r["id"].as_i64().unwrap()).collect();
+        listed.sort();
+        assert_eq!(listed, visible, "{} listing routes", viewer.username);
+    }
+
+    for uri in [
+        format!("/routes/{}/export", private),
+        format!("/routes/{}/versions", private),
+        format!("/routes/{}/versions/1", private),
+        format!("/routes/{}/forks", private),
+        format!("/routes/{}/reviews", private),
+        format!("/api/leaderboard/route/{}", private),
+    ] {
+        let (status, _) = send_json(&app, "GET", &uri, Some(&stranger.token), json!({})).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+    }
+    let (status, _) = send_json(&app, "POST", &format!("/routes/{}/score", friends), Some(&stranger.token), json!({ "time_seconds": 600.0 })).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send_json(&app, "POST", &format!("/routes/{}/score", friends), Some(&friend.token), json!({ "time_seconds": 600.0 })).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // Un parcours réservé aux amis est visible mais pas copiable
+    let (status, _) = send_json(&app, "POST", &format!("/routes/{}/fork", friends), Some(&friend.token), json!({})).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // L'ancien booléen reste accepté, mais pas en contradiction avec `visibility`
+    let (status, updated) = send_json(&app, "PUT", &format!("/routes/{}", public), Some(&owner.token), json!({ "is_public": false })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["visibility"], "private");
+    let (status, body) = send_json(&app, "PUT", &format!("/routes/{}", public), Some(&owner.token), json!({
+        "is_public": true,
+        "visibility": "friends"
+    })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["is_public"].is_array());
+    let (status, body) = send_json(&app, "GET", "/routes?visibility=secret", Some(&owner.token), json!({})).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["visibility"].is_array());
+
+    Ok(())
+}
+
+/// Identifiant de l'utilisateur, lu sur l'un de ses parcours.
+async fn owner_id(app: &axum::Router, user: &TestUser, route_id: i64) -> Result<i64, Box<dyn std::error::Error>> {
+    let (_, route) = send_json(app, "GET", &format!("/routes/{}", route_id), Some(&user.token), json!({})).await?;
+    Ok(route["user_id"].as_i64().unwrap())
+}
+
+#[tokio::test]
+async fn share_links_open_unlisted_routes_until_revoked() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let owner = new_user(&app, "share_owner").await?;
+    let stranger = new_user(&app, "share_stranger").await?;
+
+    let unlisted = create_route(&app, &owner, "unlisted").await?;
+    let public = create_route(&app, &owner, "public").await?;
+    let links_uri = format!("/routes/{}/share-links", unlisted);
+
+    let (status, _) = send_json(&app, "POST", &links_uri, Some(&stranger.token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send_json(&app, "POST", &format!("/routes/{}/share-links", public), Some(&owner.token), json!({})).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, first) = send_json(&app, "POST", &links_uri, Some(&owner.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK, "{}", first);
+    let first_path = first["path"].as_str().unwrap().to_string();
+    assert_eq!(first_path, format!("/shared/routes/{}", first["token"].as_str().unwrap()));
+    let (_, second) = send_json(&app, "POST", &links_uri, Some(&owner.token), json!({})).await?;
+    let second_path = second["path"].as_str().unwrap().to_string();
+
+    // Sans connexion
+    let (status, shared) = send_json(&app, "GET", &first_path, None, json!({})).await?;
+    assert_eq!(status, StatusCode::OK, "{}", shared);
+    assert_eq!(shared["id"], unlisted);
+    let (status, shared) = send_json(&app, "GET", &format!("{}?geometry=none", second_path), None, json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(shared["path_data"].is_null());
+    let (status, _) = send_json(&app, "GET", "/shared/routes/inconnu", None, json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, links) = send_json(&app, "GET", &links_uri, Some(&owner.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(links.as_array().unwrap().len(), 2);
+    assert!(links[0].get("token").is_none());
+
+    let revoke_uri = format!("{}/{}", links_uri, first["id"]);
+    let (status, _) = send_json(&app, "DELETE", &revoke_uri, Some(&owner.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_json(&app, "DELETE", &revoke_uri, Some(&owner.token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send_json(&app, "GET", &first_path, None, json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send_json(&app, "GET", &second_path, None, json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // Le lien suit la visibilité du parcours
+    let route_uri = format!("/routes/{}", unlisted);
+    send_json(&app, "PUT", &route_uri, Some(&owner.token), json!({ "visibility": "private" })).await?;
+    let (status, _) = send_json(&app, "GET", &second_path, None, json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    send_json(&app, "PUT", &route_uri, Some(&owner.token), json!({ "visibility": "unlisted" })).await?;
+    let (status, _) = send_json(&app, "GET", &second_path, None, json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn segments_follow_their_route_visibility() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let owner = new_user(&app, "segvis_owner").await?;
+    let friend = new_user(&app, "segvis_friend").await?;
+    let stranger = new_user(&app, "segvis_stranger").await?;
+    befriend(&app, &owner, &friend).await?;
+
+    let route = create_route(&app, &owner, "public").await?;
+    let (status, segment) = send_json(&app, "POST", "/segments", Some(&owner.token), json!({
+        "route_id": route,
+        "name": "Montée",
+        "start_distance_meters": 100.0,
+        "end_distance_meters": 600.0
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", segment);
+
+    let segment_uri = format!("/segments/{}", segment["id"]);
+    let list_uri = format!("/segments?route_id={}", route);
+    let leaderboard_uri = format!("{}/leaderboard", segment_uri);
+    // (statut de GET /segments/:id, nombre de segments listés, statut du classement)
+    let reads = |user: &TestUser| {
+        let (app, token) = (app.clone(), user.token.clone());
+        let (segment_uri, list_uri, leaderboard_uri) = (segment_uri.clone(), list_uri.clone(), leaderboard_uri.clone());
+        async move {
+            let (segment, _) = send_json(&app, "GET", &segment_uri, Some(&token), json!({})).await?;
+            let (_, list) = send_json(&app, "GET", &list_uri, Some(&token), json!({})).await?;
+            let (leaderboard, _) = send_json(&app, "GET", &leaderboard_uri, Some(&token), json!({})).await?;
+            Ok::<_, Box<dyn std::error::Error>>((segment, list["items"].as_array().unwrap().len(), leaderboard))
+        }
+    };
+
+    assert_eq!(reads(&stranger).await?, (StatusCode::OK, 1, StatusCode::OK));
+
+    let route_uri = format!("/routes/{}", route);
+    send_json(&app, "PUT", &route_uri, Some(&owner.token), json!({ "visibility": "friends" })).await?;
+    assert_eq!(reads(&friend).await?, (StatusCode::OK, 1, StatusCode::OK));
+    assert_eq!(reads(&stranger).await?, (StatusCode::NOT_FOUND, 0, StatusCode::NOT_FOUND));
+
+    send_json(&app, "PUT", &route_uri, Some(&owner.token), json!({ "visibility": "private" })).await?;
+    assert_eq!(reads(&friend).await?, (StatusCode::NOT_FOUND, 0, StatusCode::NOT_FOUND));
+    assert_eq!(reads(&owner).await?, (StatusCode::OK, 1, StatusCode::OK));
+
+    // Un segment ne peut être créé que sur un parcours public
+    let (status, _) = send_json(&app, "POST", "/segments", Some(&owner.token), json!({
+        "route_id": route,
+        "name": "Descente",
+        "start_distance_meters": 100.0,
+        "end_distance_meters": 600.0
+    })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    Ok(())
+}
@@ -1,30 +1,13 @@
-use std::env;
 
 use axum::{
     body::Body,
     http::{Request, StatusCode},
 };
-use dotenvy::dotenv;
-use rust_rmce_api::{db, routes};
-use tower::ServiceExt; 
-
-
-async fn build_app() -> Result<Option<axum::Router>, Box<dyn std::error::Error>> {
-    dotenv().ok();
-
-    let url = match env::var("DATABASE_URL") {
-        Ok(v) => v,
-        Err(_) => {
-            eprintln!("DATABASE_URL non définie, les tests des routes SQLX sont ignorés.");
-            return Ok(None);
-        }
-    };
 
-    let pool = db::create_pool(&url).await?;
-    let app = routes::create_app(pool);
+mod common;
 
-    Ok(Some(app))
-}
+use common::{build_app};
+use tower::ServiceExt; 
 
 #[tokio::test]
 async fn health_route_root_returns_200() -> Result<(), Box<dyn std::error::Error>> {
@@ -64,7 +47,7 @@ async fn get_posts_returns_200() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 #[tokio::test]
-async fn create_post_returns_200() -> Result<(), Box<dyn std::error::Error>> {
+async fn create_post_requires_authentication() -> Result<(), Box<dyn std::error::Error>> {
     let maybe_app = build_app().await?;
     let app = if let Some(app) = maybe_app {
         app
@@ -86,8 +69,8 @@ async fn create_post_returns_200() -> Result<(), Box<dyn std::error::Error>> {
 
     let response = app.clone().oneshot(request).await?;
 
-    // Peut retourner 200 ou 500 selon si l'utilisateur existe
-    assert!(response.status() == StatusCode::OK || response.status() == StatusCode::INTERNAL_SERVER_ERROR);
+    // L'auteur est l'utilisateur connecté
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     Ok(())
 }
 
@@ -110,7 +93,7 @@ async fn get_post_by_id_returns_404_for_invalid_id() -> Result<(), Box<dyn std::
 }
 
 #[tokio::test]
-async fn delete_post_returns_404_for_invalid_id() -> Result<(), Box<dyn std::error::Error>> {
+async fn delete_post_requires_authentication() -> Result<(), Box<dyn std::error::Error>> {
     let maybe_app = build_app().await?;
     let app = if let Some(app) = maybe_app {
         app
@@ -125,7 +108,7 @@ async fn delete_post_returns_404_for_invalid_id() -> Result<(), Box<dyn std::err
 
     let response = app.clone().oneshot(request).await?;
 
-    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     Ok(())
 }
 
@@ -149,7 +132,7 @@ async fn get_users_returns_200() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 #[tokio::test]
-async fn create_user_returns_200() -> Result<(), Box<dyn std::error::Error>> {
+async fn create_user_requires_authentication() -> Result<(), Box<dyn std::error::Error>> {
     let maybe_app = build_app().await?;
     let app = if let Some(app) = maybe_app {
         app
@@ -174,7 +157,7 @@ async fn create_user_returns_200() -> Result<(), Box<dyn std::error::Error>> {
 
     let response = app.clone().oneshot(request).await?;
 
-    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     Ok(())
 }
 
@@ -197,7 +180,7 @@ async fn get_user_by_id_returns_404_for_invalid_id() -> Result<(), Box<dyn std::
 }
 
 #[tokio::test]
-async fn delete_user_returns_404_for_invalid_id() -> Result<(), Box<dyn std::error::Error>> {
+async fn delete_user_requires_authentication() -> Result<(), Box<dyn std::error::Error>> {
     let maybe_app = build_app().await?;
     let app = if let Some(app) = maybe_app {
         app
@@ -212,7 +195,7 @@ async fn delete_user_returns_404_for_invalid_id() -> Result<(), Box<dyn std::err
 
     let response = app.clone().oneshot(request).await?;
 
-    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     Ok(())
 }
 
@@ -356,3 +339,24 @@ async fn login_with_invalid_credentials_returns_401() -> Result<(), Box<dyn std:
     assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     Ok(())
 }
+
+#[tokio::test]
+async fn jwks_route_returns_200() -> Result<(), Box<dyn std::error::Error>> {
+    let maybe_app = build_app().await?;
+    let app = if let Some(app) = maybe_app {
+        app
+    } else {
+        return Ok(());
+    };
+
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri("/.well-known/jwks.json").body(Body::empty())?)
+        .await?;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let jwks: serde_json::Value = serde_json::from_slice(&body_bytes)?;
+    assert!(jwks["keys"].is_array());
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use axum::http::StatusCode;
+use rust_rmce_api::anti_cheat::{self, Rule, Submission};
+use rust_rmce_api::geometry::{Position, EARTH_RADIUS_METERS};
+use rust_rmce_api::score_metrics::{Sample, STANDARD_GRAVITY};
+use serde_json::json;
+
+mod common;
+
+use common::{build_app_with_pool, send_json, user_with_role};
+
+/// Position à `north` mètres au nord de (45°, 6°).
+fn north(meters: f64) -> Position {
+    Position { lon: 6.0, lat: 45.0 + (meters / EARTH_RADIUS_METERS).to_degrees(), ele: None }
+}
+
+/// Course vers le nord à `speed_mps`, un relevé GPS par seconde à quelques millisecondes près.
+fn gps_run(seconds: i32, speed_mps: f64) -> Vec<Sample> {
+    (0..=seconds)
+        .map(|i| Sample { offset_ms: i * 1000 + i % 7 * 13, position: Some(north(i as f64 * speed_mps)), ..Sample::default() })
+        .collect()
+}
+
+fn rules(submission: &Submission, samples: &[Sample]) -> Vec<Rule> {
+    anti_cheat::evaluate(submission, samples).into_iter().map(|f| f.rule).collect()
+}
+
+#[test]
+fn pace_is_compared_to_the_world_record_for_the_distance() {
+    // 1 km en 2 min 11 s 96
+    assert!((anti_cheat::record_speed(1_000.0) - 1_000.0 / 131.96).abs() < 1e-9);
+    assert!(anti_cheat::record_speed(50.0) == anti_cheat::record_speed(100.0));
+    assert!(anti_cheat::record_speed(2_000.0) < anti_cheat::record_speed(1_500.0));
+    assert!(anti_cheat::record_speed(2_000.0) > anti_cheat::record_speed(3_000.0));
+
+    assert!(anti_cheat::impossible_pace(1_000.0, 180.0).is_none());
+    // Juste sous le record, dans la marge
+    assert!(anti_cheat::impossible_pace(1_000.0, 130.0).is_none());
+    let flag = anti_cheat::impossible_pace(10_000.0, 1_200.0).unwrap();
+    assert_eq!(flag.rule, Rule::ImpossiblePace);
+    assert!((flag.details["speed_kmh"].as_f64().unwrap() - 30.0).abs() < 1e-9);
+}
+
+#[test]
+fn gps_teleports_are_flagged_but_not_noise() {
+    let mut samples = gps_run(60, 3.0);
+    assert!(anti_cheat::gps_teleport(&samples).is_none());
+
+    // 30 m de bruit entre deux relevés rapprochés : trop court pour être un saut
+    samples[10].position = Some(north(60.0));
+    assert!(anti_cheat::gps_teleport(&samples).is_none());
+
+    samples[30].position = Some(north(5_000.0));
+    let flag = anti_cheat::gps_teleport(&samples).unwrap();
+    // Aller puis retour
+    assert_eq!(flag.details["count"], 2);
+    assert!(flag.details["max_jump_meters"].as_f64().unwrap() > 4_800.0);
+}
+
+#[test]
+fn speed_needs_accelerometer_motion() {
+    let still = |samples: &mut Vec<Sample>| {
+        for s in samples.iter_mut() {
+            s.accel = Some((0.0, 0.0, STANDARD_GRAVITY));
+        }
+    };
+
+    let mut samples = gps_run(60, 3.0);
+    still(&mut samples);
+    let flag = anti_cheat::speed_without_motion(&samples).unwrap();
+    assert_eq!(flag.details["accel_samples"], 61);
+
+    // Des foulées
+    for (i, s) in samples.iter_mut().enumerate() {
+        s.accel = Some((0.0, 0.0, STANDARD_GRAVITY + if i % 2 == 0 { 4.0 } else { -4.0 }));
+    }
+    assert!(anti_cheat::speed_without_motion(&samples).is_none());
+
+    // À l'arrêt, un téléphone immobile est normal
+    let mut samples = gps_run(60, 0.5);
+    still(&mut samples);
+    assert!(anti_cheat::speed_without_motion(&samples).is_none());
+
+    // Trop peu de relevés pour juger
+    let mut samples = gps_run(10, 3.0);
+    still(&mut samples);
+    assert!(anti_cheat::speed_without_motion(&samples).is_none());
+}
+
+#[test]
+fn perfectly_regular_samples_are_flagged() {
+    assert!(anti_cheat::regular_sampling(&gps_run(60, 3.0)).is_none());
+
+    let generated: Vec<Sample> = (0..=60).map(|i| Sample { offset_ms: i * 1000, ..Sample::default() }).collect();
+    let flag = anti_cheat::regular_sampling(&generated).unwrap();
+    assert_eq!(flag.details["interval_ms"], 1000);
+
+    assert!(anti_cheat::regular_sampling(&generated[..20]).is_none());
+}
+
+#[test]
+fn evaluate_combines_all_rules() {
+    let honest = Submission { time_seconds: 300.0, distance_meters: Some(1_000.0), duplicate_of: None };
+    assert!(rules(&honest, &gps_run(300, 3.3)).is_empty());
+    assert!(rules(&honest, &[]).is_empty());
+
+    let generated: Vec<Sample> = (0..=60).map(|i| Sample { offset_ms: i * 1000, ..Sample::default() }).collect();
+    let cheat = Submission { time_seconds: 60.0, distance_meters: Some(1_000.0), duplicate_of: Some(7) };
+    assert_eq!(rules(&cheat, &generated), vec![Rule::ImpossiblePace, Rule::RegularSampling, Rule::DuplicateSubmission]);
+}
+
+/// Relevés GPS de 0 à 1 km vers le nord, 10 m par seconde.
+fn sensor_data() -> Vec<serde_json::Value> {
+    (0..=100)
+        .map(|i| {
+            let p = north(i as f64 * 10.0);
+            json!({ "timestamp_offset_ms": i * 1000 + i % 7 * 13, "latitude": p.lat, "longitude": p.lon })
+        })
+        .collect()
+}
+
+async fn submit_run(
+    app: &axum::Router,
+    token: &str,
+    route_id: &serde_json::Value,
+    time: f64,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let (status, score) = send_json(app, "POST", &format!("/routes/{}/score", route_id), Some(token), json!({
+        "time_seconds": time
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", score);
+    let (status, upload) = send_json(app, "POST", "/sensor-data/bulk", Some(token), json!({
+        "score_id": score["id"],
+        "data": sensor_data()
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", upload);
+    assert_eq!(upload["verification"]["status"], "verified", "{}", upload);
+    Ok(score)
+}
+
+/// Signalements de la course `score_id` dans la file filtrée par `query`.
+async fn flags_of(
+    app: &axum::Router,
+    token: &str,
+    query: &str,
+    score_id: &serde_json::Value,
+) -> Result<Vec<serde_json::Value>, Box<dyn std::error::Error>> {
+    let (status, page) = send_json(app, "GET", &format!("/admin/score-flags?order=desc&limit=100&{}", query), Some(token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK, "{}", page);
+    Ok(page["items"].as_array().unwrap().iter().filter(|f| &f["score_id"] == score_id).cloned().collect())
+}
+
+async fn leaderboard_ids(app: &axum::Router, token: &str, route_id: &serde_json::Value) -> Result<Vec<i64>, Box<dyn std::error::Error>> {
+    let (status, leaderboard) = send_json(app, "GET", &format!("/api/leaderboard/route/{}", route_id), Some(token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    Ok(leaderboard.as_array().unwrap().iter().map(|e| e["user_id"].as_i64().unwrap()).collect())
+}
+
+#[tokio::test]
+async fn flagged_scores_are_hidden_until_dismissed() -> Result<(), Box<dyn std::error::Error>> {
+    let Some((app, pool)) = build_app_with_pool().await? else { return Ok(()) };
+    let (alice, alice_token) = user_with_role(&app, &pool, "user").await?;
+    let (bob, bob_token) = user_with_role(&app, &pool, "user").await?;
+    let (_, moderator) = user_with_role(&app, &pool, "moderator").await?;
+
+    let (start, finish) = (north(0.0), north(1_000.0));
+    let (status, route) = send_json(&app, "POST", "/routes", Some(&alice_token), json!({
+        "name": "Anti-triche",
+        "visibility": "public",
+        "path_data": [[start.lon, start.lat], [finish.lon, finish.lat]]
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let route_id = route["id"].clone();
+
+    // 1 km en 100 s : bien au-delà du record du monde
+    let cheat = submit_run(&app, &alice_token, &route_id, 100.0).await?;
+    let honest = submit_run(&app, &bob_token, &route_id, 300.0).await?;
+    assert_eq!(leaderboard_ids(&app, &bob_token, &route_id).await?, vec![bob]);
+
+    let flags = flags_of(&app, &moderator, "rule=impossible_pace", &cheat["id"]).await?;
+    assert_eq!(flags.len(), 1);
+    assert_eq!(flags[0]["status"], "open");
+    assert_eq!(flags[0]["user_id"], alice);
+    assert!(flags[0]["details"]["speed_kmh"].as_f64().unwrap() > 35.0);
+    assert!(flags_of(&app, &moderator, "", &honest["id"]).await?.is_empty());
+
+    // La même course envoyée deux fois
+    let (status, again) = send_json(&app, "POST", &format!("/routes/{}/score", route_id), Some(&bob_token), json!({
+        "time_seconds": 300.0
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let flags_again = flags_of(&app, &moderator, "rule=duplicate_submission", &again["id"]).await?;
+    assert_eq!(flags_again.len(), 1);
+    assert_eq!(flags_again[0]["details"]["duplicate_of"], honest["id"]);
+
+    // Réservé aux modérateurs
+    let (status, _) = send_json(&app, "GET", "/admin/score-flags", Some(&bob_token), json!({})).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let uri = format!("/admin/score-flags/{}", flags[0]["id"]);
+    let (status, _) = send_json(&app, "PUT", &uri, Some(&bob_token), json!({ "status": "dismissed" })).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = send_json(&app, "PUT", &uri, Some(&moderator), json!({ "status": "open" })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, _) = send_json(&app, "GET", "/admin/score-flags?status=closed", Some(&moderator), json!({})).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, _) = send_json(&app, "PUT", "/admin/score-flags/999999999", Some(&moderator), json!({ "status": "dismissed" })).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Faux positif : la course revient dans le classement et quitte la file
+    let (status, reviewed) = send_json(&app, "PUT", &uri, Some(&moderator), json!({
+        "status": "dismissed",
+        "note": "Chronométrage officiel vérifié"
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", reviewed);
+    assert_eq!(reviewed["status"], "dismissed");
+    assert!(reviewed["reviewed_by"].is_number());
+    assert!(reviewed["reviewed_at"].is_string());
+    assert_eq!(leaderboard_ids(&app, &bob_token, &route_id).await?, vec![alice, bob]);
+    assert!(flags_of(&app, &moderator, "rule=impossible_pace", &cheat["id"]).await?.is_empty());
+    assert_eq!(flags_of(&app, &moderator, "status=dismissed", &cheat["id"]).await?.len(), 1);
+
+    // Une nouvelle évaluation ne rouvre pas un signalement écarté
+    let (status, _) = send_json(&app, "POST", "/sensor-data/bulk", Some(&alice_token), json!({
+        "score_id": cheat["id"],
+        "data": [{ "timestamp_offset_ms": 100_500 }]
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(leaderboard_ids(&app, &bob_token, &route_id).await?, vec![alice, bob]);
+
+    Ok(())
+}
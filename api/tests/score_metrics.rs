@@ -0,0 +1,237 @@
+use axum::http::StatusCode;
+use rust_rmce_api::geometry::{Position, EARTH_RADIUS_METERS};
+use rust_rmce_api::score_metrics::{self, Sample, ScoreMetrics, STANDARD_GRAVITY};
+use serde_json::json;
+
+mod common;
+
+use common::{build_app, send_json, new_user};
+
+/// Position à `north` mètres au nord de (45°, 6°).
+fn north(meters: f64) -> Position {
+    Position { lon: 6.0, lat: 45.0 + (meters / EARTH_RADIUS_METERS).to_degrees(), ele: None }
+}
+
+/// Un relevé GPS par seconde, vers le nord, aux vitesses données (m/s) à chaque seconde.
+fn gps_run(speeds: &[f64]) -> Vec<Sample> {
+    let mut travelled = 0.0;
+    let mut samples = vec![Sample { offset_ms: 0, position: Some(north(0.0)), ..Sample::default() }];
+    for (i, speed) in speeds.iter().enumerate() {
+        travelled += speed;
+        samples.push(Sample { offset_ms: (i as i32 + 1) * 1000, position: Some(north(travelled)), ..Sample::default() });
+    }
+    samples
+}
+
+fn assert_close(actual: Option<f64>, expected: f64, tolerance: f64) {
+    let actual = actual.unwrap_or_else(|| panic!("expected {}, got None", expected));
+    assert!((actual - expected).abs() < tolerance, "expected {}, got {}", expected, actual);
+}
+
+#[test]
+fn speeds_from_gps() {
+    // 60 s à 3 m/s puis 10 s à 5 m/s
+    let speeds: Vec<f64> = std::iter::repeat_n(3.0, 60).chain(std::iter::repeat_n(5.0, 10)).collect();
+    let metrics = score_metrics::compute(&gps_run(&speeds));
+
+    assert_close(metrics.max_speed_kmh, 18.0, 1e-3);
+    assert_close(metrics.avg_speed_kmh, (180.0 + 50.0) / 70.0 * 3.6, 1e-3);
+    assert_eq!(metrics.max_g_force, None);
+    assert_eq!(metrics.max_sound_db, None);
+}
+
+#[test]
+fn gps_jumps_are_ignored() {
+    let mut samples = gps_run(&[3.0; 30]);
+    // Un relevé à 5 km, une seconde après le précédent
+    samples[10].position = Some(north(5_000.0));
+    let metrics = score_metrics::compute(&samples);
+
+    assert_close(metrics.max_speed_kmh, 10.8, 1e-3);
+    assert_close(metrics.avg_speed_kmh, 10.8, 1e-3);
+
+    // Un pic de vitesse sur une seconde est lissé par la fenêtre de 5 s
+    let mut speeds = vec![3.0; 30];
+    speeds[15] = 13.0;
+    assert_close(score_metrics::compute(&gps_run(&speeds)).max_speed_kmh, (4.0 * 3.0 + 13.0) / 5.0 * 3.6, 1e-3);
+}
+
+#[test]
+fn first_fix_outlier_does_not_drop_the_track() {
+    // Premier fix à 5 km, avant que le GPS ne se cale : la suite de la trace compte
+    let mut samples = gps_run(&[3.0; 30]);
+    samples[0].position = Some(north(5_000.0));
+    let metrics = score_metrics::compute(&samples);
+
+    assert_close(metrics.max_speed_kmh, 10.8, 1e-3);
+    assert_close(metrics.avg_speed_kmh, 10.8, 1e-3);
+
+    // Deux sauts d'affilée ne suffisent pas à remettre en cause un relevé retenu
+    let mut samples = gps_run(&[3.0; 30]);
+    samples[10].position = Some(north(5_000.0));
+    samples[11].position = Some(north(5_003.0));
+    assert_close(score_metrics::compute(&samples).avg_speed_kmh, 10.8, 1e-3);
+}
+
+#[test]
+fn device_speeds_without_gps() {
+    let samples: Vec<Sample> = [10.0, 11.0, 12.0, 80.0, 11.0, 10.0]
+        .iter()
+        .enumerate()
+        .map(|(i, &speed)| Sample { offset_ms: i as i32 * 1000, speed_kmh: Some(speed), ..Sample::default() })
+        .collect();
+    let metrics = score_metrics::compute(&samples);
+
+    // Le pic à 80 km/h disparaît
+    assert_close(metrics.max_speed_kmh, 11.5, 1e-9);
+    assert!(metrics.avg_speed_kmh.unwrap() < 12.0);
+}
+
+#[test]
+fn accelerometer_spikes_are_filtered() {
+    let mut samples: Vec<Sample> = (0..20)
+        .map(|i| Sample { offset_ms: i * 100, accel: Some((0.0, 0.0, STANDARD_GRAVITY)), ..Sample::default() })
+        .collect();
+    samples[7].accel = Some((0.0, 0.0, 15.0 * STANDARD_GRAVITY));
+    samples[12].accel = Some((0.0, 1.5 * STANDARD_GRAVITY, 0.0));
+    samples[13].accel = Some((0.0, 1.5 * STANDARD_GRAVITY, 0.0));
+    samples[14].accel = Some((0.0, 1.5 * STANDARD_GRAVITY, 0.0));
+    // Sans accéléromètre, la valeur g_force du relevé est reprise
+    samples.push(Sample { offset_ms: 2_000, g_force: Some(1.0), ..Sample::default() });
+    let metrics = score_metrics::compute(&samples);
+
+    // Le choc isolé disparaît, les 3 relevés consécutifs à 1,5 g restent
+    assert_close(metrics.max_g_force, 1.5, 1e-9);
+
+    assert_eq!(score_metrics::despike(&[1.0, 9.0, 1.0, 1.0]), vec![1.0, 1.0, 1.0, 1.0]);
+    assert!(score_metrics::despike(&[]).is_empty());
+}
+
+#[test]
+fn discrepancies_use_absolute_and_relative_tolerances() {
+    let server = ScoreMetrics {
+        max_speed_kmh: Some(20.0),
+        avg_speed_kmh: Some(10.0),
+        max_g_force: Some(1.0),
+        max_inclination_degrees: Some(8.0),
+        max_sound_db: None,
+    };
+    let client = ScoreMetrics {
+        // 15 % de 20 : 3 km/h tolérés
+        max_speed_kmh: Some(22.9),
+        avg_speed_kmh: Some(11.5),
+        max_g_force: Some(1.2),
+        max_inclination_degrees: Some(20.0),
+        max_sound_db: Some(90.0),
+    };
+
+    let flagged: Vec<&str> = score_metrics::discrepancies(&client, &server).iter().map(|d| d.metric).collect();
+    assert_eq!(flagged, vec!["avg_speed_kmh", "max_inclination_degrees"]);
+    assert!(score_metrics::discrepancies(&server, &server).is_empty());
+}
+
+#[tokio::test]
+async fn server_metrics_are_stored_next_to_client_ones() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let user = new_user(&app, "metrics_user").await?;
+
+    let (status, route) = send_json(&app, "POST", "/routes", Some(&user.token), json!({
+        "name": "Métriques",
+        "path_data": [[6.0, 45.0], [6.0, 45.01]]
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // Le client annonce 40 km/h de pointe ; la trace montre 10,8 km/h tout du long
+    let (status, score) = send_json(&app, "POST", &format!("/routes/{}/score", route["id"]), Some(&user.token), json!({
+        "time_seconds": 100.0,
+        "max_speed_kmh": 40.0,
+        "avg_speed_kmh": 10.8,
+        "max_sound_db": 70.0
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(score["server_max_speed_kmh"].is_null());
+    assert_eq!(score["metric_discrepancies"], json!([]));
+
+    let data: Vec<serde_json::Value> = gps_run(&[3.0; 100])
+        .iter()
+        .map(|s| {
+            let p = s.position.unwrap();
+            json!({ "timestamp_offset_ms": s.offset_ms, "latitude": p.lat, "longitude": p.lon, "sound_db": 70.0 })
+        })
+        .collect();
+    let (status, upload) = send_json(&app, "POST", "/sensor-data/bulk", Some(&user.token), json!({
+        "score_id": score["id"],
+        "data": data
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", upload);
+
+    let metrics = &upload["metrics"];
+    let max_speed = metrics["server"]["max_speed_kmh"].as_f64().unwrap();
+    // Coordonnées stockées en REAL : quelques décimètres d'arrondi
+    assert!((max_speed - 10.8).abs() < 1.0, "{}", max_speed);
+    assert_eq!(metrics["server"]["max_sound_db"], 70.0);
+    assert!(metrics["server"]["max_g_force"].is_null());
+    let flagged: Vec<&str> = metrics["discrepancies"].as_array().unwrap().iter().map(|d| d["metric"].as_str().unwrap()).collect();
+    assert_eq!(flagged, vec!["max_speed_kmh"]);
+    assert_eq!(metrics["discrepancies"][0]["client"], 40.0);
+
+    let (status, _) = send_json(&app, "POST", "/sensor-data/bulk", Some(&user.token), json!({
+        "score_id": 999_999_999,
+        "data": [{ "timestamp_offset_ms": 0 }]
+    })).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn speed_leaderboard_ranks_server_speeds() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let honest = new_user(&app, "metrics_honest").await?;
+    let liar = new_user(&app, "metrics_liar").await?;
+
+    let end = north(1_000.0);
+    let (status, route) = send_json(&app, "POST", "/routes", Some(&honest.token), json!({
+        "name": "Vitesse",
+        "is_public": true,
+        "path_data": [[6.0, 45.0], [end.lon, end.lat]]
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // 1 km à 7 m/s (25,2 km/h) ; le tricheur annonce 290 km/h de pointe pour la même trace
+    let data: Vec<serde_json::Value> = gps_run(&[7.0; 143])
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let p = s.position.unwrap();
+            json!({ "timestamp_offset_ms": s.offset_ms + i as i32 % 7 * 13, "latitude": p.lat, "longitude": p.lon })
+        })
+        .collect();
+    let mut scores = Vec::new();
+    for (user, max_speed) in [(&honest, 25.2), (&liar, 290.0)] {
+        let (status, score) = send_json(&app, "POST", &format!("/routes/{}/score", route["id"]), Some(&user.token), json!({
+            "time_seconds": 143.0,
+            "max_speed_kmh": max_speed
+        })).await?;
+        assert_eq!(status, StatusCode::OK, "{}", score);
+        let (status, upload) = send_json(&app, "POST", "/sensor-data/bulk", Some(&user.token), json!({
+            "score_id": score["id"],
+            "data": data
+        })).await?;
+        assert_eq!(status, StatusCode::OK, "{}", upload);
+        assert_eq!(upload["verification"]["status"], "verified", "{}", upload);
+        scores.push(score);
+    }
+
+    let (status, leaderboard) = send_json(&app, "GET", "/api/leaderboard/global/speed", Some(&honest.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let entries = leaderboard.as_array().unwrap();
+    let speeds: Vec<f64> = entries.iter().map(|e| e["max_speed_kmh"].as_f64().unwrap()).collect();
+    assert!(speeds.windows(2).all(|w| w[0] >= w[1]), "{:?}", speeds);
+    let user_ids: Vec<&serde_json::Value> = entries.iter().map(|e| &e["user_id"]).collect();
+    assert!(!user_ids.contains(&&scores[1]["user_id"]));
+    let entry = entries.iter().find(|e| e["user_id"] == scores[0]["user_id"]).expect("honest runner ranked");
+    assert!((entry["max_speed_kmh"].as_f64().unwrap() - 25.2).abs() < 1.0, "{}", entry);
+
+    Ok(())
+}
@@ -0,0 +1,264 @@
+use axum::http::StatusCode;
+use rust_rmce_api::geometry::{Position, EARTH_RADIUS_METERS};
+use rust_rmce_api::segments::TrackPoint;
+use rust_rmce_api::verification::{self, RejectionReason, VerificationStatus};
+use serde_json::json;
+
+mod common;
+
+use common::{build_app, send_json, TestUser, new_user};
+
+/// Position à `east` et `north` mètres de (45°, 6°).
+fn at(east: f64, north: f64) -> Position {
+    let lat = 45.0 + (north / EARTH_RADIUS_METERS).to_degrees();
+    let lon = 6.0 + (east / (EARTH_RADIUS_METERS * 45f64.to_radians().cos())).to_degrees();
+    Position { lon, lat, ele: None }
+}
+
+/// Trace qui relie les sommets donnés (en mètres), un relevé tous les 10 m et toutes les secondes.
+fn run(waypoints: &[(f64, f64)]) -> Vec<TrackPoint> {
+    let mut points = vec![waypoints[0]];
+    for w in waypoints.windows(2) {
+        let length = ((w[1].0 - w[0].0).powi(2) + (w[1].1 - w[0].1).powi(2)).sqrt();
+        let steps = (length / 10.0).ceil().max(1.0) as usize;
+        for k in 1..=steps {
+            let t = k as f64 / steps as f64;
+            points.push((w[0].0 + (w[1].0 - w[0].0) * t, w[0].1 + (w[1].1 - w[0].1) * t));
+        }
+    }
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, &(east, north))| TrackPoint { offset_ms: i as i32 * 1000, position: at(east, north) })
+        .collect()
+}
+
+fn route(waypoints: &[(f64, f64)]) -> Vec<Position> {
+    waypoints.iter().map(|&(east, north)| at(east, north)).collect()
+}
+
+#[test]
+fn a_run_along_the_route_is_verified() {
+    let line = route(&[(0.0, 0.0), (0.0, 1_000.0), (500.0, 1_000.0)]);
+    // Échauffement avant le départ, et quelques mètres d'écart sur tout le parcours
+    let result = verification::match_route(&line, &run(&[(-300.0, -300.0), (8.0, 0.0), (8.0, 1_008.0), (500.0, 1_008.0)]), 3_600.0);
+
+    assert_eq!(result.status, VerificationStatus::Verified);
+    assert_eq!(result.reason, None);
+    assert!(result.coverage > 0.99, "{}", result.coverage);
+    // L'arrivée dans la zone de départ compte déjà : l'écart peut y atteindre son rayon
+    let deviation = result.max_deviation_meters.unwrap();
+    assert!((7.5..=verification::GEOFENCE_RADIUS_METERS).contains(&deviation), "{}", deviation);
+}
+
+#[test]
+fn start_and_finish_geofences() {
+    let line = route(&[(0.0, 0.0), (0.0, 1_000.0)]);
+
+    let result = verification::match_route(&line, &run(&[(0.0, 200.0), (0.0, 1_000.0)]), 3_600.0);
+    assert_eq!(result.reason, Some(RejectionReason::MissedStart));
+
+    let result = verification::match_route(&line, &run(&[(0.0, 0.0), (0.0, 800.0)]), 3_600.0);
+    assert_eq!(result.reason, Some(RejectionReason::MissedFinish));
+
+    // Parcouru à l'envers : l'arrivée n'est jamais atteinte après le départ
+    let result = verification::match_route(&line, &run(&[(0.0, 1_000.0), (0.0, 0.0)]), 3_600.0);
+    assert_eq!(result.status, VerificationStatus::Rejected);
+    assert_eq!(result.reason, Some(RejectionReason::MissedFinish));
+
+    let result = verification::match_route(&line, &run(&[(0.0, 0.0)]), 3_600.0);
+    assert_eq!(result.reason, Some(RejectionReason::InsufficientGps));
+}
+
+#[test]
+fn shortcuts_and_detours() {
+    // Un L parcouru en coupant par l'hypoténuse
+    let line = route(&[(0.0, 0.0), (0.0, 1_000.0), (1_000.0, 1_000.0)]);
+    let result = verification::match_route(&line, &run(&[(0.0, 0.0), (1_000.0, 1_000.0)]), 3_600.0);
+    assert_eq!(result.reason, Some(RejectionReason::OffRoute));
+    assert!(result.max_deviation_meters.unwrap() > 300.0);
+
+    // Un aller-retour de 400 m sauté, sans jamais s'écarter du tracé
+    let spur = route(&[(0.0, 0.0), (0.0, 500.0), (200.0, 500.0), (0.0, 500.0), (0.0, 1_000.0)]);
+    let result = verification::match_route(&spur, &run(&[(0.0, 0.0), (0.0, 1_000.0)]), 3_600.0);
+    assert_eq!(result.reason, Some(RejectionReason::Incomplete));
+    assert!(result.max_deviation_meters.unwrap() < 1.0);
+    assert!(result.coverage < verification::MIN_COVERAGE);
+}
+
+#[test]
+fn claimed_time_cannot_beat_the_track() {
+    let line = route(&[(0.0, 0.0), (0.0, 1_000.0)]);
+    // Marche à 2 m/s : 900 m entre la sortie de la zone de départ et l'entrée dans celle d'arrivée
+    let walk: Vec<TrackPoint> = run(&[(0.0, 0.0), (0.0, 1_000.0)])
+        .into_iter()
+        .map(|t| TrackPoint { offset_ms: t.offset_ms * 5, ..t })
+        .collect();
+
+    let result = verification::match_route(&line, &walk, 1.0);
+    assert_eq!(result.reason, Some(RejectionReason::TimeMismatch));
+    let track_seconds = result.track_seconds.unwrap();
+    assert!((track_seconds - 450.0).abs() <= 5.0, "{}", track_seconds);
+
+    // Le temps de la trace est une borne basse : un chrono un peu plus court reste toléré
+    let result = verification::match_route(&line, &walk, track_seconds - 1.0);
+    assert_eq!(result.status, VerificationStatus::Verified);
+    let result = verification::match_route(&line, &walk, track_seconds + 60.0);
+    assert_eq!(result.status, VerificationStatus::Verified);
+}
+
+#[test]
+fn a_loop_run_backwards_is_out_of_order() {
+    let triangle = route(&[(0.0, 0.0), (1_000.0, 0.0), (500.0, 800.0), (0.0, 0.0)]);
+
+    let result = verification::match_route(&triangle, &run(&[(0.0, 0.0), (1_000.0, 0.0), (500.0, 800.0), (0.0, 0.0)]), 3_600.0);
+    assert_eq!(result.status, VerificationStatus::Verified);
+
+    let result = verification::match_route(&triangle, &run(&[(0.0, 0.0), (500.0, 800.0), (1_000.0, 0.0), (0.0, 0.0)]), 3_600.0);
+    assert_eq!(result.reason, Some(RejectionReason::WrongOrder));
+}
+
+/// Relevés de [`run`], avec un peu de gigue comme un vrai GPS : des intervalles tous égaux sont signalés.
+fn sensor_data(waypoints: &[(f64, f64)]) -> Vec<serde_json::Value> {
+    run(waypoints)
+        .iter()
+        .enumerate()
+        .map(|(i, t)| json!({ "timestamp_offset_ms": t.offset_ms + i as i32 % 7 * 13, "latitude": t.position.lat, "longitude": t.position.lon }))
+        .collect()
+}
+
+/// Score de `time` secondes, puis sa trace GPS s'il y en a une. Renvoie le score et la vérification.
+async fn submit_run(
+    app: &axum::Router,
+    user: &TestUser,
+    route_id: &serde_json::Value,
+    time: f64,
+    waypoints: &[(f64, f64)],
+) -> Result<(serde_json::Value, serde_json::Value), Box<dyn std::error::Error>> {
+    let (status, score) = send_json(app, "POST", &format!("/routes/{}/score", route_id), Some(&user.token), json!({
+        "time_seconds": time
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", score);
+    assert_eq!(score["verification_status"], "pending");
+    if waypoints.is_empty() {
+        return Ok((score, serde_json::Value::Null));
+    }
+
+    let (status, upload) = send_json(app, "POST", "/sensor-data/bulk", Some(&user.token), json!({
+        "score_id": score["id"],
+        "data": sensor_data(waypoints)
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", upload);
+    Ok((score, upload["verification"].clone()))
+}
+
+#[tokio::test]
+async fn only_verified_scores_are_ranked() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let alice = new_user(&app, "verify_alice").await?;
+    let bob = new_user(&app, "verify_bob").await?;
+    let carol = new_user(&app, "verify_carol").await?;
+
+    let path: Vec<[f64; 2]> = [(0.0, 0.0), (0.0, 1_000.0), (500.0, 1_000.0)].iter().map(|&(e, n)| {
+        let p = at(e, n);
+        [p.lon, p.lat]
+    }).collect();
+    let (status, route) = send_json(&app, "POST", "/routes", Some(&alice.token), json!({
+        "name": "Vérification",
+        "visibility": "public",
+        "path_data": path
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, verification) = submit_run(&app, &alice, &route["id"], 300.0, &[(0.0, 0.0), (0.0, 1_000.0), (500.0, 1_000.0)]).await?;
+    assert_eq!(verification["status"], "verified", "{}", verification);
+    assert!(verification["reason"].is_null());
+
+    // Bob coupe, Carol n'envoie pas de trace : aucun des deux n'est classé malgré un meilleur temps
+    let (_, verification) = submit_run(&app, &bob, &route["id"], 200.0, &[(0.0, 0.0), (500.0, 1_000.0)]).await?;
+    assert_eq!(verification["status"], "rejected");
+    assert_eq!(verification["reason"], "off_route");
+    submit_run(&app, &carol, &route["id"], 100.0, &[]).await?;
+
+    let (status, leaderboard) = send_json(&app, "GET", &format!("/api/leaderboard/route/{}", route["id"]), Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let users: Vec<&str> = leaderboard.as_array().unwrap().iter().map(|e| e["username"].as_str().unwrap()).collect();
+    assert_eq!(users, vec![alice.username.as_str()]);
+
+    // Une trace envoyée en deux fois est revérifiée en entier
+    let (score, verification) = submit_run(&app, &bob, &route["id"], 250.0, &[(0.0, 0.0), (0.0, 600.0)]).await?;
+    assert_eq!(verification["reason"], "missed_finish");
+    let (status, upload) = send_json(&app, "POST", "/sensor-data/bulk", Some(&bob.token), json!({
+        "score_id": score["id"],
+        "data": sensor_data(&[(0.0, 600.0), (0.0, 1_000.0), (500.0, 1_000.0)])
+            .into_iter()
+            .map(|mut d| {
+                d["timestamp_offset_ms"] = json!(d["timestamp_offset_ms"].as_i64().unwrap() + 100_000);
+                d
+            })
+            .collect::<Vec<_>>()
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(upload["verification"]["status"], "verified", "{}", upload);
+
+    let (_, leaderboard) = send_json(&app, "GET", &format!("/api/leaderboard/route/{}", route["id"]), Some(&bob.token), json!({})).await?;
+    let users: Vec<&str> = leaderboard.as_array().unwrap().iter().map(|e| e["username"].as_str().unwrap()).collect();
+    assert_eq!(users, vec![bob.username.as_str(), alice.username.as_str()]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn sensor_data_belongs_to_the_runner() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let alice = new_user(&app, "sensor_owner_alice").await?;
+    let mallory = new_user(&app, "sensor_owner_mallory").await?;
+
+    let waypoints = [(0.0, 0.0), (0.0, 500.0)];
+    let path: Vec<[f64; 2]> = waypoints.iter().map(|&(e, n)| {
+        let p = at(e, n);
+        [p.lon, p.lat]
+    }).collect();
+    let (status, route) = send_json(&app, "POST", "/routes", Some(&alice.token), json!({
+        "name": "Relevés",
+        "visibility": "public",
+        "path_data": path
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (verified, verification) = submit_run(&app, &alice, &route["id"], 120.0, &waypoints).await?;
+    assert_eq!(verification["status"], "verified", "{}", verification);
+    let (pending, _) = submit_run(&app, &alice, &route["id"], 130.0, &[]).await?;
+
+    // Des relevés hors parcours ajoutés par un autre utilisateur feraient rejeter la course
+    let (status, _) = send_json(&app, "POST", "/sensor-data/bulk", Some(&mallory.token), json!({
+        "score_id": verified["id"],
+        "data": sensor_data(&[(800.0, 250.0)])
+    })).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send_json(&app, "POST", "/sensor-data/bulk", Some(&mallory.token), json!({
+        "score_id": 999_999_999,
+        "data": sensor_data(&[(0.0, 0.0)])
+    })).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // La trace d'une course vérifiée sur un parcours visible est publique, pas celle d'une course en attente
+    let (status, page) = send_json(&app, "GET", &format!("/sensor-data/score/{}", verified["id"]), Some(&mallory.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!page["items"].as_array().unwrap().is_empty());
+    let (status, _) = send_json(&app, "GET", &format!("/sensor-data/score/{}", pending["id"]), Some(&mallory.token), json!({})).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send_json(&app, "GET", &format!("/sensor-data/score/{}", pending["id"]), Some(&alice.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = send_json(&app, "PUT", &format!("/routes/{}", route["id"]), Some(&alice.token), json!({
+        "visibility": "private"
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_json(&app, "GET", &format!("/sensor-data/score/{}", verified["id"]), Some(&mallory.token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send_json(&app, "GET", &format!("/sensor-data/score/{}", verified["id"]), Some(&alice.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    Ok(())
+}
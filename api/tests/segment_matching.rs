@@ -0,0 +1,74 @@
+use rust_rmce_api::geometry::{Position, EARTH_RADIUS_METERS};
+use rust_rmce_api::segments::{self, TrackPoint};
+
+/// Position à `north` mètres au nord et `east` mètres à l'est de (45°, 6°).
+fn at(north: f64, east: f64) -> Position {
+    let lat = 45.0 + (north / EARTH_RADIUS_METERS).to_degrees();
+    let lon = 6.0 + (east / (EARTH_RADIUS_METERS * 45f64.to_radians().cos())).to_degrees();
+    Position { lon, lat, ele: None }
+}
+
+/// Trace rectiligne vers le nord, un relevé par seconde, de `from` à `to` mètres à `speed` m/s.
+fn run(from: f64, to: f64, speed: f64, start_ms: i32) -> Vec<TrackPoint> {
+    let direction = if to >= from { 1.0 } else { -1.0 };
+    let steps = ((to - from).abs() / speed).round() as i32;
+    (0..=steps)
+        .map(|s| TrackPoint { offset_ms: start_ms + s * 1000, position: at(from + direction * speed * s as f64, 0.0) })
+        .collect()
+}
+
+fn segment() -> Vec<Position> {
+    vec![at(0.0, 0.0), at(250.0, 0.0), at(500.0, 0.0)]
+}
+
+#[test]
+fn straight_pass_is_timed_from_the_closest_points() {
+    let track = run(-100.0, 600.0, 5.0, 0);
+    let effort = segments::best_effort(&segment(), &track).unwrap();
+    assert_eq!(effort.start_offset_ms, 20_000);
+    assert_eq!(effort.end_offset_ms, 120_000);
+    assert_eq!(effort.elapsed_seconds(), 100.0);
+
+    // Relevés espacés de 40 m : le segment reste couvert par la trace
+    let sparse = run(-80.0, 560.0, 40.0, 0);
+    assert!(segments::best_effort(&segment(), &sparse).is_some());
+}
+
+#[test]
+fn detours_and_wrong_direction_are_rejected() {
+    // Sens inverse
+    assert!(segments::best_effort(&segment(), &run(600.0, -100.0, 5.0, 0)).is_none());
+
+    // Départ et arrivée du segment, mais en passant 150 m à l'est au milieu
+    let mut track: Vec<TrackPoint> = Vec::new();
+    let waypoints = [(0.0, 0.0), (100.0, 150.0), (400.0, 150.0), (500.0, 0.0)];
+    for (k, w) in waypoints.windows(2).enumerate() {
+        for s in 0..20 {
+            let t = s as f64 / 20.0;
+            track.push(TrackPoint {
+                offset_ms: (k as i32 * 20 + s) * 1000,
+                position: at(w[0].0 + (w[1].0 - w[0].0) * t, w[0].1 + (w[1].1 - w[0].1) * t),
+            });
+        }
+    }
+    track.push(TrackPoint { offset_ms: 60_000, position: at(500.0, 0.0) });
+    assert!(segments::best_effort(&segment(), &track).is_none());
+
+    // Trace qui s'arrête avant la fin
+    assert!(segments::best_effort(&segment(), &run(-50.0, 400.0, 5.0, 0)).is_none());
+}
+
+#[test]
+fn fastest_of_several_passes_is_kept() {
+    // Aller lent, retour hors segment, puis second passage plus rapide
+    let mut track = run(-50.0, 550.0, 4.0, 0);
+    let back_start = track.last().unwrap().offset_ms + 1000;
+    let back = run(550.0, -50.0, 10.0, back_start);
+    track.extend(back);
+    let fast_start = track.last().unwrap().offset_ms + 1000;
+    track.extend(run(-50.0, 550.0, 8.0, fast_start));
+
+    let effort = segments::best_effort(&segment(), &track).unwrap();
+    assert!(effort.start_offset_ms >= fast_start);
+    assert!((effort.elapsed_seconds() - 62.0).abs() <= 1.0, "{}", effort.elapsed_seconds());
+}
@@ -0,0 +1,127 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+use common::{build_app, send_json, TestUser, new_user};
+
+/// Position à `north` mètres au nord de (43°, 1°), en `[lon, lat]`.
+fn north_of_origin(north: f64) -> (f64, f64) {
+    (1.0, 43.0 + (north / 6_371_008.8f64).to_degrees())
+}
+
+/// Relevés GPS d'une course vers le nord, environ un par seconde, de 0 à 1 000 m à `speed` m/s.
+/// `jitter` ajoute quelques millisecondes d'écart entre relevés, comme un vrai téléphone.
+fn track(speed: f64, jitter: bool) -> Vec<serde_json::Value> {
+    let steps = (1_000.0 / speed).round() as i64;
+    (0..=steps)
+        .map(|s| {
+            let (lon, lat) = north_of_origin(speed * s as f64);
+            let offset = s * 1000 + if jitter { s % 7 * 13 } else { 0 };
+            json!({ "timestamp_offset_ms": offset, "latitude": lat, "longitude": lon })
+        })
+        .collect()
+}
+
+async fn submit_run(app: &axum::Router, user: &TestUser, route_id: &serde_json::Value, speed: f64) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    submit_track(app, user, route_id, speed, true).await
+}
+
+async fn submit_track(app: &axum::Router, user: &TestUser, route_id: &serde_json::Value, speed: f64, jitter: bool) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let (status, score) = send_json(app, "POST", &format!("/routes/{}/score", route_id), Some(&user.token), json!({
+        "time_seconds": 1_000.0 / speed
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", score);
+    let (status, upload) = send_json(app, "POST", "/sensor-data/bulk", Some(&user.token), json!({
+        "score_id": score["id"],
+        "data": track(speed, jitter)
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", upload);
+    assert_eq!(upload["segment_efforts"], 1);
+    Ok(score)
+}
+
+#[tokio::test]
+async fn segment_efforts_leaderboard_and_prs() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let alice = new_user(&app, "segment_alice").await?;
+    let bob = new_user(&app, "segment_bob").await?;
+    let carol = new_user(&app, "segment_carol").await?;
+
+    let (start, end) = (north_of_origin(0.0), north_of_origin(1_000.0));
+    let (status, route) = send_json(&app, "POST", "/routes", Some(&alice.token), json!({
+        "name": "Ligne droite",
+        "is_public": true,
+        "path_data": [[start.0, start.1], [end.0, end.1]]
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (_, private) = send_json(&app, "POST", "/routes", Some(&alice.token), json!({
+        "name": "Privé",
+        "is_public": false,
+        "path_data": [[start.0, start.1], [end.0, end.1]]
+    })).await?;
+
+    let (status, segment) = send_json(&app, "POST", "/segments", Some(&alice.token), json!({
+        "route_id": route["id"],
+        "name": "Le sprint",
+        "start_distance_meters": 200.0,
+        "end_distance_meters": 700.0
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", segment);
+    assert_eq!(segment["route_id"], route["id"]);
+    let length = segment["distance_meters"].as_f64().unwrap();
+    assert!((length - 500.0).abs() < 1.0, "{}", length);
+    let id = segment["id"].as_i64().unwrap();
+
+    let invalid = [
+        (json!({ "route_id": private["id"], "name": "Privé", "start_distance_meters": 0.0, "end_distance_meters": 500.0 }), StatusCode::UNPROCESSABLE_ENTITY),
+        (json!({ "route_id": route["id"], "name": "Trop long", "start_distance_meters": 0.0, "end_distance_meters": 5_000.0 }), StatusCode::UNPROCESSABLE_ENTITY),
+        (json!({ "route_id": route["id"], "name": "Trop court", "start_distance_meters": 100.0, "end_distance_meters": 150.0 }), StatusCode::UNPROCESSABLE_ENTITY),
+        (json!({ "route_id": 999_999_999, "name": "Absent", "start_distance_meters": 0.0, "end_distance_meters": 500.0 }), StatusCode::NOT_FOUND),
+    ];
+    for (body, expected) in invalid {
+        let (status, _) = send_json(&app, "POST", "/segments", Some(&alice.token), body.clone()).await?;
+        assert_eq!(status, expected, "{}", body);
+    }
+
+    // 500 m : 200 s pour Alice à 2,5 m/s, 100 s pour Bob à 5 m/s, puis 125 s pour Alice à 4 m/s
+    submit_run(&app, &alice, &route["id"], 2.5).await?;
+    let bob_score = submit_run(&app, &bob, &route["id"], 5.0).await?;
+    submit_run(&app, &alice, &route["id"], 4.0).await?;
+    // Les relevés parfaitement réguliers de Carol sont signalés : son passage ne compte pas
+    submit_track(&app, &carol, &route["id"], 6.0, false).await?;
+
+    let (status, leaderboard) = send_json(&app, "GET", &format!("/segments/{}/leaderboard", id), Some(&alice.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let entries = leaderboard.as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["username"], bob.username.as_str());
+    assert_eq!(entries[0]["rank"], 1);
+    assert_eq!(entries[0]["score_id"], bob_score["id"]);
+    assert!((entries[0]["elapsed_seconds"].as_f64().unwrap() - 100.0).abs() <= 1.0);
+    assert_eq!(entries[1]["username"], alice.username.as_str());
+    assert!((entries[1]["elapsed_seconds"].as_f64().unwrap() - 125.0).abs() <= 1.0);
+
+    let (status, prs) = send_json(&app, "GET", "/segments/prs", Some(&alice.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(prs.as_array().unwrap().len(), 1);
+    assert_eq!(prs[0]["segment_id"], id);
+    assert_eq!(prs[0]["segment_name"], "Le sprint");
+    assert_eq!(prs[0]["rank"], 2);
+    assert_eq!(prs[0]["effort_id"], entries[1]["effort_id"]);
+    let (status, prs) = send_json(&app, "GET", "/segments/prs", Some(&carol.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(prs.as_array().unwrap().is_empty(), "{}", prs);
+
+    let (_, list) = send_json(&app, "GET", &format!("/segments?route_id={}", route["id"]), Some(&bob.token), json!({})).await?;
+    assert_eq!(list["items"].as_array().unwrap().len(), 1);
+
+    let (status, _) = send_json(&app, "DELETE", &format!("/segments/{}", id), Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send_json(&app, "DELETE", &format!("/segments/{}", id), Some(&alice.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_json(&app, "GET", &format!("/segments/{}/leaderboard", id), Some(&alice.token), json!({})).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
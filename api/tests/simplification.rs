@@ -0,0 +1,47 @@
+use rust_rmce_api::geometry::{self, Position};
+
+fn position(lon: f64, lat: f64) -> Position {
+    Position { lon, lat, ele: None }
+}
+
+#[test]
+fn encodes_the_reference_polyline() {
+    // Exemple de la documentation Google
+    let positions = [position(-120.2, 38.5), position(-120.95, 40.7), position(-126.453, 43.252)];
+    assert_eq!(geometry::encode_polyline(&positions), "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+    assert_eq!(geometry::encode_polyline(&[]), "");
+}
+
+#[test]
+fn douglas_peucker_keeps_significant_points() {
+    // ~800 m vers l'est puis ~1,1 km vers le nord-est, avec du bruit de ~1 m
+    let positions: Vec<Position> = (0..=20)
+        .map(|i| {
+            let climb = if i > 10 { (i - 10) as f64 * 0.001 } else { 0.0 };
+            let noise = if i % 2 == 0 { 0.0 } else { 0.00001 };
+            position(6.0 + i as f64 * 0.001, 45.0 + climb + noise)
+        })
+        .collect();
+
+    let simplified = geometry::simplify(&positions, 10.0);
+    assert_eq!(simplified, vec![positions[0], positions[10], positions[20]]);
+
+    // Avec une tolérance plus large que le coude, seules les extrémités restent
+    assert_eq!(geometry::simplify(&positions, 2_000.0), vec![positions[0], positions[20]]);
+
+    // Une tolérance sous le bruit conserve tout
+    assert_eq!(geometry::simplify(&positions, 0.1).len(), positions.len());
+}
+
+#[test]
+fn simplification_handles_loops_and_long_tracks() {
+    // Boucle fermée : départ et arrivée confondus, le tracé ne doit pas disparaître
+    let square = [position(0.0, 0.0), position(0.01, 0.0), position(0.01, 0.01), position(0.0, 0.01), position(0.0, 0.0)];
+    assert_eq!(geometry::simplify(&square, 10.0), square.to_vec());
+
+    let long: Vec<Position> = (0..geometry::MAX_POSITIONS).map(|i| position(i as f64 * 1e-5, (i as f64 / 50.0).sin() * 1e-3)).collect();
+    let simplified = geometry::simplify(&long, 5.0);
+    assert!(simplified.len() > 2 && simplified.len() < long.len());
+    assert_eq!(simplified.first(), long.first());
+    assert_eq!(simplified.last(), long.last());
+}
@@ -0,0 +1,126 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+mod common;
+
+use common::{build_app, send_json, TestUser, new_user};
+
+/// Parcours de ~500 m vers l'est dont le départ est à `(lat, lon)`.
+async fn create_route(
+    app: &axum::Router,
+    user: &TestUser,
+    name: &str,
+    visibility: &str,
+    lat: f64,
+    lon: f64,
+) -> Result<i64, Box<dyn std::error::Error>> {
+    let (status, route) = send_json(app, "POST", "/routes", Some(&user.token), json!({
+        "name": name,
+        "visibility": visibility,
+        "path_data": { "type": "LineString", "coordinates": [[lon, lat], [lon + 0.005, lat]] }
+    })).await?;
+    assert_eq!(status, StatusCode::OK, "{}", route);
+    Ok(route["id"].as_i64().unwrap())
+}
+
+/// Identifiants renvoyés, restreints à ceux créés par le test (la base est partagée).
+fn returned_ids(body: &serde_json::Value, ours: &[i64]) -> Vec<i64> {
+    body.as_array()
+        .unwrap_or_else(|| panic!("expected an array, got {}", body))
+        .iter()
+        .map(|r| r["id"].as_i64().unwrap())
+        .filter(|id| ours.contains(id))
+        .collect()
+}
+
+#[tokio::test]
+async fn nearby_routes_are_ordered_by_distance_and_respect_visibility() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let alice = new_user(&app, "spatial_alice").await?;
+    let bob = new_user(&app, "spatial_bob").await?;
+
+    // Autour du lac d'Annecy ; un degré de latitude fait ~111 km
+    let (lat, lon) = (45.86, 6.17);
+    let own_private = create_route(&app, &alice, "Alice privé", "private", lat + 0.002, lon).await?;
+    let bob_public = create_route(&app, &bob, "Bob public", "public", lat + 0.010, lon).await?;
+    let bob_friends = create_route(&app, &bob, "Bob amis", "friends", lat + 0.005, lon).await?;
+    let bob_private = create_route(&app, &bob, "Bob privé", "private", lat + 0.004, lon).await?;
+    let bob_unlisted = create_route(&app, &bob, "Bob non listé", "unlisted", lat + 0.003, lon).await?;
+    let far_public = create_route(&app, &bob, "Bob loin", "public", lat + 0.500, lon).await?;
+    let ours = [own_private, bob_public, bob_friends, bob_private, bob_unlisted, far_public];
+
+    let uri = format!("/routes/nearby?lat={}&lng={}&radius_m=5000", lat, lon);
+    let (status, body) = send_json(&app, "GET", &uri, Some(&alice.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(returned_ids(&body, &ours), vec![own_private, bob_public]);
+    let first = body.as_array().unwrap().iter().find(|r| r["id"] == own_private).unwrap();
+    let distance = first["distance_from_meters"].as_f64().unwrap();
+    assert!((distance - 222.4).abs() < 1.0, "distance {}", distance);
+
+    // Amis : les parcours de Bob réservés aux amis deviennent visibles, pas ses parcours privés
+    let (status, friendship) = send_json(&app, "POST", &format!("/friends/add/{}", bob.username), Some(&alice.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let uri_accept = format!("/friends/accept/{}", friendship["id"]);
+    let (status, _) = send_json(&app, "PUT", &uri_accept, Some(&bob.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = send_json(&app, "GET", &uri, Some(&alice.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(returned_ids(&body, &ours), vec![own_private, bob_friends, bob_public]);
+
+    let uri = format!("/routes/nearby?lat={}&lng={}&radius_m=100000", lat, lon);
+    let (_, body) = send_json(&app, "GET", &uri, Some(&alice.token), json!({})).await?;
+    assert_eq!(returned_ids(&body, &ours), vec![own_private, bob_friends, bob_public, far_public]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn routes_within_a_bounding_box() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let carol = new_user(&app, "spatial_carol").await?;
+    let dave = new_user(&app, "spatial_dave").await?;
+
+    let centre = create_route(&app, &carol, "Centre", "private", -33.870, 151.210).await?;
+    // Départ hors de l'emprise mais tracé qui y entre
+    let crossing = create_route(&app, &dave, "Traversée", "public", -33.865, 151.197).await?;
+    let outside = create_route(&app, &dave, "Dehors", "public", -33.700, 151.210).await?;
+    let hidden = create_route(&app, &dave, "Caché", "private", -33.871, 151.211).await?;
+    let ours = [centre, crossing, outside, hidden];
+
+    let (status, body) = send_json(&app, "GET", "/routes/within?bbox=151.20,-33.88,151.22,-33.86", Some(&carol.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(returned_ids(&body, &ours), vec![centre, crossing]);
+
+    // Emprise qui traverse l'antiméridien
+    let east = create_route(&app, &carol, "Fidji est", "public", -17.0, 179.990).await?;
+    let west = create_route(&app, &carol, "Fidji ouest", "public", -17.0, -179.995).await?;
+    let (status, body) = send_json(&app, "GET", "/routes/within?bbox=179.9,-17.1,-179.9,-16.9", Some(&carol.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let ids = returned_ids(&body, &[east, west]);
+    assert_eq!(ids.len(), 2, "{}", body);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn spatial_queries_are_validated() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let erin = new_user(&app, "spatial_erin").await?;
+
+    let (status, body) = send_json(&app, "GET", "/routes/nearby?lat=45&radius_m=0", Some(&erin.token), json!({})).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["lng"].is_array());
+    assert!(body["details"]["radius_m"].is_array());
+
+    for bbox in ["", "1,2,3", "1,50,2,40", "a,b,c,d", "0,0,200,1"] {
+        let (status, body) = send_json(&app, "GET", &format!("/routes/within?bbox={}", bbox), Some(&erin.token), json!({})).await?;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "bbox {}", bbox);
+        assert!(body["details"]["bbox"].is_array());
+    }
+
+    let (status, _) = send_json(&app, "GET", "/routes/nearby?lat=45&lng=6&radius_m=1000", None, json!({})).await?;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    Ok(())
+}
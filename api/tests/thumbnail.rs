@@ -0,0 +1,206 @@
+use axum::body::Body;
+use axum::http::{HeaderMap, Request, StatusCode};
+use rust_rmce_api::geometry::{Position, EARTH_RADIUS_METERS};
+use rust_rmce_api::thumbnail::{self, ThumbnailFormat, ThumbnailOptions};
+use serde_json::json;
+use tower::ServiceExt;
+
+mod common;
+
+use common::{build_app, send_json, new_user};
+
+fn position(lon: f64, lat: f64) -> Position {
+    Position { lon, lat, ele: None }
+}
+
+/// Positions espacées de `step_m` mètres vers le nord.
+fn along_meridian(step_m: f64, count: usize) -> Vec<Position> {
+    let step_deg = (step_m / EARTH_RADIUS_METERS).to_degrees();
+    (0..count).map(|i| position(6.0, 45.0 + i as f64 * step_deg)).collect()
+}
+
+fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+    assert!((actual - expected).abs() < tolerance, "expected {}, got {}", expected, actual);
+}
+
+#[test]
+fn mercator_projection() {
+    let (x, y) = thumbnail::mercator(&position(0.0, 0.0));
+    assert_close(x, 0.5, 1e-12);
+    assert_close(y, 0.5, 1e-12);
+
+    let (x, y) = thumbnail::mercator(&position(-180.0, 85.051_128_78));
+    assert_close(x, 0.0, 1e-12);
+    assert_close(y, 0.0, 1e-6);
+
+    // Les pôles sont ramenés à la limite de la projection au lieu de partir à l'infini
+    let (_, y) = thumbnail::mercator(&position(0.0, -90.0));
+    assert_close(y, 1.0, 1e-6);
+}
+
+#[test]
+fn scene_keeps_aspect_ratio_and_centers_the_path() {
+    // Tracé nord-sud : la hauteur limite l'échelle, le tracé est centré horizontalement
+    let positions = along_meridian(100.0, 11);
+    let options = ThumbnailOptions { width: 400, height: 200, distance_ticks: false };
+    let scene = thumbnail::scene(&positions, &options).unwrap();
+
+    assert_eq!((scene.width, scene.height), (400, 200));
+    assert_close(scene.start.0, 200.0, 1e-6);
+    assert_close(scene.finish.0, 200.0, 1e-6);
+    // Le nord est en haut
+    assert!(scene.start.1 > scene.finish.1);
+    assert_close(scene.start.1 + scene.finish.1, 200.0, 1e-6);
+    for &(x, y) in &scene.line {
+        assert!((0.0..=400.0).contains(&x) && (0.0..=200.0).contains(&y));
+    }
+    assert!(scene.ticks.is_empty());
+
+    assert!(thumbnail::scene(&positions[..1], &options).is_none());
+}
+
+#[test]
+fn scene_drops_points_closer_than_half_a_pixel() {
+    // 10 000 points sur 1 km : bien plus que de pixels
+    let positions = along_meridian(0.1, 10_001);
+    let scene = thumbnail::scene(&positions, &ThumbnailOptions::default()).unwrap();
+    assert!(scene.line.len() < 1_000, "{} points", scene.line.len());
+    assert_eq!(scene.line[0], scene.start);
+    assert_eq!(scene.line[scene.line.len() - 1], scene.finish);
+}
+
+#[test]
+fn distance_ticks() {
+    assert_eq!(thumbnail::tick_step(1_500.0), 100.0);
+    assert_eq!(thumbnail::tick_step(5_000.0), 500.0);
+    assert_eq!(thumbnail::tick_step(42_195.0), 5_000.0);
+    assert_eq!(thumbnail::tick_step(10_000_000.0), 100_000.0);
+
+    // 5 km : une graduation tous les 500 m, sauf à l'arrivée
+    let positions = along_meridian(250.0, 21);
+    let options = ThumbnailOptions { distance_ticks: true, ..ThumbnailOptions::default() };
+    let scene = thumbnail::scene(&positions, &options).unwrap();
+    let distances: Vec<f64> = scene.ticks.iter().map(|t| t.distance_meters).collect();
+    assert_eq!(distances, (1..10).map(|i| i as f64 * 500.0).collect::<Vec<_>>());
+
+    // Tracé vertical : graduations horizontales, centrées sur le tracé
+    for tick in &scene.ticks {
+        assert_close(tick.from.1, tick.to.1, 1e-6);
+        assert_close((tick.from.0 + tick.to.0) / 2.0, scene.start.0, 1e-6);
+    }
+}
+
+#[test]
+fn svg_rendering() {
+    let positions = along_meridian(250.0, 21);
+    let options = ThumbnailOptions { width: 320, height: 240, distance_ticks: true };
+    let svg = thumbnail::render_svg(&thumbnail::scene(&positions, &options).unwrap());
+
+    assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="320" height="240" viewBox="0 0 320 240">"#));
+    assert!(svg.ends_with("</svg>"));
+    assert_eq!(svg.matches("<polyline").count(), 1);
+    assert_eq!(svg.matches("<line ").count(), 9);
+    assert_eq!(svg.matches("<circle").count(), 2);
+}
+
+fn png_size(bytes: &[u8]) -> (u32, u32) {
+    assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&bytes[12..16], b"IHDR");
+    let be = |b: &[u8]| u32::from_be_bytes([b[0], b[1], b[2], b[3]]);
+    (be(&bytes[16..20]), be(&bytes[20..24]))
+}
+
+#[test]
+fn png_rendering() {
+    let positions = along_meridian(250.0, 21);
+    let options = ThumbnailOptions { width: 64, height: 48, distance_ticks: false };
+    let scene = thumbnail::scene(&positions, &options).unwrap();
+    let png = thumbnail::render(&scene, ThumbnailFormat::Png).unwrap();
+    assert_eq!(png_size(&png), (64, 48));
+
+    assert_eq!("PNG".parse::<ThumbnailFormat>(), Ok(ThumbnailFormat::Png));
+    assert!("jpg".parse::<ThumbnailFormat>().is_err());
+}
+
+async fn fetch(
+    app: &axum::Router,
+    uri: &str,
+    token: Option<&str>,
+    if_none_match: Option<&str>,
+) -> Result<(StatusCode, HeaderMap, Vec<u8>), Box<dyn std::error::Error>> {
+    let mut builder = Request::builder().method("GET").uri(uri);
+    if let Some(t) = token {
+        builder = builder.header("Authorization", format!("Bearer {}", t));
+    }
+    if let Some(etag) = if_none_match {
+        builder = builder.header("If-None-Match", etag);
+    }
+    let response = app.clone().oneshot(builder.body(Body::empty())?).await?;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok((status, headers, body.to_vec()))
+}
+
+#[tokio::test]
+async fn route_thumbnails_are_cached_until_the_route_changes() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let owner = new_user(&app, "thumb_owner").await?;
+    let stranger = new_user(&app, "thumb_stranger").await?;
+
+    let (status, route) = send_json(&app, "POST", "/routes", Some(&owner.token), json!({
+        "name": "Vignette",
+        "visibility": "unlisted",
+        "path_data": [[-1.55, 47.21], [-1.54, 47.22], [-1.53, 47.21]]
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/routes/{}/thumbnail", route["id"]);
+
+    let (status, headers, svg) = fetch(&app, &format!("{}.svg?ticks=true", uri), Some(&owner.token), None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["content-type"], "image/svg+xml");
+    assert!(headers.contains_key("last-modified"));
+    assert!(String::from_utf8(svg)?.contains("<polyline"));
+
+    let (status, headers, png) = fetch(&app, &format!("{}.png?width=120&height=80", uri), Some(&owner.token), None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["content-type"], "image/png");
+    assert_eq!(png_size(&png), (120, 80));
+    let etag = headers["etag"].to_str()?.to_string();
+
+    let (status, headers, body) = fetch(&app, &format!("{}.png?width=120&height=80", uri), Some(&owner.token), Some(&etag)).await?;
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+    assert_eq!(headers["etag"], etag.as_str());
+    assert!(body.is_empty());
+    // Autre taille, autre ETag
+    let (status, _, _) = fetch(&app, &format!("{}.png", uri), Some(&owner.token), Some(&etag)).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // Un tracé modifié invalide l'ETag
+    let (status, _) = send_json(&app, "PUT", &format!("/routes/{}", route["id"]), Some(&owner.token), json!({
+        "path_data": [[-1.55, 47.21], [-1.50, 47.25]]
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, headers, _) = fetch(&app, &format!("{}.png?width=120&height=80", uri), Some(&owner.token), Some(&etag)).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(headers["etag"], etag.as_str());
+
+    let (status, _, _) = fetch(&app, &format!("{}.svg", uri), Some(&stranger.token), None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _, _) = fetch(&app, &format!("{}.gif", uri), Some(&owner.token), None).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, _, _) = fetch(&app, &format!("{}.png?width=5000", uri), Some(&owner.token), None).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Par lien de partage, sans connexion
+    let (status, link) = send_json(&app, "POST", &format!("/routes/{}/share-links", route["id"]), Some(&owner.token), json!({})).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, headers, _) = fetch(&app, &format!("{}/thumbnail.svg", link["path"].as_str().unwrap()), None, None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["content-type"], "image/svg+xml");
+    assert_eq!(headers["cache-control"], "public, max-age=300");
+    let (status, _, _) = fetch(&app, "/shared/routes/inconnu/thumbnail.svg", None, None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
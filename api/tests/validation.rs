@@ -0,0 +1,124 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::json;
+use tower::ServiceExt;
+
+mod common;
+
+use common::{build_app, unique_username, send_json, new_user};
+
+async fn send_raw(
+    app: &axum::Router,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: Vec<u8>,
+) -> Result<(StatusCode, serde_json::Value), Box<dyn std::error::Error>> {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json");
+    if let Some(t) = token {
+        builder = builder.header("Authorization", format!("Bearer {}", t));
+    }
+    let response = app.clone().oneshot(builder.body(Body::from(body))?).await?;
+    let status = response.status();
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let value = serde_json::from_slice(&body_bytes).unwrap_or(serde_json::Value::Null);
+    Ok((status, value))
+}
+
+#[tokio::test]
+async fn register_reports_every_invalid_field() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+
+    let (status, body) = send_json(&app, "POST", "/auth/register", None, json!({
+        "username": "",
+        "email": "not-an-email",
+        "password": "short"
+    })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "validation_failed");
+    let details = &body["details"];
+    assert!(details["username"].is_array());
+    assert!(details["email"].is_array());
+    assert_eq!(details["password"].as_array().unwrap().len(), 2, "{}", details);
+
+    let (status, body) = send_json(&app, "POST", "/auth/register", None, json!({
+        "username": unique_username("weak"),
+        "email": "weak@test.com",
+        "password": "onlyletters"
+    })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["password"].is_array());
+    assert!(body["details"]["email"].is_null());
+
+    // JSON illisible : 400, champ manquant : 422
+    let (status, body) = send_raw(&app, "POST", "/auth/register", None, b"{\"username\":".to_vec()).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "bad_request");
+    let (status, body) = send_json(&app, "POST", "/auth/register", None, json!({ "username": "abc" })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "validation_failed");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn route_score_and_sensor_bounds_are_enforced() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(app) = build_app().await? else { return Ok(()) };
+    let token = new_user(&app, "valid_user").await?.token;
+
+    let (status, body) = send_json(&app, "POST", "/routes", Some(&token), json!({
+        "name": "   ",
+        "description": "x".repeat(2_001),
+        "is_public": true,
+        "path_data": "not geometry"
+    })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    for field in ["name", "description", "path_data"] {
+        assert!(body["details"][field].is_array(), "{} should be rejected: {}", field, body);
+    }
+
+    let (status, route) = send_json(&app, "POST", "/routes", Some(&token), json!({
+        "name": "Boucle",
+        "is_public": true,
+        "path_data": { "type": "LineString", "coordinates": [[2.35, 48.85], [2.36, 48.86]] }
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+    let route_id = route["id"].as_i64().unwrap();
+
+    let (status, body) = send_json(&app, "POST", &format!("/routes/{}/score", route_id), Some(&token), json!({
+        "time_seconds": -10.0,
+        "max_speed_kmh": 12.0,
+        "avg_speed_kmh": 15.0
+    })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["time_seconds"].is_array());
+    assert!(body["details"]["avg_speed_kmh"].is_array());
+
+    let (status, score) = send_json(&app, "POST", &format!("/routes/{}/score", route_id), Some(&token), json!({
+        "time_seconds": 600.0
+    })).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = send_json(&app, "POST", "/sensor-data/bulk", Some(&token), json!({
+        "score_id": score["id"],
+        "data": [
+            { "timestamp_offset_ms": 0, "latitude": 48.85, "longitude": 2.35 },
+            { "timestamp_offset_ms": 1000, "latitude": 123.0, "longitude": 2.35 }
+        ]
+    })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["data[1].latitude"].is_array(), "{}", body);
+    assert!(body["details"]["data[0].latitude"].is_null());
+
+    let (status, body) = send_json(&app, "POST", "/sensor-data/bulk", Some(&token), json!({
+        "score_id": score["id"],
+        "data": []
+    })).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["details"]["data"].is_array());
+
+    Ok(())
+}
@@ -4,6 +4,7 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilte
 
 mod friendship;
 mod redis_client;
+mod session;
 mod ws;
 
 #[tokio::main]
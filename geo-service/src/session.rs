@@ -0,0 +1,33 @@
+use reqwest::{Client, StatusCode};
+use tracing::error;
+
+/// Asks the api whether the session behind this JWT is still active.
+/// Returns `Ok(false)` when the api rejects the token (revoked, expired or logged out).
+pub async fn is_session_active(
+    http: &Client,
+    api_base_url: &str,
+    jwt_token: &str,
+) -> Result<bool, reqwest::Error> {
+    let url = format!("{}/auth/session", api_base_url);
+
+    let resp = http
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", jwt_token))
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to reach api /auth/session: {}", e);
+            e
+        })?;
+
+    if resp.status() == StatusCode::UNAUTHORIZED {
+        return Ok(false);
+    }
+
+    resp.error_for_status().map_err(|e| {
+        error!("api /auth/session returned error status: {}", e);
+        e
+    })?;
+
+    Ok(true)
+}
@@ -16,7 +16,7 @@ use std::{sync::Arc, time::{SystemTime, UNIX_EPOCH}};
 use tokio::sync::broadcast;
 use tracing::{error, info, warn};
 
-use crate::{friendship::get_friends, redis_client::RedisHandle};
+use crate::{friendship::get_friends, redis_client::RedisHandle, session::is_session_active};
 
 #[derive(Clone)]
 pub struct AppState {
@@ -75,6 +75,23 @@ async fn handle_socket(mut socket: WebSocket, token: String, state: AppState) {
         }
     };
     let user_id = claims.user_id;
+
+    // Reject tokens whose session was revoked server-side (logout, stolen device)
+    match is_session_active(&state.http, &state.api_base_url, &token).await {
+        Ok(true) => {}
+        Ok(false) => {
+            warn!("WS auth failed: session {} revoked for user {}", claims.jti, user_id);
+            let msg = serde_json::json!({"error": "unauthorized", "message": "Session revoked"});
+            let _ = socket.send(Message::Text(msg.to_string().into())).await;
+            return;
+        }
+        Err(e) => {
+            error!("Failed to check session for user {}: {}", user_id, e);
+            let msg = serde_json::json!({"error": "internal", "message": "Failed to check session"});
+            let _ = socket.send(Message::Text(msg.to_string().into())).await;
+            return;
+        }
+    }
     info!("WS connected: user_id={}", user_id);
 
     // 2. Fetch accepted friends from the api service
@@ -136,10 +153,9 @@ async fn handle_socket(mut socket: WebSocket, token: String, state: AppState) {
     // Sender task: drains the broadcast channel, writes JSON frames to the WS client
     tokio::spawn(async move {
         while let Ok(loc) = rx.recv().await {
-            if let Ok(json) = serde_json::to_string(&loc) {
-                if ws_sender.send(Message::Text(json.into())).await.is_err() {
-                    break; // client disconnected
-                }
+            if let Ok(json) = serde_json::to_string(&loc)
+                && ws_sender.send(Message::Text(json.into())).await.is_err() {
+                break; // client disconnected
             }
         }
     });
@@ -1,20 +1,152 @@
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug)]
+/// Erreur renvoyée par les handlers. Convertie en réponse JSON stable :
+/// `{"code", "message", "details", "request_id"}`.
+#[derive(Debug, Clone)]
 pub enum AppError {
+    BadRequest(String),
+    /// Données invalides ; `details` précise les champs en erreur.
+    Validation {
+        message: String,
+        details: Option<serde_json::Value>,
+    },
     Unauthorized(String),
+    Forbidden(String),
     NotFound(String),
+    Conflict(String),
+    /// Requête bien formée mais incohérente (ex. référence vers une ressource inexistante).
+    Unprocessable(String),
+    RateLimited(String),
+    /// Le détail n'est jamais renvoyé au client.
     Internal(String),
 }
 
+/// Corps JSON des réponses d'erreur. `request_id` est renseigné par le middleware de l'api.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+    pub request_id: Option<String>,
+}
+
+impl AppError {
+    pub fn validation(message: impl Into<String>, details: serde_json::Value) -> Self {
+        AppError::Validation {
+            message: message.into(),
+            details: Some(details),
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Validation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Unprocessable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Identifiant stable, destiné au client mobile (à ne pas renommer).
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Validation { .. } => "validation_failed",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::Unprocessable(_) => "unprocessable",
+            AppError::RateLimited(_) => "rate_limited",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn body(&self) -> ErrorBody {
+        let (message, details) = match self {
+            AppError::Validation { message, details } => (message.clone(), details.clone()),
+            AppError::Internal(_) => ("Internal server error".to_string(), None),
+            AppError::BadRequest(msg)
+            | AppError::Unauthorized(msg)
+            | AppError::Forbidden(msg)
+            | AppError::NotFound(msg)
+            | AppError::Conflict(msg)
+            | AppError::Unprocessable(msg)
+            | AppError::RateLimited(msg) => (msg.clone(), None),
+        };
+
+        ErrorBody {
+            code: self.code().to_string(),
+            message,
+            details,
+            request_id: None,
+        }
+    }
+}
+
 impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            AppError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
+            AppError::Validation { message, .. } => write!(f, "Validation failed: {}", message),
             AppError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            AppError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
             AppError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            AppError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            AppError::Unprocessable(msg) => write!(f, "Unprocessable: {}", msg),
+            AppError::RateLimited(msg) => write!(f, "Rate limited: {}", msg),
             AppError::Internal(msg) => write!(f, "Internal error: {}", msg),
         }
     }
 }
 
 impl std::error::Error for AppError {}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let body = self.body();
+        let mut response = (self.status(), Json(body.clone())).into_response();
+        // Permet à un middleware de réécrire le corps avec l'identifiant de requête
+        response.extensions_mut().insert(body);
+        response
+    }
+}
+
+/// Violations de contraintes Postgres traduites en erreurs client, le reste en 500.
+#[cfg(feature = "sqlx")]
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> Self {
+        match &err {
+            sqlx::Error::RowNotFound => AppError::NotFound("Resource not found".to_string()),
+            sqlx::Error::Database(db_err) => {
+                let constraint = db_err.constraint().unwrap_or("unknown").to_string();
+                match db_err.code().as_deref() {
+                    // unique_violation
+                    Some("23505") => AppError::Conflict(format!("Already exists ({})", constraint)),
+                    // foreign_key_violation
+                    Some("23503") => AppError::Unprocessable(format!("Referenced resource does not exist ({})", constraint)),
+                    // check_violation, not_null_violation
+                    Some("23514") | Some("23502") => AppError::Validation {
+                        message: format!("Invalid value ({})", constraint),
+                        details: None,
+                    },
+                    // invalid_text_representation, invalid_datetime_format (ex. curseur falsifié)
+                    Some("22P02") | Some("22007") => AppError::BadRequest("Malformed value".to_string()),
+                    _ => AppError::Internal(err.to_string()),
+                }
+            }
+            _ => AppError::Internal(err.to_string()),
+        }
+    }
+}
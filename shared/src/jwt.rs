@@ -1,17 +1,254 @@
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    decode, decode_header, encode,
+    errors::{Error, ErrorKind},
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
 use lazy_static::lazy_static;
+use rsa::{pkcs1::DecodeRsaPublicKey, pkcs8::DecodePublicKey, traits::PublicKeyParts, RsaPublicKey};
 use serde::{Deserialize, Serialize};
-use std::time::{SystemTime, UNIX_EPOCH};
-use tracing::error;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::RwLock,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::{error, info, warn};
+
+/// Durée de vie d'un access token. Courte : la session est prolongée via un refresh token.
+pub const ACCESS_TOKEN_TTL_SECS: u64 = 15 * 60;
+
+/// Le répertoire de clés est relu périodiquement pour prendre en compte les rotations.
+const KEYS_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+/// Délai minimum entre deux relectures déclenchées par un `kid` inconnu.
+const UNKNOWN_KID_RELOAD_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// En-tête DER d'une clé publique Ed25519 (SubjectPublicKeyInfo), suivi des 32 octets de la clé.
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
 
 lazy_static! {
-    static ref JWT_SECRET: Vec<u8> = std::env::var("JWT_SECRET")
-        .map(|s| s.into_bytes())
-        .unwrap_or_else(|_| {
-            error!("JWT_SECRET n'est pas définie dans les variables d'environnement");
-            error!("Créez un fichier .env avec: JWT_SECRET=your_jwt_secret_key");
-            panic!("JWT_SECRET must be set");
+    static ref KEYS: RwLock<KeyStore> = RwLock::new(KeyStore::from_env());
+}
+
+struct VerificationKey {
+    algorithm: Algorithm,
+    key: DecodingKey,
+    jwk: Jwk,
+}
+
+struct SigningKey {
+    kid: String,
+    algorithm: Algorithm,
+    key: EncodingKey,
+}
+
+/// Clés asymétriques chargées depuis `JWT_KEYS_DIR`.
+///
+/// Chaque clé publique `<kid>.pub.pem` est acceptée en vérification. La clé privée
+/// `<kid>.pem` n'est lue que pour `JWT_SIGNING_KID`, ce qui permet aux services qui
+/// ne font que vérifier (geo-service) de ne détenir aucune clé privée.
+struct KeyDirectory {
+    dir: PathBuf,
+    signing_kid: Option<String>,
+    signing: Option<SigningKey>,
+    verification: HashMap<String, VerificationKey>,
+    loaded_at: Instant,
+}
+
+enum KeyStore {
+    /// Mode historique : HS256 avec le secret partagé `JWT_SECRET`.
+    Shared(Vec<u8>),
+    /// RS256 / EdDSA avec `kid`, plusieurs clés de vérification simultanées.
+    Directory(KeyDirectory),
+}
+
+impl KeyStore {
+    fn from_env() -> Self {
+        if let Ok(dir) = std::env::var("JWT_KEYS_DIR") {
+            let signing_kid = std::env::var("JWT_SIGNING_KID").ok();
+            return KeyStore::Directory(KeyDirectory::load(Path::new(&dir), signing_kid));
+        }
+
+        let secret = std::env::var("JWT_SECRET")
+            .map(|s| s.into_bytes())
+            .unwrap_or_else(|_| {
+                error!("Ni JWT_KEYS_DIR ni JWT_SECRET ne sont définies dans les variables d'environnement");
+                error!("Créez un fichier .env avec: JWT_SECRET=your_jwt_secret_key");
+                panic!("JWT_SECRET must be set");
+            });
+        KeyStore::Shared(secret)
+    }
+}
+
+impl KeyDirectory {
+    fn load(dir: &Path, signing_kid: Option<String>) -> Self {
+        let mut verification = HashMap::new();
+
+        match fs::read_dir(dir) {
+            Ok(entries) => {
+                for entry in entries.flatten() {
+                    let file_name = entry.file_name().to_string_lossy().to_string();
+                    let Some(kid) = file_name.strip_suffix(".pub.pem") else {
+                        continue;
+                    };
+                    match fs::read(entry.path())
+                        .map_err(|e| e.to_string())
+                        .and_then(|pem| parse_public_key(kid, &pem))
+                    {
+                        Ok(key) => {
+                            verification.insert(kid.to_string(), key);
+                        }
+                        Err(e) => error!("Clé publique JWT '{}' ignorée: {}", kid, e),
+                    }
+                }
+            }
+            Err(e) => error!("Impossible de lire JWT_KEYS_DIR {}: {}", dir.display(), e),
+        }
+
+        let signing = signing_kid
+            .as_deref()
+            .and_then(|kid| match load_signing_key(dir, kid, &verification) {
+                Ok(key) => Some(key),
+                Err(e) => {
+                    error!("Clé de signature JWT '{}' inutilisable: {}", kid, e);
+                    None
+                }
+            });
+
+        info!(
+            "{} clé(s) de vérification JWT chargée(s) depuis {} (signature: {})",
+            verification.len(),
+            dir.display(),
+            signing.as_ref().map(|k| k.kid.as_str()).unwrap_or("aucune")
+        );
+
+        KeyDirectory {
+            dir: dir.to_path_buf(),
+            signing_kid,
+            signing,
+            verification,
+            loaded_at: Instant::now(),
+        }
+    }
+}
+
+fn load_signing_key(
+    dir: &Path,
+    kid: &str,
+    verification: &HashMap<String, VerificationKey>,
+) -> Result<SigningKey, String> {
+    // L'algorithme est déduit de la clé publique associée, qui doit donc être présente
+    let algorithm = verification
+        .get(kid)
+        .map(|k| k.algorithm)
+        .ok_or_else(|| format!("{}.pub.pem introuvable", kid))?;
+
+    let pem = fs::read(dir.join(format!("{}.pem", kid))).map_err(|e| e.to_string())?;
+    let key = match algorithm {
+        Algorithm::EdDSA => EncodingKey::from_ed_pem(&pem),
+        _ => EncodingKey::from_rsa_pem(&pem),
+    }
+    .map_err(|e| e.to_string())?;
+
+    Ok(SigningKey {
+        kid: kid.to_string(),
+        algorithm,
+        key,
+    })
+}
+
+fn parse_public_key(kid: &str, pem_bytes: &[u8]) -> Result<VerificationKey, String> {
+    let pem_str = std::str::from_utf8(pem_bytes).map_err(|e| e.to_string())?;
+
+    if let Ok(rsa_key) = RsaPublicKey::from_public_key_pem(pem_str)
+        .or_else(|_| RsaPublicKey::from_pkcs1_pem(pem_str))
+    {
+        let n = URL_SAFE_NO_PAD.encode(rsa_key.n().to_bytes_be());
+        let e = URL_SAFE_NO_PAD.encode(rsa_key.e().to_bytes_be());
+        let key = DecodingKey::from_rsa_components(&n, &e).map_err(|e| e.to_string())?;
+        return Ok(VerificationKey {
+            algorithm: Algorithm::RS256,
+            key,
+            jwk: Jwk {
+                common: jwk_common(kid, KeyAlgorithm::RS256),
+                algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n,
+                    e,
+                }),
+            },
         });
+    }
+
+    let der = pem::parse(pem_bytes).map_err(|e| e.to_string())?;
+    let contents = der.contents();
+    if contents.len() == ED25519_SPKI_PREFIX.len() + 32 && contents.starts_with(&ED25519_SPKI_PREFIX) {
+        let x = URL_SAFE_NO_PAD.encode(&contents[ED25519_SPKI_PREFIX.len()..]);
+        let key = DecodingKey::from_ed_components(&x).map_err(|e| e.to_string())?;
+        return Ok(VerificationKey {
+            algorithm: Algorithm::EdDSA,
+            key,
+            jwk: Jwk {
+                common: jwk_common(kid, KeyAlgorithm::EdDSA),
+                algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x,
+                }),
+            },
+        });
+    }
+
+    Err("type de clé non supporté (RSA ou Ed25519 attendu)".to_string())
+}
+
+fn jwk_common(kid: &str, algorithm: KeyAlgorithm) -> CommonParameters {
+    CommonParameters {
+        public_key_use: Some(PublicKeyUse::Signature),
+        key_algorithm: Some(algorithm),
+        key_id: Some(kid.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Rôle applicatif, ordonné : un rôle satisfait toute exigence d'un rôle inférieur.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    User,
+    Moderator,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(Role::User),
+            "moderator" => Ok(Role::Moderator),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!("rôle inconnu: {}", other)),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -19,6 +256,11 @@ pub struct Claims {
     pub user_id: i32,
     pub username: String,
     pub email: String,
+    /// Identifiant de la session serveur ayant émis ce token (révocable).
+    pub jti: String,
+    /// Absent des tokens émis avant l'introduction des rôles : `user` par défaut.
+    #[serde(default)]
+    pub role: Role,
     pub exp: u64,
     pub iat: u64,
 }
@@ -27,6 +269,8 @@ pub fn create_jwt(
     user_id: i32,
     username: String,
     email: String,
+    jti: String,
+    role: Role,
 ) -> Result<String, jsonwebtoken::errors::Error> {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -37,22 +281,90 @@ pub fn create_jwt(
         user_id,
         username,
         email,
-        exp: now + 86400 * 7, // 7 jours
+        jti,
+        role,
+        exp: now + ACCESS_TOKEN_TTL_SECS,
         iat: now,
     };
 
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(&JWT_SECRET),
-    )
+    let keys = KEYS.read().unwrap();
+    match &*keys {
+        KeyStore::Shared(secret) => encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret),
+        ),
+        KeyStore::Directory(directory) => {
+            let signing = directory.signing.as_ref().ok_or_else(|| {
+                error!("Aucune clé de signature JWT chargée (vérifiez JWT_SIGNING_KID)");
+                Error::from(ErrorKind::InvalidKeyFormat)
+            })?;
+            let mut header = Header::new(signing.algorithm);
+            header.kid = Some(signing.kid.clone());
+            encode(&header, &claims, &signing.key)
+        }
+    }
 }
 
 pub fn verify_jwt(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
-    decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(&JWT_SECRET),
-        &Validation::default(),
-    )
-    .map(|data| data.claims)
+    let header = decode_header(token)?;
+
+    if needs_reload(header.kid.as_deref()) {
+        reload_keys();
+    }
+
+    let keys = KEYS.read().unwrap();
+    match &*keys {
+        KeyStore::Shared(secret) => decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(secret),
+            &Validation::default(),
+        )
+        .map(|data| data.claims),
+        KeyStore::Directory(directory) => {
+            let kid = header.kid.ok_or_else(|| Error::from(ErrorKind::InvalidToken))?;
+            let key = directory.verification.get(&kid).ok_or_else(|| {
+                warn!("JWT signé avec une clé inconnue: kid={}", kid);
+                Error::from(ErrorKind::InvalidSignature)
+            })?;
+            decode::<Claims>(token, &key.key, &Validation::new(key.algorithm))
+                .map(|data| data.claims)
+        }
+    }
+}
+
+fn needs_reload(kid: Option<&str>) -> bool {
+    let keys = KEYS.read().unwrap();
+    match &*keys {
+        KeyStore::Shared(_) => false,
+        KeyStore::Directory(directory) => {
+            let age = directory.loaded_at.elapsed();
+            let unknown_kid = kid.is_some_and(|k| !directory.verification.contains_key(k));
+            age > KEYS_REFRESH_INTERVAL || (unknown_kid && age > UNKNOWN_KID_RELOAD_COOLDOWN)
+        }
+    }
+}
+
+/// Relit immédiatement le répertoire de clés (nouvelle clé déployée, clé retirée).
+/// Sans effet en mode `JWT_SECRET`.
+pub fn reload_keys() {
+    let mut keys = KEYS.write().unwrap();
+    if let KeyStore::Directory(directory) = &mut *keys {
+        *directory = KeyDirectory::load(&directory.dir, directory.signing_kid.clone());
+    }
+}
+
+/// Clés publiques actuellement acceptées, au format JWKS. Vide en mode `JWT_SECRET`.
+pub fn jwks() -> JwkSet {
+    let keys = KEYS.read().unwrap();
+    let mut jwks: Vec<Jwk> = match &*keys {
+        KeyStore::Shared(_) => Vec::new(),
+        KeyStore::Directory(directory) => directory
+            .verification
+            .values()
+            .map(|k| k.jwk.clone())
+            .collect(),
+    };
+    jwks.sort_by(|a, b| a.common.key_id.cmp(&b.common.key_id));
+    JwkSet { keys: jwks }
 }
@@ -0,0 +1,81 @@
+use std::{fs, path::PathBuf};
+
+use jsonwebtoken::{decode_header, encode, Algorithm, EncodingKey, Header};
+use shared::jwt::{create_jwt, jwks, reload_keys, verify_jwt, Claims, Role};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/keys")
+        .join(name)
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+// Un seul test : le jeu de clés est global au processus et configuré par variables d'environnement.
+#[test]
+fn asymmetric_keys_sign_verify_and_rotate() {
+    let dir = std::env::temp_dir().join(format!("rmce_jwt_keys_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for name in ["ed-2026-01.pem", "ed-2026-01.pub.pem"] {
+        fs::copy(fixture(name), dir.join(name)).unwrap();
+    }
+
+    unsafe {
+        std::env::remove_var("JWT_SECRET");
+        std::env::set_var("JWT_KEYS_DIR", &dir);
+        std::env::set_var("JWT_SIGNING_KID", "ed-2026-01");
+    }
+
+    // Signature EdDSA avec kid
+    let token = create_jwt(1, "alice".into(), "alice@test.com".into(), "session".into(), Role::Admin).unwrap();
+    let header = decode_header(&token).unwrap();
+    assert_eq!(header.alg, Algorithm::EdDSA);
+    assert_eq!(header.kid.as_deref(), Some("ed-2026-01"));
+    let verified = verify_jwt(&token).unwrap();
+    assert_eq!(verified.user_id, 1);
+    assert_eq!(verified.role, Role::Admin);
+
+    let set = jwks();
+    assert_eq!(set.keys.len(), 1);
+    assert!(set.find("ed-2026-01").is_some());
+
+    // Un token signé par une clé non encore déployée est refusé
+    let rsa_private = fs::read(fixture("rsa-2026-02.pem")).unwrap();
+    let mut rsa_header = Header::new(Algorithm::RS256);
+    rsa_header.kid = Some("rsa-2026-02".into());
+    let claims = Claims {
+        user_id: 2,
+        username: "bob".into(),
+        email: "bob@test.com".into(),
+        jti: "session".into(),
+        role: Role::User,
+        exp: now() + 60,
+        iat: now(),
+    };
+    let rsa_token = encode(&rsa_header, &claims, &EncodingKey::from_rsa_pem(&rsa_private).unwrap()).unwrap();
+    assert!(verify_jwt(&rsa_token).is_err());
+
+    // Rotation : la nouvelle clé publique est ajoutée, l'ancienne reste valide
+    fs::copy(fixture("rsa-2026-02.pub.pem"), dir.join("rsa-2026-02.pub.pem")).unwrap();
+    reload_keys();
+    assert_eq!(verify_jwt(&rsa_token).unwrap().user_id, 2);
+    assert_eq!(verify_jwt(&token).unwrap().user_id, 1);
+    assert_eq!(jwks().keys.len(), 2);
+
+    // Retrait de l'ancienne clé : ses tokens ne sont plus acceptés
+    fs::remove_file(dir.join("ed-2026-01.pub.pem")).unwrap();
+    reload_keys();
+    assert!(verify_jwt(&token).is_err());
+    assert_eq!(verify_jwt(&rsa_token).unwrap().user_id, 2);
+
+    // Un token HS256 (ancien mode secret partagé) est refusé
+    let hs_token = encode(&Header::default(), &claims, &EncodingKey::from_secret(b"secret")).unwrap();
+    assert!(verify_jwt(&hs_token).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}